@@ -2,6 +2,214 @@ use std::env;
 use std::fs;
 use std::path::Path;
 
+/// 应用命令列表，用于生成 `allow-<command>` 权限（分组见 permissions/command-groups.toml）
+const APP_COMMANDS: &[&str] = &[
+    "add_account",
+    "add_accounts_batch",
+    "add_account_by_refresh_token",
+    "get_all_accounts",
+    "get_redacted_accounts",
+    "get_account",
+    "get_onboarding_state",
+    "update_account",
+    "delete_account",
+    "delete_accounts_batch",
+    "search_accounts",
+    "filter_accounts_by_group",
+    "filter_accounts_by_tags",
+    "get_token_expiry_overview",
+    "claim_account",
+    "release_account",
+    "import_accounts_from_env",
+    "set_health_ping_opt_out",
+    "set_account_schedule_overrides",
+    "update_account_notes",
+    "set_account_metadata",
+    "check_ide_shared_use",
+    "dismiss_shared_use_warning",
+    "get_token_lineage",
+    "login_account",
+    "refresh_token",
+    "refresh_expiring_tokens",
+    "bulk_relogin",
+    "get_plan_status",
+    "reset_credits",
+    "update_seats",
+    "get_billing",
+    "update_plan",
+    "cancel_subscription",
+    "resume_subscription",
+    "get_account_info",
+    "get_current_user",
+    "get_user_status",
+    "batch_reset_credits",
+    "batch_refresh_tokens",
+    "get_team_credit_entries",
+    "get_trial_payment_link",
+    "get_team_config",
+    "update_team_config",
+    "get_cascade_model_configs",
+    "get_command_model_configs",
+    "get_team_organizational_controls",
+    "upsert_team_organizational_controls",
+    "get_available_mcp_plugins",
+    "delete_windsurf_user",
+    "generate_virtual_card",
+    "open_payment_window",
+    "inject_card_info",
+    "validate_card_number",
+    "auto_fill_payment_form",
+    "get_trial_payment_link_enhanced",
+    "open_external_link",
+    "open_external_link_incognito",
+    "inject_auto_submit_script",
+    "close_payment_window",
+    "get_success_bins",
+    "add_success_bin",
+    "clear_success_bins",
+    "get_random_success_bin",
+    "reset_test_mode_progress",
+    "get_test_mode_progress",
+    "get_current_user_parsed",
+    "get_billing_parsed",
+    "batch_get_users_parsed",
+    "list_captures",
+    "open_capture",
+    "get_account_analytics",
+    "list_analytics_presets",
+    "stream_account_analytics",
+    "export_analytics_jsonl",
+    "export_session_ledger",
+    "export_model_cost_report",
+    "get_session_labels",
+    "assign_session_label",
+    "set_session_label_rules",
+    "get_usage_by_label",
+    "get_acceptance_rate_series",
+    "get_language_trends",
+    "compare_analytics_periods",
+    "get_aggregate_analytics",
+    "get_team_benchmark",
+    "get_account_credits",
+    "get_credits_history",
+    "alerts_history",
+    "get_capacity_plan",
+    "get_activity_heatmap",
+    "list_monthly_reports",
+    "get_monthly_report",
+    "generate_share_report",
+    "get_analytics_text_summary",
+    "generate_usage_insights",
+    "run_analytics_fixtures",
+    "capture_analytics_fixture",
+    "call_windsurf_endpoint",
+    "encode_proto_body",
+    "refresh_mappings",
+    "get_settings",
+    "get_redacted_settings",
+    "update_settings",
+    "get_groups",
+    "add_group",
+    "delete_group",
+    "rename_group",
+    "get_tags",
+    "add_tag",
+    "update_tag",
+    "delete_tag",
+    "batch_update_account_tags",
+    "get_logs",
+    "query_logs",
+    "export_audit_log",
+    "clear_logs",
+    "get_stats",
+    "export_data",
+    "switch_account",
+    "activate_account_in_windsurf",
+    "reset_machine_id",
+    "check_admin_privileges",
+    "get_current_windsurf_info",
+    "get_app_version",
+    "get_app_title",
+    "format_metrics",
+    "open_dashboard_window",
+    "reset_http_client",
+    "get_windsurf_path",
+    "apply_seamless_patch",
+    "restore_seamless_patch",
+    "check_patch_status",
+    "validate_windsurf_path",
+    "check_cunzhi_status",
+    "install_cunzhi",
+    "uninstall_cunzhi",
+    "create_backup",
+    "list_backups",
+    "restore_backup",
+    "list_deleted_archives",
+    "restore_deleted_archive",
+    "export_data_to_file",
+    "export_accounts",
+    "import_data_from_file",
+    "restore_from_export",
+    "generate_import_template",
+    "set_persistence_backend",
+    "list_plugins",
+    "reload_plugins",
+    "get_data_directory",
+    "get_startup_integrity_report",
+    "get_migration_report",
+    "get_encryption_status",
+    "set_master_password",
+    "unlock_store",
+    "disable_secret_encryption",
+    "get_sorted_accounts",
+    "search_accounts_paged",
+    "update_accounts_order",
+    "update_sort_config",
+    "get_sort_config",
+    "get_team_members",
+    "invite_team_members",
+    "remove_team_member",
+    "revoke_invitation",
+    "get_pending_invitations",
+    "get_my_pending_invitation",
+    "accept_invitation",
+    "reject_invitation",
+    "request_team_access",
+    "approve_team_join_request",
+    "get_credit_top_up_settings",
+    "update_credit_top_up_settings",
+    "update_codeium_access",
+    "add_user_role",
+    "remove_user_role",
+    "transfer_subscription",
+    "get_auto_reset_configs",
+    "add_auto_reset_config",
+    "update_auto_reset_config",
+    "delete_auto_reset_config",
+    "check_and_auto_reset",
+    "force_reset_config",
+    "get_reset_records",
+    "get_reset_stats",
+    "clear_reset_records",
+    "export_automation_config",
+    "import_automation_config",
+    "get_scheduler_status",
+    "run_scheduler_job",
+    "report_user_activity",
+    "get_metered_status",
+    "get_scheduler_watchdog_events",
+    "export_job_history",
+    "get_autostart_status",
+    "set_autostart",
+    "list_palette_actions",
+    "execute_palette_action",
+];
+
+/// 应用清单：声明应用命令后，各窗口只能调用 capabilities 中授权的命令
+fn app_manifest() -> tauri_build::AppManifest {
+    tauri_build::AppManifest::new().commands(APP_COMMANDS)
+}
+
 /// 复制平台特定的 cunzhi 文件到打包目录
 fn copy_platform_cunzhi() {
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
@@ -121,14 +329,17 @@ fn main() {
             tauri_build::try_build(
                 tauri_build::Attributes::new()
                     .windows_attributes(windows)
+                    .app_manifest(app_manifest())
             ).expect("failed to run build script");
         } else {
-            tauri_build::build();
+            tauri_build::try_build(tauri_build::Attributes::new().app_manifest(app_manifest()))
+                .expect("failed to run build script");
         }
     }
 
     #[cfg(not(windows))]
     {
-        tauri_build::build();
+        tauri_build::try_build(tauri_build::Attributes::new().app_manifest(app_manifest()))
+            .expect("failed to run build script");
     }
 }
@@ -1,6 +1,9 @@
-use crate::models::{Account, OperationLog, OperationType, OperationStatus};
-use crate::repository::DataStore;
+use crate::models::{Account, OnboardingState, OperationLog, OperationType, OperationStatus, ScheduleOverrides, TokenExpiryInfo, TokenProvenance, TokenSource, token_status_at};
+use crate::utils::AppError;
+use crate::repository::{DataStore, NewAccount};
 use crate::services::{AuthService, WindsurfService};
+use crate::services::env_import_service::{self, EnvImportResult};
+use crate::services::shared_use_service::{self, SharedUseAlert};
 use serde_json::json;
 use std::sync::Arc;
 use tauri::State;
@@ -40,6 +43,46 @@ pub async fn add_account(
     Ok(account)
 }
 
+/// 批量添加账号；`note_template` 为空时使用设置中的备注模板
+///
+/// `dry_run` 为 true 时只返回将要添加与跳过的账号，不保存
+#[tauri::command]
+pub async fn add_accounts_batch(
+    accounts: Vec<NewAccount>,
+    note_template: Option<String>,
+    dry_run: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<serde_json::Value, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let note_template = match note_template {
+        Some(template) => Some(template),
+        None => store.get_settings().await.map_err(|e| e.to_string())?.account_note_template,
+    };
+    let (added, skipped) = store
+        .add_accounts(accounts, note_template.as_deref(), dry_run)
+        .await
+        .map_err(|e| e.to_string())?;
+    if dry_run {
+        return Ok(json!({
+            "dry_run": true,
+            "added": added,
+            "skipped": skipped,
+        }));
+    }
+    
+    let log = OperationLog::new(
+        OperationType::AddAccount,
+        OperationStatus::Success,
+        format!("批量添加账号: 新增 {}，跳过 {}", added.len(), skipped.len()),
+    );
+    let _ = store.add_log(log).await;
+    
+    Ok(json!({
+        "added": added,
+        "skipped": skipped,
+    }))
+}
+
 /// 通过 refresh_token 添加账号
 /// 使用 refresh_token 获取 access_token，然后获取用户信息并创建账号
 #[tauri::command]
@@ -83,6 +126,7 @@ pub async fn add_account_by_refresh_token(
     // 设置标签和分组
     account.tags = tags;
     account.group = group;
+    account.record_token_provenance(TokenSource::RefreshToken, &token, Some(&new_refresh_token), Some(expires_at));
     account.token = Some(token.clone());
     account.token_expires_at = Some(expires_at);
     account.refresh_token = Some(new_refresh_token);
@@ -163,6 +207,17 @@ pub async fn get_all_accounts(
         .map_err(|e| e.to_string())
 }
 
+/// 获取去掉密码与凭据的账号列表（只读面板使用）
+#[tauri::command]
+pub async fn get_redacted_accounts(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<Account>, String> {
+    let accounts = store.get_all_accounts()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(accounts.iter().map(Account::redacted).collect())
+}
+
 #[tauri::command]
 pub async fn get_account(
     id: String,
@@ -249,6 +304,9 @@ pub async fn update_account(
     // 更新 Token（如果有）
     if let Some(token) = account.get("token").and_then(|v| v.as_str()) {
         if !token.is_empty() {
+            if existing_account.token.as_deref() != Some(token) {
+                existing_account.record_token_provenance(TokenSource::ManualPaste, token, None, existing_account.token_expires_at);
+            }
             existing_account.token = Some(token.to_string());
         }
     }
@@ -259,6 +317,7 @@ pub async fn update_account(
             "active" => crate::models::account::AccountStatus::Active,
             "inactive" => crate::models::account::AccountStatus::Inactive,
             "error" => crate::models::account::AccountStatus::Error("API错误".to_string()),
+            "needs_attention" => crate::models::account::AccountStatus::NeedsAttention,
             _ => crate::models::account::AccountStatus::Error(status.to_string()),
         };
     }
@@ -291,6 +350,20 @@ pub async fn update_account(
     Ok(())
 }
 
+/// 获取账号的引导进度（Token、API Key、额度按账号当前数据判断）
+#[tauri::command]
+pub async fn get_onboarding_state(
+    id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<OnboardingState, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+    account.sync_onboarding();
+    Ok(account.onboarding_state())
+}
+
 #[tauri::command]
 pub async fn delete_account(
     id: String,
@@ -301,17 +374,17 @@ pub async fn delete_account(
     // 获取账号信息用于日志
     let account = store.get_account(uuid).await.ok();
     
-    store.delete_account(uuid)
+    let archive = store.delete_account(uuid)
         .await
         .map_err(|e| e.to_string())?;
     
-    // 记录日志
+    // 记录日志（附带归档路径，便于 30 天内恢复）
     if let Some(acc) = account {
         let log = OperationLog::new(
             OperationType::DeleteAccount,
             OperationStatus::Success,
             format!("删除账号: {}", acc.email),
-        );
+        ).with_details(json!({ "archive": archive.to_string_lossy() }));
         let _ = store.add_log(log).await;
     }
     
@@ -323,27 +396,43 @@ pub async fn delete_accounts_batch(
     ids: Vec<String>,
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
-    let mut success_count = 0;
     let mut failed_ids = Vec::new();
+    let mut uuids = Vec::new();
     
     for id_str in ids {
-        if let Ok(uuid) = Uuid::parse_str(&id_str) {
-            if store.delete_account(uuid).await.is_ok() {
-                success_count += 1;
-            } else {
-                failed_ids.push(id_str);
-            }
-        } else {
+        match Uuid::parse_str(&id_str) {
+            Ok(uuid) => uuids.push((uuid, id_str)),
+            Err(_) => failed_ids.push(id_str),
+        }
+    }
+    
+    // 一次删除写入同一个归档
+    let targets: Vec<Uuid> = uuids.iter().map(|(uuid, _)| *uuid).collect();
+    let (removed, archive) = if targets.is_empty() {
+        (Vec::new(), None)
+    } else {
+        match store.delete_accounts(&targets).await {
+            Ok((removed, archive)) => (removed, Some(archive)),
+            Err(AppError::AccountNotFound(_)) => (Vec::new(), None),
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    for (uuid, id_str) in uuids {
+        if !removed.iter().any(|a| a.id == uuid) {
             failed_ids.push(id_str);
         }
     }
+    let success_count = removed.len();
     
     // 记录批量操作日志
-    let log = OperationLog::new(
+    let mut log = OperationLog::new(
         OperationType::BatchOperation,
         if failed_ids.is_empty() { OperationStatus::Success } else { OperationStatus::Failed },
         format!("批量删除账号: 成功{}个，失败{}个", success_count, failed_ids.len()),
     );
+    if let Some(archive) = archive {
+        log = log.with_details(json!({ "archive": archive.to_string_lossy() }));
+    }
     let _ = store.add_log(log).await;
     
     Ok(json!({
@@ -409,3 +498,243 @@ pub async fn filter_accounts_by_tags(
     
     Ok(filtered)
 }
+
+/// 当前使用者名称：优先使用设置中的名称，否则使用系统用户名
+pub(crate) async fn current_claim_user(store: &Arc<DataStore>) -> String {
+    let configured = store
+        .get_settings()
+        .await
+        .ok()
+        .and_then(|s| s.claim_user_name)
+        .filter(|name| !name.trim().is_empty());
+
+    configured
+        .or_else(|| std::env::var("USERNAME").ok())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// 占用账号（共享账号池），`ttl_minutes` 为空时使用设置中的默认时长
+#[tauri::command]
+pub async fn claim_account(
+    id: String,
+    user: Option<String>,
+    ttl_minutes: Option<i64>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let user = match user {
+        Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+        _ => current_claim_user(&store).await,
+    };
+    let ttl = match ttl_minutes {
+        Some(minutes) => minutes,
+        None => store.get_settings().await.map_err(|e| e.to_string())?.claim_ttl_minutes,
+    };
+    if ttl <= 0 {
+        return Err("占用时长必须大于0".to_string());
+    }
+
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(ttl);
+    let account = store
+        .claim_account(uuid, &user, expires_at)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!("[claim_account] {} claimed by {} until {}", account.email, user, expires_at);
+    Ok(account)
+}
+
+/// 释放账号占用，`force` 为 true 时可释放他人的占用
+#[tauri::command]
+pub async fn release_account(
+    id: String,
+    force: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let user = current_claim_user(&store).await;
+
+    store
+        .release_account(uuid, &user, force.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取账号的 Token 获取记录与 refresh 链路（最新的在前）
+#[tauri::command]
+pub async fn get_token_lineage(
+    id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<TokenProvenance>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    Ok(account.token_history.into_iter().rev().collect())
+}
+
+/// 设置账号是否退出定时健康检查
+#[tauri::command]
+pub async fn set_health_ping_opt_out(
+    id: String,
+    opt_out: bool,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    account.health_ping_opt_out = opt_out;
+    store.update_account(account.clone()).await.map_err(|e| e.to_string())?;
+    Ok(account)
+}
+
+/// 设置账号级别的定时任务间隔（覆盖全局设置）
+#[tauri::command]
+pub async fn set_account_schedule_overrides(
+    id: String,
+    overrides: ScheduleOverrides,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    if [overrides.token_refresh_minutes, overrides.health_ping_minutes]
+        .iter()
+        .flatten()
+        .any(|minutes| *minutes < 1)
+    {
+        return Err("间隔必须至少为 1 分钟".to_string());
+    }
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    account.schedule_overrides = overrides;
+    store.update_account(account.clone()).await.map_err(|e| e.to_string())?;
+    Ok(account)
+}
+
+/// 更新账号备注，空白内容清除备注
+#[tauri::command]
+pub async fn update_account_notes(
+    id: String,
+    notes: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    account.notes = notes.filter(|n| !n.trim().is_empty());
+    store.update_account(account.clone()).await.map_err(|e| e.to_string())?;
+    Ok(account)
+}
+
+/// 设置账号的一项自定义信息，`value` 为空时删除该项
+#[tauri::command]
+pub async fn set_account_metadata(
+    id: String,
+    key: String,
+    value: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err("名称不能为空".to_string());
+    }
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    match value.filter(|v| !v.is_empty()) {
+        Some(value) => {
+            account.metadata.insert(key, value);
+        }
+        None => {
+            account.metadata.remove(&key);
+        }
+    }
+    store.update_account(account.clone()).await.map_err(|e| e.to_string())?;
+    Ok(account)
+}
+
+/// 对比 IDE 当前登录的会话与本地记录，发现不一致时标记该账号疑似在其他设备使用
+#[tauri::command]
+pub async fn check_ide_shared_use(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Option<SharedUseAlert>, String> {
+    let ide = super::windsurf_info::get_current_windsurf_info().map_err(|e| e.to_string())?;
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+
+    let (account, reason) = match shared_use_service::ide_mismatch(&accounts, ide.email.as_deref(), ide.api_key.as_deref()) {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+    let alert = SharedUseAlert {
+        account_id: account.id.to_string(),
+        email: account.email.clone(),
+        reason: reason.clone(),
+    };
+    if store.flag_shared_use_no_save(account.id, reason).await.map_err(|e| e.to_string())? {
+        store.flush().await.map_err(|e| e.to_string())?;
+        store.emit_event("accounts-possibly-shared", vec![alert.clone()]);
+    }
+    Ok(Some(alert))
+}
+
+/// 清除账号的疑似共享警告（用户确认是自己的登录后调用）
+#[tauri::command]
+pub async fn dismiss_shared_use_warning(
+    id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Account, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    account.shared_use_warning = None;
+    store.update_account(account.clone()).await.map_err(|e| e.to_string())?;
+    Ok(account)
+}
+
+/// 从环境变量（默认 `WAM_ACCOUNT_*`）导入账号，用于临时虚拟机等场景的自动配置
+///
+/// `dry_run` 为 true 时只返回将要新增、更新与跳过的账号，不修改数据
+#[tauri::command]
+pub async fn import_accounts_from_env(
+    prefix: Option<String>,
+    dry_run: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<EnvImportResult, String> {
+    let prefix = prefix.unwrap_or_else(|| env_import_service::DEFAULT_ENV_PREFIX.to_string());
+    env_import_service::import_from_env(&store, &prefix, dry_run.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取所有账号的 Token 过期概览（最早过期的排在前面）
+#[tauri::command]
+pub async fn get_token_expiry_overview(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<TokenExpiryInfo>, String> {
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now();
+
+    let mut overview: Vec<TokenExpiryInfo> = accounts
+        .iter()
+        .map(|account| {
+            // 优先使用 JWT 中的 exp，解码失败时回退到本地记录
+            let (expires_at, source) = account.effective_token_expiry();
+            let seconds_remaining = expires_at.map(|exp| (exp - now).num_seconds());
+            let status = token_status_at(expires_at, now);
+
+            TokenExpiryInfo {
+                account_id: account.id,
+                email: account.email.clone(),
+                nickname: account.nickname.clone(),
+                group: account.group.clone(),
+                expires_at,
+                source: source.to_string(),
+                seconds_remaining,
+                status: status.to_string(),
+                can_refresh: account.refresh_token.is_some() || !account.password.is_empty(),
+            }
+        })
+        .collect();
+
+    overview.sort_by(|a, b| match (&a.expires_at, &b.expires_at) {
+        (Some(a_exp), Some(b_exp)) => a_exp.cmp(b_exp),
+        (Some(_), None) => std::cmp::Ordering::Greater,
+        (None, Some(_)) => std::cmp::Ordering::Less,
+        (None, None) => a.email.cmp(&b.email),
+    });
+
+    Ok(overview)
+}
@@ -1,24 +1,209 @@
 use crate::models::{
-    AnalyticsData, DailyCascadeLinesCount, ToolUsageEntry, ModelUsageEntry, ModelUsageSummary, AnalyticsSummary,
+    AnalyticsData, DailyCascadeLinesCount, ToolUsageEntry, ModelUsageEntry, ModelUsageSummary,
     PercentCodeWritten, CompletionStatistics, CompletionByDay, CompletionByLanguage,
-    ChatStats, ChatStatsByDay, ChatStatsByModel, CustomQueryResponse, CustomQueryResponseItem,
+    CascadeStats, CascadeStatsByDay, ChatStats, ChatStatsByDay, ChatStatsByModel, CustomQueryResponse, CustomQueryResponseItem,
+    AcceptanceRatePoint, AnalyticsSnapshot, CreditsSnapshot, CreditsUsage, AggregateAnalyticsData, BenchmarkMetric, TeamBenchmark, LanguageTrend, LanguageSharePoint, ActivityHeatmap,
+    MonthlyReport, MonthlyReportInfo, PeriodComparison, AnalyticsTimings, EmptyReason, CapacityAccountEntry, CapacityPlan, AnalyticsChunk, AnalyticsStreamSummary,
 };
+use crate::models::{Account, AlertEvent, AnalyticsRequestPreset, OnboardingStep, SessionLabelRule, SessionLabels, ANALYTICS_EXTRACTOR_VERSION};
 use crate::repository::DataStore;
-use crate::services::{AnalyticsService, WindsurfService, proto_parser};
-use serde_json::Value;
+use crate::services::{AnalyticsService, WindsurfService, alert_service, insights_service, mapping_service, proto_parser, session_label_service, share_service, sink_service, user_info_cache};
+use crate::services::session_label_service::LabelUsage;
+use crate::services::analytics_aggregator::AnalyticsAggregator;
+use crate::services::export_service::{analytics_to_jsonl_rows, model_cost_csv, model_cost_rows, session_ledger_csv, session_ledger_rows};
+use serde_json::{json, Value};
 use std::sync::Arc;
+use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use tauri::State;
+use std::time::Instant;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
 use uuid::Uuid;
-use chrono::{Utc, Duration, DateTime};
+use crate::utils::date_utils::{self, DayBucketing};
+use crate::utils::format::format_thousands;
+use crate::utils::fixtures::{self, FixtureResult};
+use chrono::{Utc, Duration, DateTime, Datelike, NaiveDate};
 
 /// 获取账户的使用分析数据（最近30天）
+///
+/// `top_n` 不为空时，工具、模型、语言只保留前 N 项，其余合并为 "Other"；
+/// `fill_gaps` 为 true 时为没有活动的日期补零，使每日序列覆盖完整的 30 天；
+/// `preset` 指定 GetAnalytics 请求预设（见 list_analytics_presets），为空时使用设置中的默认预设
 #[tauri::command]
 pub async fn get_account_analytics(
     id: String,
+    top_n: Option<usize>,
+    fill_gaps: Option<bool>,
+    preset: Option<String>,
     store: State<'_, Arc<DataStore>>,
 ) -> Result<AnalyticsData, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+
+    // 缓存时长内直接返回最近一次快照（指定预设时总是重新请求）
+    let cached = if preset.is_none() && settings.analytics_cache_ttl_minutes > 0 {
+        let ttl = Duration::minutes(settings.analytics_cache_ttl_minutes);
+        store.get_analytics_snapshots(uuid).await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .max_by_key(|s| s.captured_at)
+            .filter(|s| Utc::now() - s.captured_at < ttl)
+            .map(|s| s.data)
+    } else {
+        None
+    };
+
+    let mut analytics_data = match cached {
+        Some(mut data) => {
+            println!("[get_account_analytics] Using cached snapshot for {}", uuid);
+            // 最近 7/30 天的窗口按当前时间重新计算
+            let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+            AnalyticsAggregator::recompute(&mut data, bucketing);
+            data
+        }
+        None => fetch_account_analytics_with_preset(&store, uuid, preset.as_deref()).await?.1,
+    };
+    if let Some(n) = top_n {
+        collapse_top_n(&mut analytics_data, n);
+    }
+    if fill_gaps.unwrap_or(false) {
+        let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+        let today = date_utils::format_day(Utc::now().timestamp(), bucketing);
+        if let Ok(end) = NaiveDate::parse_from_str(&today, "%Y-%m-%d") {
+            fill_daily_gaps(&mut analytics_data, end - Duration::days(29), end);
+        }
+    }
+    Ok(analytics_data)
+}
+
+/// 获取可用的 GetAnalytics 请求预设：内置预设与设置中的自定义预设（同名时自定义预设覆盖内置预设）
+#[tauri::command]
+pub async fn list_analytics_presets(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<AnalyticsRequestPreset>, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let mut presets: Vec<AnalyticsRequestPreset> = crate::services::analytics_service::builtin_presets()
+        .into_iter()
+        .filter(|builtin| !settings.analytics_presets.iter().any(|p| p.name == builtin.name))
+        .collect();
+    presets.extend(settings.analytics_presets);
+    Ok(presets)
+}
+
+/// 为 [start, end] 范围内缺失的日期插入零值条目，并按日期排序
+fn fill_daily_gaps(data: &mut AnalyticsData, start: NaiveDate, end: NaiveDate) {
+    let mut day = start;
+    while day <= end {
+        let date = day.format("%Y-%m-%d").to_string();
+        let timestamp = day.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc().timestamp()).unwrap_or(0);
+
+        if !data.daily_cascade_lines.iter().any(|d| d.date == date) {
+            data.daily_cascade_lines.push(DailyCascadeLinesCount {
+                timestamp,
+                date: date.clone(),
+                accepted_lines: 0,
+                suggested_lines: 0,
+            });
+        }
+        if !data.completions_by_day.iter().any(|d| d.date == date) {
+            data.completions_by_day.push(CompletionByDay {
+                timestamp,
+                date: date.clone(),
+                statistics: CompletionStatistics::default(),
+            });
+        }
+        if !data.chats_by_day.iter().any(|d| d.date == date) {
+            data.chats_by_day.push(ChatStatsByDay {
+                timestamp,
+                date,
+                stats: ChatStats::default(),
+            });
+        }
+        day += Duration::days(1);
+    }
+
+    data.daily_cascade_lines.sort_by(|a, b| a.date.cmp(&b.date));
+    data.completions_by_day.sort_by(|a, b| a.date.cmp(&b.date));
+    data.chats_by_day.sort_by(|a, b| a.date.cmp(&b.date));
+}
+
+/// 合并后的 "其他" 项名称
+const OTHER_LABEL: &str = "Other";
+
+/// 工具、模型、语言各保留前 N 项（按次数/接受行数），其余合并为一项 "Other"
+fn collapse_top_n(data: &mut AnalyticsData, n: usize) {
+    if data.tool_usage.len() > n {
+        data.tool_usage.sort_by_key(|t| Reverse(t.count));
+        let rest = data.tool_usage.split_off(n);
+        data.tool_usage.push(ToolUsageEntry {
+            tool_name: OTHER_LABEL.to_string(),
+            count: rest.iter().map(|t| t.count).sum(),
+            percentage: rest.iter().map(|t| t.percentage).sum(),
+        });
+    }
+
+    if data.model_usage_summary.len() > n {
+        data.model_usage_summary.sort_by_key(|m| Reverse(m.total_count));
+        let rest = data.model_usage_summary.split_off(n);
+        data.model_usage_summary.push(ModelUsageSummary {
+            model_name: OTHER_LABEL.to_string(),
+            total_count: rest.iter().map(|m| m.total_count).sum(),
+            total_tokens: rest.iter().map(|m| m.total_tokens).sum(),
+            percentage: rest.iter().map(|m| m.percentage).sum(),
+        });
+    }
+
+    if data.completions_by_language.len() > n {
+        data.completions_by_language
+            .sort_by_key(|l| Reverse(l.statistics.num_lines_accepted));
+        let rest = data.completions_by_language.split_off(n);
+        let mut statistics = CompletionStatistics::default();
+        for language in &rest {
+            statistics.num_acceptances += language.statistics.num_acceptances;
+            statistics.num_rejections += language.statistics.num_rejections;
+            statistics.num_lines_accepted += language.statistics.num_lines_accepted;
+            statistics.num_bytes_accepted += language.statistics.num_bytes_accepted;
+        }
+        let decided = statistics.num_acceptances + statistics.num_rejections;
+        statistics.acceptance_rate = if decided > 0 {
+            statistics.num_acceptances as f64 / decided as f64 * 100.0
+        } else {
+            0.0
+        };
+        data.completions_by_language.push(CompletionByLanguage {
+            language_id: -1,
+            language_name: OTHER_LABEL.to_string(),
+            statistics,
+        });
+    }
+}
+
+/// 拉取账户最近30天的分析数据，返回最新的账户信息和分析数据
+pub(crate) async fn fetch_account_analytics(
+    store: &Arc<DataStore>,
+    uuid: Uuid,
+) -> Result<(Account, AnalyticsData), String> {
+    fetch_account_analytics_with_preset(store, uuid, None).await
+}
+
+/// 同上，`preset` 指定请求预设（为空时使用设置中的默认预设）；预设请求失败时回退到逐级降级请求
+pub(crate) async fn fetch_account_analytics_with_preset(
+    store: &Arc<DataStore>,
+    uuid: Uuid,
+    preset: Option<&str>,
+) -> Result<(Account, AnalyticsData), String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let preset = match preset.map(|p| p.to_string()).or_else(|| settings.analytics_preset.clone()) {
+        Some(name) => Some(
+            crate::services::analytics_service::find_preset(&settings.analytics_presets, &name)
+                .ok_or_else(|| format!("未找到请求预设: {}", name))?,
+        ),
+        None => None,
+    };
+
+    let started = Instant::now();
+    let mut timings = AnalyticsTimings::default();
 
     // 获取账号信息
     let mut account = store.get_account(uuid)
@@ -26,16 +211,22 @@ pub async fn get_account_analytics(
         .map_err(|e| e.to_string())?;
 
     // 确保有有效的Token
-    super::api_commands::ensure_valid_token(&store, &mut account, uuid).await?;
+    let token_before = account.token.clone();
+    let stage = Instant::now();
+    super::api_commands::ensure_valid_token(store, &mut account, uuid).await?;
+    timings.auth_ms = stage.elapsed().as_millis() as u64;
+    timings.token_refreshed = account.token != token_before;
 
     let token = account.token.clone().ok_or("No token available")?;
 
+    let stage = Instant::now();
+
     // 获取或更新 Windsurf API Key，同时检查是否是团队账户
     let (windsurf_api_key, is_team) = if let Some(api_key) = &account.windsurf_api_key {
-        println!("[get_account_analytics] Using cached Windsurf API Key: {}", api_key);
+        crate::proto_log!("[get_account_analytics] Using cached Windsurf API Key: {}", api_key);
         // 对于缓存的 API Key，需要再次获取用户信息来判断是否是团队账户
-        let windsurf_service = WindsurfService::new();
-        let user_info_result = windsurf_service.get_current_user(&token)
+        let windsurf_service = WindsurfService::for_account(uuid);
+        let user_info_result = user_info_cache::get_current_user_cached(&windsurf_service, &token)
             .await
             .map_err(|e| format!("Failed to get current user: {}", e))?;
         
@@ -46,7 +237,7 @@ pub async fn get_account_analytics(
         
         // 打印用户数据结构以便调试
         if let Some(user) = user_data {
-            println!("[get_account_analytics] User data keys: {:?}", user.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+            crate::proto_log!("[get_account_analytics] User data keys: {:?}", user.as_object().map(|o| o.keys().collect::<Vec<_>>()));
         }
         
         let team_id = user_data
@@ -55,14 +246,14 @@ pub async fn get_account_analytics(
             .unwrap_or("");
         
         let has_team = !team_id.is_empty();
-        println!("[get_account_analytics] Team ID: '{}', Is team account: {}", team_id, has_team);
+        crate::proto_log!("[get_account_analytics] Team ID: '{}', Is team account: {}", team_id, has_team);
         (api_key.clone(), has_team)
     } else {
-        println!("[get_account_analytics] Fetching Windsurf API Key from GetCurrentUser API");
+        crate::proto_log!("[get_account_analytics] Fetching Windsurf API Key from GetCurrentUser API");
 
         // 调用 GetCurrentUser API 获取用户的 Windsurf API Key
-        let windsurf_service = WindsurfService::new();
-        let user_info_result = windsurf_service.get_current_user(&token)
+        let windsurf_service = WindsurfService::for_account(uuid);
+        let user_info_result = user_info_cache::get_current_user_cached(&windsurf_service, &token)
             .await
             .map_err(|e| format!("Failed to get current user: {}", e))?;
 
@@ -82,7 +273,7 @@ pub async fn get_account_analytics(
         
         // 打印用户数据结构以便调试
         if let Some(user) = user_data {
-            println!("[get_account_analytics] User data keys: {:?}", user.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+            crate::proto_log!("[get_account_analytics] User data keys: {:?}", user.as_object().map(|o| o.keys().collect::<Vec<_>>()));
         }
         
         let team_id = user_data
@@ -92,8 +283,8 @@ pub async fn get_account_analytics(
         
         let has_team = !team_id.is_empty();
 
-        println!("[get_account_analytics] Got Windsurf API Key: {}", api_key);
-        println!("[get_account_analytics] Team ID: '{}', Is team account: {}", team_id, has_team);
+        crate::proto_log!("[get_account_analytics] Got Windsurf API Key: {}", api_key);
+        crate::proto_log!("[get_account_analytics] Team ID: '{}', Is team account: {}", team_id, has_team);
 
         // 更新账户信息，保存 API Key
         let mut updated_account = account.clone();
@@ -104,6 +295,7 @@ pub async fn get_account_analytics(
 
         (api_key, has_team)
     };
+    timings.profile_ms = stage.elapsed().as_millis() as u64;
 
     // 计算时间范围：最近30天
     let end_time = Utc::now();
@@ -113,105 +305,1300 @@ pub async fn get_account_analytics(
     let end_timestamp = end_time.timestamp();
 
     println!("[get_account_analytics] Fetching analytics for account: {}", account.email);
-    println!("[get_account_analytics] Time range (30 days): {} to {}", start_time.format("%Y-%m-%d"), end_time.format("%Y-%m-%d"));
-    println!("[get_account_analytics] Using Windsurf API Key: {}", windsurf_api_key);
+    crate::proto_log!("[get_account_analytics] Time range (30 days): {} to {}", start_time.format("%Y-%m-%d"), end_time.format("%Y-%m-%d"));
+    crate::proto_log!("[get_account_analytics] Using Windsurf API Key: {}", windsurf_api_key);
 
     // 调用 GetAnalytics API
-    let analytics_service = AnalyticsService::new();
-    
-    // 三层降级策略：
-    // 1. 完整请求（包含所有查询类型）
-    // 2. 非团队请求（移除 percent_code_written）
-    // 3. 仅 cascade 请求（最小化请求体）
+    let analytics_service = AnalyticsService::for_account(uuid);
     
-    let response_result = analytics_service.get_analytics(&token, start_timestamp, end_timestamp, is_team).await;
-    
-    let response_body = match response_result {
-        Ok(body) => Some(body),
-        Err(e) if is_team => {
-            // 第一次降级：团队请求失败，尝试不带 percent_code_written
-            println!("[get_account_analytics] Team request failed, retrying without percent_code_written: {}", e);
-            match analytics_service.get_analytics(&token, start_timestamp, end_timestamp, false).await {
-                Ok(body) => Some(body),
-                Err(e) => {
-                    // 第二次降级：尝试仅请求 cascade 数据
-                    println!("[get_account_analytics] Full request failed, trying cascade-only: {}", e);
-                    match analytics_service.get_analytics_cascade_only(&token, start_timestamp, end_timestamp).await {
-                        Ok(body) => Some(body),
-                        Err(e) => {
-                            // 第三次降级：无时间戳请求
-                            println!("[get_account_analytics] Cascade-only failed, trying no-timestamp: {}", e);
-                            match analytics_service.get_analytics_no_timestamp(&token).await {
-                                Ok(body) => Some(body),
-                                Err(e) => {
-                                    println!("[get_account_analytics] All requests failed, returning empty data: {}", e);
-                                    None
-                                }
-                            }
-                        }
-                    }
-                }
+    // 指定预设时先按预设请求，失败后按降级顺序请求（见 AnalyticsFallback::ladder）
+    let stage = Instant::now();
+    let preset_body = match &preset {
+        Some(preset) => match analytics_service
+            .get_analytics_with_preset(&token, preset, end_timestamp, settings.timezone.as_deref())
+            .await
+        {
+            Ok(body) => Some(body),
+            Err(e) => {
+                println!("[get_account_analytics] Preset '{}' failed, falling back: {}", preset.name, e);
+                None
             }
         },
-        Err(e) => {
-            // 第一次降级：尝试仅请求 cascade 数据
-            println!("[get_account_analytics] Full request failed, trying cascade-only: {}", e);
-            match analytics_service.get_analytics_cascade_only(&token, start_timestamp, end_timestamp).await {
-                Ok(body) => Some(body),
-                Err(e) => {
-                    // 第二次降级：无时间戳请求（模仿官网）
-                    println!("[get_account_analytics] Cascade-only failed, trying no-timestamp: {}", e);
-                    match analytics_service.get_analytics_no_timestamp(&token).await {
-                        Ok(body) => Some(body),
-                        Err(e) => {
-                            println!("[get_account_analytics] All requests failed, returning empty data: {}", e);
-                            None
-                        }
-                    }
-                }
+        None => None,
+    };
+    
+    // 只有完整的数据写入快照与触发提醒，预设或降级请求缺少的字段会被当作 0
+    let (response_body, fallback, complete) = if let Some(body) = preset_body {
+        let complete = preset.as_ref().map(crate::services::analytics_service::is_complete_preset).unwrap_or(false);
+        (Some(body), "preset", complete)
+    } else {
+        match analytics_service
+            .get_analytics_with_fallback(&token, start_timestamp, end_timestamp, is_team, settings.timezone.as_deref())
+            .await
+        {
+            Ok((body, strategy)) => (Some(body), strategy.as_str(), strategy.is_complete()),
+            Err(e) => {
+                println!("[get_account_analytics] All requests failed, returning empty data: {}", e);
+                (None, "none", false)
             }
-        },
+        }
+    };
+    timings.fetch_ms = stage.elapsed().as_millis() as u64;
+    timings.fallback = match &preset {
+        Some(preset) if fallback == "preset" => format!("preset:{}", preset.name),
+        _ => fallback.to_string(),
     };
 
     // 如果 API 调用失败，返回空数据而不是错误
-    let analytics_data = if let Some(body) = response_body {
-        // 解析响应
-        match proto_parser::parse_get_analytics_response(&body) {
-            Ok(parsed_response) => {
-                // 提取并转换数据
-                extract_analytics_data(&parsed_response).unwrap_or_default()
-            },
+    let stage = Instant::now();
+    let mut fetched = false;
+    let raw_body = if settings.keep_raw_analytics { response_body.clone() } else { None };
+    let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+    let mut analytics_data = if let Some(body) = response_body {
+        match extract_analytics_from_body(&body, bucketing) {
+            Ok(mut data) => {
+                fetched = true;
+                if !data.has_activity() {
+                    data.is_empty_because = Some(EmptyReason::NoActivity);
+                }
+                data
+            }
             Err(e) => {
                 println!("[get_account_analytics] Failed to parse response, returning empty data: {}", e);
-                AnalyticsData::default()
+                AnalyticsData { is_empty_because: Some(EmptyReason::ParseFailed), ..Default::default() }
             }
         }
     } else {
-        AnalyticsData::default()
+        AnalyticsData { is_empty_because: Some(EmptyReason::FetchFailed), ..Default::default() }
     };
+    timings.parse_ms = stage.elapsed().as_millis() as u64;
 
-    Ok(analytics_data)
+    // 保存当日快照，供历史趋势使用
+    if fetched {
+        if complete {
+            if let Err(e) = store.record_analytics_snapshot(uuid, analytics_data.clone(), raw_body.as_deref()).await {
+                println!("[get_account_analytics] Failed to record analytics snapshot: {}", e);
+            }
+            if let Err(e) = store.record_credits_snapshot(&account, analytics_data.credits_usage.clone()).await {
+                println!("[get_account_analytics] Failed to record credits snapshot: {}", e);
+            }
+            tokio::spawn(alert_service::evaluate_after_refresh(store.clone(), uuid, analytics_data.clone()));
+        } else {
+            println!("[get_account_analytics] Partial data ({}), skipping snapshot and alerts", timings.fallback);
+        }
+        let _ = store.mark_onboarding_step(uuid, OnboardingStep::AnalyticsVerified).await;
+
+        // 快照同时写入外部数据库（失败不影响返回）；缓存与失败的请求不写入，避免重复行
+        if settings.external_sink.enabled {
+            let rows = analytics_to_jsonl_rows(&account, &analytics_data);
+            let sink = settings.external_sink.clone();
+            tokio::spawn(async move {
+                match sink_service::write_to_sink(&sink, &rows).await {
+                    Ok(count) => println!("[get_account_analytics] Wrote {} rows to external sink", count),
+                    Err(e) => println!("[get_account_analytics] External sink write failed: {}", e),
+                }
+            });
+        }
+    }
+
+    timings.total_ms = started.elapsed().as_millis() as u64;
+    println!(
+        "[get_account_analytics] Timings: auth {}ms, profile {}ms, fetch {}ms ({}), parse {}ms, total {}ms",
+        timings.auth_ms, timings.profile_ms, timings.fetch_ms, timings.fallback, timings.parse_ms, timings.total_ms
+    );
+    analytics_data.timings = Some(timings);
+
+    Ok((account, analytics_data))
+}
+
+/// 分块传输分析数据：明细通过 `analytics-chunk` 事件分批发送，最后发送 `analytics-summary`
+///
+/// 用于明细行数很多（如 90 天的 Cascade 运行记录）时避免一次性传输大块 JSON。
+/// `range` 为明细时间范围（默认 90d），`chunk_size` 为每块行数（默认 500）
+#[tauri::command]
+pub async fn stream_account_analytics(
+    id: String,
+    range: Option<String>,
+    chunk_size: Option<usize>,
+    request_id: Option<String>,
+    app: AppHandle,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let request_id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let chunk_size = chunk_size.unwrap_or(500).max(1);
+
+    let (_, mut analytics_data) = fetch_account_analytics(&store, uuid).await?;
+
+    // 明细使用合并后的历史数据，覆盖比单次拉取更长的时间范围
+    let days = parse_range_days(Some(range.as_deref().unwrap_or("90d")));
+    let since = (Utc::now() - Duration::days(days)).timestamp();
+    let (runs, _) = load_merged_history(&store, uuid).await?;
+    let runs: Vec<ModelUsageEntry> = runs.into_iter().filter(|e| e.timestamp >= since).collect();
+
+    let total_rows = runs.len();
+    let total_chunks = total_rows.div_ceil(chunk_size);
+    for (index, items) in runs.chunks(chunk_size).enumerate() {
+        let chunk = AnalyticsChunk {
+            request_id: request_id.clone(),
+            account_id: uuid,
+            index,
+            total_chunks,
+            items: items.to_vec(),
+        };
+        app.emit("analytics-chunk", chunk).map_err(|e| e.to_string())?;
+    }
+
+    analytics_data.model_usage_details = Vec::new();
+    let summary = AnalyticsStreamSummary {
+        request_id: request_id.clone(),
+        account_id: uuid,
+        total_rows,
+        total_chunks,
+        data: analytics_data,
+    };
+    app.emit("analytics-summary", summary).map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "request_id": request_id,
+        "total_rows": total_rows,
+        "total_chunks": total_chunks,
+    }))
+}
+
+/// 导出分析数据为 JSON Lines（每行一个 日期/模型/账户 维度的记录）
+#[tauri::command]
+pub async fn export_analytics_jsonl(
+    export_path: String,
+    account_ids: Option<Vec<String>>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let targets: Vec<Account> = match &account_ids {
+        Some(ids) => accounts.into_iter().filter(|a| ids.contains(&a.id.to_string())).collect(),
+        None => accounts,
+    };
+
+    let mut lines = Vec::new();
+    let mut exported_accounts = 0;
+    let mut failed = Vec::new();
+
+    for account in &targets {
+        match fetch_account_analytics(&store, account.id).await {
+            Ok((account, data)) => {
+                for row in analytics_to_jsonl_rows(&account, &data) {
+                    lines.push(row.to_string());
+                }
+                exported_accounts += 1;
+            }
+            Err(e) => {
+                println!("[export_analytics_jsonl] Failed to fetch analytics for {}: {}", account.email, e);
+                failed.push(json!({ "account_id": account.id.to_string(), "email": account.email, "error": e }));
+            }
+        }
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    std::fs::write(&export_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(json!({
+        "success": true,
+        "path": export_path,
+        "rows": lines.len(),
+        "accounts": exported_accounts,
+        "failed": failed,
+    }))
+}
+
+/// 导出会话账单 CSV（每行一个 会话/模型：会话 ID、模型、Token、估算积分、日期）
+///
+/// `range` 同热力图（默认 30 天）；积分按设置中的模型单价估算
+#[tauri::command]
+pub async fn export_session_ledger(
+    account_id: String,
+    range: Option<String>,
+    export_path: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let since = (Utc::now() - Duration::days(parse_range_days(range.as_deref()))).timestamp();
+
+    let (runs, _) = load_merged_history(&store, uuid).await?;
+    let runs: Vec<ModelUsageEntry> = runs.into_iter().filter(|e| e.timestamp >= since).collect();
+
+    let rows = session_ledger_rows(&runs, &settings.model_credit_rates);
+    let total_credits: f64 = rows.iter().map(|r| r.estimated_credits).sum();
+    std::fs::write(&export_path, session_ledger_csv(&rows)).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(json!({
+        "success": true,
+        "path": export_path,
+        "rows": rows.len(),
+        "sessions": rows.iter().map(|r| r.session_id.as_str()).collect::<std::collections::HashSet<_>>().len(),
+        "total_credits": total_credits,
+    }))
+}
+
+/// 导出所有账号的模型费用报表 CSV（周期、账号、标签、模型、消息数、Token、估算积分），供报销/财务使用
+///
+/// 仅使用已保存的分析快照，不发起请求；`range` 同热力图（默认 30 天），`group_by_month` 为 false 时按天汇总
+#[tauri::command]
+pub async fn export_model_cost_report(
+    range: Option<String>,
+    group_by_month: Option<bool>,
+    export_path: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let since = (Utc::now() - Duration::days(parse_range_days(range.as_deref()))).timestamp();
+    let group_by_month = group_by_month.unwrap_or(true);
+
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let mut rows = Vec::new();
+    for account in &accounts {
+        let snapshots = store.get_analytics_snapshots(account.id).await.map_err(|e| e.to_string())?;
+        rows.extend(model_cost_rows(account, &snapshots, since, &settings.model_credit_rates, group_by_month));
+    }
+    rows.sort_by(|a, b| a.period.cmp(&b.period).then_with(|| a.account_email.cmp(&b.account_email)));
+
+    let total_credits: f64 = rows.iter().map(|r| r.estimated_credits).sum();
+    std::fs::write(&export_path, model_cost_csv(&rows)).map_err(|e| format!("Failed to write file: {}", e))?;
+    println!("[ModelCostReport] Exported {} rows to {}", rows.len(), export_path);
+
+    Ok(json!({
+        "success": true,
+        "path": export_path,
+        "rows": rows.len(),
+        "accounts": rows.iter().map(|r| r.account_email.as_str()).collect::<std::collections::HashSet<_>>().len(),
+        "total_credits": total_credits,
+    }))
+}
+
+/// 获取会话标签（手动指定与匹配规则）
+#[tauri::command]
+pub async fn get_session_labels(store: State<'_, Arc<DataStore>>) -> Result<SessionLabels, String> {
+    store.get_session_labels().await.map_err(|e| e.to_string())
+}
+
+/// 手动为会话指定标签（项目），label 为空时清除
+#[tauri::command]
+pub async fn assign_session_label(
+    session_ids: Vec<String>,
+    label: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    store
+        .assign_session_label(session_ids, label)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 保存会话标签规则（按顺序匹配，第一条命中的生效）
+#[tauri::command]
+pub async fn set_session_label_rules(
+    rules: Vec<SessionLabelRule>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    session_label_service::validate_rules(&rules).map_err(|e| e.to_string())?;
+    store.set_session_label_rules(rules).await.map_err(|e| e.to_string())
+}
+
+/// 按会话标签汇总用量，便于把用量归到客户项目
+///
+/// `range` 同热力图（默认 30 天）
+#[tauri::command]
+pub async fn get_usage_by_label(
+    account_id: String,
+    range: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<LabelUsage>, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let labels = store.get_session_labels().await.map_err(|e| e.to_string())?;
+    let since = (Utc::now() - Duration::days(parse_range_days(range.as_deref()))).timestamp();
+
+    let (runs, _) = load_merged_history(&store, uuid).await?;
+    let runs: Vec<ModelUsageEntry> = runs.into_iter().filter(|e| e.timestamp >= since).collect();
+
+    Ok(session_label_service::rollup_by_label(&runs, &labels, &settings.model_credit_rates))
+}
+
+/// 获取每日补全接受率序列，可选移动平均平滑
+///
+/// `window` 为平滑窗口天数（默认 7，传 1 表示不平滑）
+#[tauri::command]
+pub async fn get_acceptance_rate_series(
+    id: String,
+    window: Option<usize>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<AcceptanceRatePoint>, String> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let (_, analytics_data) = fetch_account_analytics(&store, uuid).await?;
+
+    Ok(build_acceptance_rate_series(&analytics_data.completions_by_day, window.unwrap_or(7)))
+}
+
+/// 计算接受率序列
+///
+/// 平滑值为窗口内（含当天向前 window 天）接受数之和除以总事件数，
+/// 避免少量补全的日子把均值拉偏
+fn build_acceptance_rate_series(days: &[CompletionByDay], window: usize) -> Vec<AcceptanceRatePoint> {
+    let window = window.max(1);
+    let mut sorted: Vec<&CompletionByDay> = days.iter().collect();
+    sorted.sort_by_key(|d| d.timestamp);
+
+    let rate_of = |acc: i64, rej: i64| -> Option<f64> {
+        if acc + rej > 0 {
+            Some(acc as f64 / (acc + rej) as f64 * 100.0)
+        } else {
+            None
+        }
+    };
+
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(index, day)| {
+            let start = (index + 1).saturating_sub(window);
+            let (window_acc, window_rej) = sorted[start..=index]
+                .iter()
+                .fold((0i64, 0i64), |(acc, rej), d| {
+                    (acc + d.statistics.num_acceptances, rej + d.statistics.num_rejections)
+                });
+
+            AcceptanceRatePoint {
+                timestamp: day.timestamp,
+                date: day.date.clone(),
+                num_acceptances: day.statistics.num_acceptances,
+                num_rejections: day.statistics.num_rejections,
+                rate: rate_of(day.statistics.num_acceptances, day.statistics.num_rejections),
+                smoothed_rate: rate_of(window_acc, window_rej),
+            }
+        })
+        .collect()
+}
+
+/// 汇总所有账号的分析数据
+///
+/// 默认使用每个账号最近一次的分析快照；`refresh` 为 true 时逐个实时拉取（失败的账号跳过）
+#[tauri::command]
+pub async fn get_aggregate_analytics(
+    refresh: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<AggregateAnalyticsData, String> {
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let mut sources: Vec<(Account, DateTime<Utc>, AnalyticsData)> = Vec::new();
+
+    for account in accounts {
+        if refresh.unwrap_or(false) {
+            match fetch_account_analytics(&store, account.id).await {
+                Ok((account, data)) => sources.push((account, Utc::now(), data)),
+                Err(e) => println!("[get_aggregate_analytics] Skipping {}: {}", account.email, e),
+            }
+        } else {
+            let snapshots = store.get_analytics_snapshots(account.id).await.map_err(|e| e.to_string())?;
+            if let Some(latest) = snapshots.into_iter().last() {
+                sources.push((account, latest.captured_at, latest.data));
+            }
+        }
+    }
+
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+    Ok(AnalyticsAggregator::aggregate(&sources, bucketing))
+}
+
+/// 团队成员对比：本账号的积分与接受行数相对团队平均水平的位置
+///
+/// 积分来自团队成员列表（GetUsers，需要团队管理员权限）；接受行数接口只返回账号自己的数据，
+/// 因此对本应用中管理的同团队账号按同一时间窗口（最近 30 天）请求团队分析数据后比较
+#[tauri::command]
+pub async fn get_team_benchmark(
+    account_id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<TeamBenchmark, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    super::api_commands::ensure_valid_token(&store, &mut account, uuid).await?;
+    let token = account.token.clone().ok_or("No token available")?;
+
+    let result = WindsurfService::for_account(uuid)
+        .get_team_members(&token, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        return Err("获取团队成员失败（需要团队账号及管理员权限）".to_string());
+    }
+    let members = extract_team_member_credits(result.get("data").unwrap_or(&Value::Null));
+    if members.is_empty() {
+        return Err("该账号不属于团队或团队没有成员".to_string());
+    }
+
+    let email = account.email.to_lowercase();
+    let prompt_credits = members
+        .iter()
+        .find(|(member, _)| *member == email)
+        .map(|(_, own)| {
+            let values: Vec<f64> = members.iter().map(|(_, credits)| *credits as f64).collect();
+            benchmark_metric(*own as f64, &values)
+        });
+
+    // 同团队成员中由本应用管理的账号，按同一时间窗口请求分析数据
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+    let end_timestamp = Utc::now().timestamp();
+    let start_timestamp = end_timestamp - Duration::days(30).num_seconds();
+    let mut own_lines = None;
+    let mut team_lines = Vec::new();
+    for mut managed in store.get_all_accounts().await.map_err(|e| e.to_string())? {
+        let managed_email = managed.email.to_lowercase();
+        if !members.iter().any(|(member, _)| *member == managed_email) {
+            continue;
+        }
+        let managed_id = managed.id;
+        if let Err(e) = super::api_commands::ensure_valid_token(&store, &mut managed, managed_id).await {
+            println!("[get_team_benchmark] Skipping {}: {}", managed.email, e);
+            continue;
+        }
+        let Some(member_token) = managed.token.clone() else { continue };
+        let data = match AnalyticsService::for_account(managed_id)
+            .get_analytics(&member_token, start_timestamp, end_timestamp, true, settings.timezone.as_deref())
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|body| extract_analytics_from_body(&body, bucketing))
+        {
+            Ok(data) => data,
+            Err(e) => {
+                println!("[get_team_benchmark] Failed to fetch analytics for {}: {}", managed.email, e);
+                continue;
+            }
+        };
+        let lines = data.summary.total_accepted_lines as f64;
+        team_lines.push(lines);
+        if managed_id == uuid {
+            own_lines = Some(lines);
+        }
+    }
+    let accepted_lines = own_lines.map(|own| benchmark_metric(own, &team_lines));
+
+    Ok(TeamBenchmark {
+        account_id: uuid,
+        email: account.email,
+        team_size: members.len(),
+        prompt_credits,
+        accepted_lines,
+        generated_at: Utc::now(),
+    })
+}
+
+/// 从 GetUsers 响应中提取成员邮箱（小写）与已用 Prompt 积分
+fn extract_team_member_credits(data: &Value) -> Vec<(String, i64)> {
+    let users: Vec<&Value> = match data.get("subMesssage_1") {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(item) => vec![item],
+        None => Vec::new(),
+    };
+    users
+        .into_iter()
+        .filter_map(|user| user.as_object().map(proto_parser::parse_user_basic_info))
+        .filter(|user| !user.email.is_empty())
+        .map(|user| (user.email.to_lowercase(), user.used_prompt_credits))
+        .collect()
+}
+
+/// 计算本账号的值相对团队的平均值与百分位（相同的值按一半计入）
+fn benchmark_metric(value: f64, values: &[f64]) -> BenchmarkMetric {
+    let n = values.len().max(1) as f64;
+    let below = values.iter().filter(|v| **v < value).count() as f64;
+    let equal = values.iter().filter(|v| **v == value).count() as f64;
+    BenchmarkMetric {
+        value,
+        team_average: values.iter().sum::<f64>() / n,
+        percentile: (below + equal / 2.0) / n * 100.0,
+        sample_size: values.len(),
+    }
+}
+
+/// 由积分历史估算账号的日均消耗：累计相邻记录之间已用额度的增加量（续费重置后按重置后的已用额度计），
+/// 除以记录覆盖的天数；覆盖不足 1 天时返回 None
+fn estimate_daily_burn(history: &[CreditsSnapshot]) -> Option<f64> {
+    let points: Vec<(DateTime<Utc>, i32)> = history
+        .iter()
+        .filter_map(|s| s.used_quota.map(|used| (s.captured_at, used)))
+        .collect();
+    let (first, last) = (points.first()?, points.last()?);
+    let days = (last.0 - first.0).num_seconds() as f64 / 86400.0;
+    if days < 1.0 {
+        return None;
+    }
+    // 已用额度减少说明期间续费重置过，重置后的已用额度即为这段时间的消耗
+    let consumed: i64 = points
+        .windows(2)
+        .map(|pair| {
+            let (before, after) = (pair[0].1 as i64, pair[1].1 as i64);
+            if after >= before { after - before } else { after }
+        })
+        .sum();
+    Some(consumed as f64 / days)
+}
+
+/// 逐日模拟账号池的剩余积分：续费日（之后每 30 天）该账号的额度重置为总额度，
+/// 每天的消耗优先扣除最早续费的账号；续费日期已过（订阅已到期）的账号不再续费
+fn build_capacity_plan(
+    accounts: Vec<CapacityAccountEntry>,
+    daily_burn: f64,
+    burn_source: &str,
+    horizon_days: i64,
+    lead_days: i64,
+    today: NaiveDate,
+) -> CapacityPlan {
+    let total_remaining: i64 = accounts.iter().map(|a| a.remaining_credits).sum();
+    let mut balances: Vec<f64> = accounts.iter().map(|a| a.remaining_credits as f64).collect();
+    let renewals: Vec<Option<(NaiveDate, i64)>> = accounts
+        .iter()
+        .map(|a| a.renews_on.filter(|d| *d > today).zip(a.total_credits))
+        .collect();
+    let mut order: Vec<usize> = (0..accounts.len()).collect();
+    order.sort_by_key(|i| renewals[*i].map(|(d, _)| d).unwrap_or(NaiveDate::MAX));
+    let mut depleted_day = None;
+    let mut shortfall = 0.0;
+
+    for day in 1..=horizon_days {
+        let date = today + Duration::days(day);
+        for (balance, renewal) in balances.iter_mut().zip(&renewals) {
+            if let Some((renews_on, total)) = renewal {
+                let offset = (date - *renews_on).num_days();
+                if offset >= 0 && offset % 30 == 0 {
+                    *balance = *total as f64;
+                }
+            }
+        }
+        let mut burn = daily_burn;
+        for index in &order {
+            let take = burn.min(balances[*index]);
+            balances[*index] -= take;
+            burn -= take;
+        }
+        if burn > 0.0 {
+            depleted_day.get_or_insert(day);
+            shortfall += burn;
+        }
+    }
+
+    let depleted_on = depleted_day.map(|day| today + Duration::days(day));
+    let totals: Vec<i64> = accounts.iter().filter_map(|a| a.total_credits).filter(|t| *t > 0).collect();
+    let average_total = if totals.is_empty() { 0 } else { totals.iter().sum::<i64>() / totals.len() as i64 };
+    let shortfall_credits = shortfall.ceil() as i64;
+    let accounts_needed = if shortfall_credits > 0 && average_total > 0 {
+        ((shortfall_credits + average_total - 1) / average_total) as usize
+    } else {
+        0
+    };
+
+    CapacityPlan {
+        generated_at: Utc::now(),
+        daily_burn,
+        burn_source: burn_source.to_string(),
+        total_remaining,
+        horizon_days,
+        days_until_depleted: depleted_day,
+        depleted_on,
+        add_accounts_by: depleted_on.map(|date| (date - Duration::days(lead_days)).max(today)),
+        lead_days,
+        shortfall_credits,
+        accounts_needed,
+        accounts,
+    }
+}
+
+/// 账号池容量规划：根据日均积分消耗与各账号的剩余额度、续费日期，计算账号池可用天数与补充账号的时间
+///
+/// `daily_burn` 为空时使用积分历史估算（各账号日均消耗之和）；`horizon_days` 默认 90 天，`lead_days` 默认 3 天
+#[tauri::command]
+pub async fn get_capacity_plan(
+    daily_burn: Option<f64>,
+    horizon_days: Option<i64>,
+    lead_days: Option<i64>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<CapacityPlan, String> {
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for account in accounts.iter().filter(|a| a.is_disabled != Some(true)) {
+        let history = store.get_credits_history(account.id).await.map_err(|e| e.to_string())?;
+        entries.push(CapacityAccountEntry {
+            account_id: account.id,
+            email: account.email.clone(),
+            remaining_credits: match (account.used_quota, account.total_quota) {
+                (Some(used), Some(total)) => (total - used).max(0) as i64,
+                _ => 0,
+            },
+            total_credits: account.total_quota.map(|t| t as i64),
+            renews_on: account.subscription_expires_at.map(|t| t.date_naive()),
+            daily_burn: estimate_daily_burn(&history),
+        });
+    }
+
+    let (burn, source) = match daily_burn {
+        Some(burn) if burn >= 0.0 => (burn, "manual"),
+        Some(_) => return Err("日均消耗不能为负数".to_string()),
+        None => (entries.iter().filter_map(|e| e.daily_burn).sum(), "history"),
+    };
+
+    Ok(build_capacity_plan(
+        entries,
+        burn,
+        source,
+        horizon_days.unwrap_or(90).clamp(1, 365),
+        lead_days.unwrap_or(3).max(0),
+        Utc::now().date_naive(),
+    ))
+}
+
+/// 获取账户当前的积分用量与剩余额度（实时拉取分析数据并记录快照）
+#[tauri::command]
+pub async fn get_account_credits(
+    account_id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Option<CreditsSnapshot>, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    fetch_account_analytics(&store, uuid).await?;
+
+    let history = store.get_credits_history(uuid).await.map_err(|e| e.to_string())?;
+    Ok(history.into_iter().last())
+}
+
+/// 获取账户的积分用量历史（按时间升序），`limit` 只保留最近的 N 条
+#[tauri::command]
+pub async fn get_credits_history(
+    account_id: String,
+    limit: Option<usize>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<CreditsSnapshot>, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let mut history = store.get_credits_history(uuid).await.map_err(|e| e.to_string())?;
+    if let Some(limit) = limit {
+        let skip = history.len().saturating_sub(limit);
+        history.drain(..skip);
+    }
+    Ok(history)
+}
+
+/// 获取用量告警历史（按时间降序），`account_id` 为空时返回所有账号，`limit` 只保留最近的 N 条
+#[tauri::command]
+pub async fn alerts_history(
+    account_id: Option<String>,
+    limit: Option<usize>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<AlertEvent>, String> {
+    let uuid = account_id
+        .map(|id| Uuid::parse_str(&id).map_err(|e| e.to_string()))
+        .transpose()?;
+    let mut history = store.get_alerts_history(uuid).await.map_err(|e| e.to_string())?;
+    if let Some(limit) = limit {
+        history.truncate(limit);
+    }
+    Ok(history)
+}
+
+/// 获取账户各语言占比的历史趋势（基于已保存的分析快照）
+#[tauri::command]
+pub async fn get_language_trends(
+    account_id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<LanguageTrend>, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let snapshots = store.get_analytics_snapshots(uuid).await.map_err(|e| e.to_string())?;
+
+    Ok(build_language_trends(&snapshots))
+}
+
+/// 计算各语言在每个快照中的占比及变化趋势
+///
+/// 占比按接受行数计算；变化超过 1 个百分点视为上升/下降
+fn build_language_trends(snapshots: &[AnalyticsSnapshot]) -> Vec<LanguageTrend> {
+    let mut trends: HashMap<i32, LanguageTrend> = HashMap::new();
+
+    for snapshot in snapshots {
+        let languages = &snapshot.data.completions_by_language;
+        let total: i64 = languages.iter().map(|l| l.statistics.num_lines_accepted).sum();
+        if total <= 0 {
+            continue;
+        }
+
+        for language in languages {
+            let share = language.statistics.num_lines_accepted as f64 / total as f64 * 100.0;
+            let trend = trends.entry(language.language_id).or_insert_with(|| LanguageTrend {
+                language_id: language.language_id,
+                language_name: language.language_name.clone(),
+                points: Vec::new(),
+                first_share: 0.0,
+                latest_share: 0.0,
+                change: 0.0,
+                trend: String::new(),
+            });
+            trend.points.push(LanguageSharePoint {
+                date: snapshot.date.clone(),
+                lines_accepted: language.statistics.num_lines_accepted,
+                share,
+            });
+        }
+    }
+
+    let mut result: Vec<LanguageTrend> = trends
+        .into_values()
+        .map(|mut trend| {
+            trend.first_share = trend.points.first().map(|p| p.share).unwrap_or(0.0);
+            trend.latest_share = trend.points.last().map(|p| p.share).unwrap_or(0.0);
+            trend.change = trend.latest_share - trend.first_share;
+            trend.trend = if trend.points.len() < 2 {
+                "stable"
+            } else if trend.change > 1.0 {
+                "rising"
+            } else if trend.change < -1.0 {
+                "falling"
+            } else {
+                "stable"
+            }
+            .to_string();
+            trend
+        })
+        .collect();
+
+    // 按最新占比降序
+    result.sort_by(|a, b| b.latest_share.partial_cmp(&a.latest_share).unwrap_or(std::cmp::Ordering::Equal));
+    result
+}
+
+/// 对比最近两个时间段的各项指标，`range` 同热力图但默认 7 天（即本周 vs 上周）
+///
+/// 仅使用已保存的分析快照，不发起请求；日期按设置中的分桶方式计算
+#[tauri::command]
+pub async fn compare_analytics_periods(
+    account_id: String,
+    range: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<PeriodComparison, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+    let days = range.as_deref().map(|r| parse_range_days(Some(r))).unwrap_or(7);
+
+    let today = NaiveDate::parse_from_str(&date_utils::format_day(Utc::now().timestamp(), bucketing), "%Y-%m-%d")
+        .map_err(|e| e.to_string())?;
+    let snapshots = store.get_analytics_snapshots(uuid).await.map_err(|e| e.to_string())?;
+    Ok(AnalyticsAggregator::compare_periods(&snapshots, today, days))
+}
+
+/// 获取账户的 星期 × 小时 活跃度热力图
+///
+/// `range` 支持 "7d" / "30d" / "90d" 或天数，默认 30 天；按设置中的时区分桶
+#[tauri::command]
+pub async fn get_activity_heatmap(
+    account_id: String,
+    range: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<ActivityHeatmap, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let tz = date_utils::parse_timezone(settings.timezone.as_deref());
+    let range_days = parse_range_days(range.as_deref());
+
+    let (model_usage, completions) = load_merged_history(&store, uuid).await?;
+    let cutoff = (Utc::now() - Duration::days(range_days)).timestamp();
+
+    let mut heatmap = ActivityHeatmap {
+        timezone: tz.map(|t| t.name().to_string()).unwrap_or_else(|| "system".to_string()),
+        range_days,
+        cascade_runs: vec![vec![0; 24]; 7],
+        cascade_tokens: vec![vec![0; 24]; 7],
+        completions_by_weekday: vec![0; 7],
+        ..Default::default()
+    };
+
+    for entry in model_usage.iter().filter(|e| e.timestamp >= cutoff) {
+        if let Some((weekday, hour)) = date_utils::weekday_hour(entry.timestamp, tz) {
+            heatmap.cascade_runs[weekday][hour] += 1;
+            heatmap.cascade_tokens[weekday][hour] += entry.token_usage;
+            heatmap.total_runs += 1;
+        }
+    }
+
+    // 补全数据是按天的时间戳（当天 00:00 UTC），只取星期
+    for day in completions.iter().filter(|d| d.timestamp >= cutoff) {
+        if let Some(weekday) = DateTime::from_timestamp(day.timestamp, 0)
+            .map(|dt| dt.weekday().num_days_from_monday() as usize)
+        {
+            heatmap.completions_by_weekday[weekday] += day.statistics.num_acceptances;
+        }
+    }
+
+    let mut peak = 0;
+    for (weekday, hours) in heatmap.cascade_runs.iter().enumerate() {
+        for (hour, count) in hours.iter().enumerate() {
+            if *count > peak {
+                peak = *count;
+                heatmap.peak_weekday = Some(weekday);
+                heatmap.peak_hour = Some(hour);
+            }
+        }
+    }
+
+    Ok(heatmap)
+}
+
+/// 解析时间范围（"7d" / "30d" / "90d" / 纯数字），默认 30 天
+fn parse_range_days(range: Option<&str>) -> i64 {
+    range
+        .map(|r| r.trim().trim_end_matches('d'))
+        .and_then(|r| r.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(30)
+}
+
+/// 合并账户所有快照中的明细数据：Cascade 运行按会话去重，补全按日期取最新快照
+///
+/// 没有任何快照时实时拉取一次
+async fn load_merged_history(
+    store: &Arc<DataStore>,
+    uuid: Uuid,
+) -> Result<(Vec<ModelUsageEntry>, Vec<CompletionByDay>), String> {
+    let mut snapshots = store.get_analytics_snapshots(uuid).await.map_err(|e| e.to_string())?;
+    if snapshots.is_empty() {
+        fetch_account_analytics(store, uuid).await?;
+        snapshots = store.get_analytics_snapshots(uuid).await.map_err(|e| e.to_string())?;
+    }
+
+    let runs = AnalyticsAggregator::merge_runs(&snapshots);
+    let mut completions: HashMap<String, CompletionByDay> = HashMap::new();
+    for snapshot in snapshots {
+        for day in snapshot.data.completions_by_day {
+            completions.insert(day.date.clone(), day);
+        }
+    }
+
+    let mut completions: Vec<CompletionByDay> = completions.into_values().collect();
+    completions.sort_by_key(|d| d.timestamp);
+
+    Ok((runs, completions))
+}
+
+/// 获取已归档的月度报告列表
+#[tauri::command]
+pub async fn list_monthly_reports(
+    account_id: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<MonthlyReportInfo>, String> {
+    let uuid = match account_id {
+        Some(id) => Some(Uuid::parse_str(&id).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let reports = store.list_monthly_reports(uuid).await.map_err(|e| e.to_string())?;
+    Ok(reports
+        .into_iter()
+        .map(|r| MonthlyReportInfo {
+            account_id: r.account_id,
+            email: r.email,
+            month: r.month,
+            generated_at: r.generated_at,
+            summary: r.summary,
+        })
+        .collect())
+}
+
+/// 获取指定账户某个月份的月度报告
+#[tauri::command]
+pub async fn get_monthly_report(
+    account_id: String,
+    month: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<MonthlyReport, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    store.get_monthly_report(uuid, &month).await.map_err(|e| e.to_string())
+}
+
+/// 生成分析数据的纯文本摘要（便于读屏软件朗读或粘贴到聊天中）
+///
+/// `lang` 为 "en" 时输出英文，其余情况输出中文
+#[tauri::command]
+pub async fn get_analytics_text_summary(
+    account_id: String,
+    lang: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<String, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let (account, analytics_data) = fetch_account_analytics(&store, uuid).await?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+
+    let display_name = if settings.privacy_mode {
+        share_service::mask_email(&account.email)
+    } else {
+        account.email.clone()
+    };
+    let english = lang.as_deref().map(|l| l.eq_ignore_ascii_case("en")).unwrap_or(false);
+
+    Ok(build_text_summary(&display_name, &analytics_data, english))
+}
+
+/// 生成使用洞察（需在设置中启用并配置 OpenAI 兼容接口）
+///
+/// 只发送匿名的聚合指标，不包含邮箱、Token 等账号信息
+#[tauri::command]
+pub async fn generate_usage_insights(
+    account_id: String,
+    lang: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<String, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    if !settings.insights.enabled {
+        return Err("未启用使用洞察".to_string());
+    }
+
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let (_, analytics_data) = fetch_account_analytics(&store, uuid).await?;
+    let metrics = insights_service::build_anonymized_metrics(&analytics_data);
+    let english = lang.as_deref().map(|l| l.eq_ignore_ascii_case("en")).unwrap_or(false);
+
+    insights_service::generate_insights(&settings.insights, &metrics, english)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 组装文本摘要
+fn build_text_summary(display_name: &str, data: &AnalyticsData, english: bool) -> String {
+    let summary = &data.summary;
+    let cascade_lines = summary.total_accepted_lines;
+    let completion_lines = data.completion_stats.num_lines_accepted;
+    let total_lines = cascade_lines + completion_lines;
+    let cascade_share = if total_lines > 0 {
+        cascade_lines as f64 / total_lines as f64 * 100.0
+    } else {
+        0.0
+    };
+    let top_language = data
+        .completions_by_language
+        .iter()
+        .max_by_key(|l| l.statistics.num_lines_accepted)
+        .map(|l| l.language_name.clone())
+        .unwrap_or_default();
+    let days = data.daily_cascade_lines.len().max(data.completions_by_day.len());
+
+    let mut sentences = Vec::new();
+    if english {
+        sentences.push(format!(
+            "Usage summary for {} over the last {} days.",
+            display_name, days
+        ));
+        sentences.push(format!(
+            "You accepted {} lines, {:.0}% via Cascade and {:.0}% via autocomplete.",
+            format_thousands(total_lines as f64),
+            cascade_share,
+            if total_lines > 0 { 100.0 - cascade_share } else { 0.0 }
+        ));
+        if data.completion_stats.num_acceptances + data.completion_stats.num_rejections > 0 {
+            sentences.push(format!(
+                "Your completion acceptance rate was {:.0}%.",
+                data.completion_stats.acceptance_rate
+            ));
+        }
+        if !summary.primary_model.is_empty() {
+            sentences.push(format!(
+                "Top model was {} across {} Cascade sessions, using {} tokens.",
+                summary.primary_model,
+                format_thousands(summary.total_sessions as f64),
+                format_thousands(summary.total_tokens as f64)
+            ));
+        }
+        if !top_language.is_empty() {
+            sentences.push(format!("Most accepted code was written in {}.", top_language));
+        }
+        if summary.peak_lines > 0 {
+            sentences.push(format!(
+                "Your busiest day was {} with {} accepted lines.",
+                summary.peak_date,
+                format_thousands(summary.peak_lines as f64)
+            ));
+        }
+    } else {
+        sentences.push(format!("{} 最近 {} 天的使用摘要。", display_name, days));
+        sentences.push(format!(
+            "共接受 {} 行代码，其中 {:.0}% 来自 Cascade，{:.0}% 来自自动补全。",
+            format_thousands(total_lines as f64),
+            cascade_share,
+            if total_lines > 0 { 100.0 - cascade_share } else { 0.0 }
+        ));
+        if data.completion_stats.num_acceptances + data.completion_stats.num_rejections > 0 {
+            sentences.push(format!("补全接受率为 {:.0}%。", data.completion_stats.acceptance_rate));
+        }
+        if !summary.primary_model.is_empty() {
+            sentences.push(format!(
+                "最常用的模型是 {}，共 {} 次 Cascade 会话，消耗 {} Token。",
+                summary.primary_model,
+                format_thousands(summary.total_sessions as f64),
+                format_thousands(summary.total_tokens as f64)
+            ));
+        }
+        if !top_language.is_empty() {
+            sentences.push(format!("接受代码最多的语言是 {}。", top_language));
+        }
+        if summary.peak_lines > 0 {
+            sentences.push(format!(
+                "使用最多的一天是 {}，接受了 {} 行代码。",
+                summary.peak_date,
+                format_thousands(summary.peak_lines as f64)
+            ));
+        }
+    }
+
+    sentences.join(if english { " " } else { "" })
+}
+
+/// 生成单个账户的只读分享报告（自包含静态 HTML）
+///
+/// 未指定 `output_path` 时保存到应用数据目录的 shared_reports 下；
+/// `upload` 为 true 时按设置上传到 WebDAV/S3，并返回访问地址
+#[tauri::command]
+pub async fn generate_share_report(
+    account_id: String,
+    upload: Option<bool>,
+    output_path: Option<String>,
+    app: AppHandle,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let uuid = Uuid::parse_str(&account_id).map_err(|e| e.to_string())?;
+    let (account, analytics_data) = fetch_account_analytics(&store, uuid).await?;
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+
+    // 隐私模式下隐藏邮箱
+    let display_name = if settings.privacy_mode {
+        share_service::mask_email(&account.email)
+    } else {
+        account.email.clone()
+    };
+    let html = share_service::render_html_report(&display_name, account.plan_name.as_deref(), &analytics_data);
+
+    // 文件名使用随机 ID，避免上传后通过邮箱猜测地址
+    let file_name = format!("report_{}.html", Uuid::new_v4().simple());
+    let path = match output_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let dir = app
+                .path()
+                .app_data_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join("shared_reports");
+            std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+            dir.join(&file_name)
+        }
+    };
+    std::fs::write(&path, &html).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let url = if upload.unwrap_or(false) {
+        let url = share_service::upload_report(&settings.share_upload, &file_name, &html)
+            .await
+            .map_err(|e| e.to_string())?;
+        if url.is_none() {
+            return Err("未配置分享报告上传目标".to_string());
+        }
+        println!("[generate_share_report] Uploaded report for {}", account.email);
+        url
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "success": true,
+        "path": path.to_string_lossy(),
+        "url": url,
+    }))
+}
+
+/// 从项目仓库下载最新的语言/模型/工具映射表，核对校验值（仅发现下载损坏）后立即生效
+///
+/// `url` 为空时使用设置中的地址
+#[tauri::command]
+pub async fn refresh_mappings(
+    url: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<mapping_service::MappingRefreshResult, String> {
+    let url = match url {
+        Some(url) if !url.trim().is_empty() => url.trim().to_string(),
+        _ => store.get_settings().await.map_err(|e| e.to_string())?.mappings_url,
+    };
+
+    mapping_service::refresh_mappings(&store.get_data_dir(), &url)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 解析 GetAnalytics 原始响应体并提取分析数据
+fn extract_analytics_from_body(body: &[u8], bucketing: DayBucketing) -> Result<AnalyticsData, String> {
+    let parsed_response = proto_parser::parse_get_analytics_response(body)?;
+    extract_analytics_data(&parsed_response, bucketing)
+}
+
+/// 是否允许调试命令：调试构建或开启了开发者模式
+pub(crate) async fn is_dev_mode(store: &Arc<DataStore>) -> bool {
+    cfg!(debug_assertions) || store.get_settings().await.map(|s| s.dev_mode).unwrap_or(false)
+}
+
+/// 对样例目录中的原始响应重新运行抽取，并与期望结果比较（开发者模式）
+///
+/// `dir` 为空时使用仓库内的 tests/fixtures/analytics
+#[tauri::command]
+pub async fn run_analytics_fixtures(
+    dir: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<FixtureResult>, String> {
+    if !is_dev_mode(&store).await {
+        return Err("需要开启开发者模式".to_string());
+    }
+
+    let dir = dir.map(PathBuf::from).unwrap_or_else(fixtures::default_fixture_dir);
+    run_fixtures_in(&dir)
+}
+
+fn run_fixtures_in(dir: &std::path::Path) -> Result<Vec<FixtureResult>, String> {
+    let loaded = fixtures::load_fixtures(dir).map_err(|e| format!("Failed to load fixtures: {}", e))?;
+
+    Ok(loaded
+        .into_iter()
+        .map(|fixture| match extract_analytics_from_body(&fixture.body, DayBucketing::Utc) {
+            Ok(data) => {
+                let actual = serde_json::to_value(&data).unwrap_or(Value::Null);
+                let diffs = fixtures::json_subset_diff(&fixture.expected, &actual);
+                FixtureResult {
+                    name: fixture.name,
+                    passed: diffs.is_empty(),
+                    diffs,
+                    error: None,
+                }
+            }
+            Err(e) => FixtureResult {
+                name: fixture.name,
+                passed: false,
+                diffs: Vec::new(),
+                error: Some(e),
+            },
+        })
+        .collect())
+}
+
+/// 抓取账户当前的 GetAnalytics 原始响应，保存为回归样例（开发者模式）
+///
+/// 同时写入当前抽取结果作为期望值；提交前请检查并删除不需要比较的字段
+#[tauri::command]
+pub async fn capture_analytics_fixture(
+    id: String,
+    name: String,
+    dir: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<String, String> {
+    if !is_dev_mode(&store).await {
+        return Err("需要开启开发者模式".to_string());
+    }
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err("样例名称只能包含字母、数字、下划线和连字符".to_string());
+    }
+
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    super::api_commands::ensure_valid_token(&store, &mut account, uuid).await?;
+    let token = account.token.clone().ok_or("No token available")?;
+
+    let end_time = Utc::now();
+    let start_time = end_time - Duration::days(30);
+    let body = AnalyticsService::for_account(uuid)
+        .get_analytics(&token, start_time.timestamp(), end_time.timestamp(), false, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut data = extract_analytics_from_body(&body, DayBucketing::Utc)?;
+    data.timings = None;
+
+    let dir = dir.map(PathBuf::from).unwrap_or_else(fixtures::default_fixture_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    std::fs::write(dir.join(format!("{}{}", name, fixtures::BODY_SUFFIX)), &body)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    let expected = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{}{}", name, fixtures::EXPECTED_SUFFIX)), expected)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    println!("[capture_analytics_fixture] Saved fixture {} to {}", name, dir.display());
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// 定时任务入口：用当前的抽取逻辑重新处理保存了原始响应的旧快照，让历史数据也能用上新映射的字段
+pub(crate) async fn reprocess_snapshots(store: &Arc<DataStore>) -> Result<String, String> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let bucketing = match store.get_settings().await {
+        Ok(settings) => DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref()),
+        Err(_) => DayBucketing::Utc,
+    };
+
+    // 解码与抽取不持有快照锁，避免阻塞读取和正在进行的拉取
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for snapshot in store.outdated_snapshots(ANALYTICS_EXTRACTOR_VERSION).await {
+        let result = general_purpose::STANDARD
+            .decode(&snapshot.raw_body)
+            .map_err(|e| e.to_string())
+            .and_then(|body| extract_analytics_from_body(&body, bucketing));
+        match result {
+            Ok(mut data) => {
+                if !data.has_activity() {
+                    data.is_empty_because = Some(EmptyReason::NoActivity);
+                }
+                results.push((snapshot, data));
+            }
+            Err(e) => {
+                println!("[reprocess_snapshots] Failed to reprocess {} {}: {}", snapshot.account_id, snapshot.date, e);
+                failed += 1;
+            }
+        }
+    }
+    let updated = store.apply_reprocessed_snapshots(results, ANALYTICS_EXTRACTOR_VERSION).await;
+
+    if updated > 0 {
+        store.save_snapshots().await.map_err(|e| e.to_string())?;
+    }
+    Ok(format!("重新处理 {} 份快照，失败 {} 份", updated, failed))
+}
+
+/// 取出 repeated 字段（subMesssage_1 / repeated_1）的全部元素
+///
+/// 只有一个元素时解析器输出的是对象而不是数组，这里统一为列表
+fn repeated_entries(data: &Value) -> Option<Cow<'_, [Value]>> {
+    match data.get("subMesssage_1").or_else(|| data.get("repeated_1"))? {
+        Value::Array(items) => Some(Cow::Borrowed(items.as_slice())),
+        item @ Value::Object(_) => Some(Cow::Owned(vec![item.clone()])),
+        _ => None,
+    }
 }
 
 /// 从解析后的 Protobuf 数据中提取分析数据
-fn extract_analytics_data(parsed: &Value) -> Result<AnalyticsData, String> {
-    println!("[extract_analytics_data] Starting data extraction");
-    println!("[extract_analytics_data] Parsed data keys: {:?}", parsed.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+fn extract_analytics_data(parsed: &Value, bucketing: DayBucketing) -> Result<AnalyticsData, String> {
+    crate::proto_log!("[extract_analytics_data] Starting data extraction");
+    crate::proto_log!("[extract_analytics_data] Parsed data keys: {:?}", parsed.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
     let raw_data = parsed.get("raw_data")
         .or_else(|| parsed.get("parsed_data"))
         .ok_or("No data found in response")?;
 
-    println!("[extract_analytics_data] Raw data keys: {:?}", raw_data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+    crate::proto_log!("[extract_analytics_data] Raw data keys: {:?}", raw_data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
     // GetAnalyticsResponse 包含 repeated QueryResult (field 1)
     // 实际数据结构是 subMesssage_1 而不是 repeated_1
-    let query_results = raw_data.get("subMesssage_1")
-        .or_else(|| raw_data.get("repeated_1"))
-        .and_then(|v| v.as_array())
+    let query_results = repeated_entries(raw_data)
         .ok_or("No query results found")?;
 
-    println!("[extract_analytics_data] Found {} query results", query_results.len());
+    crate::proto_log!("[extract_analytics_data] Found {} query results", query_results.len());
 
     // 原有字段
     let mut daily_cascade_lines = Vec::new();
@@ -227,110 +1614,113 @@ fn extract_analytics_data(parsed: &Value) -> Result<AnalyticsData, String> {
     let mut chats_by_day = Vec::new();
     let mut chats_by_model = Vec::new();
     let mut custom_query_results = CustomQueryResponse::default();
+    let mut credits_usage = CreditsUsage::default();
+    let mut cascade_stats = CascadeStats::default();
 
     // 遍历查询结果
     for (index, result) in query_results.iter().enumerate() {
-        println!("[extract_analytics_data] Processing result {}: {:?}", index, result.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+        crate::proto_log!("[extract_analytics_data] Processing result {}: {:?}", index, result.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
         // ===== 原有字段 =====
         
         // 检查 Cascade 代码行数统计 (Field 18: cascade_lines)
         if let Some(cascade_lines_data) = result.get("subMesssage_18") {
-            println!("[extract_analytics_data] Found cascade_lines data (Field 18)");
-            daily_cascade_lines = extract_cascade_lines(cascade_lines_data)?;
-            println!("[extract_analytics_data] Extracted {} cascade lines entries", daily_cascade_lines.len());
+            crate::proto_log!("[extract_analytics_data] Found cascade_lines data (Field 18)");
+            daily_cascade_lines = extract_cascade_lines(cascade_lines_data, bucketing)?;
+            crate::proto_log!("[extract_analytics_data] Extracted {} cascade lines entries", daily_cascade_lines.len());
         }
 
         // 检查工具使用统计 (Field 19: cascade_tool_usage)
         if let Some(tool_data) = result.get("subMesssage_19") {
-            println!("[extract_analytics_data] Found cascade_tool_usage data (Field 19)");
+            crate::proto_log!("[extract_analytics_data] Found cascade_tool_usage data (Field 19)");
             tool_usage = extract_tool_usage(tool_data)?;
-            println!("[extract_analytics_data] Extracted {} tool usage entries", tool_usage.len());
+            crate::proto_log!("[extract_analytics_data] Extracted {} tool usage entries", tool_usage.len());
         }
 
         // 检查模型运行记录 (Field 20: cascade_runs)
         if let Some(model_data) = result.get("subMesssage_20") {
-            println!("[extract_analytics_data] Found cascade_runs data (Field 20)");
+            crate::proto_log!("[extract_analytics_data] Found cascade_runs data (Field 20)");
             model_usage_details = extract_model_usage(model_data)?;
-            println!("[extract_analytics_data] Extracted {} model usage entries", model_usage_details.len());
+            crate::proto_log!("[extract_analytics_data] Extracted {} model usage entries", model_usage_details.len());
         }
         
         // ===== 新增字段 =====
         
         // 检查代码贡献百分比 (Field 9: percent_code_written)
         if let Some(pcw_data) = result.get("subMesssage_9") {
-            println!("[extract_analytics_data] Found percent_code_written data (Field 9)");
+            crate::proto_log!("[extract_analytics_data] Found percent_code_written data (Field 9)");
             percent_code_written = extract_percent_code_written(pcw_data)?;
         }
         
         // 检查补全统计 (Field 1: completion_stats)
         if let Some(cs_data) = result.get("subMesssage_1") {
-            println!("[extract_analytics_data] Found completion_stats data (Field 1)");
+            crate::proto_log!("[extract_analytics_data] Found completion_stats data (Field 1)");
             completion_stats = extract_completion_stats(cs_data)?;
         }
         
         // 检查按日期的补全统计 (Field 2: completions_by_day)
         if let Some(cbd_data) = result.get("subMesssage_2") {
-            println!("[extract_analytics_data] Found completions_by_day data (Field 2)");
-            completions_by_day = extract_completions_by_day(cbd_data)?;
+            crate::proto_log!("[extract_analytics_data] Found completions_by_day data (Field 2)");
+            completions_by_day = extract_completions_by_day(cbd_data, bucketing)?;
         }
         
         // 检查按语言的补全统计 (Field 3: completions_by_language)
         if let Some(cbl_data) = result.get("subMesssage_3") {
-            println!("[extract_analytics_data] Found completions_by_language data (Field 3)");
+            crate::proto_log!("[extract_analytics_data] Found completions_by_language data (Field 3)");
             completions_by_language = extract_completions_by_language(cbl_data)?;
         }
         
         // 检查 Chat 统计 (Field 11: chat_stats)
         if let Some(chat_data) = result.get("subMesssage_11") {
-            println!("[extract_analytics_data] Found chat_stats data (Field 11)");
+            crate::proto_log!("[extract_analytics_data] Found chat_stats data (Field 11)");
             chat_stats = extract_chat_stats(chat_data)?;
         }
         
         // 检查按日期的 Chat 统计 (Field 6: chats_by_day)
         if let Some(chats_day_data) = result.get("subMesssage_6") {
-            println!("[extract_analytics_data] Found chats_by_day data (Field 6)");
-            chats_by_day = extract_chats_by_day(chats_day_data)?;
+            crate::proto_log!("[extract_analytics_data] Found chats_by_day data (Field 6)");
+            chats_by_day = extract_chats_by_day(chats_day_data, bucketing)?;
         }
         
         // 检查按模型的 Chat 统计 (Field 7: chats_by_model)
         if let Some(chats_model_data) = result.get("subMesssage_7") {
-            println!("[extract_analytics_data] Found chats_by_model data (Field 7)");
+            crate::proto_log!("[extract_analytics_data] Found chats_by_model data (Field 7)");
             chats_by_model = extract_chats_by_model(chats_model_data)?;
         }
         
         // 检查自定义查询结果 (Field 16: custom_stats)
         if let Some(custom_data) = result.get("subMesssage_16") {
-            println!("[extract_analytics_data] Found custom_stats data (Field 16)");
+            crate::proto_log!("[extract_analytics_data] Found custom_stats data (Field 16)");
             custom_query_results = extract_custom_query_response(custom_data)?;
         }
 
-        // ===== 新增字段调试 - 打印所有字段以便分析 =====
-        // cascade_stats (QueryRequest field 20) 对应的响应字段
+        // 检查 Cascade 统计 (Field 15: cascade_stats，对应 QueryRequest field 20)
         if let Some(stats_data) = result.get("subMesssage_15") {
-            println!("[extract_analytics_data] Found subMesssage_15 (cascade_stats?): {:?}", stats_data);
+            crate::proto_log!("[extract_analytics_data] Found cascade_stats data (Field 15)");
+            cascade_stats = extract_cascade_stats(stats_data, bucketing);
         }
-        // cascade_summary (QueryRequest field 31) 对应的响应字段
+        // 积分与会话用量 (Field 26: cascade_summary，对应 QueryRequest field 31)
         if let Some(summary_data) = result.get("subMesssage_26") {
-            println!("[extract_analytics_data] Found subMesssage_26 (cascade_summary?): {:?}", summary_data);
+            crate::proto_log!("[extract_analytics_data] Found cascade_summary data (Field 26)");
+            credits_usage = extract_credits_usage(summary_data);
         }
         // 打印所有未处理的字段
         if let Some(obj) = result.as_object() {
             for (key, value) in obj.iter() {
                 if !["subMesssage_1", "subMesssage_2", "subMesssage_3", "subMesssage_6", "subMesssage_7", 
-                     "subMesssage_9", "subMesssage_11", "subMesssage_16", "subMesssage_18", "subMesssage_19", 
-                     "subMesssage_20"].contains(&key.as_str()) {
-                    println!("[extract_analytics_data] UNHANDLED field {}: {:?}", key, value);
+                     "subMesssage_9", "subMesssage_11", "subMesssage_15", "subMesssage_16", "subMesssage_18",
+                     "subMesssage_19", "subMesssage_20", "subMesssage_26"].contains(&key.as_str()) {
+                    crate::proto_log!("[extract_analytics_data] UNHANDLED field {}: {:?}", key, value);
                 }
             }
         }
     }
 
     // 计算模型使用汇总
-    let model_usage_summary = calculate_model_summary(&model_usage_details);
+    let model_usage_summary = AnalyticsAggregator::model_summary(&model_usage_details);
 
     // 计算总体统计
-    let summary = calculate_summary(&daily_cascade_lines, &tool_usage, &model_usage_details, &model_usage_summary);
+    let summary = AnalyticsAggregator::summary(&daily_cascade_lines, &tool_usage, &model_usage_details, &model_usage_summary, bucketing);
 
     Ok(AnalyticsData {
         daily_cascade_lines,
@@ -347,14 +1737,79 @@ fn extract_analytics_data(parsed: &Value) -> Result<AnalyticsData, String> {
         chats_by_day,
         chats_by_model,
         custom_query_results,
+        credits_usage,
+        cascade_stats,
+        timings: None,
+        is_empty_because: None,
+    })
+}
+
+/// 提取积分与会话用量 (Field 26: cascade_summary)
+///
+/// 字段顺序与官网一致：conversations, credits, commands, workflows, memories, terminal messages
+fn extract_credits_usage(data: &Value) -> CreditsUsage {
+    crate::proto_log!("[extract_credits_usage] Data: {:?}", data);
+
+    // 与 chat_stats 相同，统计值可能嵌套在 subMesssage_1 中
+    let stats = data.get("subMesssage_1").unwrap_or(data);
+    let int = |field: &str| stats.get(field).and_then(|v| v.as_i64()).unwrap_or(0);
+
+    CreditsUsage {
+        conversations: int("int_1"),
+        prompt_credits_used: int("int_2"),
+        commands: int("int_3"),
+        workflows: int("int_4"),
+        memories: int("int_5"),
+        terminal_messages: int("int_6"),
+    }
+}
+
+/// 提取 Cascade 统计 (Field 15: cascade_stats)
+///
+/// 数据结构（回归样例见 tests/fixtures/analytics/cascade_stats）：
+/// {
+///   "int_1": messages_sent (发送的消息数),
+///   "int_2": active_days (活跃天数),
+///   "subMesssage_3": [ { "subMesssage_1": { "int_1": timestamp }, "int_2": messages_sent } ]
+/// }
+/// 数值为 0 时 Protobuf 不编码该字段，缺失的字段按 0 处理
+fn extract_cascade_stats(data: &Value, bucketing: DayBucketing) -> CascadeStats {
+    crate::proto_log!("[extract_cascade_stats] Data: {:?}", data);
+
+    let int = |value: &Value, field: &str| value.get(field).and_then(|v| v.as_i64()).unwrap_or(0);
+    let by_day: Vec<CascadeStatsByDay> = match data.get("subMesssage_3") {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(item @ Value::Object(_)) => vec![item],
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .map(|entry| {
+        let timestamp = entry.get("subMesssage_1").map(|t| int(t, "int_1")).unwrap_or(0);
+        CascadeStatsByDay {
+            timestamp,
+            date: date_utils::format_day(timestamp, bucketing),
+            messages_sent: int(entry, "int_2"),
+        }
     })
+    .collect();
+
+    let mut messages_sent = int(data, "int_1");
+    if messages_sent == 0 {
+        messages_sent = by_day.iter().map(|d| d.messages_sent).sum();
+    }
+    let mut active_days = int(data, "int_2");
+    if active_days == 0 {
+        active_days = by_day.iter().filter(|d| d.messages_sent > 0).count() as i64;
+    }
+
+    CascadeStats { messages_sent, active_days, by_day }
 }
 
 /// 提取每日 Cascade 代码行数统计 (Field 18: cascade_lines)
-fn extract_cascade_lines(data: &Value) -> Result<Vec<DailyCascadeLinesCount>, String> {
+fn extract_cascade_lines(data: &Value, bucketing: DayBucketing) -> Result<Vec<DailyCascadeLinesCount>, String> {
     let mut lines_stats = Vec::new();
 
-    println!("[extract_cascade_lines] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+    crate::proto_log!("[extract_cascade_lines] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
     // 实际数据结构：subMesssage_18.subMesssage_1 是一个数组，包含多天的数据
     // 每个元素的结构：
@@ -363,12 +1818,10 @@ fn extract_cascade_lines(data: &Value) -> Result<Vec<DailyCascadeLinesCount>, St
     //   "int_2": accepted_lines (接受的代码行数),
     //   "int_3": suggested_lines (建议的代码行数)
     // }
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
 
     if let Some(repeated) = repeated {
-        println!("[extract_cascade_lines] Found array with {} entries", repeated.len());
+        crate::proto_log!("[extract_cascade_lines] Found array with {} entries", repeated.len());
 
         for (index, entry) in repeated.iter().enumerate() {
             // 从 subMesssage_1.int_1 获取时间戳
@@ -383,17 +1836,11 @@ fn extract_cascade_lines(data: &Value) -> Result<Vec<DailyCascadeLinesCount>, St
             // 从 int_3 获取接受的代码行数 (lines_accepted = 3)
             let accepted_lines = entry.get("int_3").and_then(|v| v.as_i64()).unwrap_or(0);
 
-            println!("[extract_cascade_lines] Entry {}: timestamp={}, accepted={}, suggested={}",
+            crate::proto_log!("[extract_cascade_lines] Entry {}: timestamp={}, accepted={}, suggested={}",
                      index, timestamp, accepted_lines, suggested_lines);
 
             // 转换时间戳为日期字符串
-            let date = if timestamp > 0 {
-                DateTime::from_timestamp(timestamp, 0)
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_else(|| "Unknown".to_string())
-            } else {
-                "Unknown".to_string()
-            };
+            let date = date_utils::format_day(timestamp, bucketing);
 
             lines_stats.push(DailyCascadeLinesCount {
                 timestamp,
@@ -403,10 +1850,10 @@ fn extract_cascade_lines(data: &Value) -> Result<Vec<DailyCascadeLinesCount>, St
             });
         }
     } else {
-        println!("[extract_cascade_lines] No array found in subMesssage_1 or repeated_1");
+        crate::proto_log!("[extract_cascade_lines] No array found in subMesssage_1 or repeated_1");
     }
 
-    println!("[extract_cascade_lines] Returning {} lines stats entries", lines_stats.len());
+    crate::proto_log!("[extract_cascade_lines] Returning {} lines stats entries", lines_stats.len());
     Ok(lines_stats)
 }
 
@@ -415,26 +1862,25 @@ fn extract_tool_usage(data: &Value) -> Result<Vec<ToolUsageEntry>, String> {
     let mut usage = Vec::new();
     let mut total_count = 0i64;
 
-    println!("[extract_tool_usage] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+    crate::proto_log!("[extract_tool_usage] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
     // 实际数据结构：subMesssage_19.subMesssage_1 是一个数组
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
 
     if let Some(repeated) = repeated {
-        println!("[extract_tool_usage] Found {} tool entries", repeated.len());
+        crate::proto_log!("[extract_tool_usage] Found {} tool entries", repeated.len());
 
-        for entry in repeated {
-            let tool_name = entry.get("string_2")
+        for entry in repeated.iter() {
+            let raw_name = entry.get("string_2")
                 .and_then(|v| v.as_str())
-                .unwrap_or("Unknown")
-                .to_string();
+                .unwrap_or("Unknown");
+            let tool_name = mapping_service::tool_name(raw_name)
+                .unwrap_or_else(|| raw_name.to_string());
 
             let count = entry.get("int_3").and_then(|v| v.as_i64()).unwrap_or(0);
             total_count += count;
 
-            println!("[extract_tool_usage] Tool: {}, Count: {}", tool_name, count);
+            crate::proto_log!("[extract_tool_usage] Tool: {}, Count: {}", tool_name, count);
 
             usage.push(ToolUsageEntry {
                 tool_name,
@@ -456,7 +1902,7 @@ fn extract_tool_usage(data: &Value) -> Result<Vec<ToolUsageEntry>, String> {
     // 按使用次数降序排序
     usage.sort_by(|a, b| b.count.cmp(&a.count));
 
-    println!("[extract_tool_usage] Returning {} tool usage entries", usage.len());
+    crate::proto_log!("[extract_tool_usage] Returning {} tool usage entries", usage.len());
     Ok(usage)
 }
 
@@ -464,17 +1910,15 @@ fn extract_tool_usage(data: &Value) -> Result<Vec<ToolUsageEntry>, String> {
 fn extract_model_usage(data: &Value) -> Result<Vec<ModelUsageEntry>, String> {
     let mut usage = Vec::new();
 
-    println!("[extract_model_usage] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+    crate::proto_log!("[extract_model_usage] Data structure: {:?}", data.as_object().map(|o| o.keys().collect::<Vec<_>>()));
 
     // 实际数据结构：subMesssage_20.subMesssage_1 是一个数组
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
 
     if let Some(repeated) = repeated {
-        println!("[extract_model_usage] Found {} model entries", repeated.len());
+        crate::proto_log!("[extract_model_usage] Found {} model entries", repeated.len());
 
-        for entry in repeated {
+        for entry in repeated.iter() {
             let timestamp = entry.get("subMesssage_1")
                 .and_then(|v| v.get("int_1"))
                 .and_then(|v| v.as_i64())
@@ -506,7 +1950,7 @@ fn extract_model_usage(data: &Value) -> Result<Vec<ModelUsageEntry>, String> {
                 .unwrap_or("")
                 .to_string();
 
-            println!("[extract_model_usage] Model: {}, Sessions: {}, Tokens: {}", model_name, session_count, token_usage);
+            crate::proto_log!("[extract_model_usage] Model: {}, Sessions: {}, Tokens: {}", model_name, session_count, token_usage);
 
             usage.push(ModelUsageEntry {
                 timestamp,
@@ -520,98 +1964,10 @@ fn extract_model_usage(data: &Value) -> Result<Vec<ModelUsageEntry>, String> {
         }
     }
 
-    println!("[extract_model_usage] Returning {} model usage entries", usage.len());
+    crate::proto_log!("[extract_model_usage] Returning {} model usage entries", usage.len());
     Ok(usage)
 }
 
-/// 计算模型使用汇总
-fn calculate_model_summary(details: &[ModelUsageEntry]) -> Vec<ModelUsageSummary> {
-    let mut summary_map: HashMap<String, (i64, i64)> = HashMap::new();
-
-    for entry in details {
-        let counter = summary_map.entry(entry.model_name.clone()).or_insert((0, 0));
-        counter.0 += entry.session_count;
-        counter.1 += entry.token_usage;
-    }
-
-    let total_count: i64 = summary_map.values().map(|(count, _)| count).sum();
-
-    let mut summary: Vec<ModelUsageSummary> = summary_map
-        .into_iter()
-        .map(|(model_name, (total_count_val, total_tokens))| {
-            let percentage = if total_count > 0 {
-                (total_count_val as f64 / total_count as f64) * 100.0
-            } else {
-                0.0
-            };
-
-            ModelUsageSummary {
-                model_name,
-                total_count: total_count_val,
-                total_tokens,
-                percentage,
-            }
-        })
-        .collect();
-
-    // 按使用次数降序排序
-    summary.sort_by(|a, b| b.total_count.cmp(&a.total_count));
-
-    summary
-}
-
-/// 计算总体统计摘要
-fn calculate_summary(
-    cascade_lines: &[DailyCascadeLinesCount],
-    tool_usage: &[ToolUsageEntry],
-    model_details: &[ModelUsageEntry],
-    model_summary: &[ModelUsageSummary],
-) -> AnalyticsSummary {
-    // 计算总代码行数
-    let total_accepted_lines: i64 = cascade_lines.iter().map(|s| s.accepted_lines).sum();
-    let total_suggested_lines: i64 = cascade_lines.iter().map(|s| s.suggested_lines).sum();
-
-    let avg_daily_accepted_lines = if !cascade_lines.is_empty() {
-        total_accepted_lines as f64 / cascade_lines.len() as f64
-    } else {
-        0.0
-    };
-
-    // 找到峰值日期（接受代码行数最多的一天）
-    let (peak_date, peak_lines) = cascade_lines
-        .iter()
-        .max_by_key(|s| s.accepted_lines)
-        .map(|s| (s.date.clone(), s.accepted_lines))
-        .unwrap_or_else(|| (String::new(), 0));
-
-    let total_tool_usage: i64 = tool_usage.iter().map(|t| t.count).sum();
-    let total_sessions: i64 = model_details.iter().map(|m| m.session_count).sum();
-    let total_tokens: i64 = model_details.iter().map(|m| m.token_usage).sum();
-
-    let primary_model = model_summary
-        .first()
-        .map(|m| m.model_name.clone())
-        .unwrap_or_else(|| String::from("N/A"));
-
-    let primary_tool = tool_usage
-        .first()
-        .map(|t| t.tool_name.clone())
-        .unwrap_or_else(|| String::from("N/A"));
-
-    AnalyticsSummary {
-        total_accepted_lines,
-        total_suggested_lines,
-        avg_daily_accepted_lines,
-        peak_date,
-        peak_lines,
-        total_tool_usage,
-        total_sessions,
-        total_tokens,
-        primary_model,
-        primary_tool,
-    }
-}
-
 // ===== 新增提取函数 =====
 
 /// 提取代码贡献百分比 (Field 9: percent_code_written)
@@ -623,7 +1979,7 @@ fn calculate_summary(
 /// - int_7: 未知 (1)
 /// - int_8: codeium_bytes_by_cascade (4383028)
 fn extract_percent_code_written(data: &Value) -> Result<PercentCodeWritten, String> {
-    println!("[extract_percent_code_written] Data: {:?}", data);
+    crate::proto_log!("[extract_percent_code_written] Data: {:?}", data);
     
     let codeium_bytes = data.get("int_5").and_then(|v| v.as_i64()).unwrap_or(0);
     let codeium_bytes_by_cascade = data.get("int_8").and_then(|v| v.as_i64()).unwrap_or(0);
@@ -644,7 +2000,7 @@ fn extract_percent_code_written(data: &Value) -> Result<PercentCodeWritten, Stri
 
 /// 提取补全统计 (Field 1: completion_stats)
 fn extract_completion_stats(data: &Value) -> Result<CompletionStatistics, String> {
-    println!("[extract_completion_stats] Data: {:?}", data);
+    crate::proto_log!("[extract_completion_stats] Data: {:?}", data);
     
     // CompletionStatistics 嵌套在 subMesssage_1 中
     let stats_data = data.get("subMesssage_1").unwrap_or(data);
@@ -671,27 +2027,19 @@ fn extract_completion_stats(data: &Value) -> Result<CompletionStatistics, String
 }
 
 /// 提取按日期的补全统计 (Field 2: completions_by_day)
-fn extract_completions_by_day(data: &Value) -> Result<Vec<CompletionByDay>, String> {
+fn extract_completions_by_day(data: &Value, bucketing: DayBucketing) -> Result<Vec<CompletionByDay>, String> {
     let mut results = Vec::new();
     
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
     
     if let Some(entries) = repeated {
-        for entry in entries {
+        for entry in entries.iter() {
             let timestamp = entry.get("subMesssage_1")
                 .and_then(|v| v.get("int_1"))
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0);
             
-            let date = if timestamp > 0 {
-                DateTime::from_timestamp(timestamp, 0)
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_else(|| "Unknown".to_string())
-            } else {
-                "Unknown".to_string()
-            };
+            let date = date_utils::format_day(timestamp, bucketing);
             
             // 提取 CompletionStatistics (subMesssage_2)
             let stats_data = entry.get("subMesssage_2").unwrap_or(entry);
@@ -720,7 +2068,7 @@ fn extract_completions_by_day(data: &Value) -> Result<Vec<CompletionByDay>, Stri
         }
     }
     
-    println!("[extract_completions_by_day] Extracted {} entries", results.len());
+    crate::proto_log!("[extract_completions_by_day] Extracted {} entries", results.len());
     Ok(results)
 }
 
@@ -728,12 +2076,10 @@ fn extract_completions_by_day(data: &Value) -> Result<Vec<CompletionByDay>, Stri
 fn extract_completions_by_language(data: &Value) -> Result<Vec<CompletionByLanguage>, String> {
     let mut results = Vec::new();
     
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
     
     if let Some(entries) = repeated {
-        for entry in entries {
+        for entry in entries.iter() {
             let language_id = entry.get("int_1").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
             let language_name = get_language_name(language_id);
             
@@ -767,13 +2113,13 @@ fn extract_completions_by_language(data: &Value) -> Result<Vec<CompletionByLangu
     // 按接受次数降序排序
     results.sort_by(|a, b| b.statistics.num_acceptances.cmp(&a.statistics.num_acceptances));
     
-    println!("[extract_completions_by_language] Extracted {} entries", results.len());
+    crate::proto_log!("[extract_completions_by_language] Extracted {} entries", results.len());
     Ok(results)
 }
 
 /// 提取 Chat 统计 (Field 11: chat_stats)
 fn extract_chat_stats(data: &Value) -> Result<ChatStats, String> {
-    println!("[extract_chat_stats] Data: {:?}", data);
+    crate::proto_log!("[extract_chat_stats] Data: {:?}", data);
     
     // ChatStats 嵌套在 subMesssage_1 中
     let stats_data = data.get("subMesssage_1").unwrap_or(data);
@@ -798,27 +2144,19 @@ fn extract_chat_stats(data: &Value) -> Result<ChatStats, String> {
 }
 
 /// 提取按日期的 Chat 统计 (Field 6: chats_by_day)
-fn extract_chats_by_day(data: &Value) -> Result<Vec<ChatStatsByDay>, String> {
+fn extract_chats_by_day(data: &Value, bucketing: DayBucketing) -> Result<Vec<ChatStatsByDay>, String> {
     let mut results = Vec::new();
     
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
     
     if let Some(entries) = repeated {
-        for entry in entries {
+        for entry in entries.iter() {
             let timestamp = entry.get("subMesssage_1")
                 .and_then(|v| v.get("int_1"))
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0);
             
-            let date = if timestamp > 0 {
-                DateTime::from_timestamp(timestamp, 0)
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_else(|| "Unknown".to_string())
-            } else {
-                "Unknown".to_string()
-            };
+            let date = date_utils::format_day(timestamp, bucketing);
             
             // 提取 ChatStats (subMesssage_2)
             let stats_data = entry.get("subMesssage_2").unwrap_or(entry);
@@ -847,7 +2185,7 @@ fn extract_chats_by_day(data: &Value) -> Result<Vec<ChatStatsByDay>, String> {
         }
     }
     
-    println!("[extract_chats_by_day] Extracted {} entries", results.len());
+    crate::proto_log!("[extract_chats_by_day] Extracted {} entries", results.len());
     Ok(results)
 }
 
@@ -855,12 +2193,10 @@ fn extract_chats_by_day(data: &Value) -> Result<Vec<ChatStatsByDay>, String> {
 fn extract_chats_by_model(data: &Value) -> Result<Vec<ChatStatsByModel>, String> {
     let mut results = Vec::new();
     
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
     
     if let Some(entries) = repeated {
-        for entry in entries {
+        for entry in entries.iter() {
             let model_id = entry.get("int_1").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
             let model_name = get_model_name(model_id);
             
@@ -894,7 +2230,7 @@ fn extract_chats_by_model(data: &Value) -> Result<Vec<ChatStatsByModel>, String>
     // 按发送数降序排序
     results.sort_by(|a, b| b.stats.chats_sent.cmp(&a.stats.chats_sent));
     
-    println!("[extract_chats_by_model] Extracted {} entries", results.len());
+    crate::proto_log!("[extract_chats_by_model] Extracted {} entries", results.len());
     Ok(results)
 }
 
@@ -902,12 +2238,10 @@ fn extract_chats_by_model(data: &Value) -> Result<Vec<ChatStatsByModel>, String>
 fn extract_custom_query_response(data: &Value) -> Result<CustomQueryResponse, String> {
     let mut items = Vec::new();
     
-    let repeated = data.get("subMesssage_1")
-        .or_else(|| data.get("repeated_1"))
-        .and_then(|v| v.as_array());
+    let repeated = repeated_entries(data);
     
     if let Some(entries) = repeated {
-        for entry in entries {
+        for entry in entries.iter() {
             // 每个 QueryResponseItem 包含 map<string, string> item (field 1)
             let mut data_map = HashMap::new();
             
@@ -923,12 +2257,16 @@ fn extract_custom_query_response(data: &Value) -> Result<CustomQueryResponse, St
         }
     }
     
-    println!("[extract_custom_query_response] Extracted {} items", items.len());
+    crate::proto_log!("[extract_custom_query_response] Extracted {} items", items.len());
     Ok(CustomQueryResponse { items })
 }
 
 /// 根据语言ID获取语言名称
 fn get_language_name(language_id: i32) -> String {
+    // 优先使用下载的映射表
+    if let Some(name) = mapping_service::language_name(language_id) {
+        return name;
+    }
     match language_id {
         0 => "Unspecified".to_string(),
         1 => "C".to_string(),
@@ -982,6 +2320,9 @@ fn get_language_name(language_id: i32) -> String {
 
 /// 根据模型ID获取模型名称
 fn get_model_name(model_id: i32) -> String {
+    if let Some(name) = mapping_service::model_name(model_id) {
+        return name;
+    }
     match model_id {
         0 => "Unspecified".to_string(),
         1 => "GPT-3.5".to_string(),
@@ -992,3 +2333,189 @@ fn get_model_name(model_id: i32) -> String {
         _ => format!("Model_{}", model_id),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 仓库内的所有抽取样例必须与期望结果一致
+    #[test]
+    fn test_analytics_fixtures() {
+        let results = run_fixtures_in(&fixtures::default_fixture_dir()).unwrap();
+        let failures: Vec<&FixtureResult> = results.iter().filter(|r| !r.passed).collect();
+        assert!(failures.is_empty(), "fixture mismatches: {:#?}", failures);
+    }
+
+    /// 通过 MockTransport 把样例作为 GetAnalytics 响应回放给 AnalyticsService，再走完整的抽取流程
+    #[test]
+    fn test_analytics_fixture_replay() {
+        use crate::services::transport::MockTransport;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let loaded = fixtures::load_fixtures(&fixtures::default_fixture_dir()).unwrap();
+        assert!(!loaded.is_empty());
+        for fixture in loaded {
+            let transport = Arc::new(MockTransport::new().with_response("GetAnalytics", 200, fixture.body.clone()));
+            let service = AnalyticsService::with_transport(transport.clone());
+            let body = runtime
+                .block_on(service.get_analytics("token", 1717200000, 1717804800, false, Some("UTC")))
+                .unwrap();
+            let data = extract_analytics_from_body(&body, DayBucketing::Utc).unwrap();
+            let diffs = fixtures::json_subset_diff(&fixture.expected, &serde_json::to_value(&data).unwrap());
+            assert!(diffs.is_empty(), "{}: {:?}", fixture.name, diffs);
+
+            let requests = transport.requests();
+            assert_eq!(requests.len(), 1);
+            assert!(requests[0].url.ends_with("/GetAnalytics"));
+        }
+    }
+
+    #[test]
+    fn test_capacity_plan() {
+        let now = Utc::now();
+        let snapshot = |days_ago: i64, used: i32| CreditsSnapshot::test_snapshot(now - Duration::days(days_ago), used);
+        // 续费后已用额度归零，重置后的 40 计入消耗
+        let history = vec![snapshot(4, 100), snapshot(2, 300), snapshot(0, 40)];
+        assert_eq!(estimate_daily_burn(&history), Some(60.0));
+        assert_eq!(estimate_daily_burn(&history[2..]), None);
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let account = |remaining: i64, renews_on: Option<NaiveDate>| CapacityAccountEntry {
+            account_id: Uuid::new_v4(),
+            email: "a@example.com".to_string(),
+            remaining_credits: remaining,
+            total_credits: Some(500),
+            renews_on,
+            daily_burn: None,
+        };
+
+        let plan = build_capacity_plan(vec![account(100, None), account(150, None)], 50.0, "manual", 10, 2, today);
+        assert_eq!(plan.days_until_depleted, Some(6));
+        assert_eq!(plan.add_accounts_by, NaiveDate::from_ymd_opt(2026, 1, 5));
+        assert_eq!(plan.shortfall_credits, 250);
+        assert_eq!(plan.accounts_needed, 1);
+
+        // 第 3 天续费恢复 500，规划期内不会耗尽
+        let renews_on = NaiveDate::from_ymd_opt(2026, 1, 4);
+        let plan = build_capacity_plan(vec![account(100, renews_on)], 50.0, "manual", 10, 2, today);
+        assert_eq!(plan.days_until_depleted, None);
+        assert_eq!(plan.accounts_needed, 0);
+
+        // 续费把账号重置为总额度而不是在剩余额度上累加：450 → 350 → 重置为 500，第 7 天耗尽
+        let plan = build_capacity_plan(vec![account(450, NaiveDate::from_ymd_opt(2026, 1, 3))], 100.0, "manual", 10, 2, today);
+        assert_eq!(plan.days_until_depleted, Some(7));
+
+        // 订阅已到期的账号不再按 30 天周期续费
+        let plan = build_capacity_plan(vec![account(100, NaiveDate::from_ymd_opt(2025, 12, 3))], 50.0, "manual", 10, 2, today);
+        assert_eq!(plan.days_until_depleted, Some(3));
+    }
+
+    #[test]
+    fn test_team_benchmark_helpers() {
+        let data = json!({
+            "subMesssage_1": [
+                { "string_1": "k1", "string_3": "A@example.com", "int_28": 100 },
+                { "string_1": "k2", "string_3": "b@example.com", "int_28": 300 },
+                { "string_1": "k3", "string_3": "c@example.com" },
+            ]
+        });
+        let members = extract_team_member_credits(&data);
+        assert_eq!(members[0], ("a@example.com".to_string(), 100));
+        assert_eq!(members[2].1, 0);
+
+        let metric = benchmark_metric(100.0, &[100.0, 300.0, 0.0]);
+        assert!((metric.team_average - 400.0 / 3.0).abs() < 1e-9);
+        assert!((metric.percentile - 50.0).abs() < 1e-9);
+        assert_eq!(metric.sample_size, 3);
+    }
+
+    #[test]
+    fn test_single_query_result_object() {
+        // 只有一个 QueryResult、且其中只有一天数据时，两层 repeated 都被解析为对象
+        let parsed = json!({
+            "raw_data": {
+                "subMesssage_1": {
+                    "subMesssage_18": {
+                        "subMesssage_1": {
+                            "subMesssage_1": { "int_1": 1767225600 },
+                            "int_2": 10,
+                            "int_3": 7
+                        }
+                    }
+                }
+            }
+        });
+
+        let data = extract_analytics_data(&parsed, DayBucketing::Utc).unwrap();
+        assert_eq!(data.daily_cascade_lines.len(), 1);
+        assert_eq!(data.daily_cascade_lines[0].accepted_lines, 7);
+        assert_eq!(data.daily_cascade_lines[0].suggested_lines, 10);
+    }
+
+    #[test]
+    fn test_extract_credits_usage() {
+        let parsed = json!({
+            "raw_data": {
+                "subMesssage_1": {
+                    "subMesssage_26": {
+                        "subMesssage_1": { "int_1": 12, "int_2": 340, "int_3": 5, "int_6": 2 }
+                    }
+                }
+            }
+        });
+
+        let data = extract_analytics_data(&parsed, DayBucketing::Utc).unwrap();
+        assert_eq!(data.credits_usage.conversations, 12);
+        assert_eq!(data.credits_usage.prompt_credits_used, 340);
+        assert_eq!(data.credits_usage.commands, 5);
+        assert_eq!(data.credits_usage.memories, 0);
+        assert_eq!(data.credits_usage.terminal_messages, 2);
+    }
+
+    #[test]
+    fn test_extract_cascade_stats() {
+        let parsed = json!({
+            "raw_data": {
+                "subMesssage_1": {
+                    "subMesssage_15": {
+                        "int_1": 42,
+                        "subMesssage_3": [
+                            { "subMesssage_1": { "int_1": 1717200000 }, "int_2": 30 },
+                            { "subMesssage_1": { "int_1": 1717286400 } },
+                            { "subMesssage_1": { "int_1": 1717372800 }, "int_2": 12 }
+                        ]
+                    }
+                }
+            }
+        });
+
+        let data = extract_analytics_data(&parsed, DayBucketing::Utc).unwrap();
+        assert_eq!(data.cascade_stats.messages_sent, 42);
+        // 响应中没有 int_2 时按有消息的天数计算
+        assert_eq!(data.cascade_stats.active_days, 2);
+        assert_eq!(data.cascade_stats.by_day.len(), 3);
+        assert_eq!(data.cascade_stats.by_day[0].date, "2024-06-01");
+        assert!(data.has_activity());
+    }
+
+    #[test]
+    fn test_fill_daily_gaps() {
+        let mut data = AnalyticsData::default();
+        data.daily_cascade_lines.push(DailyCascadeLinesCount {
+            timestamp: 0,
+            date: "2026-01-02".to_string(),
+            accepted_lines: 5,
+            suggested_lines: 8,
+        });
+
+        let start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2026, 1, 3).unwrap();
+        fill_daily_gaps(&mut data, start, end);
+
+        let dates: Vec<&str> = data.daily_cascade_lines.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, vec!["2026-01-01", "2026-01-02", "2026-01-03"]);
+        assert_eq!(data.daily_cascade_lines[1].accepted_lines, 5);
+        assert_eq!(data.completions_by_day.len(), 3);
+        assert_eq!(data.chats_by_day.len(), 3);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::models::{Account, OperationLog, OperationType, OperationStatus};
+use crate::models::{Account, AccountFilter, OperationLog, OperationType, OperationStatus, TokenSource};
 use crate::repository::DataStore;
-use crate::services::{AuthService, WindsurfService, UpdateSeatsResult};
-use crate::utils::AppError;
+use crate::services::{AuthService, WindsurfService, UpdateSeatsResult, user_info_cache};
+use crate::utils::{AppError, AppResult, CommandError, CommandResult};
 use serde_json::json;
+use std::future::Future;
 use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
@@ -13,14 +14,14 @@ pub async fn ensure_valid_token(
     store: &Arc<DataStore>,
     account: &mut Account,
     uuid: Uuid,
-) -> Result<(), String> {
+) -> CommandResult<()> {
     ensure_valid_token_with_force(store, account, uuid, false).await
 }
 
 /// 检查账号是否为团队所有者（Admin角色）
 /// 通过 GetCurrentUser API 获取 roles 字段判断是否为 root.admin
 pub async fn check_is_team_owner(windsurf_service: &WindsurfService, token: &str, _email: &str) -> bool {
-    if let Ok(user_result) = windsurf_service.get_current_user(token).await {
+    if let Ok(user_result) = user_info_cache::get_current_user_cached(windsurf_service, token).await {
         // 检查 user_info.is_root_admin 字段（由 proto_parser 解析）
         if let Some(user_info) = user_result.get("user_info") {
             if let Some(is_root_admin) = user_info.get("is_root_admin").and_then(|v| v.as_bool()) {
@@ -46,7 +47,7 @@ pub async fn ensure_valid_token_with_force(
     account: &mut Account,
     uuid: Uuid,
     force_refresh: bool,
-) -> Result<(), String> {
+) -> CommandResult<()> {
     // 如果不是强制刷新且token有效，直接返回
     if !force_refresh && 
        account.token.is_some() && 
@@ -62,33 +63,29 @@ pub async fn ensure_valid_token_with_force(
     let auth_service = AuthService::new();
     
     // 优先尝试使用refresh token
-    let (token, refresh_token_new, expires_at) = if let Some(refresh_token) = &account.refresh_token {
+    let (token, refresh_token_new, expires_at, source) = if let Some(refresh_token) = &account.refresh_token {
         match auth_service.refresh_token(refresh_token).await {
-            Ok(result) => result,
+            Ok((token, refresh_token, expires_at)) => (token, refresh_token, expires_at, TokenSource::RefreshToken),
             Err(_) => {
                 // refresh token失败，重新登录
-                let password = store.get_decrypted_password(uuid)
-                    .await
-                    .map_err(|e| e.to_string())?;
-                auth_service.sign_in(&account.email, &password)
-                    .await
-                    .map_err(|e| e.to_string())?
+                let password = store.get_decrypted_password(uuid).await?;
+                let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await?;
+                (token, refresh_token, expires_at, TokenSource::PasswordLogin)
             }
         }
     } else {
         // 没有refresh token，直接重新登录
-        let password = store.get_decrypted_password(uuid)
-            .await
-            .map_err(|e| e.to_string())?;
-        auth_service.sign_in(&account.email, &password)
-            .await
-            .map_err(|e| e.to_string())?
+        let password = store.get_decrypted_password(uuid).await?;
+        // 仅内存模式下重启后 Token 不再可用，没有密码时无法重新获取
+        if password.is_empty() && store.get_settings().await.map(|s| s.memory_only_secrets).unwrap_or(false) {
+            return Err(AppError::SessionSecretUnavailable(account.email.clone()).into());
+        }
+        let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await?;
+        (token, refresh_token, expires_at, TokenSource::PasswordLogin)
     };
     
     // 更新token到数据库
-    store.update_account_tokens(uuid, token.clone(), refresh_token_new.clone(), expires_at)
-        .await
-        .map_err(|e| e.to_string())?;
+    store.update_account_tokens(uuid, token.clone(), refresh_token_new.clone(), expires_at, source).await?;
     
     // 更新内存中的账户对象
     account.token = Some(token);
@@ -98,40 +95,132 @@ pub async fn ensure_valid_token_with_force(
     Ok(())
 }
 
+/// 错误信息是否表明缓存的 Windsurf API Key 已失效（401/403 或 permission_denied）
+fn is_stale_api_key_message(message: &str) -> bool {
+    message.contains("permission_denied")
+        || message.contains("status code 401")
+        || message.contains("status code 403")
+}
+
+/// 检查 API 响应是否表明缓存的 Windsurf API Key 已失效
+pub fn is_stale_api_key_error(result: &serde_json::Value) -> bool {
+    let status_code = result.get("status_code").and_then(|v| v.as_u64()).unwrap_or(0);
+    if status_code == 401 || status_code == 403 {
+        return true;
+    }
+    ["error", "error_details"]
+        .iter()
+        .filter_map(|key| result.get(*key).and_then(|v| v.as_str()))
+        .any(is_stale_api_key_message)
+}
+
+/// 通过 GetCurrentUser 重新获取失效的 API Key 并记录日志
+///
+/// 只写回 API Key 字段；重新获取失败时保留原来的 Key
+pub async fn recycle_api_key(
+    store: &Arc<DataStore>,
+    account: &mut Account,
+    uuid: Uuid,
+    reason: &str,
+) -> Result<String, String> {
+    println!("[recycle_api_key] API Key for {} looks stale ({}), fetching a new one", account.email, reason);
+    let old_api_key = account.windsurf_api_key.clone();
+    
+    let fetched = async {
+        // 401 多半是 Token 已在服务端失效，先强制刷新 Token 再取 Key
+        ensure_valid_token_with_force(store, account, uuid, reason.contains("401")).await?;
+        let token = account.token.clone().ok_or("No token available")?;
+        // 缓存的用户信息里仍是旧 Key，必须绕过缓存
+        user_info_cache::invalidate(&token);
+        let user_info = user_info_cache::get_current_user_cached(&WindsurfService::for_account(uuid), &token)
+            .await
+            .map_err(|e| format!("Failed to get current user: {}", e))?;
+        user_info
+            .pointer("/parsed_data/subMesssage_1/string_1")
+            .and_then(|v| v.as_str())
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_string())
+            .ok_or_else(|| "Failed to extract Windsurf API Key from user info".to_string())
+    }
+    .await;
+    
+    // 重新获取失败时保留原来的 Key，避免一次网络错误就清空账号的 Key
+    if let Ok(api_key) = &fetched {
+        account.windsurf_api_key = Some(api_key.clone());
+        store.update_account_api_key(uuid, api_key.clone())
+            .await
+            .map_err(|e| format!("Failed to update account with API key: {}", e))?;
+    }
+    
+    let (status, message) = match &fetched {
+        Ok(_) => (OperationStatus::Success, "API Key 已失效，已重新获取".to_string()),
+        Err(e) => (OperationStatus::Failed, format!("API Key 已失效，重新获取失败: {}", e)),
+    };
+    let log = OperationLog::new(OperationType::GetAccountInfo, status, message)
+        .with_account(uuid, account.email.clone())
+        .with_details(json!({
+            "reason": reason,
+            "changed": fetched.as_ref().ok() != old_api_key.as_ref(),
+        }));
+    let _ = store.add_log(log).await;
+    
+    fetched
+}
+
+/// 使用账号缓存的 API Key 调用接口；返回 Key 失效的错误时统一重新获取 Key 并重试一次
+pub async fn call_with_api_key<F, Fut>(
+    store: &Arc<DataStore>,
+    account: &mut Account,
+    uuid: Uuid,
+    call: F,
+) -> CommandResult<serde_json::Value>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = AppResult<serde_json::Value>>,
+{
+    let api_key = account.windsurf_api_key.clone()
+        .filter(|k| !k.is_empty())
+        .ok_or("账号没有 API Key，请先刷新账号信息")?;
+    
+    let reason = match call(api_key).await {
+        Ok(result) if is_stale_api_key_error(&result) => format!(
+            "HTTP {}",
+            result.get("status_code").and_then(|v| v.as_u64()).unwrap_or(0)
+        ),
+        Err(e) if is_stale_api_key_message(&e.to_string()) => e.to_string(),
+        other => return other.map_err(CommandError::from),
+    };
+    
+    let api_key = recycle_api_key(store, account, uuid, &reason).await?;
+    call(api_key).await.map_err(CommandError::from)
+}
+
 #[tauri::command]
 pub async fn login_account(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
-    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+) -> CommandResult<serde_json::Value> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| CommandError::NotFound(e.to_string()))?;
     
     // 获取账号信息
-    let account = store.get_account(uuid)
-        .await
-        .map_err(|e| e.to_string())?;
+    let account = store.get_account(uuid).await?;
     
     // 解密密码
-    let password = store.get_decrypted_password(uuid)
-        .await
-        .map_err(|e| e.to_string())?;
+    let password = store.get_decrypted_password(uuid).await?;
     
     // 登录获取Token
     let auth_service = AuthService::new();
-    let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password)
-        .await
-        .map_err(|e| e.to_string())?;
+    let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await?;
     
     // 更新Token和Refresh Token
-    store.update_account_tokens(uuid, token.clone(), refresh_token, expires_at)
-        .await
-        .map_err(|e| e.to_string())?;
+    store.update_account_tokens(uuid, token.clone(), refresh_token, expires_at, TokenSource::PasswordLogin).await?;
     
     // 获取最新的配额信息
-    let windsurf_service = WindsurfService::new();
-    let mut updated_account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    let windsurf_service = WindsurfService::for_account(uuid);
+    let mut updated_account = store.get_account(uuid).await?;
     
     // 读取设置，判断使用哪个 API
-    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await?;
     println!("[login_account] use_lightweight_api = {}", settings.use_lightweight_api);
     
     if settings.use_lightweight_api {
@@ -170,7 +259,7 @@ pub async fn login_account(
         }
     } else {
         // 使用完整的 GetCurrentUser API
-        if let Ok(user_info_result) = windsurf_service.get_current_user(&token).await {
+        if let Ok(user_info_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
             if let Some(user_info) = user_info_result.get("user_info") {
                 // 提取用户基本信息（包含api_key）
                 if let Some(user) = user_info.get("user") {
@@ -255,13 +344,11 @@ pub async fn login_account(
 pub async fn refresh_token(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
-    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+) -> CommandResult<serde_json::Value> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| CommandError::NotFound(e.to_string()))?;
     
     // 获取账号信息
-    let account = store.get_account(uuid)
-        .await
-        .map_err(|e| e.to_string())?;
+    let account = store.get_account(uuid).await?;
     
     // 保留过期时间信息用于参考
     let old_expires_at = account.token_expires_at
@@ -271,41 +358,33 @@ pub async fn refresh_token(
     let auth_service = AuthService::new();
     
     // 优先尝试使用refresh token
-    let (token, refresh_token_new, expires_at) = if let Some(refresh_token) = &account.refresh_token {
+    let (token, refresh_token_new, expires_at, source) = if let Some(refresh_token) = &account.refresh_token {
         // 尝试使用refresh token
         match auth_service.refresh_token(refresh_token).await {
-            Ok(result) => result,
+            Ok((token, refresh_token, expires_at)) => (token, refresh_token, expires_at, TokenSource::RefreshToken),
             Err(_) => {
                 // refresh token失败，重新登录
-                let password = store.get_decrypted_password(uuid)
-                    .await
-                    .map_err(|e| e.to_string())?;
-                auth_service.sign_in(&account.email, &password)
-                    .await
-                    .map_err(|e| e.to_string())?
+                let password = store.get_decrypted_password(uuid).await?;
+                let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await?;
+                (token, refresh_token, expires_at, TokenSource::PasswordLogin)
             }
         }
     } else {
         // 没有refresh token，直接重新登录
-        let password = store.get_decrypted_password(uuid)
-            .await
-            .map_err(|e| e.to_string())?;
-        auth_service.sign_in(&account.email, &password)
-            .await
-            .map_err(|e| e.to_string())?
+        let password = store.get_decrypted_password(uuid).await?;
+        let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await?;
+        (token, refresh_token, expires_at, TokenSource::PasswordLogin)
     };
     
     // 更新Token和Refresh Token
-    store.update_account_tokens(uuid, token.clone(), refresh_token_new, expires_at)
-        .await
-        .map_err(|e| e.to_string())?;
+    store.update_account_tokens(uuid, token.clone(), refresh_token_new, expires_at, source).await?;
     
     // 获取最新的配额信息
-    let windsurf_service = WindsurfService::new();
-    let mut updated_account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
+    let windsurf_service = WindsurfService::for_account(uuid);
+    let mut updated_account = store.get_account(uuid).await?;
     
     // 读取设置，判断使用哪个 API
-    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let settings = store.get_settings().await?;
     println!("[refresh_token] use_lightweight_api = {}", settings.use_lightweight_api);
     
     if settings.use_lightweight_api {
@@ -344,7 +423,7 @@ pub async fn refresh_token(
         }
     } else {
         // 使用完整的 GetCurrentUser API
-        if let Ok(user_info_result) = windsurf_service.get_current_user(&token).await {
+        if let Ok(user_info_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
             if let Some(user_info) = user_info_result.get("user_info") {
                 // 提取用户基本信息（包含api_key）
                 if let Some(user) = user_info.get("user") {
@@ -435,7 +514,7 @@ pub async fn refresh_token(
 pub async fn get_plan_status(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -453,10 +532,9 @@ pub async fn get_plan_status(
         .ok_or("No token available")?;
     
     // 调用GetPlanStatus API
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_plan_status(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
     
     // 如果成功，更新账号的配额信息
     if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
@@ -506,7 +584,7 @@ pub async fn reset_credits(
     id: String,
     seat_count: Option<i32>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -528,10 +606,9 @@ pub async fn reset_credits(
     let seat_count_options = settings.seat_count_options;
     
     // 执行积分重置
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: serde_json::Value = windsurf_service.reset_credits(&token, seat_count, account.last_seat_count, &seat_count_options)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
     
     // 更新最后使用的座位数
     if let Some(used_seat_count) = result.get("seat_count_used").and_then(|v| v.as_i64()) {
@@ -562,7 +639,7 @@ pub async fn update_seats(
     seat_count: i32,
     retry_times: i32,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -577,10 +654,11 @@ pub async fn update_seats(
     let token = account.token.ok_or("No token available")?;
     
     // 执行座位更新
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: UpdateSeatsResult = windsurf_service.update_seats(&token, seat_count, retry_times)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
+    // 账号状态已变化，丢弃缓存的用户信息
+    user_info_cache::invalidate(&token);
     
     // 记录日志
     let account = store.get_account(uuid).await.ok();
@@ -635,7 +713,7 @@ pub async fn update_seats(
 pub async fn get_billing(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -650,10 +728,9 @@ pub async fn get_billing(
     let token = account.token.ok_or("No token available")?;
     
     // 获取账单信息
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_team_billing(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
     
     // 记录日志
     let account = store.get_account(uuid).await.ok();
@@ -685,7 +762,7 @@ pub async fn cancel_subscription(
     id: String,
     reason: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取Token
@@ -695,10 +772,11 @@ pub async fn cancel_subscription(
         .ok_or("No token available")?;
 
     // 取消订阅
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: serde_json::Value = windsurf_service.cancel_plan(&token, &reason)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
+    // 账号状态已变化，丢弃缓存的用户信息
+    user_info_cache::invalidate(&token);
 
     // 获取账号信息用于日志记录
     let account = store.get_account(uuid).await.ok();
@@ -733,7 +811,7 @@ pub async fn cancel_subscription(
 pub async fn resume_subscription(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取Token
@@ -743,10 +821,11 @@ pub async fn resume_subscription(
         .ok_or("No token available")?;
 
     // 恢复订阅
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: serde_json::Value = windsurf_service.resume_plan(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
+    // 账号状态已变化，丢弃缓存的用户信息
+    user_info_cache::invalidate(&token);
 
     // 获取账号信息用于日志记录
     let account = store.get_account(uuid).await.ok();
@@ -796,7 +875,7 @@ async fn reset_credits_internal(
     let seat_count_options = settings.seat_count_options;
     
     // 执行积分重置
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: serde_json::Value = windsurf_service.reset_credits(&token, seat_count, account.last_seat_count, &seat_count_options)
         .await
         .map_err(|e: AppError| e.to_string())?;
@@ -834,7 +913,7 @@ pub async fn update_plan(
     payment_period: Option<u8>,
     preview: Option<bool>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     let period = payment_period.unwrap_or(1); // 默认月付
     let is_preview = preview.unwrap_or(false); // 默认非预览模式
@@ -846,10 +925,11 @@ pub async fn update_plan(
         .ok_or("No token available")?;
 
     // 更换订阅计划
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result: serde_json::Value = windsurf_service.update_plan(&token, &plan_type, period, is_preview)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
+    // 账号状态已变化，丢弃缓存的用户信息
+    user_info_cache::invalidate(&token);
 
     // 获取账号信息用于日志记录
     let account = store.get_account(uuid).await.ok();
@@ -911,7 +991,7 @@ pub async fn update_plan(
             }
         } else {
             // 使用完整的 GetCurrentUser API
-            if let Ok(user_info_result) = windsurf_service.get_current_user(&token).await {
+            if let Ok(user_info_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
                 if let Some(user_info) = user_info_result.get("user_info") {
                     // 提取用户基本信息（包含api_key）
                     if let Some(user) = user_info.get("user") {
@@ -970,8 +1050,8 @@ pub async fn update_plan(
 pub async fn get_current_user(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
-    get_current_user_internal(&id, &store, false).await
+) -> CommandResult<serde_json::Value> {
+    Ok(get_current_user_internal(&id, &store, false).await?)
 }
 
 /// 内部实现，支持 401 自动重试
@@ -999,7 +1079,7 @@ fn get_current_user_internal<'a>(
     
     // 读取设置，判断使用哪个 API
     let settings = store.get_settings().await.map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     println!("[get_current_user] use_lightweight_api = {}", settings.use_lightweight_api);
     
@@ -1096,7 +1176,7 @@ fn get_current_user_internal<'a>(
         // 使用完整的 GetCurrentUser API
         println!("[get_current_user] Using GetCurrentUser API");
         
-        let result: serde_json::Value = windsurf_service.get_current_user(&token)
+        let result: serde_json::Value = user_info_cache::get_current_user_cached(&windsurf_service, &token)
             .await
             .map_err(|e: AppError| e.to_string())?;
         
@@ -1174,7 +1254,7 @@ fn get_current_user_internal<'a>(
 pub async fn get_account_info(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -1186,7 +1266,7 @@ pub async fn get_account_info(
     ensure_valid_token(&store, &mut account, uuid).await?;
     
     // 使用缓存的或新刷新的Token
-    let token = account.token.ok_or("No token available")?;
+    let token = account.token.clone().ok_or("No token available")?;
     
     // 使用AuthService获取Firebase账户信息
     let auth_service = AuthService::new();
@@ -1194,8 +1274,23 @@ pub async fn get_account_info(
         .await
         .map_err(|e| e.to_string())?;
     
+    // IDE 使用的用户状态（套餐限制、功能开关、限流状态），获取失败不影响账号信息
+    let windsurf_service = WindsurfService::for_account(uuid);
+    let user_status = match &account.windsurf_api_key {
+        Some(_) => call_with_api_key(&store, &mut account, uuid, |api_key| {
+            let windsurf_service = &windsurf_service;
+            async move { windsurf_service.get_user_status(&api_key).await }
+        })
+        .await
+        .ok()
+        .filter(|r| r.get("success").and_then(|v| v.as_bool()) == Some(true))
+        .and_then(|r| r.get("user_status").cloned()),
+        None => None,
+    };
+    
     Ok(json!({
         "success": true,
+        "user_status": user_status,
         "local_info": {
             "id": account.id,
             "email": account.email,
@@ -1225,11 +1320,34 @@ pub async fn get_account_info(
     }))
 }
 
+/// 获取 IDE 使用的用户状态（GetUserStatus）
+#[tauri::command]
+pub async fn get_user_status(
+    id: String,
+    store: State<'_, Arc<DataStore>>,
+) -> CommandResult<serde_json::Value> {
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if account.windsurf_api_key.is_none() {
+        return Err("账号没有 API Key，请先获取账号信息".into());
+    }
+    
+    let windsurf_service = WindsurfService::for_account(uuid);
+    call_with_api_key(&store, &mut account, uuid, |api_key| {
+        let windsurf_service = &windsurf_service;
+        async move { windsurf_service.get_user_status(&api_key).await }
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn get_team_credit_entries(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
     // 获取账号信息
@@ -1243,7 +1361,7 @@ pub async fn get_team_credit_entries(
     let token = account.token.ok_or("No token available")?;
     
     // 调用GetTeamCreditEntries API
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_team_credit_entries(&token)
         .await
         .map_err(|e| e.to_string())?;
@@ -1256,7 +1374,7 @@ pub async fn batch_reset_credits(
     ids: Vec<String>,
     seat_count: Option<i32>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     use futures::stream::{self, StreamExt};
     
     // 设置并发限制，避免过多并发请求
@@ -1332,7 +1450,7 @@ pub async fn batch_reset_credits(
 pub async fn batch_refresh_tokens(
     ids: Vec<String>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     use futures::stream::{self, StreamExt};
     
     let store_arc = store.inner().clone();
@@ -1398,6 +1516,166 @@ pub async fn batch_refresh_tokens(
     }))
 }
 
+/// 刷新即将过期（默认 60 分钟内）或已过期的 Token
+#[tauri::command]
+pub async fn refresh_expiring_tokens(
+    within_minutes: Option<i64>,
+    store: State<'_, Arc<DataStore>>,
+) -> CommandResult<serde_json::Value> {
+    let threshold = chrono::Utc::now() + chrono::Duration::minutes(within_minutes.unwrap_or(60));
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+
+    let ids: Vec<String> = accounts
+        .iter()
+        .filter(|a| a.refresh_token.is_some() || !a.password.is_empty())
+        .filter(|a| {
+            let expires_at = a.token.as_deref()
+                .and_then(crate::utils::jwt::expires_at)
+                .or(a.token_expires_at);
+            expires_at.map(|exp| exp <= threshold).unwrap_or(true)
+        })
+        .map(|a| a.id.to_string())
+        .collect();
+
+    if ids.is_empty() {
+        return Ok(json!({
+            "success": true,
+            "total": 0,
+            "message": "没有需要刷新的Token"
+        }));
+    }
+
+    batch_refresh_tokens(ids, store).await
+}
+
+/// 使用已保存的密码批量重新登录匹配的账号，刷新 Token 与 Windsurf API Key
+///
+/// 用于服务端使所有会话失效后的批量恢复；并发数遵循设置中的并发限制
+#[tauri::command]
+pub async fn bulk_relogin(
+    filter: Option<AccountFilter>,
+    store: State<'_, Arc<DataStore>>,
+) -> CommandResult<serde_json::Value> {
+    use futures::stream::{self, StreamExt};
+
+    let filter = filter.unwrap_or_default();
+    let store_arc = store.inner().clone();
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+
+    let (targets, skipped): (Vec<Account>, Vec<Account>) = accounts
+        .into_iter()
+        .filter(|a| filter.matches(a))
+        .partition(|a| !a.password.is_empty());
+
+    // 批量重新登录属于密集的认证请求，始终遵循并发限制
+    let max_concurrent = settings.concurrent_limit.max(1);
+
+    let mut results: Vec<serde_json::Value> = stream::iter(targets)
+        .map(|account| {
+            let store_clone = store_arc.clone();
+            async move {
+                match relogin_account_internal(&store_clone, &account).await {
+                    Ok(data) => json!({
+                        "id": account.id.to_string(),
+                        "email": account.email,
+                        "success": true,
+                        "data": data
+                    }),
+                    Err(err) => json!({
+                        "id": account.id.to_string(),
+                        "email": account.email,
+                        "success": false,
+                        "error": err
+                    }),
+                }
+            }
+        })
+        .buffer_unordered(max_concurrent)
+        .collect()
+        .await;
+
+    store.flush().await.map_err(|e| e.to_string())?;
+
+    for account in &skipped {
+        results.push(json!({
+            "id": account.id.to_string(),
+            "email": account.email,
+            "success": false,
+            "skipped": true,
+            "error": "未保存密码"
+        }));
+    }
+
+    let success_count = results.iter()
+        .filter(|r| r.get("success").and_then(|s| s.as_bool()).unwrap_or(false))
+        .count();
+
+    let log = OperationLog::new(
+        OperationType::BatchOperation,
+        if success_count > 0 { OperationStatus::Success } else { OperationStatus::Failed },
+        format!("批量重新登录: 成功 {}/{} 个账号，跳过 {} 个无密码账号",
+            success_count, results.len() - skipped.len(), skipped.len()),
+    )
+    .with_details(json!({ "filter": filter }));
+    let _ = store.add_log(log).await;
+
+    Ok(json!({
+        "results": results,
+        "success_count": success_count,
+        "skipped_count": skipped.len(),
+        "total_count": results.len()
+    }))
+}
+
+/// 使用密码重新登录单个账号（不立即保存），并刷新 API Key 与套餐信息
+async fn relogin_account_internal(
+    store: &Arc<DataStore>,
+    account: &Account,
+) -> Result<serde_json::Value, String> {
+    let auth_service = AuthService::new();
+    let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &account.password)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    store.update_account_tokens_no_save(account.id, token.clone(), refresh_token, expires_at, TokenSource::PasswordLogin)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 重新获取 API Key（旧会话失效后 API Key 也可能被轮换）
+    let windsurf_service = WindsurfService::for_account(account.id);
+    let mut updated_account = store.get_account(account.id).await.map_err(|e| e.to_string())?;
+    if let Ok(user_info_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
+        if let Some(user_info) = user_info_result.get("user_info") {
+            if let Some(user) = user_info.get("user") {
+                if let Some(api_key) = user.get("api_key").and_then(|v| v.as_str()) {
+                    updated_account.windsurf_api_key = Some(api_key.to_string());
+                }
+                if let Some(disable_codeium) = user.get("disable_codeium").and_then(|v| v.as_bool()) {
+                    updated_account.is_disabled = Some(disable_codeium);
+                }
+            }
+            if let Some(plan_name) = user_info.get("plan")
+                .and_then(|plan| plan.get("plan_name"))
+                .and_then(|v| v.as_str())
+            {
+                updated_account.plan_name = Some(plan_name.to_string());
+            }
+            updated_account.last_quota_update = Some(chrono::Utc::now());
+        }
+    }
+
+    store.update_account_no_save(updated_account.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "expires_at": expires_at.to_rfc3339(),
+        "api_key_refreshed": updated_account.windsurf_api_key != account.windsurf_api_key,
+        "plan_name": updated_account.plan_name
+    }))
+}
+
 /// 内部刷新 Token 方法（支持延迟保存）
 async fn refresh_token_internal(
     id: &str,
@@ -1411,30 +1689,32 @@ async fn refresh_token_internal(
     let auth_service = AuthService::new();
     
     // 刷新 token
-    let (token, refresh_token_new, expires_at) = if let Some(ref_token) = &account.refresh_token {
+    let (token, refresh_token_new, expires_at, source) = if let Some(ref_token) = &account.refresh_token {
         match auth_service.refresh_token(ref_token).await {
-            Ok(result) => result,
+            Ok((token, refresh_token, expires_at)) => (token, refresh_token, expires_at, TokenSource::RefreshToken),
             Err(_) => {
                 let password = store.get_decrypted_password(uuid).await.map_err(|e| e.to_string())?;
-                auth_service.sign_in(&account.email, &password).await.map_err(|e| e.to_string())?
+                let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await.map_err(|e| e.to_string())?;
+                (token, refresh_token, expires_at, TokenSource::PasswordLogin)
             }
         }
     } else {
         let password = store.get_decrypted_password(uuid).await.map_err(|e| e.to_string())?;
-        auth_service.sign_in(&account.email, &password).await.map_err(|e| e.to_string())?
+        let (token, refresh_token, expires_at) = auth_service.sign_in(&account.email, &password).await.map_err(|e| e.to_string())?;
+        (token, refresh_token, expires_at, TokenSource::PasswordLogin)
     };
     
     // 使用延迟保存的方法更新 token
     if save_immediately {
-        store.update_account_tokens(uuid, token.clone(), refresh_token_new, expires_at)
+        store.update_account_tokens(uuid, token.clone(), refresh_token_new, expires_at, source)
             .await.map_err(|e| e.to_string())?;
     } else {
-        store.update_account_tokens_no_save(uuid, token.clone(), refresh_token_new, expires_at)
+        store.update_account_tokens_no_save(uuid, token.clone(), refresh_token_new, expires_at, source)
             .await.map_err(|e| e.to_string())?;
     }
     
     // 获取配额信息
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let mut updated_account = store.get_account(uuid).await.map_err(|e| e.to_string())?;
     
     if use_lightweight_api {
@@ -1464,7 +1744,7 @@ async fn refresh_token_internal(
         }
     } else {
         // 使用完整的 GetCurrentUser API
-        if let Ok(user_info_result) = windsurf_service.get_current_user(&token).await {
+        if let Ok(user_info_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
             if let Some(user_info) = user_info_result.get("user_info") {
                 // 提取用户基本信息（包含api_key）
                 if let Some(user) = user_info.get("user") {
@@ -1514,7 +1794,7 @@ async fn refresh_token_internal(
     
     // 如果使用轻量级 API，需要单独获取 is_team_owner
     if updated_account.is_team_owner.is_none() {
-        if let Ok(user_result) = windsurf_service.get_current_user(&token).await {
+        if let Ok(user_result) = user_info_cache::get_current_user_cached(&windsurf_service, &token).await {
             if let Some(user_info) = user_result.get("user_info") {
                 let is_root_admin = user_info.get("is_root_admin")
                     .and_then(|v| v.as_bool())
@@ -1557,7 +1837,7 @@ pub async fn get_trial_payment_link(
     id: String,
     price_id: Option<String>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取账号信息
@@ -1575,10 +1855,9 @@ pub async fn get_trial_payment_link(
     let final_price_id = price_id.as_deref().unwrap_or(default_price_id);
 
     // 调用Windsurf API获取支付链接
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.subscribe_to_plan(&token, final_price_id)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     // 记录日志
     let success = result.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
@@ -1610,7 +1889,7 @@ pub async fn get_trial_payment_link(
 pub async fn get_team_config(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取账号信息
@@ -1624,10 +1903,9 @@ pub async fn get_team_config(
     let token = account.token.ok_or("No token available")?;
 
     // 调用API获取团队配置
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_team_config(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     Ok(result)
 }
@@ -1638,7 +1916,7 @@ pub async fn update_team_config(
     id: String,
     config: serde_json::Value,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取账号信息
@@ -1652,10 +1930,9 @@ pub async fn update_team_config(
     let token = account.token.ok_or("No token available")?;
 
     // 调用API更新团队配置
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.update_team_config(&token, config)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     // 记录日志
     let success = result.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
@@ -1680,7 +1957,7 @@ pub async fn update_team_config(
 pub async fn get_cascade_model_configs(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     let mut account = store.get_account(uuid)
@@ -1691,10 +1968,9 @@ pub async fn get_cascade_model_configs(
 
     let token = account.token.ok_or("No token available")?;
 
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_cascade_model_configs(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     Ok(result)
 }
@@ -1704,7 +1980,7 @@ pub async fn get_cascade_model_configs(
 pub async fn get_command_model_configs(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     let mut account = store.get_account(uuid)
@@ -1715,10 +1991,9 @@ pub async fn get_command_model_configs(
 
     let token = account.token.ok_or("No token available")?;
 
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_command_model_configs(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     Ok(result)
 }
@@ -1728,7 +2003,7 @@ pub async fn get_command_model_configs(
 pub async fn get_team_organizational_controls(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     let mut account = store.get_account(uuid)
@@ -1739,10 +2014,9 @@ pub async fn get_team_organizational_controls(
 
     let token = account.token.ok_or("No token available")?;
 
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.get_team_organizational_controls(&token)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     Ok(result)
 }
@@ -1756,7 +2030,7 @@ pub async fn upsert_team_organizational_controls(
     command_models: Vec<String>,
     extension_models: Vec<String>,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     let mut account = store.get_account(uuid)
@@ -1767,7 +2041,7 @@ pub async fn upsert_team_organizational_controls(
 
     let token = account.token.ok_or("No token available")?;
 
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     let result = windsurf_service.upsert_team_organizational_controls(
         &token,
         &team_id,
@@ -1775,8 +2049,7 @@ pub async fn upsert_team_organizational_controls(
         command_models,
         extension_models,
     )
-        .await
-        .map_err(|e: AppError| e.to_string())?;
+        .await?;
 
     Ok(result)
 }
@@ -1786,7 +2059,7 @@ pub async fn upsert_team_organizational_controls(
 pub async fn get_available_mcp_plugins(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取账号信息
@@ -1797,19 +2070,69 @@ pub async fn get_available_mcp_plugins(
     // 确保有有效的Token
     ensure_valid_token(&store, &mut account, uuid).await?;
 
-    // 获取 api_key (windsurf_api_key)
-    let api_key = account.windsurf_api_key.clone().unwrap_or_default();
-    if api_key.is_empty() {
-        return Err("账号没有 API Key，请先刷新账号信息".to_string());
+    // 调用 API 获取 MCP 插件列表（API Key 失效时自动重新获取）
+    let windsurf_service = WindsurfService::for_account(uuid);
+    call_with_api_key(&store, &mut account, uuid, |api_key| {
+        let windsurf_service = &windsurf_service;
+        async move { windsurf_service.get_available_mcp_plugins(&api_key).await }
+    })
+    .await
+}
+
+/// 开发者控制台：使用账号的 Token 向任意 Windsurf 接口发送原始 protobuf 请求体（开发者模式）
+///
+/// `hex_body` 为十六进制编码的请求体，返回原始响应（十六进制）与尝试解析的结果
+#[tauri::command]
+pub async fn call_windsurf_endpoint(
+    id: String,
+    path: String,
+    hex_body: String,
+    store: State<'_, Arc<DataStore>>,
+) -> CommandResult<serde_json::Value> {
+    if !super::analytics_commands::is_dev_mode(&store).await {
+        return Err("需要开启开发者模式".into());
     }
 
-    // 调用 API 获取 MCP 插件列表
-    let windsurf_service = WindsurfService::new();
-    let result = windsurf_service.get_available_mcp_plugins(&api_key)
+    let compact: String = hex_body.chars().filter(|c| !c.is_whitespace()).collect();
+    let body = hex::decode(&compact).map_err(|e| format!("请求体不是有效的十六进制: {}", e))?;
+
+    let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let mut account = store.get_account(uuid)
         .await
-        .map_err(|e: AppError| e.to_string())?;
+        .map_err(|e| e.to_string())?;
+    ensure_valid_token(&store, &mut account, uuid).await?;
+    let token = account.token.clone().ok_or("No token available")?;
 
-    Ok(result)
+    crate::utils::proto_debug::log_body(&format!("Console request {}", path), &body);
+    let (status_code, response_body) = WindsurfService::for_account(uuid)
+        .call_raw_endpoint(&path, &token, body)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::utils::proto_debug::log_body(&format!("Console response {}", path), &response_body);
+
+    let (parsed, parse_error) = match crate::services::proto_parser::ProtobufParser::new(response_body.clone()).parse_message() {
+        Ok(parsed) => (Some(parsed), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    Ok(json!({
+        "success": status_code == 200,
+        "status_code": status_code,
+        "raw_hex": hex::encode(&response_body),
+        "raw_text": String::from_utf8_lossy(&response_body),
+        "parsed": parsed,
+        "parse_error": parse_error,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// 开发者控制台：由字段列表（字段号 + 类型 + 值）编码请求体，返回十六进制，可直接用于 call_windsurf_endpoint
+#[tauri::command]
+pub async fn encode_proto_body(
+    fields: Vec<crate::utils::proto_builder::ProtoField>,
+) -> CommandResult<String> {
+    let body = crate::utils::proto_builder::encode_fields(&fields)?;
+    Ok(hex::encode(body))
 }
 
 /// 删除用户 (Windsurf DeleteUser API)
@@ -1817,7 +2140,7 @@ pub async fn get_available_mcp_plugins(
 pub async fn delete_windsurf_user(
     id: String,
     store: State<'_, Arc<DataStore>>,
-) -> Result<serde_json::Value, String> {
+) -> CommandResult<serde_json::Value> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
     // 获取账号信息
@@ -1830,22 +2153,16 @@ pub async fn delete_windsurf_user(
 
     let token = account.token.clone().unwrap_or_default();
     if token.is_empty() {
-        return Err("账号没有有效的 Token".to_string());
-    }
-
-    // 获取 api_key
-    let api_key = account.windsurf_api_key.clone().unwrap_or_default();
-    if api_key.is_empty() {
-        return Err("账号没有 API Key，请先刷新账号信息".to_string());
+        return Err(CommandError::Auth("账号没有有效的 Token".to_string()));
     }
 
     log::info!("[DeleteWindsurfUser] Deleting user for account: {}", account.email);
 
-    // 调用 DeleteUser API
-    let windsurf_service = WindsurfService::new();
-    let result = windsurf_service.delete_user(&token, &api_key)
-        .await
-        .map_err(|e: AppError| e.to_string())?;
-
-    Ok(result)
+    // 调用 DeleteUser API（API Key 失效时自动重新获取）
+    let windsurf_service = WindsurfService::for_account(uuid);
+    call_with_api_key(&store, &mut account, uuid, |api_key| {
+        let (windsurf_service, token) = (&windsurf_service, &token);
+        async move { windsurf_service.delete_user(token, &api_key).await }
+    })
+    .await
 }
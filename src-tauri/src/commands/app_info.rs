@@ -1,6 +1,9 @@
-use tauri::{command, AppHandle};
+use tauri::{command, AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder};
 use serde_json::json;
+use std::sync::Arc;
+use crate::repository::DataStore;
 use crate::services;
+use crate::utils::format::{self, MetricKind};
 
 /// 获取应用版本信息
 #[command]
@@ -22,6 +25,46 @@ pub async fn get_app_title(app: AppHandle) -> Result<String, String> {
     Ok(format!("windsurf-account-manager-simple v{}", version))
 }
 
+/// 批量格式化数值（字节、紧凑计数、千分位、百分比），保证各界面显示一致
+///
+/// `locale` 为空时使用设置中的界面语言
+#[command]
+pub async fn format_metrics(
+    values: Vec<f64>,
+    kind: MetricKind,
+    locale: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<String>, String> {
+    let locale = match locale {
+        Some(locale) => locale,
+        None => store.get_settings().await.map_err(|e| e.to_string())?.language,
+    };
+    Ok(values.into_iter().map(|v| format::format_metric(v, kind, &locale)).collect())
+}
+
+/// 打开只读仪表盘窗口
+///
+/// 窗口标签为 `dashboard`，只被授予 capabilities/dashboard.json 中的只读命令
+#[command]
+pub async fn open_dashboard_window(app: AppHandle, view: Option<String>) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("dashboard") {
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    
+    // 前端根据 view 参数渲染对应视图
+    let view = view.unwrap_or_else(|| "dashboard".to_string());
+    let url = format!("index.html?view={}", view);
+    WebviewWindowBuilder::new(&app, "dashboard", WebviewUrl::App(url.into()))
+        .title("Dashboard")
+        .inner_size(1000.0, 700.0)
+        .resizable(true)
+        .center()
+        .build()
+        .map_err(|e| format!("创建仪表盘窗口失败: {}", e))?;
+    Ok(())
+}
+
 /// 重置HTTP客户端（用于从网络故障中恢复）
 #[command]
 pub async fn reset_http_client() -> Result<serde_json::Value, String> {
@@ -103,6 +103,33 @@ impl AutoResetStore {
         }
     }
     
+    /// 按目标（类型 + ID）合并导入的配置：已存在的更新规则参数，不存在的新增
+    pub async fn upsert_by_target(&self, imported: Vec<AutoResetConfig>) -> Result<(usize, usize), String> {
+        let mut configs = self.configs.write().await;
+        let (mut added, mut updated) = (0, 0);
+        
+        for config in imported {
+            if let Some(existing) = configs.iter_mut().find(|c| c.target_type == config.target_type && c.target_id == config.target_id) {
+                existing.enabled = config.enabled;
+                existing.check_interval = config.check_interval;
+                existing.usage_threshold = config.usage_threshold;
+                existing.remaining_threshold = config.remaining_threshold;
+                updated += 1;
+            } else {
+                let mut config = config;
+                config.id = uuid::Uuid::new_v4().to_string();
+                config.last_check_at = None;
+                config.last_reset_at = None;
+                configs.push(config);
+                added += 1;
+            }
+        }
+        
+        drop(configs);
+        self.save().await?;
+        Ok((added, updated))
+    }
+    
     pub async fn update_check_time(&self, id: &str) -> Result<(), String> {
         let mut configs = self.configs.write().await;
         
@@ -356,13 +383,13 @@ pub async fn check_and_auto_reset(
         return Ok(json!({ "skipped": true, "reason": "没有符合条件的主号" }));
     }
     
-    let windsurf_service = WindsurfService::new();
     let mut checked_count = 0;
     let mut reset_count = 0;
     let mut results = Vec::new();
     
     // 遍历每个主号，获取其团队成员并检测
     for master_account in master_accounts {
+        let windsurf_service = WindsurfService::for_account(master_account.id);
         // 检查主号是否有有效的 token
         let master_token = match &master_account.token {
             Some(t) if !t.is_empty() => t.clone(),
@@ -753,12 +780,12 @@ pub async fn force_reset_config(
         return Ok(json!({ "skipped": true, "reason": "没有符合条件的主号" }));
     }
     
-    let windsurf_service = WindsurfService::new();
     let mut reset_count = 0;
     let mut results = Vec::new();
     
     // 遍历每个主号，获取其团队成员并重置
     for master_account in master_accounts {
+        let windsurf_service = WindsurfService::for_account(master_account.id);
         let master_token = match &master_account.token {
             Some(t) if !t.is_empty() => t.clone(),
             _ => continue,
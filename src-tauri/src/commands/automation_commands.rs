@@ -0,0 +1,321 @@
+use crate::commands::AutoResetStore;
+use crate::models::{Account, AutoExportConfig, AutoResetConfig, HealthPingConfig, PluginConfig, SessionLabelRule};
+use crate::repository::DataStore;
+use crate::services::{plugin_service, session_label_service};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+/// 自动化配置文档的格式版本
+pub const AUTOMATION_DOC_VERSION: u32 = 1;
+
+/// 自动化配置文档：规则、定时任务、事件钩子与策略
+///
+/// 导入时缺省的部分保持不变，便于只在 git 中维护其中一部分
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutomationDocument {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<AutomationRules>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedules: Option<AutomationSchedules>,
+    /// 事件钩子（外部进程插件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Vec<PluginConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policies: Option<AutomationPolicies>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutomationRules {
+    /// 自动重置规则（不含检查/重置时间等运行状态）
+    #[serde(default)]
+    pub auto_reset: Vec<AutoResetConfig>,
+    #[serde(default)]
+    pub session_labels: Vec<SessionLabelRule>,
+    /// 账号目标 ID 对应的邮箱，导入到其他机器时据此重新映射
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub account_emails: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutomationSchedules {
+    #[serde(default)]
+    pub auto_export: AutoExportConfig,
+    #[serde(default)]
+    pub health_ping: HealthPingConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutomationPolicies {
+    #[serde(default)]
+    pub auth_failure_threshold: Option<u32>,
+    #[serde(default)]
+    pub quota_alert_percent: Option<f64>,
+    #[serde(default)]
+    pub model_credit_rates: Option<HashMap<String, f64>>,
+}
+
+/// 路径扩展名为 .yaml / .yml 时使用 YAML，否则使用 JSON
+fn is_yaml_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"))
+        .unwrap_or(false)
+}
+
+/// 解析文档内容：以 `{` 开头按 JSON 解析，否则按 YAML 解析
+fn parse_document(content: &str) -> Result<AutomationDocument, String> {
+    let document: AutomationDocument = if content.trim_start().starts_with('{') {
+        serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?
+    } else {
+        serde_yaml::from_str(content).map_err(|e| format!("Invalid YAML: {}", e))?
+    };
+    if document.version > AUTOMATION_DOC_VERSION {
+        return Err(format!(
+            "不支持的配置版本 {}（当前支持 {}）",
+            document.version, AUTOMATION_DOC_VERSION
+        ));
+    }
+    Ok(document)
+}
+
+/// 把导入的自动重置规则映射到本机的账号与分组
+///
+/// 账号目标先按 ID 匹配，再按导出时记录的邮箱匹配；本机不存在的账号或分组被丢弃
+fn remap_auto_reset_targets(
+    configs: Vec<AutoResetConfig>,
+    account_emails: &HashMap<String, String>,
+    accounts: &[Account],
+    groups: &[String],
+) -> (Vec<AutoResetConfig>, Vec<String>) {
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+    for mut config in configs {
+        let target = if config.target_type == "account" {
+            if accounts.iter().any(|a| a.id.to_string() == config.target_id) {
+                Some(config.target_id.clone())
+            } else {
+                account_emails
+                    .get(&config.target_id)
+                    .and_then(|email| accounts.iter().find(|a| a.email.eq_ignore_ascii_case(email)))
+                    .map(|a| a.id.to_string())
+            }
+        } else if groups.contains(&config.target_id) {
+            Some(config.target_id.clone())
+        } else {
+            None
+        };
+        match target {
+            Some(target_id) => {
+                config.target_id = target_id;
+                kept.push(config);
+            }
+            None => dropped.push(format!("{}:{}", config.target_type, config.target_id)),
+        }
+    }
+    (kept, dropped)
+}
+
+/// 导出自动化配置为 YAML 或 JSON 文档（按文件扩展名决定）
+#[tauri::command]
+pub async fn export_automation_config(
+    export_path: String,
+    store: State<'_, Arc<DataStore>>,
+    auto_reset_store: State<'_, Arc<AutoResetStore>>,
+) -> Result<Value, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let labels = store.get_session_labels().await.map_err(|e| e.to_string())?;
+
+    let auto_reset: Vec<AutoResetConfig> = auto_reset_store
+        .get_all()
+        .await
+        .into_iter()
+        .map(|mut config| {
+            config.last_check_at = None;
+            config.last_reset_at = None;
+            config
+        })
+        .collect();
+
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let account_emails: HashMap<String, String> = auto_reset
+        .iter()
+        .filter(|c| c.target_type == "account")
+        .filter_map(|c| {
+            accounts
+                .iter()
+                .find(|a| a.id.to_string() == c.target_id)
+                .map(|a| (c.target_id.clone(), a.email.clone()))
+        })
+        .collect();
+
+    let document = AutomationDocument {
+        version: AUTOMATION_DOC_VERSION,
+        exported_at: Some(Utc::now()),
+        rules: Some(AutomationRules {
+            auto_reset,
+            session_labels: labels.rules,
+            account_emails,
+        }),
+        schedules: Some(AutomationSchedules {
+            auto_export: settings.auto_export,
+            health_ping: settings.health_ping,
+        }),
+        hooks: Some(settings.plugins),
+        policies: Some(AutomationPolicies {
+            auth_failure_threshold: Some(settings.auth_failure_threshold),
+            quota_alert_percent: Some(settings.quota_alert_percent),
+            model_credit_rates: Some(settings.model_credit_rates),
+        }),
+    };
+
+    let path = Path::new(&export_path);
+    let content = if is_yaml_path(path) {
+        serde_yaml::to_string(&document).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?
+    };
+    std::fs::write(path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    println!("[Automation] Exported automation config to {}", export_path);
+    Ok(json!({
+        "success": true,
+        "path": export_path,
+        "format": if is_yaml_path(path) { "yaml" } else { "json" },
+    }))
+}
+
+/// 导入自动化配置文档（YAML 或 JSON），文档中出现的部分覆盖本地配置
+///
+/// 钩子会在本机启动外部程序，只有 `install_hooks` 为 true 时才导入；
+/// 否则跳过并在结果中返回 `hooks_skipped`，由前端确认后再次导入
+#[tauri::command]
+pub async fn import_automation_config(
+    import_path: String,
+    install_hooks: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+    auto_reset_store: State<'_, Arc<AutoResetStore>>,
+) -> Result<Value, String> {
+    let content = std::fs::read_to_string(&import_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let document = parse_document(&content)?;
+
+    // 先校验，避免导入一半后失败
+    if let Some(rules) = &document.rules {
+        session_label_service::validate_rules(&rules.session_labels).map_err(|e| e.to_string())?;
+    }
+
+    let mut sections = Vec::new();
+    let mut auto_reset = (0, 0);
+    let mut auto_reset_dropped = Vec::new();
+
+    if let Some(rules) = document.rules {
+        let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+        let groups = store.get_groups().await.map_err(|e| e.to_string())?;
+        let (configs, dropped) = remap_auto_reset_targets(rules.auto_reset, &rules.account_emails, &accounts, &groups);
+        if !dropped.is_empty() {
+            println!("[Automation] Dropped auto-reset rules for unknown targets: {:?}", dropped);
+        }
+        auto_reset_dropped = dropped;
+        auto_reset = auto_reset_store.upsert_by_target(configs).await?;
+        store
+            .set_session_label_rules(rules.session_labels)
+            .await
+            .map_err(|e| e.to_string())?;
+        sections.push("rules");
+    }
+
+    let mut settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let mut plugins_changed = false;
+    let mut hooks_skipped = false;
+    if let Some(schedules) = document.schedules {
+        settings.auto_export = schedules.auto_export;
+        settings.health_ping = schedules.health_ping;
+        sections.push("schedules");
+    }
+    if let Some(hooks) = document.hooks {
+        if install_hooks.unwrap_or(false) {
+            settings.plugins = hooks;
+            plugins_changed = true;
+            sections.push("hooks");
+        } else if !hooks.is_empty() {
+            hooks_skipped = true;
+        }
+    }
+    if let Some(policies) = document.policies {
+        if let Some(threshold) = policies.auth_failure_threshold {
+            settings.auth_failure_threshold = threshold;
+        }
+        if let Some(percent) = policies.quota_alert_percent {
+            settings.quota_alert_percent = percent;
+        }
+        if let Some(rates) = policies.model_credit_rates {
+            settings.model_credit_rates = rates;
+        }
+        sections.push("policies");
+    }
+    store.update_settings(settings).await.map_err(|e| e.to_string())?;
+
+    // 插件配置变化后停止旧进程，下次事件时按新配置启动
+    if plugins_changed {
+        plugin_service::stop_all().await;
+    }
+
+    println!("[Automation] Imported automation config from {}: {:?}", import_path, sections);
+    Ok(json!({
+        "success": true,
+        "sections": sections,
+        "auto_reset_added": auto_reset.0,
+        "auto_reset_updated": auto_reset.1,
+        "auto_reset_dropped": auto_reset_dropped,
+        "hooks_skipped": hooks_skipped,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document() {
+        let yaml = "version: 1\npolicies:\n  quota_alert_percent: 5.0\nhooks: []\n";
+        let document = parse_document(yaml).unwrap();
+        assert!(document.rules.is_none());
+        assert_eq!(document.hooks.map(|h| h.len()), Some(0));
+        assert_eq!(document.policies.unwrap().quota_alert_percent, Some(5.0));
+
+        let json = r#"{"version":1,"schedules":{"health_ping":{"enabled":true}}}"#;
+        let document = parse_document(json).unwrap();
+        assert!(document.schedules.unwrap().health_ping.enabled);
+
+        assert!(parse_document("version: 99").is_err());
+        assert!(is_yaml_path(Path::new("automation.YML")));
+        assert!(!is_yaml_path(Path::new("automation.json")));
+    }
+
+    #[test]
+    fn test_remap_auto_reset_targets() {
+        let local = Account::new("a@example.com".to_string(), String::new(), "A".to_string(), vec![]);
+        let groups = vec!["team".to_string()];
+        let emails = HashMap::from([("remote-id".to_string(), "A@example.com".to_string())]);
+        let configs = vec![
+            AutoResetConfig::new("account".to_string(), "remote-id".to_string()),
+            AutoResetConfig::new("account".to_string(), "missing-id".to_string()),
+            AutoResetConfig::new("group".to_string(), "team".to_string()),
+            AutoResetConfig::new("group".to_string(), "other".to_string()),
+        ];
+
+        let (kept, dropped) = remap_auto_reset_targets(configs, &emails, std::slice::from_ref(&local), &groups);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].target_id, local.id.to_string());
+        assert_eq!(kept[1].target_id, "team");
+        assert_eq!(dropped, vec!["account:missing-id".to_string(), "group:other".to_string()]);
+    }
+}
@@ -12,6 +12,9 @@ pub mod analytics_commands;
 pub mod team_commands;
 pub mod auto_reset_commands;
 pub mod cunzhi_commands;
+pub mod scheduler_commands;
+pub mod palette_commands;
+pub mod automation_commands;
 
 pub use account_commands::*;
 pub use api_commands::*;
@@ -27,3 +30,6 @@ pub use analytics_commands::*;
 pub use team_commands::*;
 pub use auto_reset_commands::*;
 pub use cunzhi_commands::*;
+pub use scheduler_commands::*;
+pub use palette_commands::*;
+pub use automation_commands::*;
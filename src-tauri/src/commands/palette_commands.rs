@@ -0,0 +1,172 @@
+use crate::repository::DataStore;
+use crate::services::export_service;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::sync::Arc;
+use tauri::State;
+
+/// 命令面板动作描述（供前端 Ctrl+K 面板展示与模糊搜索）
+#[derive(Debug, Clone, Serialize)]
+pub struct PaletteAction {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub title_en: &'static str,
+    pub description: &'static str,
+    /// 额外的搜索关键词
+    pub keywords: &'static [&'static str],
+    /// 是否需要在 args 中提供 account_id
+    pub requires_account: bool,
+}
+
+/// 已注册的命令面板动作
+const PALETTE_ACTIONS: &[PaletteAction] = &[
+    PaletteAction {
+        id: "switch_account",
+        title: "切换账号",
+        title_en: "Switch account",
+        description: "将 Windsurf 切换到所选账号",
+        keywords: &["switch", "qiehuan", "login", "use"],
+        requires_account: true,
+    },
+    PaletteAction {
+        id: "refresh_token",
+        title: "刷新 Token",
+        title_en: "Refresh token",
+        description: "刷新所选账号的访问令牌",
+        keywords: &["refresh", "token", "shuaxin"],
+        requires_account: true,
+    },
+    PaletteAction {
+        id: "export_now",
+        title: "立即导出",
+        title_en: "Export now",
+        description: "按自动导出设置立即导出账号与分析数据",
+        keywords: &["export", "backup", "daochu", "csv"],
+        requires_account: false,
+    },
+    PaletteAction {
+        id: "open_analytics",
+        title: "打开使用分析",
+        title_en: "Open analytics",
+        description: "打开所选账号的使用分析页面",
+        keywords: &["analytics", "usage", "fenxi", "stats"],
+        requires_account: true,
+    },
+];
+
+/// 模糊匹配得分：query 的字符需按顺序出现在 text 中，连续匹配与靠前匹配得分更高
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0i64;
+    let mut qi = 0;
+    let mut last_match: Option<usize> = None;
+    for (ti, c) in text.iter().enumerate() {
+        if qi < query.len() && *c == query[qi] {
+            score += match last_match {
+                Some(last) if last + 1 == ti => 5,
+                _ => 1,
+            };
+            if ti == 0 {
+                score += 3;
+            }
+            last_match = Some(ti);
+            qi += 1;
+        }
+    }
+    if qi == query.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+/// 动作与查询的最佳匹配得分（标题、英文标题、ID、关键词中取最高）
+fn action_score(action: &PaletteAction, query: &str) -> Option<i64> {
+    [action.title, action.title_en, action.id]
+        .iter()
+        .chain(action.keywords.iter())
+        .filter_map(|text| fuzzy_score(query, text))
+        .max()
+}
+
+/// 列出命令面板动作，提供 query 时按模糊匹配得分排序并过滤
+#[tauri::command]
+pub async fn list_palette_actions(query: Option<String>) -> Result<Vec<PaletteAction>, String> {
+    let query = query.unwrap_or_default();
+    let mut matched: Vec<(i64, &PaletteAction)> = PALETTE_ACTIONS
+        .iter()
+        .filter_map(|action| action_score(action, &query).map(|score| (score, action)))
+        .collect();
+    matched.sort_by_key(|(score, _)| Reverse(*score));
+    Ok(matched.into_iter().map(|(_, action)| action.clone()).collect())
+}
+
+/// 执行命令面板动作
+#[tauri::command]
+pub async fn execute_palette_action(
+    action_id: String,
+    args: Option<Value>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let args = args.unwrap_or(Value::Null);
+    let action = PALETTE_ACTIONS
+        .iter()
+        .find(|a| a.id == action_id)
+        .ok_or_else(|| format!("未知的命令: {}", action_id))?;
+
+    let account_id = args
+        .get("account_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    if action.requires_account && account_id.is_none() {
+        return Err(format!("命令 {} 需要指定账号", action.id));
+    }
+
+    println!("[Palette] Executing action: {}", action.id);
+    match action.id {
+        "switch_account" => super::switch_account(account_id.unwrap_or_default(), store).await,
+        "refresh_token" => super::refresh_token(account_id.unwrap_or_default(), store).await.map_err(|e| e.to_string()),
+        "export_now" => {
+            let mut config = store.get_settings().await.map_err(|e| e.to_string())?.auto_export;
+            if let Some(folder) = args.get("folder").and_then(|v| v.as_str()) {
+                config.folder = folder.to_string();
+            }
+            if config.folder.trim().is_empty() {
+                return Err("未配置导出目录".to_string());
+            }
+            let export_dir = export_service::write_export(&store, &config)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "success": true, "export_dir": export_dir.to_string_lossy() }))
+        }
+        "open_analytics" => {
+            // 页面跳转由前端完成，这里通知前端目标页面
+            let payload = json!({ "route": "analytics", "account_id": account_id });
+            store.emit_event("palette-navigate", payload.clone());
+            Ok(json!({ "success": true, "navigate": payload }))
+        }
+        _ => Err(format!("未实现的命令: {}", action.id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("rt", "refresh_token").is_some());
+        assert!(fuzzy_score("xyz", "refresh_token").is_none());
+        assert!(fuzzy_score("exp", "export_now") > fuzzy_score("exp", "open_analytics_export"));
+
+        let switch = PALETTE_ACTIONS.iter().find(|a| a.id == "switch_account").unwrap();
+        assert!(action_score(switch, "qh").is_some());
+        assert!(action_score(switch, "切换").is_some());
+    }
+}
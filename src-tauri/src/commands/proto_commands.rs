@@ -1,6 +1,7 @@
 use crate::models::{OperationLog, OperationType, OperationStatus};
 use crate::repository::DataStore;
 use crate::services::WindsurfService;
+use crate::utils::proto_debug::{self, CaptureInfo};
 use crate::utils::AppError;
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -29,7 +30,7 @@ pub async fn get_current_user_parsed(
     let token = account.token.ok_or("No token available")?;
     
     // 用户详情始终使用完整的 GetCurrentUser API（不受轻量级 API 设置影响）
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     println!("[get_current_user_parsed] Using GetCurrentUser API for account: {}", id);
     
     let result: serde_json::Value = windsurf_service.get_current_user(&token)
@@ -88,7 +89,7 @@ pub async fn get_billing_parsed(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     // 暂时调用原始API，后续可添加Protobuf解析
-    super::api_commands::get_billing(id, store).await
+    super::api_commands::get_billing(id, store).await.map_err(|e| e.to_string())
 }
 
 /// 批量获取用户信息（带Protobuf解析）
@@ -125,3 +126,15 @@ pub async fn batch_get_users_parsed(
         "timestamp": chrono::Utc::now().to_rfc3339(),
     }))
 }
+
+/// 列出抓包文件（设置中开启抓包模式后生成），最新的在前
+#[tauri::command]
+pub async fn list_captures() -> Result<Vec<CaptureInfo>, String> {
+    Ok(proto_debug::list_captures())
+}
+
+/// 读取单个抓包文件：请求/响应的十六进制与解析结果
+#[tauri::command]
+pub async fn open_capture(name: String) -> Result<Value, String> {
+    proto_debug::read_capture(&name)
+}
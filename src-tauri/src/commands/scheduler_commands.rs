@@ -0,0 +1,105 @@
+use crate::services::autostart::{self, AutostartMode, AutostartStatus};
+use crate::repository::DataStore;
+use crate::services::daemon_ipc;
+use crate::services::export_service;
+use crate::services::network_cost::{self, MeteredStatus};
+use crate::models::JobKind;
+use crate::services::scheduler::{JobStatus, Scheduler, WatchdogEvent};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::State;
+
+/// 获取后台定时任务状态
+#[tauri::command]
+pub async fn get_scheduler_status(
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<Vec<JobStatus>, String> {
+    // 后台进程运行时，定时任务由后台进程执行
+    if daemon_ipc::is_connected() {
+        let status = daemon_ipc::call("scheduler_status", Value::Null)
+            .await
+            .map_err(|e| e.to_string())?;
+        return serde_json::from_value(status).map_err(|e| e.to_string());
+    }
+    Ok(scheduler.get_status().await)
+}
+
+/// 立即执行一次指定的后台任务
+#[tauri::command]
+pub async fn run_scheduler_job(
+    job: JobKind,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<String, String> {
+    if daemon_ipc::is_connected() {
+        let result = daemon_ipc::call("run_job", json!({ "job": job }))
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(result.as_str().unwrap_or_default().to_string());
+    }
+    scheduler.run_job(job).await
+}
+
+/// 获取看门狗因运行超时而取消的后台任务，用于诊断卡住的定时任务
+#[tauri::command]
+pub async fn get_scheduler_watchdog_events(
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<Vec<WatchdogEvent>, String> {
+    if daemon_ipc::is_connected() {
+        let events = daemon_ipc::call("watchdog_events", Value::Null)
+            .await
+            .map_err(|e| e.to_string())?;
+        return serde_json::from_value(events).map_err(|e| e.to_string());
+    }
+    Ok(scheduler.get_watchdog_events().await)
+}
+
+/// 导出任务历史 CSV：批量操作日志与后台定时任务记录，`since` / `until` 为空时不限制
+#[tauri::command]
+pub async fn export_job_history(
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    export_path: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    // 定时任务记录已持久化，后台进程写入的记录会同步到本进程
+    let runs = store.get_job_history().await.map_err(|e| e.to_string())?;
+    let logs = store.get_logs(None).await.map_err(|e| e.to_string())?;
+
+    let rows = export_service::job_history_rows(&logs, &runs, since, until);
+    std::fs::write(&export_path, export_service::job_history_csv(&rows)).map_err(|e| format!("Failed to write file: {}", e))?;
+    println!("[JobHistory] Exported {} rows to {}", rows.len(), export_path);
+
+    Ok(json!({
+        "success": true,
+        "path": export_path,
+        "rows": rows.len(),
+    }))
+}
+
+/// 前端报告用户操作（节流调用），用于判断应用是否空闲
+#[tauri::command]
+pub async fn report_user_activity() -> Result<(), String> {
+    crate::services::prefetch_service::record_activity();
+    Ok(())
+}
+
+/// 获取当前网络是否按流量计费（手动标记 + 系统检测），用于诊断定时任务被跳过的原因
+#[tauri::command]
+pub async fn get_metered_status(store: State<'_, Arc<DataStore>>) -> Result<MeteredStatus, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    Ok(network_cost::status(settings.metered_connection).await)
+}
+
+/// 获取开机自启动状态（完整界面 / 仅后台服务）
+#[tauri::command]
+pub async fn get_autostart_status() -> Result<AutostartStatus, String> {
+    Ok(autostart::status())
+}
+
+/// 设置开机自启动；`daemon` 方式只启动调度器与通知插件，与完整界面的自启动项互相独立
+#[tauri::command]
+pub async fn set_autostart(mode: AutostartMode, enabled: bool) -> Result<AutostartStatus, String> {
+    autostart::set_enabled(mode, enabled).map_err(|e| e.to_string())?;
+    Ok(autostart::status())
+}
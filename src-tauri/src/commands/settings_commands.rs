@@ -1,5 +1,10 @@
-use crate::models::{Settings, OperationLog, GlobalTag, SortField, SortDirection, SortConfig, Account};
-use crate::repository::{DataStore, ImportResult, BackupInfo};
+use crate::models::{Settings, OperationLog, OperationStatus, OperationType, GlobalTag, SortField, SortDirection, SortConfig, Account, AccountFilter, AccountPage, LogFilter, LogPage};
+use crate::repository::{secrets, DataStore, ImportResult, BackupInfo, DeletedArchiveInfo, IntegrityReport, MigrationReport};
+use crate::services::export_service::{self, RestoreResult};
+use crate::services::keyring_service;
+use crate::services::daemon_ipc;
+use crate::services::plugin_service::{self, PluginStatus};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use std::path::PathBuf;
 use tauri::State;
@@ -13,14 +18,58 @@ pub async fn get_settings(
         .map_err(|e| e.to_string())
 }
 
+/// 获取去掉密钥与连接串的设置（只读面板使用）
+#[tauri::command]
+pub async fn get_redacted_settings(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Settings, String> {
+    let settings = store.get_settings()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(settings.redacted())
+}
+
 #[tauri::command]
 pub async fn update_settings(
-    settings: Settings,
+    mut settings: Settings,
     store: State<'_, Arc<DataStore>>,
 ) -> Result<(), String> {
     // 检查配置变化
     let old_settings = store.get_settings().await.map_err(|e| e.to_string())?;
     
+    if let Some(preset) = settings.analytics_presets.iter()
+        .find(|p| p.name.trim().is_empty() || (p.query_fields.is_empty() && p.time_zone_fields.is_empty()))
+    {
+        return Err(format!("请求预设 '{}' 需要名称和至少一个查询字段", preset.name));
+    }
+    if let Some(name) = &settings.analytics_preset {
+        if crate::services::analytics_service::find_preset(&settings.analytics_presets, name).is_none() {
+            return Err(format!("未找到请求预设: {}", name));
+        }
+    }
+    if let Some(tz) = settings.timezone.as_deref().filter(|tz| !tz.trim().is_empty()) {
+        if crate::utils::date_utils::parse_timezone(Some(tz)).is_none() {
+            return Err(format!("无效的时区: {}", tz));
+        }
+    }
+    if settings.request_timeout_secs == 0 {
+        return Err("请求超时必须大于 0 秒".to_string());
+    }
+    if settings.analytics_cache_ttl_minutes < 0 {
+        return Err("分析数据缓存时长不能为负数".to_string());
+    }
+    if settings.storage_backend != keyring_service::BACKEND_DATASTORE
+        && settings.storage_backend != keyring_service::BACKEND_KEYRING {
+        return Err(format!("不支持的 Refresh Token 存储位置: {}", settings.storage_backend));
+    }
+    if ![secrets::CACHE_PLAINTEXT, secrets::CACHE_ENCRYPTED, secrets::CACHE_MEMORY].contains(&settings.cache_storage.as_str()) {
+        return Err(format!("不支持的缓存存放方式: {}", settings.cache_storage));
+    }
+    if settings.cache_storage == secrets::CACHE_ENCRYPTED
+        && store.config.read().await.secret_encryption.mode == secrets::MODE_NONE {
+        return Err("加密缓存需要先启用凭据加密".to_string());
+    }
+    
     // 代理配置变化
     if old_settings.proxy_enabled != settings.proxy_enabled 
         || old_settings.proxy_url != settings.proxy_url {
@@ -32,14 +81,94 @@ pub async fn update_settings(
         );
     }
     
+    // 请求超时变化
+    if old_settings.request_timeout_secs != settings.request_timeout_secs {
+        println!("[Settings] Request timeout changed: {}s", settings.request_timeout_secs);
+        crate::services::update_request_timeout(settings.request_timeout_secs);
+    }
+    
+    crate::utils::retry::set_policy(settings.api_retry.clone());
+    
+    // 限速配置变化时重置令牌桶
+    if old_settings.rate_limit != settings.rate_limit {
+        println!("[Settings] Rate limit changed: {:?}", settings.rate_limit);
+        crate::services::rate_limiter::set_config(settings.rate_limit.clone());
+    }
+    
+    if old_settings.custom_headers != settings.custom_headers {
+        crate::services::custom_headers::set_config(settings.custom_headers.clone());
+    }
+    
+    // 协议调试日志开关变化
+    if old_settings.proto_debug_logging != settings.proto_debug_logging {
+        println!("[Settings] Proto debug logging changed: {}", settings.proto_debug_logging);
+        crate::utils::proto_debug::set_enabled(settings.proto_debug_logging);
+    }
+    
+    // 抓包模式开关变化
+    if old_settings.proto_capture != settings.proto_capture {
+        println!("[Settings] Proto capture changed: {}", settings.proto_capture);
+        crate::utils::proto_debug::set_capture(settings.proto_capture);
+    }
+    
+    // 协议详细日志开关变化
+    if old_settings.verbose_protocol_logging != settings.verbose_protocol_logging {
+        println!("[Settings] Verbose protocol logging changed: {:?}", settings.verbose_protocol_logging);
+        crate::utils::proto_debug::set_verbose(settings.verbose_protocol_logging);
+    }
+    
     // 轻量级API配置变化
     if old_settings.use_lightweight_api != settings.use_lightweight_api {
         println!("[Settings] Lightweight API config changed: {}", settings.use_lightweight_api);
     }
     
+    // Refresh Token 存放位置变化：保存时写入密钥链或写回数据存储
+    if old_settings.storage_backend != settings.storage_backend {
+        println!("[Settings] Refresh token storage changed: {}", settings.storage_backend);
+    }
+    
+    // 分析缓存存放方式变化：保存设置后按新方式重写缓存
+    let cache_storage_changed = old_settings.cache_storage != settings.cache_storage;
+    let memory_only_enabled = !old_settings.memory_only_secrets && settings.memory_only_secrets;
+    if old_settings.memory_only_secrets != settings.memory_only_secrets {
+        println!("[Settings] Memory-only secrets changed: {}", settings.memory_only_secrets);
+    }
+    if cache_storage_changed {
+        println!("[Settings] Cache storage changed: {}", settings.cache_storage);
+    }
+    
+    // 存储后端变化：先按旧后端保存，再迁移到新后端；未传入时保持当前后端
+    if settings.persistence_backend.is_empty() {
+        settings.persistence_backend = store.storage_backend().to_string();
+    }
+    let backend_changed = settings.persistence_backend != store.storage_backend();
+    let backend = settings.persistence_backend.clone();
+    
     store.update_settings(settings)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    
+    // 再保存一次，让滚动备份也不再包含 Token
+    if memory_only_enabled {
+        store.save()
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    if cache_storage_changed {
+        store.apply_cache_storage()
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    if backend_changed {
+        println!("[Settings] Persistence backend changed: {}", backend);
+        store.switch_storage_backend(&backend)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    Ok(())
 }
 
 #[tauri::command]
@@ -92,6 +221,46 @@ pub async fn get_logs(
         .map_err(|e| e.to_string())
 }
 
+/// 按级别、模块、时间范围与关键字查询操作日志（最新的在前），默认每页 50 条
+#[tauri::command]
+pub async fn query_logs(
+    filter: Option<LogFilter>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<LogPage, String> {
+    store
+        .query_logs(&filter.unwrap_or_default(), page.unwrap_or(1), page_size.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 导出审计日志（操作日志）CSV，`since` / `until` 为空时不限制，供团队合规审查
+#[tauri::command]
+pub async fn export_audit_log(
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    export_path: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<serde_json::Value, String> {
+    let filter = LogFilter { since, until, ..Default::default() };
+    let logs: Vec<OperationLog> = store
+        .get_logs(None)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|log| filter.matches(log))
+        .collect();
+    std::fs::write(&export_path, export_service::audit_log_csv(&logs)).map_err(|e| format!("Failed to write file: {}", e))?;
+    println!("[AuditLog] Exported {} logs to {}", logs.len(), export_path);
+
+    Ok(serde_json::json!({
+        "success": true,
+        "path": export_path,
+        "rows": logs.len(),
+    }))
+}
+
 #[tauri::command]
 pub async fn clear_logs(
     store: State<'_, Arc<DataStore>>,
@@ -272,6 +441,40 @@ pub async fn restore_backup(
     }))
 }
 
+/// 获取 30 天内可恢复的删除归档
+#[tauri::command]
+pub async fn list_deleted_archives(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<DeletedArchiveInfo>, String> {
+    store.list_deleted_archives()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 从删除归档恢复账号（`file_name` 为 list_deleted_archives 返回的归档文件名）
+#[tauri::command]
+pub async fn restore_deleted_archive(
+    file_name: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<serde_json::Value, String> {
+    let restored = store.restore_deleted_archive(&file_name)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let log = OperationLog::new(
+        OperationType::DataRecovery,
+        OperationStatus::Success,
+        format!("从删除归档恢复账号: {}个", restored),
+    ).with_details(serde_json::json!({ "archive": file_name }));
+    let _ = store.add_log(log).await;
+    
+    Ok(serde_json::json!({
+        "success": true,
+        "restored": restored,
+        "message": format!("已恢复{}个账号", restored)
+    }))
+}
+
 /// 导出数据到指定路径
 #[tauri::command]
 pub async fn export_data_to_file(
@@ -290,19 +493,172 @@ pub async fn export_data_to_file(
     }))
 }
 
-/// 从文件导入数据
+/// 导出全部账号（JSON / CSV），`include_secrets` 控制是否包含密码与凭据，提供 `password` 时加密导出文件
+#[tauri::command]
+pub async fn export_accounts(
+    export_path: String,
+    format: String,
+    include_secrets: bool,
+    password: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<serde_json::Value, String> {
+    if include_secrets && store.is_locked().await {
+        return Err("数据已锁定，请先输入主密码再导出凭据".to_string());
+    }
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let content = export_service::build_account_export(&accounts, &format, include_secrets, password.as_deref())
+        .map_err(|e| e.to_string())?;
+    std::fs::write(&export_path, content).map_err(|e| e.to_string())?;
+    
+    let encrypted = password.map(|p| !p.is_empty()).unwrap_or(false);
+    let log = OperationLog::new(
+        OperationType::BatchOperation,
+        OperationStatus::Success,
+        format!("导出账号: {}个", accounts.len()),
+    ).with_details(serde_json::json!({
+        "path": export_path,
+        "format": format,
+        "include_secrets": include_secrets,
+        "encrypted": encrypted,
+    }));
+    let _ = store.add_log(log).await;
+    
+    Ok(serde_json::json!({
+        "success": true,
+        "path": export_path,
+        "count": accounts.len(),
+        "encrypted": encrypted,
+        "message": "账号导出成功"
+    }))
+}
+
+/// 从文件导入数据；`dry_run` 为 true 时只返回预演结果，不备份也不修改数据
+///
+/// 加密的账号导出文件需提供导出时的 `password`
 #[tauri::command]
 pub async fn import_data_from_file(
     import_path: String,
     merge: bool,
+    dry_run: Option<bool>,
+    password: Option<String>,
     store: State<'_, Arc<DataStore>>,
 ) -> Result<ImportResult, String> {
-    let path = PathBuf::from(&import_path);
-    store.import_data(&path, merge)
+    let content = std::fs::read_to_string(&import_path).map_err(|e| e.to_string())?;
+    let import_data = export_service::read_import_content(&content, password.as_deref())
+        .map_err(|e| e.to_string())?;
+    store.import_data(import_data, merge, dry_run.unwrap_or(false))
         .await
         .map_err(|e| e.to_string())
 }
 
+/// 从自动导出的文件夹恢复数据
+///
+/// `path` 可以是某次导出目录，也可以是自动导出文件夹（使用最近一次导出）；
+/// `dry_run` 为 true 时只返回将要恢复、移除的账号，不修改数据
+#[tauri::command]
+pub async fn restore_from_export(
+    path: String,
+    dry_run: Option<bool>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<RestoreResult, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let result = export_service::restore_from_export(&store, &PathBuf::from(&path), dry_run)
+        .await
+        .map_err(|e| e.to_string())?;
+    if dry_run {
+        return Ok(result);
+    }
+
+    let log = OperationLog::new(
+        OperationType::DataRecovery,
+        OperationStatus::Success,
+        format!("从导出恢复 {} 个账号: {}", result.accounts_restored, result.export_dir),
+    );
+    let _ = store.add_log(log).await;
+
+    Ok(result)
+}
+
+/// 生成批量导入模板（每行 `邮箱 密码 [备注]`，与批量导入对话框的格式一致）
+#[tauri::command]
+pub async fn generate_import_template(path: String) -> Result<String, String> {
+    std::fs::write(&path, export_service::import_template_text())
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(path)
+}
+
+/// 获取启动完整性报告（当前数据与上次保存时记录的校验清单的差异）
+#[tauri::command]
+pub async fn get_startup_integrity_report(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<IntegrityReport, String> {
+    Ok(store.get_startup_report())
+}
+
+/// 获取本次启动时的数据迁移结果（未迁移时为 null）
+#[tauri::command]
+pub async fn get_migration_report(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Option<MigrationReport>, String> {
+    Ok(store.get_migration_report())
+}
+
+/// 获取凭据加密状态
+#[tauri::command]
+pub async fn get_encryption_status(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<serde_json::Value, String> {
+    let mode = store.config.read().await.secret_encryption.mode.clone();
+    Ok(serde_json::json!({
+        "mode": mode,
+        "locked": store.is_locked().await,
+    }))
+}
+
+/// 设置主密码并重新加密已保存的凭据；密码为空时改用系统密钥链中的密钥
+#[tauri::command]
+pub async fn set_master_password(
+    password: Option<String>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    store
+        .set_master_password(password.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("[Settings] Secret encryption updated");
+    Ok(())
+}
+
+/// 关闭凭据加密，之后凭据以明文保存
+#[tauri::command]
+pub async fn disable_secret_encryption(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    store.disable_encryption().await.map_err(|e| e.to_string())?;
+    println!("[Settings] Secret encryption disabled");
+    Ok(())
+}
+
+/// 输入主密码解锁数据，解锁后重新加载账号
+///
+/// 连接到后台进程时同时解锁后台进程，否则它的定时任务会一直跳过
+#[tauri::command]
+pub async fn unlock_store(
+    password: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<(), String> {
+    store.unlock(&password).await.map_err(|e| e.to_string())?;
+    // 自定义请求头的值与凭据一起加密，解锁后才能附加
+    if let Ok(settings) = store.get_settings().await {
+        crate::services::custom_headers::set_config(settings.custom_headers);
+    }
+    if daemon_ipc::is_connected() {
+        daemon_ipc::unlock(&store, &password).await.map_err(|e| e.to_string())?;
+    }
+    store.emit_event("store-reloaded", ());
+    Ok(())
+}
+
 /// 获取数据目录路径
 #[tauri::command]
 pub async fn get_data_directory(
@@ -325,6 +681,23 @@ pub async fn get_sorted_accounts(
         .map_err(|e| e.to_string())
 }
 
+/// 按条件搜索账户并分页（`page` 从 1 开始，默认每页 50 条）
+#[tauri::command]
+pub async fn search_accounts_paged(
+    filter: Option<AccountFilter>,
+    sort_field: Option<SortField>,
+    sort_direction: Option<SortDirection>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<AccountPage, String> {
+    let filter = filter.unwrap_or_default();
+    let sort = sort_field.map(|field| (field, sort_direction.unwrap_or_default()));
+    store.search_accounts(&filter, sort, page.unwrap_or(1), page_size.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// 更新账户排序顺序（用于拖拽排序）
 #[tauri::command]
 pub async fn update_accounts_order(
@@ -357,3 +730,30 @@ pub async fn get_sort_config(
     let settings = store.get_settings().await.map_err(|e| e.to_string())?;
     Ok(settings.sort_config)
 }
+
+/// 切换持久化后端（json / sqlite），返回迁移的数据项数量
+#[tauri::command]
+pub async fn set_persistence_backend(
+    backend: String,
+    store: State<'_, Arc<DataStore>>,
+) -> Result<usize, String> {
+    store.switch_storage_backend(&backend)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取已配置插件及其运行状态
+#[tauri::command]
+pub async fn list_plugins(
+    store: State<'_, Arc<DataStore>>,
+) -> Result<Vec<PluginStatus>, String> {
+    plugin_service::list_status(&store)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 停止所有插件进程，下次事件时按最新配置重新启动，返回停止的进程数
+#[tauri::command]
+pub async fn reload_plugins() -> Result<usize, String> {
+    Ok(plugin_service::stop_all().await)
+}
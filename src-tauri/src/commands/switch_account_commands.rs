@@ -1,4 +1,6 @@
+use crate::models::OnboardingStep;
 use crate::repository::DataStore;
+use crate::services::windsurf_config_service;
 use crate::utils::errors::{AppError, AppResult};
 use chrono::Utc;
 use log::{error, info, warn};
@@ -237,9 +239,16 @@ pub async fn switch_account(
     id: String,
     data_store: State<'_, Arc<DataStore>>,
 ) -> Result<Value, String> {
-    info!("Switching account: {}", id);
-    
     let account_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    switch_account_by_id(&data_store, account_id).await
+}
+
+/// 切换到指定账号（命令与启动时应用默认账号共用）
+pub(crate) async fn switch_account_by_id(
+    data_store: &Arc<DataStore>,
+    account_id: Uuid,
+) -> Result<Value, String> {
+    info!("Switching account: {}", account_id);
     
     // 获取账号信息
     let account = data_store
@@ -247,6 +256,15 @@ pub async fn switch_account(
         .await
         .map_err(|e| e.to_string())?;
     
+    // 共享账号池：已被他人占用的账号不允许切换
+    let claim_user = crate::commands::account_commands::current_claim_user(data_store).await;
+    if account.is_claimed_by_other(&claim_user) {
+        return Ok(json!({
+            "success": false,
+            "error": format!("账号已被 {} 占用", account.active_claim().unwrap_or_default())
+        }));
+    }
+
     // 检查是否有refresh_token
     if account.refresh_token.is_none() || account.refresh_token.as_ref().unwrap().is_empty() {
         return Ok(json!({
@@ -339,11 +357,30 @@ pub async fn switch_account(
     if let Err(e) = data_store.update_account_token(
         account_id,
         access_token.clone(),
-        expires_at
+        expires_at,
+        crate::models::TokenSource::RefreshToken,
     ).await {
         error!("Failed to update account token: {:?}", e);
     }
     
+    // 配置了使用者名称（团队共享模式）时，切换后自动占用该账号
+    if let Ok(settings) = data_store.get_settings().await {
+        if settings.claim_user_name.as_deref().map(|n| !n.trim().is_empty()).unwrap_or(false) {
+            let claim_expires_at = Utc::now() + chrono::Duration::minutes(settings.claim_ttl_minutes.max(1));
+            if let Err(e) = data_store.claim_account(account_id, &claim_user, claim_expires_at).await {
+                warn!("Failed to claim account after switch: {:?}", e);
+            }
+        }
+    }
+    
+    let _ = data_store.mark_onboarding_step(account_id, OnboardingStep::IdeApplied).await;
+    
+    // 通知前端与插件：账号已切换
+    data_store.emit_event("account-switched", json!({
+        "account_id": account_id.to_string(),
+        "email": account.email,
+    }));
+    
     info!("Successfully triggered Windsurf login for account");
     
     Ok(json!({
@@ -358,6 +395,50 @@ pub async fn switch_account(
     }))
 }
 
+/// 将账号直接写入本地 Windsurf 的登录状态（写入前自动备份），Windsurf 重启后生效
+#[tauri::command]
+pub async fn activate_account_in_windsurf(
+    id: String,
+    data_store: State<'_, Arc<DataStore>>,
+) -> Result<Value, String> {
+    let account_id = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let account = data_store
+        .get_account(account_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let claim_user = crate::commands::account_commands::current_claim_user(&data_store).await;
+    if account.is_claimed_by_other(&claim_user) {
+        return Err(format!("账号已被 {} 占用", account.active_claim().unwrap_or_default()));
+    }
+    
+    let api_key = account.windsurf_api_key.clone()
+        .filter(|k| !k.is_empty())
+        .ok_or("账号没有 API Key，请先刷新账号信息")?;
+    
+    let backup_dir = data_store.get_data_dir().join("backups").join("windsurf");
+    let written = windsurf_config_service::write_auth_status(&account.email, &account.nickname, &api_key, &backup_dir)
+        .map_err(|e| e.to_string())?;
+    info!("Backed up Windsurf state to {:?}", written.backup_path);
+    
+    let _ = data_store.mark_onboarding_step(account_id, OnboardingStep::IdeApplied).await;
+    data_store.emit_event("account-switched", json!({
+        "account_id": account_id.to_string(),
+        "email": account.email,
+    }));
+    
+    Ok(json!({
+        "success": true,
+        "message": if written.session_written {
+            "已写入 Windsurf 登录状态，重启 Windsurf 后生效"
+        } else {
+            "已写入 Windsurf 登录状态，启动 Windsurf 后需重新登录一次"
+        },
+        "backup_path": written.backup_path.to_string_lossy(),
+        "session_written": written.session_written,
+    }))
+}
+
 /// 内部重置机器ID函数
 async fn reset_machine_id_internal() -> AppResult<()> {
     use std::fs;
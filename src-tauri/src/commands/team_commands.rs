@@ -2,7 +2,7 @@
 //! 
 //! 包含团队成员管理、邀请、移除等功能
 
-use crate::commands::api_commands::{ensure_valid_token, ensure_valid_token_with_force, is_401_error};
+use crate::commands::api_commands::{call_with_api_key, ensure_valid_token, ensure_valid_token_with_force, is_401_error};
 use crate::repository::DataStore;
 use crate::services::WindsurfService;
 use serde::{Deserialize, Serialize};
@@ -25,7 +25,7 @@ pub async fn get_team_members(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     // 最多重试一次（401 时强制刷新 token）
     for retry in 0..2 {
@@ -61,7 +61,7 @@ pub async fn invite_team_members(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     // 转换为 (name, email) 元组
     let user_tuples: Vec<(String, String)> = users
@@ -102,7 +102,7 @@ pub async fn remove_team_member(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -136,7 +136,7 @@ pub async fn revoke_invitation(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -169,7 +169,7 @@ pub async fn get_pending_invitations(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -202,7 +202,7 @@ pub async fn get_my_pending_invitation(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -246,7 +246,7 @@ pub async fn accept_invitation(
     
     let token = account.token.ok_or("No token available")?;
     
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     // 如果没有提供 approval_id，先获取最新的待处理邀请
     let actual_approval_id = if approval_id.is_empty() {
@@ -297,7 +297,7 @@ pub async fn reject_invitation(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -332,20 +332,22 @@ pub async fn request_team_access(
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
     
-    let account = store.get_account(uuid)
+    let mut account = store.get_account(uuid)
         .await
         .map_err(|e| e.to_string())?;
     
     // 需要用户的 api_key（UUID格式）
-    let api_key = account.windsurf_api_key
-        .ok_or("需要先登录获取 API Key")?;
-    
-    let windsurf_service = WindsurfService::new();
-    let result = windsurf_service.request_team_access(&api_key, &invite_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    if account.windsurf_api_key.is_none() {
+        return Err("需要先登录获取 API Key".to_string());
+    }
     
-    Ok(result)
+    let windsurf_service = WindsurfService::for_account(uuid);
+    call_with_api_key(&store, &mut account, uuid, |api_key| {
+        let (windsurf_service, invite_id) = (&windsurf_service, &invite_id);
+        async move { windsurf_service.request_team_access(&api_key, invite_id).await }
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 /// 审批用户加入团队申请（管理员）
@@ -358,7 +360,7 @@ pub async fn approve_team_join_request(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     // 2 = APPROVED, 3 = REJECTED
     let status: u8 = match action.to_lowercase().as_str() {
@@ -400,7 +402,7 @@ pub async fn get_credit_top_up_settings(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -436,7 +438,7 @@ pub async fn update_credit_top_up_settings(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -478,7 +480,7 @@ pub async fn update_codeium_access(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -514,7 +516,7 @@ pub async fn add_user_role(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -550,7 +552,7 @@ pub async fn remove_user_role(
     store: State<'_, Arc<DataStore>>,
 ) -> Result<serde_json::Value, String> {
     let uuid = Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     for retry in 0..2 {
         let mut account = store.get_account(uuid)
@@ -594,7 +596,7 @@ pub async fn transfer_subscription(
     ensure_valid_token(&store, &mut account, uuid).await?;
     
     let token = account.token.ok_or("No token available")?;
-    let windsurf_service = WindsurfService::new();
+    let windsurf_service = WindsurfService::for_account(uuid);
     
     // Step 1: 获取当前用户信息
     let current_user = windsurf_service.get_current_user(&token)
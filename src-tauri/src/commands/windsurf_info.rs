@@ -1,6 +1,5 @@
 use crate::utils::errors::AppError;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WindsurfCurrentInfo {
@@ -29,13 +28,8 @@ struct WindsurfAuthStatus {
 #[tauri::command]
 pub fn get_current_windsurf_info() -> Result<WindsurfCurrentInfo, AppError> {
     // 获取state.vscdb路径
-    let appdata = std::env::var("APPDATA")
-        .map_err(|e| AppError::Config(format!("Failed to get APPDATA: {}", e)))?;
-    let db_path = PathBuf::from(appdata)
-        .join("Windsurf")
-        .join("User")
-        .join("globalStorage")
-        .join("state.vscdb");
+    let db_path = crate::services::windsurf_config_service::state_db_path()
+        .ok_or_else(|| AppError::Config("Failed to locate Windsurf data directory".to_string()))?;
     
     if !db_path.exists() {
         return Ok(WindsurfCurrentInfo {
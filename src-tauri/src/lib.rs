@@ -9,16 +9,50 @@ use commands::{AutoResetStore, ResetRecordStore};
 use std::sync::Arc;
 use tauri::Manager;
 
+/// 以 `--daemon` 启动时只运行后台服务（调度器、插件通知），不创建窗口
+fn daemon_requested() -> bool {
+    std::env::args().any(|arg| arg == "--daemon")
+}
+
+/// `--import-accounts <来源>`：启动时从标准输入（`-`）或命名管道读取账号并导入
+fn import_source_requested() -> Option<String> {
+    let mut args = std::env::args();
+    while let Some(arg) = args.next() {
+        if arg == "--import-accounts" {
+            return args.next();
+        }
+        if let Some(source) = arg.strip_prefix("--import-accounts=") {
+            return Some(source.to_string());
+        }
+    }
+    None
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
     
+    let daemon = daemon_requested();
+    if daemon {
+        println!("[Daemon] Starting in background mode without window");
+    }
+    let import_source = import_source_requested();
+    
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .setup(move |app| {
+            // 检测后台进程：运行中时由它恢复未完成的写入，本进程的写入交给它合并保存
+            let daemon_info = if daemon {
+                None
+            } else {
+                app.path().app_data_dir().ok()
+                    .and_then(|dir| tauri::async_runtime::block_on(services::daemon_ipc::probe(&dir)))
+            };
+            
             // 初始化数据存储
-            let store = DataStore::new(app.handle())
+            let store = DataStore::new(app.handle(), daemon_info.is_none())
                 .expect("Failed to initialize data store");
             let store = Arc::new(store);
             
@@ -35,10 +69,45 @@ pub fn run() {
                 .expect("Failed to initialize reset record store");
             app.manage(Arc::new(reset_record_store));
             
+            // 启动后台任务调度器
+            // 后台模式同时开放本机 IPC；界面进程检测到后台进程时由后台进程执行定时任务
+            let scheduler = Arc::new(services::scheduler::Scheduler::new(store.clone()));
+            // 连接到后台进程时由后台进程负责启动时的默认账号检查
+            let mut owns_background = true;
+            if daemon {
+                scheduler.start();
+                let (store, scheduler) = (store.clone(), scheduler.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = services::daemon_ipc::start_server(store, scheduler).await {
+                        println!("[Daemon] Failed to start IPC server: {}", e);
+                    }
+                });
+            } else if let Some(info) = daemon_info.filter(|info| {
+                tauri::async_runtime::block_on(services::daemon_ipc::attach(info.clone(), store.clone(), scheduler.clone()))
+            }) {
+                println!("[Init] Background jobs run in daemon (pid {})", info.pid);
+                owns_background = false;
+            } else {
+                scheduler.start();
+            }
+            app.manage(scheduler);
+            
+            // 将生命周期事件转发给外部插件
+            services::plugin_service::register_listeners(app.handle(), store.clone());
+            services::notification_service::register_listeners(app.handle(), store.clone());
+            
             // 初始化代理配置
             let store_for_proxy = store.clone();
             tauri::async_runtime::spawn(async move {
                 if let Ok(settings) = store_for_proxy.get_settings().await {
+                    utils::proto_debug::init(&store_for_proxy.get_data_dir(), settings.proto_debug_logging);
+                    utils::proto_debug::set_verbose(settings.verbose_protocol_logging);
+                    utils::proto_debug::set_capture(settings.proto_capture);
+                    services::mapping_service::load_local(&store_for_proxy.get_data_dir());
+                    services::update_request_timeout(settings.request_timeout_secs);
+                    utils::retry::set_policy(settings.api_retry.clone());
+                    services::rate_limiter::set_config(settings.rate_limit.clone());
+                    services::custom_headers::set_config(settings.custom_headers.clone());
                     if settings.proxy_enabled || settings.proxy_url.is_some() {
                         println!("[Init] Loading proxy config: enabled={}, url={:?}", 
                             settings.proxy_enabled, settings.proxy_url);
@@ -48,13 +117,46 @@ pub fn run() {
                         );
                     }
                 }
+                
+                // 临时环境通过环境变量预置账号
+                if let Err(e) = services::env_import_service::import_from_env(
+                    &store_for_proxy,
+                    services::env_import_service::DEFAULT_ENV_PREFIX,
+                    false,
+                ).await {
+                    println!("[Init] Failed to import accounts from env: {}", e);
+                }
+                
+                // 密码管理器或部署脚本通过管道传入账号，避免写入临时文件
+                if let Some(source) = import_source {
+                    if let Err(e) = services::env_import_service::import_from_source(&store_for_proxy, &source, false).await {
+                        println!("[Init] Failed to import accounts from {}: {}", source, e);
+                    }
+                }
+                
+                // 检查默认账号，按设置自动应用到 IDE
+                if owns_background {
+                    if let Err(e) = services::default_account_service::run_startup(&store_for_proxy).await {
+                        println!("[Init] Failed to check default account: {}", e);
+                    }
+                }
             });
             
-            // 获取版本号并设置窗口标题
-            let version = app.package_info().version.to_string();
-            if let Some(window) = app.get_webview_window("main") {
-                let title = format!("windsurf-account-manager-simple v{}", version);
-                window.set_title(&title).ok();
+            // 主窗口在配置中设为不自动创建，后台模式下不创建
+            if !daemon {
+                // 界面进程在前端上报操作前不视为空闲
+                services::prefetch_service::record_activity();
+
+                if let Some(config) = app.config().app.windows.iter().find(|w| w.label == "main") {
+                    tauri::WebviewWindowBuilder::from_config(app.handle(), config)?.build()?;
+                }
+                
+                // 获取版本号并设置窗口标题
+                let version = app.package_info().version.to_string();
+                if let Some(window) = app.get_webview_window("main") {
+                    let title = format!("windsurf-account-manager-simple v{}", version);
+                    window.set_title(&title).ok();
+                }
             }
             
             Ok(())
@@ -62,19 +164,35 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // 账号管理命令
             commands::add_account,
+            commands::add_accounts_batch,
             commands::add_account_by_refresh_token,
             commands::get_all_accounts,
+            commands::get_redacted_accounts,
             commands::get_account,
+            commands::get_onboarding_state,
             commands::update_account,
             commands::delete_account,
             commands::delete_accounts_batch,
             commands::search_accounts,
             commands::filter_accounts_by_group,
             commands::filter_accounts_by_tags,
+            commands::get_token_expiry_overview,
+            commands::claim_account,
+            commands::release_account,
+            commands::import_accounts_from_env,
+            commands::set_health_ping_opt_out,
+            commands::set_account_schedule_overrides,
+            commands::update_account_notes,
+            commands::set_account_metadata,
+            commands::check_ide_shared_use,
+            commands::dismiss_shared_use_warning,
+            commands::get_token_lineage,
             
             // API操作命令
             commands::login_account,
             commands::refresh_token,
+            commands::refresh_expiring_tokens,
+            commands::bulk_relogin,
             commands::get_plan_status,
             commands::reset_credits,
             commands::update_seats,
@@ -84,6 +202,7 @@ pub fn run() {
             commands::resume_subscription,
             commands::get_account_info,
             commands::get_current_user,
+            commands::get_user_status,
             commands::batch_reset_credits,
             commands::batch_refresh_tokens,
             commands::get_team_credit_entries,
@@ -119,12 +238,44 @@ pub fn run() {
             commands::get_current_user_parsed,
             commands::get_billing_parsed,
             commands::batch_get_users_parsed,
+            commands::list_captures,
+            commands::open_capture,
 
             // Analytics 分析命令
             commands::get_account_analytics,
+            commands::list_analytics_presets,
+            commands::stream_account_analytics,
+            commands::export_analytics_jsonl,
+            commands::export_session_ledger,
+            commands::export_model_cost_report,
+            commands::get_session_labels,
+            commands::assign_session_label,
+            commands::set_session_label_rules,
+            commands::get_usage_by_label,
+            commands::get_acceptance_rate_series,
+            commands::get_language_trends,
+            commands::compare_analytics_periods,
+            commands::get_aggregate_analytics,
+            commands::get_team_benchmark,
+            commands::get_account_credits,
+            commands::get_credits_history,
+            commands::alerts_history,
+            commands::get_capacity_plan,
+            commands::get_activity_heatmap,
+            commands::list_monthly_reports,
+            commands::get_monthly_report,
+            commands::generate_share_report,
+            commands::get_analytics_text_summary,
+            commands::generate_usage_insights,
+            commands::run_analytics_fixtures,
+            commands::capture_analytics_fixture,
+            commands::call_windsurf_endpoint,
+            commands::encode_proto_body,
+            commands::refresh_mappings,
 
             // 设置管理命令
             commands::get_settings,
+            commands::get_redacted_settings,
             commands::update_settings,
             commands::get_groups,
             commands::add_group,
@@ -136,12 +287,15 @@ pub fn run() {
             commands::delete_tag,
             commands::batch_update_account_tags,
             commands::get_logs,
+            commands::query_logs,
+            commands::export_audit_log,
             commands::clear_logs,
             commands::get_stats,
             commands::export_data,
             
             // 切号相关命令
             commands::switch_account,
+            commands::activate_account_in_windsurf,
             commands::reset_machine_id,
             commands::check_admin_privileges,
             
@@ -151,6 +305,8 @@ pub fn run() {
             // 应用信息命令
             commands::get_app_version,
             commands::get_app_title,
+            commands::format_metrics,
+            commands::open_dashboard_window,
             commands::reset_http_client,
             
             // 无感换号补丁命令
@@ -169,12 +325,27 @@ pub fn run() {
             commands::create_backup,
             commands::list_backups,
             commands::restore_backup,
+            commands::list_deleted_archives,
+            commands::restore_deleted_archive,
             commands::export_data_to_file,
+            commands::export_accounts,
             commands::import_data_from_file,
+            commands::restore_from_export,
+            commands::generate_import_template,
+            commands::set_persistence_backend,
+            commands::list_plugins,
+            commands::reload_plugins,
             commands::get_data_directory,
+            commands::get_startup_integrity_report,
+            commands::get_migration_report,
+            commands::get_encryption_status,
+            commands::set_master_password,
+            commands::unlock_store,
+            commands::disable_secret_encryption,
             
             // 排序命令
             commands::get_sorted_accounts,
+            commands::search_accounts_paged,
             commands::update_accounts_order,
             commands::update_sort_config,
             commands::get_sort_config,
@@ -209,7 +380,36 @@ pub fn run() {
             commands::get_reset_records,
             commands::get_reset_stats,
             commands::clear_reset_records,
+            
+            // 自动化配置导入导出
+            commands::export_automation_config,
+            commands::import_automation_config,
+            
+            // 后台任务命令
+            commands::get_scheduler_status,
+            commands::run_scheduler_job,
+            commands::report_user_activity,
+            commands::get_metered_status,
+            commands::get_scheduler_watchdog_events,
+            commands::export_job_history,
+            commands::get_autostart_status,
+            commands::set_autostart,
+            
+            // 命令面板
+            commands::list_palette_actions,
+            commands::execute_palette_action,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app, event| match event {
+            // 后台模式没有窗口，阻止事件循环因“所有窗口已关闭”而退出
+            tauri::RunEvent::ExitRequested { api, code: None, .. } if daemon => {
+                api.prevent_exit();
+            }
+            tauri::RunEvent::Exit if daemon => {
+                let store = app.state::<Arc<DataStore>>();
+                services::daemon_ipc::remove_daemon_file(&store.get_data_dir());
+            }
+            _ => {}
+        });
 }
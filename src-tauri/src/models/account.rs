@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 
 /// 带颜色的标签
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +48,331 @@ pub struct Account {
     // 自定义排序顺序（用于拖拽排序）
     #[serde(default, rename = "sortOrder")]
     pub sort_order: i32,
+    // 共享账号池占用信息：占用人与占用到期时间（过期后自动失效）
+    #[serde(default)]
+    pub claimed_by: Option<String>,
+    #[serde(default)]
+    pub claim_expires_at: Option<DateTime<Utc>>,
+    // 后台任务中连续认证失败的次数（成功刷新后清零）
+    #[serde(default)]
+    pub auth_failure_count: u32,
+    #[serde(default)]
+    pub last_auth_error: Option<String>,
+    // 定时健康检查：是否退出检查，以及最近一次检查时间
+    #[serde(default)]
+    pub health_ping_opt_out: bool,
+    #[serde(default)]
+    pub last_health_ping_at: Option<DateTime<Utc>>,
+    // Token 来源与 refresh 链路记录（最新的在最后）
+    #[serde(default)]
+    pub token_history: Vec<TokenProvenance>,
+    // 账号级别的定时任务间隔，覆盖全局设置
+    #[serde(default)]
+    pub schedule_overrides: ScheduleOverrides,
+    // 疑似在其他设备同时使用的警告（用户确认后清除）
+    #[serde(default)]
+    pub shared_use_warning: Option<SharedUseWarning>,
+    // 新账号引导进度
+    #[serde(default)]
+    pub onboarding: OnboardingProgress,
+    // 备注（账号来源、绑定的卡、续费日期等）
+    #[serde(default)]
+    pub notes: Option<String>,
+    // 自定义键值信息
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// 新账号引导步骤（按完成顺序排列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    /// 已添加 Token 或 Refresh Token
+    TokenAdded,
+    /// 已获取 Windsurf API Key
+    ApiKeyFetched,
+    /// 已获取额度信息
+    QuotaKnown,
+    /// 已成功拉取一次使用分析
+    AnalyticsVerified,
+    /// 已切换到本地 Windsurf
+    IdeApplied,
+}
+
+impl OnboardingStep {
+    pub fn all() -> &'static [OnboardingStep] {
+        &[
+            OnboardingStep::TokenAdded,
+            OnboardingStep::ApiKeyFetched,
+            OnboardingStep::QuotaKnown,
+            OnboardingStep::AnalyticsVerified,
+            OnboardingStep::IdeApplied,
+        ]
+    }
+}
+
+/// 各引导步骤的完成时间
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OnboardingProgress {
+    #[serde(default)]
+    pub token_added_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub api_key_fetched_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub quota_known_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub analytics_verified_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ide_applied_at: Option<DateTime<Utc>>,
+}
+
+impl OnboardingProgress {
+    fn slot(&mut self, step: OnboardingStep) -> &mut Option<DateTime<Utc>> {
+        match step {
+            OnboardingStep::TokenAdded => &mut self.token_added_at,
+            OnboardingStep::ApiKeyFetched => &mut self.api_key_fetched_at,
+            OnboardingStep::QuotaKnown => &mut self.quota_known_at,
+            OnboardingStep::AnalyticsVerified => &mut self.analytics_verified_at,
+            OnboardingStep::IdeApplied => &mut self.ide_applied_at,
+        }
+    }
+
+    pub fn completed_at(&self, step: OnboardingStep) -> Option<DateTime<Utc>> {
+        match step {
+            OnboardingStep::TokenAdded => self.token_added_at,
+            OnboardingStep::ApiKeyFetched => self.api_key_fetched_at,
+            OnboardingStep::QuotaKnown => self.quota_known_at,
+            OnboardingStep::AnalyticsVerified => self.analytics_verified_at,
+            OnboardingStep::IdeApplied => self.ide_applied_at,
+        }
+    }
+
+    /// 标记步骤完成，返回是否为首次完成
+    pub fn complete(&mut self, step: OnboardingStep) -> bool {
+        let slot = self.slot(step);
+        if slot.is_some() {
+            return false;
+        }
+        *slot = Some(Utc::now());
+        true
+    }
+
+    /// 合并另一份进度（调用方持有的账号副本可能早于最近一次标记）
+    pub fn merge(&mut self, other: &OnboardingProgress) {
+        for step in OnboardingStep::all() {
+            if let Some(at) = other.completed_at(*step) {
+                self.slot(*step).get_or_insert(at);
+            }
+        }
+    }
+
+    /// 当前需要完成的步骤（第一个未完成的步骤），全部完成时为 None
+    pub fn current_step(&self) -> Option<OnboardingStep> {
+        OnboardingStep::all().iter().copied().find(|step| self.completed_at(*step).is_none())
+    }
+}
+
+/// 引导状态（用于前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    pub account_id: String,
+    pub current_step: Option<OnboardingStep>,
+    pub completed: bool,
+    pub steps: Vec<OnboardingStepState>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStepState {
+    pub step: OnboardingStep,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// 疑似在其他设备同时登录使用的警告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedUseWarning {
+    pub reason: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// 账号级别的定时任务间隔（分钟），为空时使用全局设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleOverrides {
+    /// Token 的最长刷新间隔：距上次获取超过该时长即刷新，即将过期时仍会提前刷新
+    #[serde(default)]
+    pub token_refresh_minutes: Option<i64>,
+    /// 健康检查间隔
+    #[serde(default)]
+    pub health_ping_minutes: Option<i64>,
+}
+
+/// Token 历史最多保留的条数
+pub const MAX_TOKEN_HISTORY: usize = 50;
+
+/// Token 的获取方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenSource {
+    /// 邮箱密码登录
+    PasswordLogin,
+    /// 使用 refresh_token 刷新
+    RefreshToken,
+    /// 手动粘贴/编辑
+    ManualPaste,
+}
+
+/// 一次 Token 获取记录，只保存指纹不保存原文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenProvenance {
+    pub source: TokenSource,
+    pub obtained_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub token_fingerprint: String,
+    /// 获取后账号持有的 refresh_token 指纹
+    pub refresh_fingerprint: Option<String>,
+    /// 刷新时使用的 refresh_token 指纹（链路中的上一环）
+    pub parent_refresh_fingerprint: Option<String>,
+    /// 本次获取是否更换了 refresh_token
+    pub refresh_rotated: bool,
+}
+
+/// Token 指纹：SHA-256 的前 12 位十六进制
+pub fn token_fingerprint(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(token.as_bytes()))[..12].to_string()
+}
+
+/// 账号筛选条件（各条件之间为“且”关系，未设置的条件不参与筛选）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountFilter {
+    #[serde(default)]
+    pub ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// 包含任意一个标签即匹配
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// "active" / "inactive" / "error" / "needs_attention"
+    #[serde(default)]
+    pub status: Option<String>,
+    /// 邮箱或备注名包含的关键字
+    #[serde(default)]
+    pub query: Option<String>,
+    /// 套餐名称（不区分大小写）
+    #[serde(default)]
+    pub plan_name: Option<String>,
+    /// "expired" / "expiring_soon" / "valid" / "missing"
+    #[serde(default)]
+    pub token_status: Option<String>,
+    /// 最近登录时间范围，从未登录的账号只匹配 `never_used`
+    #[serde(default)]
+    pub last_used_after: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_used_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub never_used: Option<bool>,
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountPage {
+    pub items: Vec<Account>,
+    /// 筛选后的总数
+    pub total: usize,
+    /// 页码，从 1 开始
+    pub page: usize,
+    pub page_size: usize,
+}
+
+impl AccountFilter {
+    pub fn matches(&self, account: &Account) -> bool {
+        if let Some(ids) = &self.ids {
+            if !ids.contains(&account.id.to_string()) {
+                return false;
+            }
+        }
+        if let Some(group) = &self.group {
+            if account.group.as_ref() != Some(group) {
+                return false;
+            }
+        }
+        if let Some(tags) = &self.tags {
+            if !tags.is_empty() && !tags.iter().any(|tag| account.tags.contains(tag)) {
+                return false;
+            }
+        }
+        if let Some(status) = &self.status {
+            let current = match account.status {
+                AccountStatus::Active => "active",
+                AccountStatus::Inactive => "inactive",
+                AccountStatus::Error(_) => "error",
+                AccountStatus::NeedsAttention => "needs_attention",
+            };
+            if !status.eq_ignore_ascii_case(current) {
+                return false;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = query.to_lowercase();
+            if !account.email.to_lowercase().contains(&query)
+                && !account.nickname.to_lowercase().contains(&query)
+            {
+                return false;
+            }
+        }
+        if let Some(plan_name) = &self.plan_name {
+            if !account.plan_name.as_deref().map(|p| p.eq_ignore_ascii_case(plan_name)).unwrap_or(false) {
+                return false;
+            }
+        }
+        if let Some(token_status) = &self.token_status {
+            let (expires_at, _) = account.effective_token_expiry();
+            if !token_status.eq_ignore_ascii_case(token_status_at(expires_at, Utc::now())) {
+                return false;
+            }
+        }
+        if let Some(never_used) = self.never_used {
+            if never_used != account.last_login_at.is_none() {
+                return false;
+            }
+        }
+        if self.last_used_after.is_some() || self.last_used_before.is_some() {
+            let Some(last_used) = account.last_login_at else { return false };
+            if self.last_used_after.map(|after| last_used < after).unwrap_or(false)
+                || self.last_used_before.map(|before| last_used > before).unwrap_or(false)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 由过期时间得到 Token 状态："expired" / "expiring_soon"（1 小时内）/ "valid" / "missing"
+pub fn token_status_at(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> &'static str {
+    match expires_at.map(|exp| (exp - now).num_seconds()) {
+        None => "missing",
+        Some(secs) if secs <= 0 => "expired",
+        Some(secs) if secs <= 3600 => "expiring_soon",
+        Some(_) => "valid",
+    }
+}
+
+/// 账号 Token 过期概览条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenExpiryInfo {
+    pub account_id: Uuid,
+    pub email: String,
+    pub nickname: String,
+    pub group: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 过期时间来源: "jwt"（从 Token 解码）/ "stored"（本地记录）/ "none"
+    pub source: String,
+    /// 距离过期的秒数（已过期为负数）
+    pub seconds_remaining: Option<i64>,
+    /// "expired" / "expiring_soon"（1 小时内）/ "valid" / "missing"
+    pub status: String,
+    /// 是否可以自动刷新（有 refresh_token 或密码）
+    pub can_refresh: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +381,8 @@ pub enum AccountStatus {
     Active,
     Inactive,
     Error(String),
+    /// 连续认证失败，需要人工处理（后台任务不再处理该账号）
+    NeedsAttention,
 }
 
 impl Account {
@@ -84,9 +412,120 @@ impl Account {
             is_disabled: None,
             is_team_owner: None,
             sort_order: 0,
+            claimed_by: None,
+            claim_expires_at: None,
+            auth_failure_count: 0,
+            last_auth_error: None,
+            health_ping_opt_out: false,
+            last_health_ping_at: None,
+            token_history: Vec::new(),
+            schedule_overrides: ScheduleOverrides::default(),
+            shared_use_warning: None,
+            onboarding: OnboardingProgress::default(),
+            notes: None,
+            metadata: HashMap::new(),
         }
     }
 
+    /// 去掉密码与凭据的副本，用于导出和只读面板
+    pub fn redacted(&self) -> Self {
+        let mut account = self.clone();
+        account.password.clear();
+        account.token = None;
+        account.refresh_token = None;
+        account.windsurf_api_key = None;
+        account
+    }
+
+    /// Token 的实际过期时间与来源：优先使用 JWT 中的 exp（"jwt"），解码失败时回退到本地记录（"stored"）
+    pub fn effective_token_expiry(&self) -> (Option<DateTime<Utc>>, &'static str) {
+        match self.token.as_deref().and_then(crate::utils::jwt::expires_at) {
+            Some(exp) => (Some(exp), "jwt"),
+            None => match self.token_expires_at {
+                Some(exp) if self.token.is_some() => (Some(exp), "stored"),
+                _ => (None, "none"),
+            },
+        }
+    }
+
+    /// 根据账号数据推进可自动判断的引导步骤（Token、API Key、额度），返回是否有变化
+    pub fn sync_onboarding(&mut self) -> bool {
+        let mut changed = false;
+        if self.token.is_some() || self.refresh_token.as_deref().map(|t| !t.is_empty()).unwrap_or(false) {
+            changed |= self.onboarding.complete(OnboardingStep::TokenAdded);
+        }
+        if self.windsurf_api_key.as_deref().map(|k| !k.is_empty()).unwrap_or(false) {
+            changed |= self.onboarding.complete(OnboardingStep::ApiKeyFetched);
+        }
+        if self.total_quota.is_some() {
+            changed |= self.onboarding.complete(OnboardingStep::QuotaKnown);
+        }
+        changed
+    }
+
+    pub fn onboarding_state(&self) -> OnboardingState {
+        let current_step = self.onboarding.current_step();
+        OnboardingState {
+            account_id: self.id.to_string(),
+            current_step,
+            completed: current_step.is_none(),
+            steps: OnboardingStep::all()
+                .iter()
+                .map(|step| OnboardingStepState {
+                    step: *step,
+                    completed_at: self.onboarding.completed_at(*step),
+                })
+                .collect(),
+        }
+    }
+
+    /// 记录新 Token 的来源，需在覆盖 token/refresh_token 字段之前调用
+    ///
+    /// 与最近一条记录相同的 Token（例如复用缓存 Token）不会重复记录
+    pub fn record_token_provenance(
+        &mut self,
+        source: TokenSource,
+        token: &str,
+        refresh_token: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) {
+        let fingerprint = token_fingerprint(token);
+        if self.token_history.last().map(|p| p.token_fingerprint == fingerprint).unwrap_or(false) {
+            return;
+        }
+
+        let previous_refresh = self.refresh_token.as_deref().filter(|t| !t.is_empty()).map(token_fingerprint);
+        let refresh_fingerprint = refresh_token.map(token_fingerprint).or_else(|| previous_refresh.clone());
+        let refresh_rotated = previous_refresh.is_some() && refresh_fingerprint != previous_refresh;
+
+        self.token_history.push(TokenProvenance {
+            source,
+            obtained_at: Utc::now(),
+            expires_at,
+            token_fingerprint: fingerprint,
+            refresh_fingerprint,
+            parent_refresh_fingerprint: if source == TokenSource::RefreshToken { previous_refresh } else { None },
+            refresh_rotated,
+        });
+        if self.token_history.len() > MAX_TOKEN_HISTORY {
+            let excess = self.token_history.len() - MAX_TOKEN_HISTORY;
+            self.token_history.drain(..excess);
+        }
+    }
+
+    /// 当前有效的占用人（占用已过期时返回 None）
+    pub fn active_claim(&self) -> Option<&str> {
+        match (&self.claimed_by, &self.claim_expires_at) {
+            (Some(user), Some(expires_at)) if *expires_at > Utc::now() => Some(user.as_str()),
+            _ => None,
+        }
+    }
+
+    /// 是否被其他人占用
+    pub fn is_claimed_by_other(&self, user: &str) -> bool {
+        self.active_claim().map(|owner| owner != user).unwrap_or(false)
+    }
+
     pub fn is_token_valid(&self) -> bool {
         if let Some(expires_at) = self.token_expires_at {
             expires_at > Utc::now()
@@ -95,3 +534,41 @@ impl Account {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_onboarding_transitions() {
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "A".to_string(), Vec::new());
+        assert_eq!(account.onboarding.current_step(), Some(OnboardingStep::TokenAdded));
+        assert!(!account.sync_onboarding());
+
+        // 额度先于 Token 获得时，当前步骤仍停在第一个未完成的步骤
+        account.total_quota = Some(100);
+        assert!(account.sync_onboarding());
+        assert_eq!(account.onboarding.current_step(), Some(OnboardingStep::TokenAdded));
+
+        account.token = Some("token".to_string());
+        account.windsurf_api_key = Some("key".to_string());
+        assert!(account.sync_onboarding());
+        assert!(!account.sync_onboarding());
+        assert_eq!(account.onboarding.current_step(), Some(OnboardingStep::AnalyticsVerified));
+
+        // 重复标记不覆盖首次完成时间
+        assert!(account.onboarding.complete(OnboardingStep::AnalyticsVerified));
+        let verified_at = account.onboarding.completed_at(OnboardingStep::AnalyticsVerified);
+        assert!(!account.onboarding.complete(OnboardingStep::AnalyticsVerified));
+        assert_eq!(account.onboarding.completed_at(OnboardingStep::AnalyticsVerified), verified_at);
+
+        // 合并较旧的副本时补上缺失的步骤，已有的时间保持不变
+        let mut stale = OnboardingProgress::default();
+        stale.complete(OnboardingStep::IdeApplied);
+        stale.analytics_verified_at = Some(Utc::now() + chrono::Duration::days(1));
+        account.onboarding.merge(&stale);
+        assert_eq!(account.onboarding.completed_at(OnboardingStep::AnalyticsVerified), verified_at);
+        assert_eq!(account.onboarding.current_step(), None);
+        assert!(account.onboarding_state().completed);
+    }
+}
@@ -70,6 +70,23 @@ pub struct CompletionByLanguage {
     pub statistics: CompletionStatistics,
 }
 
+/// 每日接受率（图表数据）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptanceRatePoint {
+    /// 日期时间戳
+    pub timestamp: i64,
+    /// 日期字符串
+    pub date: String,
+    /// 接受次数
+    pub num_acceptances: i64,
+    /// 拒绝次数
+    pub num_rejections: i64,
+    /// 当日接受率 (%)，无补全时为 None
+    pub rate: Option<f64>,
+    /// 平滑后的接受率 (%)，窗口内无补全时为 None
+    pub smoothed_rate: Option<f64>,
+}
+
 // ============== Chat 统计 (Field 11: chat_stats) ==============
 
 /// Chat 统计
@@ -119,6 +136,28 @@ pub struct ChatStatsByDay {
     pub stats: ChatStats,
 }
 
+/// Cascade 统计 (Field 15: cascade_stats，对应 QueryRequest field 20)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CascadeStats {
+    /// 发送的 Cascade 消息数 (int_1)
+    pub messages_sent: i64,
+    /// 使用 Cascade 的活跃天数 (int_2)，响应中缺失时按每日统计计算
+    pub active_days: i64,
+    /// 按日期的消息数 (subMesssage_3)
+    pub by_day: Vec<CascadeStatsByDay>,
+}
+
+/// 按日期的 Cascade 消息数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CascadeStatsByDay {
+    /// 日期时间戳
+    pub timestamp: i64,
+    /// 日期字符串
+    pub date: String,
+    /// 当天发送的消息数
+    pub messages_sent: i64,
+}
+
 /// 按模型的 Chat 统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatStatsByModel {
@@ -247,6 +286,48 @@ pub struct AnalyticsData {
     pub chats_by_model: Vec<ChatStatsByModel>,
     /// 自定义查询结果 (Field 16: custom_stats)
     pub custom_query_results: CustomQueryResponse,
+    /// 积分与会话用量 (Field 26: cascade_summary)
+    #[serde(default)]
+    pub credits_usage: CreditsUsage,
+    /// Cascade 消息数与活跃天数 (Field 15: cascade_stats)
+    #[serde(default)]
+    pub cascade_stats: CascadeStats,
+    /// 本次加载的耗时分解（仅实时拉取时存在）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<AnalyticsTimings>,
+    /// 数据为空的原因（有数据时为空），用于区分新账号没有活动与请求失败
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_empty_because: Option<EmptyReason>,
+}
+
+/// 分析数据为空的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyReason {
+    /// 请求成功，但时间范围内没有任何活动（通常是新账号）
+    NoActivity,
+    /// 所有请求方式均失败
+    FetchFailed,
+    /// 请求成功但响应无法解析
+    ParseFailed,
+}
+
+/// 分析数据加载耗时分解（毫秒）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalyticsTimings {
+    /// 校验/刷新 Token 耗时
+    pub auth_ms: u64,
+    /// 本次是否刷新了 Token
+    pub token_refreshed: bool,
+    /// GetCurrentUser 耗时
+    pub profile_ms: u64,
+    /// GetAnalytics 请求耗时（包含所有降级重试）
+    pub fetch_ms: u64,
+    /// Protobuf 解析与数据提取耗时
+    pub parse_ms: u64,
+    pub total_ms: u64,
+    /// 实际使用的请求方式: full / team_fallback / cascade_only / no_timestamp / none
+    pub fallback: String,
 }
 
 /// 总体统计摘要
@@ -272,6 +353,47 @@ pub struct AnalyticsSummary {
     pub primary_model: String,
     /// 主要使用的工具
     pub primary_tool: String,
+    /// 最近 7 天
+    #[serde(default)]
+    pub last_7_days: RollingWindow,
+    /// 最近 30 天
+    #[serde(default)]
+    pub last_30_days: RollingWindow,
+    /// Token 消耗最多的会话（前 5 个）
+    #[serde(default)]
+    pub top_sessions: Vec<TopSession>,
+}
+
+/// 按 Token 消耗排名的会话
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopSession {
+    /// 会话ID
+    pub session_id: String,
+    /// 会话中 Token 消耗最多的模型
+    pub model: String,
+    /// 会话开始日期
+    pub date: String,
+    /// Token消耗
+    pub tokens: i64,
+}
+
+/// 最近 N 天（含今天）的汇总，没有数据的日期按 0 计入平均值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RollingWindow {
+    /// 窗口天数
+    pub days: i64,
+    /// 接受的代码行数
+    pub accepted_lines: i64,
+    /// 建议的代码行数
+    pub suggested_lines: i64,
+    /// 平均每日接受的代码行数
+    pub avg_daily_accepted_lines: f64,
+    /// 会话数/消息数
+    pub sessions: i64,
+    /// Token消耗
+    pub tokens: i64,
+    /// 平均每日Token消耗
+    pub avg_daily_tokens: f64,
 }
 
 impl AnalyticsData {
@@ -293,6 +415,9 @@ impl AnalyticsData {
                 total_tokens: 0,
                 primary_model: String::new(),
                 primary_tool: String::new(),
+                last_7_days: RollingWindow::default(),
+                last_30_days: RollingWindow::default(),
+                top_sessions: Vec::new(),
             },
             // 新增字段
             percent_code_written: PercentCodeWritten::default(),
@@ -303,7 +428,402 @@ impl AnalyticsData {
             chats_by_day: Vec::new(),
             chats_by_model: Vec::new(),
             custom_query_results: CustomQueryResponse::default(),
+            credits_usage: CreditsUsage::default(),
+            cascade_stats: CascadeStats::default(),
+            timings: None,
+            is_empty_because: None,
         }
     }
+
+    /// 是否包含任何活动记录
+    pub fn has_activity(&self) -> bool {
+        !self.daily_cascade_lines.is_empty()
+            || !self.tool_usage.is_empty()
+            || !self.model_usage_details.is_empty()
+            || !self.completions_by_day.is_empty()
+            || !self.chats_by_day.is_empty()
+            || self.credits_usage.conversations > 0
+            || self.credits_usage.prompt_credits_used > 0
+            || self.cascade_stats.messages_sent > 0
+    }
+}
+
+
+// ============== 多账号汇总 ==============
+
+/// 参与汇总的单个账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateAccountEntry {
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    /// 数据采集时间（实时拉取时为当前时间）
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub total_accepted_lines: i64,
+    pub total_tokens: i64,
+}
+
+/// 所有账号合并后的分析数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateAnalyticsData {
+    /// 参与汇总的账号（没有分析数据的账号不计入）
+    pub accounts: Vec<AggregateAccountEntry>,
+    /// 按日期合并的 Cascade 代码行数
+    pub daily_cascade_lines: Vec<DailyCascadeLinesCount>,
+    /// 按工具合并的使用次数（百分比重新计算）
+    pub tool_usage: Vec<ToolUsageEntry>,
+    /// 按模型合并的使用汇总（百分比重新计算）
+    pub model_usage_summary: Vec<ModelUsageSummary>,
+    /// 积分与会话用量合计
+    pub credits_usage: CreditsUsage,
+    /// 基于合并数据重新计算的总体统计
+    pub summary: AnalyticsSummary,
+}
+
+// ============== 团队对比 ==============
+
+/// 单项指标与团队的对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkMetric {
+    /// 本账号的值
+    pub value: f64,
+    /// 团队平均值（包含本账号）
+    pub team_average: f64,
+    /// 在团队中的百分位（0-100，越高表示用量越大）
+    pub percentile: f64,
+    /// 参与比较的成员数
+    pub sample_size: usize,
+}
+
+/// 团队成员与团队平均水平的对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamBenchmark {
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    /// 团队成员数（GetUsers 返回的成员）
+    pub team_size: usize,
+    /// 已用 Prompt 积分（来自团队成员列表）
+    pub prompt_credits: Option<BenchmarkMetric>,
+    /// 接受的代码行数（本应用中管理的同团队账号最近 30 天的分析数据）
+    pub accepted_lines: Option<BenchmarkMetric>,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// ============== 账号池容量规划 ==============
+
+/// 容量规划中的单个账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityAccountEntry {
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    pub remaining_credits: i64,
+    pub total_credits: Option<i64>,
+    /// 下次续费日期（订阅到期时间），续费后额度恢复为总额度
+    pub renews_on: Option<chrono::NaiveDate>,
+    /// 根据积分历史估算的日均消耗，历史不足时为空
+    pub daily_burn: Option<f64>,
 }
 
+/// 账号池容量规划
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityPlan {
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    /// 整个账号池的日均消耗
+    pub daily_burn: f64,
+    /// 日均消耗来源："history"（积分历史）/ "manual"（调用方指定）
+    pub burn_source: String,
+    pub total_remaining: i64,
+    pub horizon_days: i64,
+    /// 当前账号池（含续费）可用的天数，规划期内不会耗尽时为空
+    pub days_until_depleted: Option<i64>,
+    pub depleted_on: Option<chrono::NaiveDate>,
+    /// 建议最晚在此日期前补充账号（耗尽日期前预留的天数见 lead_days）
+    pub add_accounts_by: Option<chrono::NaiveDate>,
+    pub lead_days: i64,
+    /// 覆盖整个规划期还缺少的积分
+    pub shortfall_credits: i64,
+    /// 按账号平均总额度估算需要补充的账号数
+    pub accounts_needed: usize,
+    pub accounts: Vec<CapacityAccountEntry>,
+}
+
+// ============== 历史快照 ==============
+
+/// 积分与会话用量 (Field 26: cascade_summary)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreditsUsage {
+    /// 会话数 (int_1)
+    pub conversations: i64,
+    /// 已消耗的 Prompt 积分 (int_2)
+    pub prompt_credits_used: i64,
+    /// 执行的命令数 (int_3)
+    pub commands: i64,
+    /// 运行的 Workflow 数 (int_4)
+    pub workflows: i64,
+    /// 创建的 Memory 数 (int_5)
+    pub memories: i64,
+    /// 终端消息数 (int_6)
+    pub terminal_messages: i64,
+}
+
+/// 积分用量快照（每次拉取分析数据时记录）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditsSnapshot {
+    pub account_id: uuid::Uuid,
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub usage: CreditsUsage,
+    /// 记录时账号的已用/总额度（来自账号信息）
+    pub used_quota: Option<i32>,
+    pub total_quota: Option<i32>,
+    /// 剩余额度，没有额度信息时为空
+    pub remaining_quota: Option<i32>,
+}
+
+/// 一次触发的用量告警
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub id: uuid::Uuid,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    pub metric: String,
+    pub operator: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub triggered_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// GetAnalytics 抽取逻辑的版本，新增或修正字段映射时递增，保存了原始响应的旧快照会被重新处理
+pub const ANALYTICS_EXTRACTOR_VERSION: u32 = 2;
+
+/// 分析数据快照（每个账户每天保留一份）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsSnapshot {
+    pub account_id: uuid::Uuid,
+    /// 快照日期 (YYYY-MM-DD)
+    pub date: String,
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub data: AnalyticsData,
+    /// 原始响应体（Base64），仅在设置中开启保留原始响应时保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_body: Option<String>,
+    /// 生成 `data` 时的抽取逻辑版本，旧数据为 0
+    #[serde(default)]
+    pub extractor_version: u32,
+}
+
+/// 语言占比数据点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageSharePoint {
+    /// 快照日期
+    pub date: String,
+    /// 接受的代码行数
+    pub lines_accepted: i64,
+    /// 占全部语言的比例 (%)
+    pub share: f64,
+}
+
+/// 单个语言的趋势
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageTrend {
+    pub language_id: i32,
+    pub language_name: String,
+    /// 各快照中的占比
+    pub points: Vec<LanguageSharePoint>,
+    /// 第一个快照中的占比 (%)
+    pub first_share: f64,
+    /// 最新快照中的占比 (%)
+    pub latest_share: f64,
+    /// 占比变化（百分点）
+    pub change: f64,
+    /// 趋势: "rising" / "falling" / "stable"
+    pub trend: String,
+}
+
+/// 两个时间段之间单项指标的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodMetric {
+    /// 指标名：accepted_lines / suggested_lines / cascade_runs / tokens / completions_accepted / chats_sent / cascade_messages
+    pub metric: String,
+    pub current: i64,
+    pub previous: i64,
+    /// current - previous
+    pub change: i64,
+    /// 相对上一时间段的变化 (%)，上一时间段为 0 时为空
+    pub change_percent: Option<f64>,
+    /// 趋势: "rising" / "falling" / "stable"
+    pub trend: String,
+}
+
+/// 最近一个时间段与之前相同长度时间段的对比（如本周 vs 上周）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    /// 每个时间段的天数
+    pub days: i64,
+    /// 当前时间段（含首尾，YYYY-MM-DD）
+    pub current_start: String,
+    pub current_end: String,
+    /// 上一时间段（含首尾）
+    pub previous_start: String,
+    pub previous_end: String,
+    pub metrics: Vec<PeriodMetric>,
+}
+
+/// 星期 × 小时 活跃度热力图
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityHeatmap {
+    /// 使用的时区（"system" 表示系统时区）
+    pub timezone: String,
+    /// 统计天数
+    pub range_days: i64,
+    /// Cascade 运行次数矩阵 [星期(周一=0)][小时]
+    pub cascade_runs: Vec<Vec<i64>>,
+    /// Cascade Token 消耗矩阵 [星期][小时]
+    pub cascade_tokens: Vec<Vec<i64>>,
+    /// 按星期的补全接受次数（补全数据只有日粒度）
+    pub completions_by_weekday: Vec<i64>,
+    /// 运行次数最多的时段
+    pub peak_weekday: Option<usize>,
+    pub peak_hour: Option<usize>,
+    pub total_runs: i64,
+}
+
+// ============== 月度报告 ==============
+
+/// 月度报告摘要
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonthlyReportSummary {
+    /// 接受的 Cascade 代码行数
+    pub total_accepted_lines: i64,
+    /// 建议的 Cascade 代码行数
+    pub total_suggested_lines: i64,
+    /// 有 Cascade 记录的天数
+    pub active_days: i64,
+    /// Cascade 运行次数
+    pub total_runs: i64,
+    /// Token 消耗
+    pub total_tokens: i64,
+    /// 补全接受次数
+    pub completions_accepted: i64,
+    /// 补全接受率 (%)
+    pub acceptance_rate: f64,
+    /// 主要使用的模型
+    pub primary_model: String,
+    /// 主要使用的语言
+    pub primary_language: String,
+}
+
+/// 冻结的月度报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyReport {
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    /// 月份 (YYYY-MM)
+    pub month: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    /// 用于生成报告的快照数量
+    pub snapshot_count: usize,
+    pub summary: MonthlyReportSummary,
+    /// 每日 Cascade 代码行数
+    pub daily_cascade_lines: Vec<DailyCascadeLinesCount>,
+    /// 每日补全统计
+    pub completions_by_day: Vec<CompletionByDay>,
+    /// 模型使用汇总
+    pub model_usage_summary: Vec<ModelUsageSummary>,
+    /// 语言统计（取当月最后一个快照）
+    pub completions_by_language: Vec<CompletionByLanguage>,
+}
+
+/// 月度报告列表项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyReportInfo {
+    pub account_id: uuid::Uuid,
+    pub email: String,
+    pub month: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub summary: MonthlyReportSummary,
+}
+
+// ============== 分块传输 ==============
+
+/// 分块传输的明细数据（通过 `analytics-chunk` 事件发送）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsChunk {
+    pub request_id: String,
+    pub account_id: uuid::Uuid,
+    /// 从 0 开始的块序号
+    pub index: usize,
+    pub total_chunks: usize,
+    pub items: Vec<ModelUsageEntry>,
+}
+
+/// 分块传输结束时的汇总（通过 `analytics-summary` 事件发送）
+///
+/// `data` 中的 `model_usage_details` 为空，明细已通过分块事件发送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsStreamSummary {
+    pub request_id: String,
+    pub account_id: uuid::Uuid,
+    pub total_rows: usize,
+    pub total_chunks: usize,
+    pub data: AnalyticsData,
+}
+
+// ============== 测试数据构造 ==============
+
+#[cfg(test)]
+impl ModelUsageEntry {
+    /// 测试用运行记录：2026-01-01 的单条消息，时间与消息数用 `at` / `messages` 修改
+    pub fn test_run(session_id: &str, model: &str, tokens: i64) -> Self {
+        Self {
+            timestamp: 0,
+            date: "2026-01-01".to_string(),
+            model_name: model.to_string(),
+            mode: "write".to_string(),
+            session_count: 1,
+            token_usage: tokens,
+            session_id: session_id.to_string(),
+        }
+    }
+
+    pub fn at(mut self, timestamp: i64, date: &str) -> Self {
+        self.timestamp = timestamp;
+        self.date = date.to_string();
+        self
+    }
+
+    pub fn messages(mut self, count: i64) -> Self {
+        self.session_count = count;
+        self
+    }
+}
+
+#[cfg(test)]
+impl AnalyticsSnapshot {
+    /// 测试用快照（当前抽取版本，不含原始响应）
+    pub fn test_snapshot(account_id: uuid::Uuid, date: &str, data: AnalyticsData) -> Self {
+        Self {
+            account_id,
+            date: date.to_string(),
+            captured_at: chrono::Utc::now(),
+            data,
+            raw_body: None,
+            extractor_version: ANALYTICS_EXTRACTOR_VERSION,
+        }
+    }
+}
+
+#[cfg(test)]
+impl CreditsSnapshot {
+    /// 测试用积分快照（总额度 500）
+    pub fn test_snapshot(captured_at: chrono::DateTime<chrono::Utc>, used: i32) -> Self {
+        Self {
+            account_id: uuid::Uuid::nil(),
+            captured_at,
+            usage: CreditsUsage::default(),
+            used_quota: Some(used),
+            total_quota: Some(500),
+            remaining_quota: Some(500 - used),
+        }
+    }
+}
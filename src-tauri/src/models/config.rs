@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
 
 // 支付页面延迟的默认值
 fn default_payment_page_delay() -> i32 {
@@ -18,12 +20,71 @@ pub struct GlobalTag {
     pub color: String, // 默认颜色，RGBA或HEX格式
 }
 
+/// 会话标签规则：会话元数据字段匹配正则时归入该标签（项目）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLabelRule {
+    pub label: String,
+    pub pattern: String,
+    /// 匹配的字段：session_id / model / mode
+    #[serde(default = "default_session_label_field")]
+    pub field: String,
+}
+
+fn default_session_label_field() -> String {
+    "session_id".to_string()
+}
+
+/// 会话标签：手动指定优先，其次按规则顺序匹配
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionLabels {
+    /// 会话 ID -> 标签
+    #[serde(default)]
+    pub assignments: HashMap<String, String>,
+    #[serde(default)]
+    pub rules: Vec<SessionLabelRule>,
+}
+
+/// 凭据加密配置：由专门的命令修改，不随设置一起保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretEncryption {
+    /// "none" / "keyring"（密钥保存在系统密钥链）/ "password"（由主密码派生）
+    #[serde(default = "default_secret_encryption_mode")]
+    pub mode: String,
+    /// 主密码模式的盐（base64）
+    #[serde(default)]
+    pub salt: Option<String>,
+    /// 用于校验主密码的密文
+    #[serde(default)]
+    pub check: Option<String>,
+}
+
+fn default_secret_encryption_mode() -> String {
+    "none".to_string()
+}
+
+impl Default for SecretEncryption {
+    fn default() -> Self {
+        Self {
+            mode: default_secret_encryption_mode(),
+            salt: None,
+            check: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// 数据结构版本，旧版本数据启动时自动迁移
+    #[serde(default)]
+    pub schema_version: u32,
     pub accounts: Vec<super::Account>,
     pub groups: Vec<String>,
     #[serde(default)]
     pub tags: Vec<GlobalTag>,  // 全局标签列表
+    #[serde(default)]
+    pub session_labels: SessionLabels,  // 会话的项目标签
+    #[serde(default)]
+    pub secret_encryption: SecretEncryption,  // 凭据加密方式
     pub settings: Settings,
     #[serde(default)]  // 保留字段以便向后兼容，但不再使用
     pub logs: Vec<super::OperationLog>,  // 日志现在存储在独立的 logs.json 文件中
@@ -40,6 +101,7 @@ pub enum SortField {
     TokenExpiresAt,   // Token过期时间
     SubscriptionExpiresAt, // 订阅到期时间
     PlanName,         // 套餐类型
+    ValueScore,       // 综合价值（剩余积分 × 套餐权重 ÷ 风险系数）
 }
 
 impl Default for SortField {
@@ -122,6 +184,557 @@ pub struct Settings {
     pub proxy_url: Option<String>,  // 代理地址 (如 http://127.0.0.1:7890)
     #[serde(default, rename = "useLightweightApi")]
     pub use_lightweight_api: bool,  // 使用轻量级API(GetPlanStatus)获取配额信息，否则使用GetCurrentUser
+    #[serde(default, rename = "externalSink")]
+    pub external_sink: ExternalSinkConfig,  // 外部数据库写入配置（ClickHouse/PostgreSQL）
+    #[serde(default)]
+    pub timezone: Option<String>,  // 统计使用的时区（IANA 名称，如 Asia/Shanghai），为空时使用系统时区
+    #[serde(default = "default_date_bucketing", rename = "dateBucketing")]
+    pub date_bucketing: String,  // 每日统计的日期归属："utc" 或 "timezone"（按上面的时区）
+    #[serde(default, rename = "shareUpload")]
+    pub share_upload: ShareUploadConfig,  // 分享报告上传目标（WebDAV/S3）
+    #[serde(default, rename = "claimUserName")]
+    pub claim_user_name: Option<String>,  // 共享账号池中本机使用者的名称
+    #[serde(default = "default_claim_ttl", rename = "claimTtlMinutes")]
+    pub claim_ttl_minutes: i64,  // 占用账号的默认时长（分钟）
+    #[serde(default)]
+    pub insights: InsightsConfig,  // 使用洞察（自备 Key 的 OpenAI 兼容接口）
+    #[serde(default, rename = "autoExport")]
+    pub auto_export: AutoExportConfig,  // 定时导出到指定文件夹
+    #[serde(default = "default_auth_failure_threshold", rename = "authFailureThreshold")]
+    pub auth_failure_threshold: u32,  // 后台任务连续认证失败多少次后标记为需要处理
+    #[serde(default, rename = "protoDebugLogging")]
+    pub proto_debug_logging: bool,  // 将原始请求体（十六进制）写入 proto-debug.log
+    #[serde(default, rename = "protoCapture")]
+    pub proto_capture: bool,  // 抓包模式：每个请求/响应体（十六进制 + 解析结果）写入 captures/ 下的独立文件
+    #[serde(default, rename = "verboseProtocolLogging")]
+    pub verbose_protocol_logging: Option<bool>,  // 控制台输出逐条解析日志，为空时仅调试构建开启
+    #[serde(default, rename = "devMode")]
+    pub dev_mode: bool,  // 开发者模式：启用抽取回归样例等调试命令
+    #[serde(default, rename = "persistenceBackend")]
+    pub persistence_backend: String,  // 持久化后端：json / sqlite，为空时保持当前后端
+    #[serde(default, rename = "memoryOnlySecrets")]
+    pub memory_only_secrets: bool,  // 仅内存模式：Token 只在本次会话中保留，不写入存储和备份
+    #[serde(default = "default_cache_storage", rename = "cacheStorage")]
+    pub cache_storage: String,  // 分析快照与积分历史的存放方式：plaintext / encrypted / memory（仅内存）
+    #[serde(default = "default_storage_backend", rename = "storageBackend")]
+    pub storage_backend: String,  // Refresh Token 存放位置：datastore / keyring（系统密钥链）
+    #[serde(default = "default_mappings_url", rename = "mappingsUrl")]
+    pub mappings_url: String,  // 语言/模型映射表下载地址
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,  // 外部进程插件
+    #[serde(default = "default_quota_alert_percent", rename = "quotaAlertPercent")]
+    pub quota_alert_percent: f64,  // 剩余额度低于该百分比时触发额度提醒
+    #[serde(default, rename = "healthPing")]
+    pub health_ping: HealthPingConfig,  // 定时健康检查
+    #[serde(default, rename = "modelCreditRates")]
+    pub model_credit_rates: HashMap<String, f64>,  // 模型名称 -> 每条消息消耗的积分（会话账单估算用）
+    #[serde(default, rename = "analyticsPresets")]
+    pub analytics_presets: Vec<AnalyticsRequestPreset>,  // 自定义的 GetAnalytics 请求预设（同名时覆盖内置预设）
+    #[serde(default, rename = "analyticsPreset")]
+    pub analytics_preset: Option<String>,  // 默认使用的请求预设，为空时使用内置的逐级降级请求
+    #[serde(default = "default_request_timeout", rename = "requestTimeoutSecs")]
+    pub request_timeout_secs: u64,  // HTTP 请求总超时（秒）
+    #[serde(default, rename = "keepRawAnalytics")]
+    pub keep_raw_analytics: bool,  // 在分析快照中保留原始响应，抽取逻辑升级后可重新处理历史数据
+    #[serde(default, rename = "analyticsCacheTtlMinutes")]
+    pub analytics_cache_ttl_minutes: i64,  // 分析数据缓存时长（分钟），期间直接返回最近的快照，0 表示每次都请求
+    #[serde(default = "default_language")]
+    pub language: String,  // 界面语言（如 zh-CN、en-US）
+    #[serde(default, rename = "apiRetry")]
+    pub api_retry: ApiRetryConfig,  // Windsurf 接口的重试策略
+    #[serde(default, rename = "rateLimit")]
+    pub rate_limit: RateLimitConfig,  // Windsurf 接口的请求限速
+    #[serde(default, rename = "customHeaders")]
+    pub custom_headers: CustomHeadersConfig,  // 附加到 Windsurf 请求的自定义请求头（租户 ID、网关密钥等）
+    #[serde(default, rename = "idlePrefetch")]
+    pub idle_prefetch: IdlePrefetchConfig,  // 空闲时预取使用分析
+    #[serde(default, rename = "accountNoteTemplate")]
+    pub account_note_template: Option<String>,  // 批量添加账号时的备注模板，支持 {email}、{nickname}、{created}
+    #[serde(default, rename = "meteredConnection")]
+    pub metered_connection: bool,  // 手动标记当前网络按流量计费
+    #[serde(default, rename = "defaultAccount")]
+    pub default_account: DefaultAccountConfig,  // 启动时检查并应用到 IDE 的默认账号
+    #[serde(default, rename = "alertRules")]
+    pub alert_rules: Vec<AlertRule>,  // 用量告警规则，每次拉取分析数据后检查
+    #[serde(default)]
+    pub notifications: NotificationConfig,  // 桌面通知及各类通知的开关
+}
+
+/// GetAnalytics 请求预设：请求哪些查询、是否带时间范围、使用的时区
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsRequestPreset {
+    pub name: String,
+    /// QueryRequest 中的查询字段号（如 20 = cascade_stats，25 = cascade_runs）
+    #[serde(default)]
+    pub query_fields: Vec<u32>,
+    /// 需要附带 time_zone 参数的查询字段号（如 2 = completions_by_day，10 = chats_by_day）
+    #[serde(default)]
+    pub time_zone_fields: Vec<u32>,
+    /// 为空时使用设置中的时区，再为空时使用系统时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// 是否发送 start/end 时间戳
+    #[serde(default = "default_true")]
+    pub include_timestamps: bool,
+    /// end_timestamp 的 nanos（官网固定为 999999000）
+    #[serde(default)]
+    pub end_nanos: Option<i32>,
+    /// 时间范围天数
+    #[serde(default = "default_preset_days")]
+    pub days: i64,
+}
+
+fn default_preset_days() -> i64 {
+    30
+}
+
+/// Windsurf 接口重试策略：仅在网络错误或 5xx 时按指数退避重试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiRetryConfig {
+    #[serde(default = "default_retry_max_attempts", rename = "maxAttempts")]
+    pub max_attempts: u32,  // 最多尝试次数（含第一次），1 表示不重试
+    #[serde(default = "default_retry_base_delay", rename = "baseDelayMs")]
+    pub base_delay_ms: u64,  // 第一次重试前的等待，之后每次翻倍
+    #[serde(default = "default_retry_max_delay", rename = "maxDelayMs")]
+    pub max_delay_ms: u64,  // 单次等待上限
+    #[serde(default = "default_true")]
+    pub jitter: bool,  // 是否在等待时间上加入随机抖动
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay() -> u64 {
+    500
+}
+
+fn default_retry_max_delay() -> u64 {
+    8000
+}
+
+impl Default for ApiRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            base_delay_ms: default_retry_base_delay(),
+            max_delay_ms: default_retry_max_delay(),
+            jitter: true,
+        }
+    }
+}
+
+/// Windsurf 接口限速（令牌桶）：批量刷新大量账号时避免触发风控
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_rate_global_per_minute", rename = "globalPerMinute")]
+    pub global_per_minute: u32,  // 所有账号合计每分钟请求数
+    #[serde(default = "default_rate_global_burst", rename = "globalBurst")]
+    pub global_burst: u32,  // 全局允许的瞬时突发请求数
+    #[serde(default = "default_rate_account_per_minute", rename = "accountPerMinute")]
+    pub account_per_minute: u32,  // 单个账号每分钟请求数
+    #[serde(default = "default_rate_account_burst", rename = "accountBurst")]
+    pub account_burst: u32,  // 单个账号允许的瞬时突发请求数
+}
+
+fn default_rate_global_per_minute() -> u32 {
+    120
+}
+
+fn default_rate_global_burst() -> u32 {
+    10
+}
+
+fn default_rate_account_per_minute() -> u32 {
+    30
+}
+
+fn default_rate_account_burst() -> u32 {
+    5
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            global_per_minute: default_rate_global_per_minute(),
+            global_burst: default_rate_global_burst(),
+            account_per_minute: default_rate_account_per_minute(),
+            account_burst: default_rate_account_burst(),
+        }
+    }
+}
+
+/// 自定义请求头：部分企业部署的网关要求额外的请求头（租户 ID、网关密钥等）
+///
+/// 同名请求头按 账号 > 接口 > 全局 的优先级覆盖；启用凭据加密时请求头的值与账号凭据一起加密保存
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomHeadersConfig {
+    #[serde(default)]
+    pub global: HashMap<String, String>,  // 所有请求都附加的请求头
+    #[serde(default)]
+    pub endpoints: HashMap<String, HashMap<String, String>>,  // 按接口名（如 GetAnalytics）或域名（如 server.codeium.com）附加
+    #[serde(default)]
+    pub accounts: HashMap<String, HashMap<String, String>>,  // 按账号 ID 附加
+}
+
+/// 桌面通知配置：后台任务发现问题时弹出系统通知，可按类别关闭
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true", rename = "tokenExpired")]
+    pub token_expired: bool,  // Token 刷新失败、认证失败
+    #[serde(default = "default_true", rename = "analyticsFailed")]
+    pub analytics_failed: bool,  // 后台拉取使用分析失败
+    #[serde(default = "default_true", rename = "quotaLow")]
+    pub quota_low: bool,  // 额度不足、用量告警
+    #[serde(default = "default_true", rename = "accountBanned")]
+    pub account_banned: bool,  // 账号被禁用或不可用
+    #[serde(default = "default_true", rename = "jobOverrun")]
+    pub job_overrun: bool,  // 后台任务运行超时被取消
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            token_expired: true,
+            analytics_failed: true,
+            quota_low: true,
+            account_banned: true,
+            job_overrun: true,
+        }
+    }
+}
+
+/// 用量告警规则：指标超过（或低于）阈值时告警
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub metric: String,  // daily_tokens / daily_runs / daily_accepted_lines / prompt_credits_used / credits_remaining
+    #[serde(default = "default_alert_operator")]
+    pub operator: String,  // gt：大于阈值时告警，lt：小于阈值时告警
+    pub threshold: f64,
+    #[serde(default, rename = "accountIds")]
+    pub account_ids: Vec<String>,  // 只检查这些账号，为空时检查所有账号
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_alert_cooldown_minutes", rename = "cooldownMinutes")]
+    pub cooldown_minutes: i64,  // 同一规则对同一账号的最短告警间隔
+}
+
+fn default_alert_operator() -> String {
+    "gt".to_string()
+}
+
+fn default_alert_cooldown_minutes() -> i64 {
+    1440
+}
+
+/// 默认账号配置：应用或后台进程启动时检查该账号，可选自动应用到 IDE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultAccountConfig {
+    #[serde(default, rename = "accountId")]
+    pub account_id: Option<String>,  // 默认账号 ID，为空时不做任何处理
+    #[serde(default = "default_true", rename = "verifyOnStartup")]
+    pub verify_on_startup: bool,  // 启动时检查账号状态，异常时发出通知
+    #[serde(default, rename = "applyOnStartup")]
+    pub apply_on_startup: bool,  // 启动时自动切换到该账号（检查异常时不切换）
+}
+
+impl Default for DefaultAccountConfig {
+    fn default() -> Self {
+        Self {
+            account_id: None,
+            verify_on_startup: true,
+            apply_on_startup: false,
+        }
+    }
+}
+
+/// 空闲预取配置：应用空闲时预先拉取指定账号的使用分析，打开账号页面时直接使用快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlePrefetchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_prefetch_idle_minutes", rename = "idleMinutes")]
+    pub idle_minutes: i64,  // 无操作多久后视为空闲
+    #[serde(default)]
+    pub tags: Vec<String>,  // 只预取带有这些标签的账号，与固定账号都为空时预取所有账号
+    #[serde(default, rename = "pinnedAccounts")]
+    pub pinned_accounts: Vec<Uuid>,  // 固定预取的账号，不受标签限制且优先预取
+    #[serde(default = "default_prefetch_max_accounts", rename = "maxAccounts")]
+    pub max_accounts: usize,  // 每轮最多预取的账号数（请求预算）
+    #[serde(default = "default_prefetch_fresh_minutes", rename = "freshMinutes")]
+    pub fresh_minutes: i64,  // 快照在该时长内视为最新，不再预取
+    #[serde(default = "default_true", rename = "skipOnMetered")]
+    pub skip_on_metered: bool,  // 按流量计费的网络下不预取
+}
+
+fn default_prefetch_idle_minutes() -> i64 {
+    5
+}
+
+fn default_prefetch_max_accounts() -> usize {
+    5
+}
+
+fn default_prefetch_fresh_minutes() -> i64 {
+    60
+}
+
+impl Default for IdlePrefetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: default_prefetch_idle_minutes(),
+            tags: Vec::new(),
+            pinned_accounts: Vec::new(),
+            max_accounts: default_prefetch_max_accounts(),
+            fresh_minutes: default_prefetch_fresh_minutes(),
+            skip_on_metered: true,
+        }
+    }
+}
+
+/// 定时健康检查配置：定期对每个账号发起一次轻量的认证请求，保持会话并尽早发现封禁
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthPingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_health_ping_interval")]
+    pub interval_minutes: i64,  // 每个账号的检查间隔
+    #[serde(default = "default_health_ping_jitter")]
+    pub jitter_seconds: u64,  // 相邻两次请求之间的随机等待上限
+}
+
+fn default_health_ping_interval() -> i64 {
+    180
+}
+
+fn default_health_ping_jitter() -> u64 {
+    20
+}
+
+impl Default for HealthPingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_health_ping_interval(),
+            jitter_seconds: default_health_ping_jitter(),
+        }
+    }
+}
+
+/// 外部进程插件配置：通过 stdio 上的 JSON-RPC 接收生命周期事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub name: String,
+    pub command: String,  // 可执行文件路径
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub events: Vec<String>,  // 订阅的事件，为空时接收全部事件
+}
+
+/// 定时自动导出配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoExportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub folder: String,  // 导出目录（可以是 Dropbox 等同步盘目录）
+    #[serde(default = "default_export_interval")]
+    pub interval_hours: u64,
+    #[serde(default = "default_export_keep")]
+    pub keep: usize,  // 保留最近几次导出
+    #[serde(default)]
+    pub include_secrets: bool,  // 是否包含密码和 refresh_token
+    #[serde(default)]
+    pub password: Option<String>,  // 导出口令：包含凭据时必须设置，账号数据整体加密后再写入；启用凭据加密时口令本身也加密保存
+}
+
+fn default_export_interval() -> u64 {
+    24
+}
+
+fn default_export_keep() -> usize {
+    7
+}
+
+impl Default for AutoExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folder: String::new(),
+            interval_hours: default_export_interval(),
+            keep: default_export_keep(),
+            include_secrets: false,
+            password: None,
+        }
+    }
+}
+
+/// 使用洞察配置：只发送匿名聚合指标，不发送邮箱与 Token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsightsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub endpoint: String,  // OpenAI 兼容接口地址，如 https://api.openai.com/v1
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default = "default_insights_model")]
+    pub model: String,
+}
+
+fn default_insights_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+impl Default for InsightsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            api_key: String::new(),
+            model: default_insights_model(),
+        }
+    }
+}
+
+/// 分享报告上传配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareUploadConfig {
+    #[serde(default = "default_share_target")]
+    pub target: String,  // "none"、"webdav" 或 "s3"
+    #[serde(default)]
+    pub webdav_url: String,  // WebDAV 目录地址
+    #[serde(default)]
+    pub webdav_username: Option<String>,
+    #[serde(default)]
+    pub webdav_password: Option<String>,
+    #[serde(default)]
+    pub s3_endpoint: String,  // 如 https://s3.amazonaws.com 或兼容存储地址
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    #[serde(default)]
+    pub s3_bucket: String,
+    #[serde(default)]
+    pub s3_access_key: String,
+    #[serde(default)]
+    pub s3_secret_key: String,
+    #[serde(default)]
+    pub s3_public_base_url: Option<String>,  // 公开访问地址前缀（如 CDN），为空时返回对象地址
+}
+
+fn default_share_target() -> String {
+    "none".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+impl Default for ShareUploadConfig {
+    fn default() -> Self {
+        Self {
+            target: default_share_target(),
+            webdav_url: String::new(),
+            webdav_username: None,
+            webdav_password: None,
+            s3_endpoint: String::new(),
+            s3_region: default_s3_region(),
+            s3_bucket: String::new(),
+            s3_access_key: String::new(),
+            s3_secret_key: String::new(),
+            s3_public_base_url: None,
+        }
+    }
+}
+
+/// 外部数据库写入配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSinkConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_sink_kind")]
+    pub kind: String,  // "clickhouse" 或 "postgres"
+    #[serde(default)]
+    pub url: String,  // ClickHouse HTTP 地址或 PostgreSQL 连接串
+    #[serde(default = "default_sink_table")]
+    pub table: String,
+    #[serde(default)]
+    pub username: Option<String>,  // ClickHouse HTTP 认证用户名
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_sink_kind() -> String {
+    "clickhouse".to_string()
+}
+
+fn default_sink_table() -> String {
+    "windsurf_analytics".to_string()
+}
+
+impl Default for ExternalSinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: default_sink_kind(),
+            url: String::new(),
+            table: default_sink_table(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+fn default_auth_failure_threshold() -> u32 {
+    3
+}
+
+fn default_claim_ttl() -> i64 {
+    120
+}
+
+fn default_persistence_backend() -> String {
+    "json".to_string()
+}
+
+fn default_cache_storage() -> String {
+    crate::repository::secrets::CACHE_PLAINTEXT.to_string()
+}
+
+fn default_storage_backend() -> String {
+    crate::services::keyring_service::BACKEND_DATASTORE.to_string()
+}
+
+fn default_date_bucketing() -> String {
+    "utc".to_string()
+}
+
+fn default_quota_alert_percent() -> f64 {
+    10.0
+}
+
+fn default_request_timeout() -> u64 {
+    30
+}
+
+fn default_language() -> String {
+    "zh-CN".to_string()
+}
+
+fn default_mappings_url() -> String {
+    crate::services::mapping_service::DEFAULT_MAPPINGS_URL.to_string()
 }
 
 fn default_browser_mode() -> String {
@@ -136,6 +749,23 @@ fn default_true() -> bool {
     true
 }
 
+impl Settings {
+    /// 去掉各类密钥、密码与连接串的副本，用于只读面板
+    pub fn redacted(&self) -> Self {
+        let mut settings = self.clone();
+        settings.proxy_url = None;
+        settings.external_sink.url.clear();
+        settings.external_sink.password = None;
+        settings.auto_export.password = None;
+        settings.share_upload.webdav_password = None;
+        settings.share_upload.s3_access_key.clear();
+        settings.share_upload.s3_secret_key.clear();
+        settings.insights.api_key.clear();
+        settings.custom_headers = CustomHeadersConfig::default();
+        settings
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -167,6 +797,43 @@ impl Default for Settings {
             proxy_enabled: false,  // 默认关闭代理
             proxy_url: None,  // 默认无代理地址
             use_lightweight_api: false,  // 默认关闭轻量级API
+            external_sink: ExternalSinkConfig::default(),  // 默认不写入外部数据库
+            timezone: None,  // 默认使用系统时区
+            date_bucketing: default_date_bucketing(),  // 默认按 UTC 日期统计
+            share_upload: ShareUploadConfig::default(),  // 默认只生成本地文件
+            claim_user_name: None,  // 默认使用系统用户名
+            claim_ttl_minutes: 120,  // 默认占用2小时
+            insights: InsightsConfig::default(),  // 默认关闭使用洞察
+            auto_export: AutoExportConfig::default(),  // 默认关闭自动导出
+            auth_failure_threshold: 3,  // 默认连续失败3次后停止自动处理
+            proto_debug_logging: false,  // 默认关闭协议调试日志
+            proto_capture: false,  // 默认关闭抓包
+            verbose_protocol_logging: None,  // 默认跟随构建类型
+            dev_mode: false,  // 默认关闭开发者模式
+            persistence_backend: default_persistence_backend(),  // 默认使用 JSON 文件
+            memory_only_secrets: false,  // 默认持久化 Token
+            cache_storage: default_cache_storage(),  // 默认明文保存到数据目录
+            storage_backend: default_storage_backend(),  // 默认与其他数据一起保存
+            mappings_url: default_mappings_url(),  // 默认从项目仓库下载
+            plugins: Vec::new(),  // 默认没有插件
+            quota_alert_percent: default_quota_alert_percent(),  // 默认剩余10%时提醒
+            health_ping: HealthPingConfig::default(),  // 默认关闭健康检查
+            model_credit_rates: HashMap::new(),  // 默认每条消息按 1 积分估算
+            analytics_presets: Vec::new(),  // 默认只有内置预设
+            analytics_preset: None,  // 默认使用逐级降级请求
+            request_timeout_secs: default_request_timeout(),  // 默认30秒
+            analytics_cache_ttl_minutes: 0,  // 默认不缓存
+            keep_raw_analytics: false,  // 默认不保留原始响应
+            language: default_language(),  // 默认简体中文
+            api_retry: ApiRetryConfig::default(),  // 默认最多尝试3次
+            rate_limit: RateLimitConfig::default(),  // 默认全局每分钟120次、单账号每分钟30次
+            custom_headers: CustomHeadersConfig::default(),  // 默认不附加请求头
+            idle_prefetch: IdlePrefetchConfig::default(),  // 默认关闭空闲预取
+            account_note_template: None,  // 默认不生成备注
+            metered_connection: false,  // 默认不按流量计费
+            default_account: DefaultAccountConfig::default(),  // 默认不设置默认账号
+            alert_rules: Vec::new(),  // 默认没有告警规则
+            notifications: NotificationConfig::default(),  // 默认开启所有通知
         }
     }
 }
@@ -174,9 +841,12 @@ impl Default for Settings {
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            schema_version: crate::repository::migrations::CURRENT_SCHEMA_VERSION,
             accounts: Vec::new(),
             groups: vec!["默认分组".to_string()],
             tags: Vec::new(),
+            session_labels: SessionLabels::default(),
+            secret_encryption: SecretEncryption::default(),
             settings: Settings::default(),
             logs: Vec::new(),
         }
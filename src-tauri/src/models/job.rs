@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 后台定时任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// 月末归档月度报告
+    MonthlyReport,
+    /// 定时导出账号 CSV 与分析 JSONL 到指定文件夹
+    AutoExport,
+    /// 刷新即将过期的 Token，连续失败的账号标记为需要处理
+    TokenRefresh,
+    /// 定时健康检查：保持会话活跃并尽早发现封禁
+    HealthPing,
+    /// 应用空闲时预取使用分析
+    IdlePrefetch,
+    /// 抽取逻辑升级后重新处理保存了原始响应的分析快照
+    ReprocessSnapshots,
+}
+
+impl JobKind {
+    pub fn all() -> &'static [JobKind] {
+        &[JobKind::MonthlyReport, JobKind::AutoExport, JobKind::TokenRefresh, JobKind::HealthPing, JobKind::IdlePrefetch, JobKind::ReprocessSnapshots]
+    }
+
+    /// 任务执行间隔
+    pub fn interval(&self) -> chrono::Duration {
+        match self {
+            JobKind::MonthlyReport => chrono::Duration::hours(1),
+            // 实际导出间隔由设置控制，这里只是检查频率
+            JobKind::AutoExport => chrono::Duration::minutes(30),
+            JobKind::TokenRefresh => chrono::Duration::minutes(10),
+            // 每个账号的检查间隔由设置控制
+            JobKind::HealthPing => chrono::Duration::minutes(15),
+            // 是否空闲由设置中的空闲时长判断
+            JobKind::IdlePrefetch => chrono::Duration::minutes(5),
+            // 只处理版本落后的快照，没有需要处理的快照时几乎没有开销
+            JobKind::ReprocessSnapshots => chrono::Duration::hours(24),
+        }
+    }
+
+    /// 任务允许的最长运行时间，超过后视为卡住（如 HTTP 请求无响应），由看门狗强制取消
+    pub fn max_duration(&self) -> chrono::Duration {
+        match self {
+            JobKind::MonthlyReport => chrono::Duration::minutes(10),
+            JobKind::AutoExport => chrono::Duration::minutes(15),
+            JobKind::TokenRefresh => chrono::Duration::minutes(10),
+            // 账号之间有随机等待
+            JobKind::HealthPing => chrono::Duration::minutes(30),
+            JobKind::IdlePrefetch => chrono::Duration::minutes(20),
+            JobKind::ReprocessSnapshots => chrono::Duration::minutes(30),
+        }
+    }
+
+    /// 是否为大量请求的任务，按流量计费的网络下跳过
+    pub fn is_heavy(&self) -> bool {
+        matches!(self, JobKind::HealthPing | JobKind::IdlePrefetch)
+    }
+}
+
+/// 一次任务运行记录（用于导出任务历史）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job: JobKind,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub success: bool,
+    #[serde(default)]
+    pub skipped: bool,
+    pub message: String,
+}
@@ -14,7 +14,7 @@ pub struct OperationLog {
     pub details: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum OperationType {
     Login,
@@ -28,9 +28,12 @@ pub enum OperationType {
     DeleteAccount,
     EditAccount,
     BatchOperation,
+    DataRecovery,
+    /// 后台任务运行超时被看门狗取消
+    JobCancelled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum OperationStatus {
     Success,
@@ -68,3 +71,63 @@ impl OperationLog {
         self
     }
 }
+
+/// 操作日志筛选条件，未设置的条件不参与筛选
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    /// 日志级别（操作状态），如 failed
+    #[serde(default)]
+    pub level: Option<OperationStatus>,
+    /// 模块（操作类型），如 get_account_info
+    #[serde(default)]
+    pub module: Option<OperationType>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// 在消息、账号邮箱与详情中搜索（不区分大小写）
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub account_id: Option<Uuid>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &OperationLog) -> bool {
+        if self.level.as_ref().is_some_and(|level| *level != log.status) {
+            return false;
+        }
+        if self.module.as_ref().is_some_and(|module| *module != log.operation_type) {
+            return false;
+        }
+        if self.since.is_some_and(|since| log.timestamp < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| log.timestamp > until) {
+            return false;
+        }
+        if self.account_id.is_some() && self.account_id != log.account_id {
+            return false;
+        }
+        if let Some(text) = self.text.as_deref().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+            let in_message = log.message.to_lowercase().contains(&text);
+            let in_email = log.account_email.as_deref().is_some_and(|e| e.to_lowercase().contains(&text));
+            let in_details = log.details.as_ref().is_some_and(|d| d.to_string().to_lowercase().contains(&text));
+            if !in_message && !in_email && !in_details {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 日志分页查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPage {
+    pub items: Vec<OperationLog>,
+    /// 筛选后的总数
+    pub total: usize,
+    /// 页码，从 1 开始
+    pub page: usize,
+    pub page_size: usize,
+}
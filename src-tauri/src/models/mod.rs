@@ -3,9 +3,11 @@ pub mod config;
 pub mod log;
 pub mod analytics;
 pub mod reset_record;
+pub mod job;
 
 pub use account::*;
 pub use config::*;
 pub use log::*;
 pub use analytics::*;
 pub use reset_record::*;
+pub use job::*;
@@ -1,5 +1,13 @@
-use crate::models::{Account, AppConfig, OperationLog};
+use crate::models::{Account, AlertEvent, OnboardingStep, AnalyticsData, AnalyticsSnapshot, AppConfig, CreditsSnapshot, CreditsUsage, JobRun, MonthlyReport, OperationLog, OperationStatus, OperationType, TokenSource};
 use crate::utils::{AppError, AppResult};
+use super::integrity::{self, IntegrityReport};
+use super::journal::RecoveryAction;
+use super::merge;
+use super::migrations::{self, MigrationReport};
+use super::secrets;
+use super::storage::{self, Storage};
+use crate::utils::crypto::CryptoService;
+use crate::services::keyring_service;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -7,7 +15,8 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 use tauri::{Manager, Emitter};
 use chrono::Local;
-use serde::Serialize;
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
 
 /// Token 刷新事件负载
 #[derive(Clone, Serialize)]
@@ -17,16 +26,89 @@ pub struct TokenRefreshedPayload {
     pub token_expires_at: String,
 }
 
+/// 待重新处理的分析快照：定位信息与原始响应（Base64）
+pub struct OutdatedSnapshot {
+    pub index: usize,
+    pub account_id: Uuid,
+    pub date: String,
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub raw_body: String,
+}
+
+/// 额度提醒事件负载（剩余额度首次低于设置的百分比时发出）
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QuotaAlertPayload {
+    pub account_id: String,
+    pub email: String,
+    pub used_quota: i32,
+    pub total_quota: i32,
+    pub remaining_percent: f64,
+}
+
+/// 剩余额度百分比，没有额度信息时返回 None
+fn remaining_percent(account: &Account) -> Option<f64> {
+    match (account.used_quota, account.total_quota) {
+        (Some(used), Some(total)) if total > 0 => Some((total - used).max(0) as f64 * 100.0 / total as f64),
+        _ => None,
+    }
+}
+
+/// 两次拉取的分析数据是否相同（忽略每次加载都不同的耗时信息）
+fn same_analytics(a: &AnalyticsData, b: &AnalyticsData) -> bool {
+    let strip = |data: &AnalyticsData| {
+        let mut value = serde_json::to_value(data).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            object.remove("timings");
+        }
+        value
+    };
+    strip(a) == strip(b)
+}
+
 pub struct DataStore {
     pub config: Arc<RwLock<AppConfig>>,
     config_path: PathBuf,
     pub logs: Arc<RwLock<Vec<OperationLog>>>,
-    logs_path: PathBuf,
+    /// 分析数据历史快照（独立存储在 analytics_snapshots 数据项）
+    pub analytics_snapshots: Arc<RwLock<Vec<AnalyticsSnapshot>>>,
+    /// 冻结的月度报告（独立存储在 monthly_reports 数据项）
+    pub monthly_reports: Arc<RwLock<Vec<MonthlyReport>>>,
+    /// 积分用量历史（独立存储在 credits_history 数据项）
+    pub credits_history: Arc<RwLock<Vec<CreditsSnapshot>>>,
+    /// 用量告警历史（独立存储在 alerts_history 数据项）
+    pub alerts_history: Arc<RwLock<Vec<AlertEvent>>>,
+    /// 后台定时任务运行记录（独立存储在 job_history 数据项）
+    pub job_history: Arc<RwLock<Vec<JobRun>>>,
+    /// 持久化后端（JSON 文件 / SQLite / 内存），运行时可切换
+    storage: std::sync::RwLock<Arc<dyn Storage>>,
     app_handle: tauri::AppHandle,
+    /// 启动时生成的完整性报告
+    startup_report: IntegrityReport,
+    /// 本次启动时执行的数据迁移
+    migration_report: Option<MigrationReport>,
+    /// 写入数据项后的回调（后台进程据此通知界面进程同步）
+    save_hook: std::sync::RwLock<Option<SaveHook>>,
+    /// 连接到后台进程时的写入通道，设置后所有数据项都交给后台进程合并保存
+    remote_writer: std::sync::RwLock<Option<RemoteWriter>>,
+    /// 各数据项上次与后台进程同步时的内容（三方合并的基准）
+    synced: std::sync::Mutex<std::collections::HashMap<&'static str, serde_json::Value>>,
+    /// 凭据加密密钥（未启用加密或尚未用主密码解锁时为 None）
+    crypto: std::sync::RwLock<Option<Arc<CryptoService>>>,
 }
 
+/// 保存回调，参数为写入的数据项
+pub type SaveHook = Box<dyn Fn(&'static str) + Send + Sync>;
+
+/// 远程写入：(数据项, 上次同步的内容, 本进程当前的内容)，由后台进程合并后保存
+pub type RemoteWriter = Arc<
+    dyn Fn(&'static str, serde_json::Value, serde_json::Value) -> futures::future::BoxFuture<'static, AppResult<()>>
+        + Send
+        + Sync,
+>;
+
 impl DataStore {
-    pub fn new(app_handle: &tauri::AppHandle) -> AppResult<Self> {
+    /// 打开数据存储，`recover` 为 false 时跳过未完成写入的恢复（后台进程正在运行）
+    pub fn new(app_handle: &tauri::AppHandle, recover: bool) -> AppResult<Self> {
         let app_data_dir = app_handle.path().app_data_dir()
             .map_err(|e| AppError::Config(format!("Failed to get app data dir: {}", e)))?;
         
@@ -34,10 +116,45 @@ impl DataStore {
         fs::create_dir_all(&app_data_dir)?;
         
         let config_path = app_data_dir.join("accounts.json");
-        let mut config = Self::load_config(&config_path)?;
         
-        let logs_path = app_data_dir.join("logs.json");
-        let mut logs = Self::load_logs(&logs_path)?;
+        // 进程被强制结束时可能留下未完成的写入，JSON 后端打开时先重放或回滚
+        let backend = storage::selected_backend(&app_data_dir);
+        let (storage, recovery_reports) = storage::open_storage(&app_data_dir, &backend, recover)?;
+        println!("[DataStore] Using {} storage backend", storage.name());
+        
+        let (mut config, mut migration_report) = Self::load_config(storage.as_ref())?;
+        let mut logs = Self::load_logs(storage.as_ref())?;
+        let monthly_reports = Self::load_monthly_reports(storage.as_ref());
+        let alerts_history = Self::load_alerts_history(storage.as_ref());
+        let job_history = Self::load_job_history(storage.as_ref());
+        config.settings.persistence_backend = storage.name().to_string();
+        
+        // 旧版本数据：先备份迁移前的原始文件，再写回迁移后的数据
+        if let Some(report) = migration_report.as_mut() {
+            if let Some(raw) = storage.load(storage::KEY_CONFIG)? {
+                let backup_dir = app_data_dir.join("backups");
+                fs::create_dir_all(&backup_dir)?;
+                let backup_path = backup_dir.join(format!(
+                    "pre_migration_v{}_{}.json",
+                    report.from_version,
+                    Local::now().format("%Y%m%d_%H%M%S")
+                ));
+                fs::write(&backup_path, raw)?;
+                report.backup_path = Some(backup_path.to_string_lossy().to_string());
+            }
+            storage.save(storage::KEY_CONFIG, &serde_json::to_string_pretty(&config)?)?;
+            println!("[DataStore] Migrated data from schema v{} to v{}: {}",
+                report.from_version, report.to_version, report.applied.join("; "));
+            logs.push(
+                OperationLog::new(
+                    OperationType::DataRecovery,
+                    OperationStatus::Success,
+                    format!("数据迁移 v{} → v{}", report.from_version, report.to_version),
+                )
+                .with_details(serde_json::to_value(&*report).unwrap_or_default()),
+            );
+            storage.save(storage::KEY_LOGS, &serde_json::to_string_pretty(&logs)?)?;
+        }
         
         // 迁移旧的日志数据
         if !config.logs.is_empty() && logs.is_empty() {
@@ -45,161 +162,526 @@ impl DataStore {
             config.logs.clear();
             
             // 保存迁移后的数据
-            let logs_data = serde_json::to_string_pretty(&logs)?;
-            fs::write(&logs_path, logs_data)?;
+            storage.save(storage::KEY_LOGS, &serde_json::to_string_pretty(&logs)?)?;
+            storage.save(storage::KEY_CONFIG, &serde_json::to_string_pretty(&config)?)?;
+        }
+        
+        // 将恢复结果写入操作日志
+        if !recovery_reports.is_empty() {
+            for report in &recovery_reports {
+                println!("[DataStore] Recovered pending write for {}: {:?} ({})",
+                    report.target, report.action, report.detail);
+                let status = if report.action == RecoveryAction::RolledBack {
+                    OperationStatus::Failed
+                } else {
+                    OperationStatus::Success
+                };
+                logs.push(
+                    OperationLog::new(
+                        OperationType::DataRecovery,
+                        status,
+                        format!("启动恢复 {}: {}", report.target, report.detail),
+                    )
+                    .with_details(serde_json::to_value(report).unwrap_or_default()),
+                );
+            }
             
-            let config_data = serde_json::to_string_pretty(&config)?;
-            fs::write(&config_path, config_data)?;
+            storage.save(storage::KEY_LOGS, &serde_json::to_string_pretty(&logs)?)?;
+        }
+        
+        // 内存中保存明文凭据，写入时加密；主密码模式需解锁后才能解密
+        let crypto = match secrets::open_crypto(&config.secret_encryption) {
+            Ok(crypto) => crypto.map(Arc::new),
+            Err(e) => {
+                println!("[DataStore] {}", e);
+                None
+            }
+        };
+        Self::decrypt_loaded_config(&mut config, crypto.as_deref());
+        // 占位值不论当前设置如何都取回，切换回数据存储后下次保存即写回明文
+        keyring_service::resolve_refresh_tokens(&mut config.accounts);
+        
+        let analytics_snapshots = Self::load_snapshots(storage.as_ref(), crypto.as_deref());
+        let credits_history = Self::load_credits_history(storage.as_ref(), crypto.as_deref());
+        
+        // 与上次保存时写入的校验清单比较，记录意外变化
+        let manifest = storage.load(storage::KEY_INTEGRITY).unwrap_or_else(|e| {
+            println!("[DataStore] Failed to load integrity manifest: {}", e);
+            None
+        });
+        let startup_report = integrity::build_report(manifest.as_deref(), &config, recovery_reports);
+        if startup_report.has_unexpected_changes {
+            println!("[DataStore] Startup integrity check: {} missing, {} credential changes",
+                startup_report.accounts_missing.len(), startup_report.credentials_changed.len());
         }
         
         Ok(Self {
             config: Arc::new(RwLock::new(config)),
             config_path,
             logs: Arc::new(RwLock::new(logs)),
-            logs_path,
+            analytics_snapshots: Arc::new(RwLock::new(analytics_snapshots)),
+            monthly_reports: Arc::new(RwLock::new(monthly_reports)),
+            credits_history: Arc::new(RwLock::new(credits_history)),
+            alerts_history: Arc::new(RwLock::new(alerts_history)),
+            job_history: Arc::new(RwLock::new(job_history)),
+            storage: std::sync::RwLock::new(storage),
             app_handle: app_handle.clone(),
+            startup_report,
+            migration_report,
+            save_hook: std::sync::RwLock::new(None),
+            remote_writer: std::sync::RwLock::new(None),
+            synced: std::sync::Mutex::new(std::collections::HashMap::new()),
+            crypto: std::sync::RwLock::new(crypto),
         })
     }
 
-    fn load_config(path: &PathBuf) -> AppResult<AppConfig> {
-        if path.exists() {
-            match fs::read_to_string(path) {
-                Ok(data) => {
-                    match serde_json::from_str(&data) {
-                        Ok(config) => Ok(config),
-                        Err(e) => {
-                            // JSON 解析失败，尝试从备份恢复
-                            println!("[DataStore] Config file corrupted: {}, trying backup...", e);
-                            Self::recover_from_backup(path)
-                        }
+    fn load_config(storage: &dyn Storage) -> AppResult<(AppConfig, Option<MigrationReport>)> {
+        match storage.load(storage::KEY_CONFIG) {
+            Ok(Some(data)) => {
+                match migrations::parse_config(&data) {
+                    Ok(loaded) => Ok(loaded),
+                    Err(e) => {
+                        // JSON 解析失败，尝试从备份恢复
+                        println!("[DataStore] Config file corrupted: {}, trying backup...", e);
+                        Self::recover_from_backup(storage)
                     }
                 }
-                Err(e) => {
-                    // 文件读取失败，尝试从备份恢复
-                    println!("[DataStore] Failed to read config: {}, trying backup...", e);
-                    Self::recover_from_backup(path)
-                }
             }
-        } else {
-            Ok(AppConfig::default())
+            Ok(None) => Ok((AppConfig::default(), None)),
+            Err(e) => {
+                // 读取失败，尝试从备份恢复
+                println!("[DataStore] Failed to read config: {}, trying backup...", e);
+                Self::recover_from_backup(storage)
+            }
         }
     }
     
-    /// 从备份文件恢复配置
-    fn recover_from_backup(path: &PathBuf) -> AppResult<AppConfig> {
-        let backup_path = path.with_extension("json.backup");
-        
-        if backup_path.exists() {
-            println!("[DataStore] Found backup file, attempting recovery...");
-            let data = fs::read_to_string(&backup_path)?;
-            let config: AppConfig = serde_json::from_str(&data)?;
-            
-            // 恢复成功后，将备份复制回主文件
-            fs::copy(&backup_path, path)?;
-            println!("[DataStore] Successfully recovered from backup!");
-            
-            Ok(config)
-        } else {
-            println!("[DataStore] No backup found, using default config");
-            Ok(AppConfig::default())
+    /// 从备份恢复配置
+    fn recover_from_backup(storage: &dyn Storage) -> AppResult<(AppConfig, Option<MigrationReport>)> {
+        match storage.load_backup(storage::KEY_CONFIG)? {
+            Some(data) => {
+                println!("[DataStore] Found backup, attempting recovery...");
+                let loaded = migrations::parse_config(&data)?;
+                println!("[DataStore] Successfully recovered from backup!");
+                Ok(loaded)
+            }
+            None => {
+                println!("[DataStore] No backup found, using default config");
+                Ok((AppConfig::default(), None))
+            }
         }
     }
     
-    fn load_logs(path: &PathBuf) -> AppResult<Vec<OperationLog>> {
-        if path.exists() {
-            let data = fs::read_to_string(path)?;
-            let logs: Vec<OperationLog> = serde_json::from_str(&data)?;
-            Ok(logs)
-        } else {
-            Ok(Vec::new())
+    fn load_logs(storage: &dyn Storage) -> AppResult<Vec<OperationLog>> {
+        match storage.load(storage::KEY_LOGS)? {
+            Some(data) => Ok(serde_json::from_str(&data)?),
+            None => Ok(Vec::new()),
         }
     }
 
-    pub async fn save(&self) -> AppResult<()> {
-        let config = self.config.read().await;
-        let data = serde_json::to_string_pretty(&*config)?;
-        let path = self.config_path.clone();
-        drop(config); // 提前释放读锁
+    /// 加载分析快照，数据损坏时不影响启动
+    fn load_snapshots(storage: &dyn Storage, crypto: Option<&CryptoService>) -> Vec<AnalyticsSnapshot> {
+        match Self::load_cache(storage, storage::KEY_SNAPSHOTS, crypto) {
+            Ok(None) => Vec::new(),
+            Ok(Some(Ok(snapshots))) => snapshots,
+            _ => {
+                println!("[DataStore] Failed to load analytics snapshots, starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// 加载月度报告，数据损坏时不影响启动
+    fn load_monthly_reports(storage: &dyn Storage) -> Vec<MonthlyReport> {
+        match storage.load(storage::KEY_REPORTS).map(|data| data.map(|d| serde_json::from_str(&d))) {
+            Ok(None) => Vec::new(),
+            Ok(Some(Ok(reports))) => reports,
+            _ => {
+                println!("[DataStore] Failed to load monthly reports, starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// 加载用量告警历史，数据损坏时不影响启动
+    fn load_alerts_history(storage: &dyn Storage) -> Vec<AlertEvent> {
+        match storage.load(storage::KEY_ALERTS).map(|data| data.map(|d| serde_json::from_str(&d))) {
+            Ok(None) => Vec::new(),
+            Ok(Some(Ok(history))) => history,
+            _ => {
+                println!("[DataStore] Failed to load alerts history, starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// 加载任务运行记录，数据损坏时不影响启动
+    fn load_job_history(storage: &dyn Storage) -> Vec<JobRun> {
+        match storage.load(storage::KEY_JOBS).map(|data| data.map(|d| serde_json::from_str(&d))) {
+            Ok(None) => Vec::new(),
+            Ok(Some(Ok(history))) => history,
+            _ => {
+                println!("[DataStore] Failed to load job history, starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// 加载积分用量历史，数据损坏时不影响启动
+    fn load_credits_history(storage: &dyn Storage, crypto: Option<&CryptoService>) -> Vec<CreditsSnapshot> {
+        match Self::load_cache(storage, storage::KEY_CREDITS, crypto) {
+            Ok(None) => Vec::new(),
+            Ok(Some(Ok(history))) => history,
+            _ => {
+                println!("[DataStore] Failed to load credits history, starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// 读取缓存数据项：加密的缓存用当前密钥解密；未启用加密时仅为读取旧版本写入的缓存才使用系统密钥链中的密钥
+    fn load_cache<T: serde::de::DeserializeOwned>(
+        storage: &dyn Storage,
+        key: &str,
+        crypto: Option<&CryptoService>,
+    ) -> AppResult<Option<serde_json::Result<T>>> {
+        let Some(data) = storage.load(key)? else { return Ok(None) };
+        if !secrets::is_encrypted(&data) {
+            return Ok(Some(serde_json::from_str(&data)));
+        }
+        let fallback;
+        let crypto = match crypto {
+            Some(crypto) => crypto,
+            None => {
+                fallback = CryptoService::new()
+                    .map_err(|e| AppError::Config(format!("Failed to open keyring: {}", e)))?;
+                &fallback
+            }
+        };
+        let data = secrets::decrypt_blob(data, crypto)
+            .ok_or_else(|| AppError::Config(format!("Failed to decrypt {}", key)))?;
+        Ok(Some(serde_json::from_str(&data)))
+    }
+
+    /// 按缓存存放设置编码缓存数据项
+    async fn encode_cache(&self, data: String) -> AppResult<String> {
+        let (cache_storage, mode) = {
+            let config = self.config.read().await;
+            (config.settings.cache_storage.clone(), config.secret_encryption.mode.clone())
+        };
+        match cache_storage.as_str() {
+            secrets::CACHE_MEMORY => Ok("[]".to_string()),
+            secrets::CACHE_ENCRYPTED => match self.crypto() {
+                Some(crypto) => secrets::encrypt_blob(&data, &crypto),
+                // 未启用凭据加密时不依赖系统密钥链，按明文保存
+                None if mode == secrets::MODE_NONE => Ok(data),
+                None => Err(AppError::StoreLocked),
+            },
+            _ => Ok(data),
+        }
+    }
+    
+    /// 缓存存放设置变化后立即按新方式重写缓存
+    pub async fn apply_cache_storage(&self) -> AppResult<()> {
+        self.save_snapshots().await?;
+        self.save_credits_history().await
+    }
+
+    /// 当前使用的存储后端
+    fn storage(&self) -> Arc<dyn Storage> {
+        self.storage.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 写入一个数据项
+    async fn write_item(&self, key: &'static str, data: String) -> AppResult<()> {
+        let storage = self.storage();
         
-        // 使用 spawn_blocking 将同步文件写入移到阻塞线程池，避免阻塞 tokio 运行时
-        tokio::task::spawn_blocking(move || {
-            Self::atomic_write(&path, &data)
-        }).await
-            .map_err(|e| AppError::Config(format!("Task join error: {}", e)))?
-            .map_err(AppError::from)?;
+        // 使用 spawn_blocking 将同步写入移到阻塞线程池，避免阻塞 tokio 运行时
+        tokio::task::spawn_blocking(move || storage.save(key, &data))
+            .await
+            .map_err(|e| AppError::Config(format!("Task join error: {}", e)))??;
         
+        if let Some(hook) = self.save_hook.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            hook(key);
+        }
         Ok(())
     }
     
-    /// 原子写入：先写临时文件，创建备份，再重命名
-    fn atomic_write(path: &PathBuf, data: &str) -> std::io::Result<()> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        // 使用时间戳+进程ID生成唯一临时文件名，避免并发冲突
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let pid = std::process::id();
-        
-        let file_stem = path.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("data");
-        let parent = path.parent().unwrap_or(path);
-        
-        let temp_path = parent.join(format!("{}.tmp.{}.{}", file_stem, pid, timestamp));
-        let backup_path = path.with_extension("json.backup");
-        
-        // 1. 先写入临时文件
-        fs::write(&temp_path, data)?;
-        
-        // 2. 验证临时文件可以正常解析
-        let verify_data = fs::read_to_string(&temp_path)?;
-        if serde_json::from_str::<serde_json::Value>(&verify_data).is_err() {
-            let _ = fs::remove_file(&temp_path);
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Written data failed JSON validation"
-            ));
+    /// 设置或清除远程写入通道（连接/断开后台进程时调用）
+    pub fn set_remote_writer(&self, writer: Option<RemoteWriter>) {
+        if writer.is_none() {
+            self.synced.lock().unwrap_or_else(|e| e.into_inner()).clear();
         }
-        
-        // 3. 如果原文件存在，创建备份
-        if path.exists() {
-            // 复制到备份文件（覆盖旧备份）
-            if let Err(e) = fs::copy(path, &backup_path) {
-                let _ = fs::remove_file(&temp_path);
-                return Err(e);
-            }
+        *self.remote_writer.write().unwrap_or_else(|e| e.into_inner()) = writer;
+    }
+    
+    fn remote_writer(&self) -> Option<RemoteWriter> {
+        self.remote_writer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// 连接到后台进程时不允许只在本进程生效的操作（切换后端、更换密钥）
+    fn ensure_local_writer(&self) -> AppResult<()> {
+        if self.remote_writer().is_some() {
+            return Err(AppError::Config("已连接到后台进程，请在后台进程停止后再执行此操作".to_string()));
         }
-        
-        // 4. 原子重命名临时文件为目标文件
-        if let Err(e) = fs::rename(&temp_path, path) {
-            // 重命名失败时清理临时文件
-            let _ = fs::remove_file(&temp_path);
+        Ok(())
+    }
+    
+    fn shared_key(key: &str) -> AppResult<&'static str> {
+        storage::SHARED_KEYS
+            .iter()
+            .find(|k| **k == key)
+            .copied()
+            .ok_or_else(|| AppError::Config(format!("Unknown data item: {}", key)))
+    }
+    
+    async fn value_of<T: Serialize>(lock: &RwLock<T>) -> AppResult<serde_json::Value> {
+        Ok(serde_json::to_value(&*lock.read().await)?)
+    }
+    
+    /// 数据项在内存中的当前内容
+    pub async fn item_value(&self, key: &str) -> AppResult<serde_json::Value> {
+        match Self::shared_key(key)? {
+            storage::KEY_CONFIG => Self::value_of(&self.config).await,
+            storage::KEY_LOGS => Self::value_of(&self.logs).await,
+            storage::KEY_SNAPSHOTS => Self::value_of(&self.analytics_snapshots).await,
+            storage::KEY_REPORTS => Self::value_of(&self.monthly_reports).await,
+            storage::KEY_CREDITS => Self::value_of(&self.credits_history).await,
+            storage::KEY_JOBS => Self::value_of(&self.job_history).await,
+            _ => Self::value_of(&self.alerts_history).await,
+        }
+    }
+    
+    /// 在写锁内用 `f` 更新数据项，返回更新后的内容
+    async fn update_with<T, F>(lock: &RwLock<T>, f: F) -> AppResult<serde_json::Value>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+        F: FnOnce(serde_json::Value) -> serde_json::Value,
+    {
+        let mut item = lock.write().await;
+        let updated = f(serde_json::to_value(&*item)?);
+        *item = serde_json::from_value(updated.clone())?;
+        Ok(updated)
+    }
+    
+    async fn update_item<F>(&self, key: &'static str, f: F) -> AppResult<serde_json::Value>
+    where
+        F: FnOnce(serde_json::Value) -> serde_json::Value,
+    {
+        match key {
+            storage::KEY_CONFIG => Self::update_with(&self.config, f).await,
+            storage::KEY_LOGS => Self::update_with(&self.logs, f).await,
+            storage::KEY_SNAPSHOTS => Self::update_with(&self.analytics_snapshots, f).await,
+            storage::KEY_REPORTS => Self::update_with(&self.monthly_reports, f).await,
+            storage::KEY_CREDITS => Self::update_with(&self.credits_history, f).await,
+            storage::KEY_JOBS => Self::update_with(&self.job_history, f).await,
+            _ => Self::update_with(&self.alerts_history, f).await,
+        }
+    }
+    
+    /// 保存一个数据项
+    async fn save_item(&self, key: &'static str) -> AppResult<()> {
+        match key {
+            storage::KEY_CONFIG => self.save().await,
+            storage::KEY_LOGS => self.save_logs().await,
+            storage::KEY_SNAPSHOTS => self.save_snapshots().await,
+            storage::KEY_REPORTS => self.save_monthly_reports().await,
+            storage::KEY_CREDITS => self.save_credits_history().await,
+            storage::KEY_JOBS => self.save_job_history().await,
+            _ => self.save_alerts_history().await,
+        }
+    }
+    
+    /// 界面进程：连接到后台进程时把数据项交给后台进程合并保存，返回 true 表示已转发
+    ///
+    /// 只发送相对上次同步的修改，后台进程在此期间的修改不会被覆盖
+    async fn forward_write(&self, key: &'static str) -> AppResult<bool> {
+        let Some(writer) = self.remote_writer() else { return Ok(false) };
+        let ours = self.item_value(key).await?;
+        // 先把基准更新为本次发送的内容，之后收到的同步以此为基准合并
+        let base = self.synced.lock().unwrap_or_else(|e| e.into_inner()).insert(key, ours.clone());
+        if let Err(e) = writer(key, base.clone().unwrap_or(serde_json::Value::Null), ours).await {
+            let mut synced = self.synced.lock().unwrap_or_else(|e| e.into_inner());
+            match base {
+                Some(base) => synced.insert(key, base),
+                None => synced.remove(key),
+            };
             return Err(e);
         }
-        
+        Ok(true)
+    }
+    
+    /// 后台进程：把界面进程的修改合并到内存并保存，双方修改同一字段时以界面进程为准
+    pub async fn apply_remote_write(&self, key: &str, base: serde_json::Value, ours: serde_json::Value) -> AppResult<()> {
+        let key = Self::shared_key(key)?;
+        self.update_item(key, |current| merge::merge3(&base, &ours, &current)).await?;
+        self.save_item(key).await
+    }
+    
+    /// 界面进程：合并后台进程的最新内容，保留本进程尚未保存的修改（`*_no_save` 批次、仅内存的凭据）
+    ///
+    /// `base` 为空时使用上次同步的内容；首次同步时传入本进程当前内容
+    pub async fn absorb_remote(&self, key: &str, base: Option<serde_json::Value>, theirs: serde_json::Value) -> AppResult<()> {
+        let key = Self::shared_key(key)?;
+        let synced = &self.synced;
+        self.update_item(key, |current| {
+            let mut synced = synced.lock().unwrap_or_else(|e| e.into_inner());
+            let base = base.or_else(|| synced.get(key).cloned()).unwrap_or_else(|| current.clone());
+            let merged = merge::merge3(&base, &current, &theirs);
+            synced.insert(key, theirs);
+            merged
+        })
+        .await?;
+        Ok(())
+    }
+
+    fn crypto(&self) -> Option<Arc<CryptoService>> {
+        self.crypto.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// 启用了加密但尚未解锁（此时不允许写入账号配置）
+    pub async fn is_locked(&self) -> bool {
+        self.crypto().is_none() && self.config.read().await.secret_encryption.mode != secrets::MODE_NONE
+    }
+    
+    /// 锁定时拒绝读写账号：内存中只有密文，修改后也无法保存
+    async fn ensure_unlocked(&self) -> AppResult<()> {
+        if self.is_locked().await {
+            return Err(AppError::StoreLocked);
+        }
         Ok(())
     }
     
+    /// 序列化账号配置，启用加密时凭据字段写入密文，Refresh Token 存入密钥链时写入占位值
+    fn encode_config(&self, config: &crate::models::AppConfig) -> AppResult<String> {
+        let use_keyring = config.settings.storage_backend == keyring_service::BACKEND_KEYRING;
+        let memory_only = config.settings.memory_only_secrets;
+        if config.secret_encryption.mode == secrets::MODE_NONE && !use_keyring && !memory_only {
+            return Ok(serde_json::to_string_pretty(config)?);
+        }
+        let mut encoded = config.clone();
+        if memory_only {
+            secrets::strip_session_secrets(&mut encoded.accounts);
+        } else if use_keyring {
+            keyring_service::externalize_refresh_tokens(&mut encoded.accounts);
+        }
+        if config.secret_encryption.mode != secrets::MODE_NONE {
+            let crypto = self.crypto()
+                .ok_or_else(|| AppError::StoreLocked)?;
+            secrets::encrypt_config(&mut encoded, &crypto)?;
+        }
+        Ok(serde_json::to_string_pretty(&encoded)?)
+    }
+    
+    /// 解密启动时读入的配置（keyring 模式在此解密，主密码模式保留密文等待解锁）
+    fn decrypt_loaded_config(config: &mut crate::models::AppConfig, crypto: Option<&CryptoService>) {
+        if let Some(crypto) = crypto {
+            let failed = secrets::decrypt_config(config, crypto);
+            if failed > 0 {
+                println!("[DataStore] Failed to decrypt {} stored secrets", failed);
+            }
+        } else if config.secret_encryption.mode != secrets::MODE_NONE {
+            println!("[DataStore] Store is locked until the master password is entered");
+        }
+    }
+    
+    /// 解密从存储或备份读入的配置（未解锁时保留密文），并从密钥链取回 Refresh Token
+    fn decrypt_config(&self, config: &mut crate::models::AppConfig) {
+        if let Some(crypto) = self.crypto() {
+            let failed = secrets::decrypt_config(config, &crypto);
+            if failed > 0 {
+                println!("[DataStore] Failed to decrypt {} stored secrets", failed);
+            }
+        }
+        keyring_service::resolve_refresh_tokens(&mut config.accounts);
+    }
+    
+    pub async fn save(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_CONFIG).await? {
+            return Ok(());
+        }
+        let config = self.config.read().await;
+        let data = self.encode_config(&config)?;
+        let manifest = serde_json::to_string(&integrity::build_manifest(&config))?;
+        drop(config); // 提前释放读锁
+        
+        self.write_item(storage::KEY_CONFIG, data).await?;
+        self.write_item(storage::KEY_INTEGRITY, manifest).await
+    }
+    
+    /// 设置保存回调
+    pub fn set_save_hook(&self, hook: SaveHook) {
+        *self.save_hook.write().unwrap_or_else(|e| e.into_inner()) = Some(hook);
+    }
+    
     pub async fn save_logs(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_LOGS).await? {
+            return Ok(());
+        }
         let logs = self.logs.read().await;
         let data = serde_json::to_string_pretty(&*logs)?;
-        let path = self.logs_path.clone();
         drop(logs); // 提前释放读锁
         
-        // 使用 spawn_blocking 将同步文件写入移到阻塞线程池，避免阻塞 tokio 运行时
-        tokio::task::spawn_blocking(move || {
-            fs::write(&path, data)
-        }).await
-            .map_err(|e| AppError::Config(format!("Task join error: {}", e)))?
-            .map_err(AppError::from)?;
+        self.write_item(storage::KEY_LOGS, data).await
+    }
+
+    /// 当前存储后端名称
+    pub fn storage_backend(&self) -> &'static str {
+        self.storage().name()
+    }
+
+    /// 切换存储后端：把当前内存中的全部数据写入新后端，并记录选择供下次启动使用
+    pub async fn switch_storage_backend(&self, backend: &str) -> AppResult<usize> {
+        let backend = backend.trim().to_lowercase();
+        if backend == self.storage_backend() {
+            return Ok(0);
+        }
+        self.ensure_local_writer()?;
+        // 内存后端不落盘，切换后所有修改在退出时丢失，只允许通过环境变量在启动时启用
+        if backend == storage::BACKEND_MEMORY {
+            return Err(AppError::Config("内存后端不保存数据，只能通过环境变量 WAM_STORAGE_BACKEND 启用".to_string()));
+        }
         
-        Ok(())
+        let data_dir = self.get_data_dir();
+        let (target, _) = storage::open_storage(&data_dir, &backend, true)?;
+        
+        {
+            let mut config = self.config.write().await;
+            config.settings.persistence_backend = target.name().to_string();
+        }
+        
+        // 从内存状态写入，保证新后端拿到的是最新数据
+        let items = [
+            (storage::KEY_CONFIG, self.encode_config(&*self.config.read().await)?),
+            (storage::KEY_LOGS, serde_json::to_string_pretty(&*self.logs.read().await)?),
+            (storage::KEY_SNAPSHOTS, self.encode_cache(serde_json::to_string(&*self.analytics_snapshots.read().await)?).await?),
+            (storage::KEY_REPORTS, serde_json::to_string(&*self.monthly_reports.read().await)?),
+            (storage::KEY_CREDITS, self.encode_cache(serde_json::to_string(&*self.credits_history.read().await)?).await?),
+            (storage::KEY_ALERTS, serde_json::to_string(&*self.alerts_history.read().await)?),
+            (storage::KEY_JOBS, serde_json::to_string(&*self.job_history.read().await)?),
+            (storage::KEY_INTEGRITY, serde_json::to_string(&integrity::build_manifest(&*self.config.read().await))?),
+        ];
+        let written = items.len();
+        let target_clone = target.clone();
+        tokio::task::spawn_blocking(move || -> AppResult<()> {
+            for (key, data) in &items {
+                target_clone.save(key, data)?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| AppError::Config(format!("Task join error: {}", e)))??;
+        
+        storage::write_backend_marker(&data_dir, target.name())?;
+        *self.storage.write().unwrap_or_else(|e| e.into_inner()) = target;
+        
+        println!("[DataStore] Switched storage backend to {}", backend);
+        Ok(written)
     }
 
     // 账号管理方法
     pub async fn add_account(&self, email: String, password: String, nickname: String) -> AppResult<Account> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         
         // 检查邮箱是否已存在
@@ -217,7 +699,57 @@ impl DataStore {
         Ok(account)
     }
 
+    /// 批量添加账号（只保存一次），已存在的邮箱跳过；`note_template` 非空时为每个新账号渲染备注
+    ///
+    /// `dry_run` 为 true 时只返回将要添加与跳过的账号，不修改数据
+    pub async fn add_accounts(&self, entries: Vec<NewAccount>, note_template: Option<&str>, dry_run: bool) -> AppResult<(Vec<Account>, Vec<String>)> {
+        self.ensure_unlocked().await?;
+        let note_template = note_template.filter(|t| !t.trim().is_empty());
+        let mut guard = self.config.write().await;
+        let mut staged;
+        // 预演时在副本上执行同样的逻辑，结果与实际导入一致
+        let config: &mut AppConfig = if dry_run {
+            staged = (*guard).clone();
+            &mut staged
+        } else {
+            &mut guard
+        };
+        let mut added = Vec::new();
+        let mut skipped = Vec::new();
+        
+        for entry in entries {
+            let email = entry.email.trim().to_string();
+            if email.is_empty() {
+                skipped.push("缺少邮箱".to_string());
+                continue;
+            }
+            if config.accounts.iter().any(|a| a.email.eq_ignore_ascii_case(&email)) {
+                skipped.push(format!("{}: 账号已存在", email));
+                continue;
+            }
+            
+            let nickname = entry
+                .nickname
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| email.split('@').next().unwrap_or(&email).to_string());
+            let mut account = Account::new(email, entry.password, nickname, entry.tags);
+            account.group = entry.group;
+            if let Some(template) = note_template {
+                account.notes = Some(render_note_template(template, &account));
+            }
+            config.accounts.push(account.clone());
+            added.push(account);
+        }
+        drop(guard);
+        
+        if !added.is_empty() && !dry_run {
+            self.save().await?;
+        }
+        Ok((added, skipped))
+    }
+
     pub async fn get_account(&self, id: Uuid) -> AppResult<Account> {
+        self.ensure_unlocked().await?;
         let config = self.config.read().await;
         config.accounts
             .iter()
@@ -227,6 +759,7 @@ impl DataStore {
     }
 
     pub async fn get_all_accounts(&self) -> AppResult<Vec<Account>> {
+        self.ensure_unlocked().await?;
         let config = self.config.read().await;
         Ok(config.accounts.clone())
     }
@@ -242,12 +775,42 @@ impl DataStore {
     
     /// 内部方法：更新账号信息
     async fn update_account_internal(&self, account: Account, save_immediately: bool) -> AppResult<()> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
+        let alert_percent = config.settings.quota_alert_percent;
+        let mut quota_alert = None;
         
         if let Some(existing) = config.accounts.iter_mut().find(|a| a.id == account.id) {
             // 保存原有的密码（永远不通过这个方法更新密码）
             let original_password = existing.password.clone();
             
+            // 剩余额度从阈值之上降到阈值之下时提醒一次
+            let before = remaining_percent(existing);
+            if let Some(after) = remaining_percent(&account) {
+                if after < alert_percent && before.map(|b| b >= alert_percent).unwrap_or(true) {
+                    quota_alert = Some(QuotaAlertPayload {
+                        account_id: account.id.to_string(),
+                        email: account.email.clone(),
+                        used_quota: account.used_quota.unwrap_or(0),
+                        total_quota: account.total_quota.unwrap_or(0),
+                        remaining_percent: after,
+                    });
+                }
+            }
+            
+            // 调用方持有的账号副本可能早于最近一次 Token 更新，此时保留较新的 Token 记录
+            let newer_history = match (existing.token_history.last(), account.token_history.last()) {
+                (Some(current), Some(incoming)) => current.obtained_at > incoming.obtained_at,
+                (Some(_), None) => true,
+                _ => false,
+            };
+            let original_history = if newer_history {
+                Some(std::mem::take(&mut existing.token_history))
+            } else {
+                None
+            };
+            let original_onboarding = existing.onboarding.clone();
+            
             // Token直接保存，不加密
             
             // 更新账号信息
@@ -255,41 +818,215 @@ impl DataStore {
             
             // 恢复原有密码（密码更新应该通过专门的update_account_password方法）
             existing.password = original_password;
+            if let Some(history) = original_history {
+                existing.token_history = history;
+            }
+            existing.onboarding.merge(&original_onboarding);
+            existing.sync_onboarding();
         } else {
             return Err(AppError::AccountNotFound(account.id.to_string()));
         }
         
         drop(config);
         
+        // 系统通知由 notification_service 监听该事件弹出
+        if let Some(payload) = quota_alert {
+            self.emit_event("quota-alert", payload);
+        }
+        
         if save_immediately {
             self.save().await?;
         }
         Ok(())
     }
 
-    pub async fn delete_account(&self, id: Uuid) -> AppResult<()> {
+    pub async fn delete_account(&self, id: Uuid) -> AppResult<PathBuf> {
+        self.delete_accounts(&[id]).await.map(|(_, archive)| archive)
+    }
+    
+    /// 批量删除账号：删除前先将账号记录加密归档到备份目录，返回实际删除的账号和归档路径
+    pub async fn delete_accounts(&self, ids: &[Uuid]) -> AppResult<(Vec<Account>, PathBuf)> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         
-        let initial_len = config.accounts.len();
-        config.accounts.retain(|a| a.id != id);
-        
-        if config.accounts.len() == initial_len {
-            return Err(AppError::AccountNotFound(id.to_string()));
+        let removed: Vec<Account> = config.accounts.iter()
+            .filter(|a| ids.contains(&a.id))
+            .cloned()
+            .collect();
+        if removed.is_empty() {
+            return Err(AppError::AccountNotFound(
+                ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+            ));
         }
         
+        // 归档失败时不删除，保证“永久删除”仍有恢复途径
+        let mut archived = removed.clone();
+        if config.settings.memory_only_secrets {
+            secrets::strip_session_secrets(&mut archived);
+        }
+        let archive = self.write_deleted_archive(&archived, &config.secret_encryption.mode)?;
+        config.accounts.retain(|a| !ids.contains(&a.id));
+        let use_keyring = config.settings.storage_backend == keyring_service::BACKEND_KEYRING;
         drop(config);
         
+        // 归档中已保存明文 Refresh Token，密钥链中的条目可以删除
+        if use_keyring {
+            for account in &removed {
+                keyring_service::delete_refresh_token(&account.id.to_string());
+            }
+        }
+        
         // 同时删除相关日志
         let mut logs = self.logs.write().await;
-        logs.retain(|log| log.account_id != Some(id));
+        logs.retain(|log| !log.account_id.map(|id| ids.contains(&id)).unwrap_or(false));
         drop(logs);
         
         self.save().await?;
         self.save_logs().await?;
-        Ok(())
+        Ok((removed, archive))
+    }
+    
+    fn deleted_archive_dir(&self) -> AppResult<PathBuf> {
+        Ok(self.config_path.parent()
+            .ok_or_else(|| AppError::Config("Invalid config path".to_string()))?
+            .join("backups")
+            .join("deleted"))
+    }
+    
+    /// 删除归档使用的密钥：启用加密时沿用当前密钥，否则使用系统密钥链中的密钥
+    fn crypto_for_mode(&self, mode: &str) -> AppResult<Arc<CryptoService>> {
+        if mode == secrets::MODE_NONE {
+            return CryptoService::new()
+                .map(Arc::new)
+                .map_err(|e| AppError::Config(format!("Failed to open keyring: {}", e)));
+        }
+        self.crypto()
+            .ok_or_else(|| AppError::StoreLocked)
+    }
+    
+    /// 写入删除归档：启用加密时用当前密钥加密；未启用加密时尽量用系统密钥链中的密钥，
+    /// 没有可用的密钥链（无界面的服务器等）时写入明文，与账号配置本身的保存方式一致
+    fn write_deleted_archive(&self, accounts: &[Account], mode: &str) -> AppResult<PathBuf> {
+        let plaintext = serde_json::to_string(accounts)?;
+        let (encryption, payload) = match self.crypto_for_mode(mode) {
+            Ok(crypto) => {
+                let payload = crypto
+                    .encrypt(&plaintext)
+                    .map_err(|e| AppError::Config(format!("Failed to encrypt archive: {}", e)))?;
+                let encryption = if mode == secrets::MODE_PASSWORD { secrets::MODE_PASSWORD } else { secrets::MODE_KEYRING };
+                (encryption, payload)
+            }
+            Err(e) if mode == secrets::MODE_NONE => {
+                println!("[DataStore] {}, archiving deleted accounts without encryption", e);
+                (secrets::MODE_NONE, plaintext)
+            }
+            Err(e) => return Err(e),
+        };
+        let archive = DeletedArchive {
+            version: 1,
+            archived_at: chrono::Utc::now().timestamp(),
+            encryption: encryption.to_string(),
+            emails: accounts.iter().map(|a| a.email.clone()).collect(),
+            payload,
+        };
+        
+        let archive_dir = self.deleted_archive_dir()?;
+        fs::create_dir_all(&archive_dir)?;
+        Self::cleanup_expired_archives(&archive_dir);
+        
+        let path = archive_dir.join(format!("deleted_{}.json", Local::now().format("%Y%m%d_%H%M%S_%3f")));
+        fs::write(&path, serde_json::to_string_pretty(&archive)?)?;
+        println!("[DataStore] Archived {} deleted accounts to {:?}", accounts.len(), path);
+        Ok(path)
+    }
+    
+    /// 清理超过保留期的删除归档
+    fn cleanup_expired_archives(archive_dir: &PathBuf) {
+        let now = chrono::Utc::now().timestamp();
+        let Ok(entries) = fs::read_dir(archive_dir) else { return };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let expired = fs::read_to_string(&path).ok()
+                .and_then(|data| serde_json::from_str::<DeletedArchive>(&data).ok())
+                .map(|archive| archive_expired(archive.archived_at, now))
+                .unwrap_or(false);
+            if expired {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+    
+    /// 列出保留期内的删除归档（最新的在前）
+    pub async fn list_deleted_archives(&self) -> AppResult<Vec<DeletedArchiveInfo>> {
+        let archive_dir = self.deleted_archive_dir()?;
+        if !archive_dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let now = chrono::Utc::now().timestamp();
+        let mut archives: Vec<DeletedArchiveInfo> = fs::read_dir(&archive_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let archive: DeletedArchive = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+                if archive_expired(archive.archived_at, now) {
+                    return None;
+                }
+                Some(DeletedArchiveInfo {
+                    file_name: path.file_name()?.to_string_lossy().to_string(),
+                    path: path.to_string_lossy().to_string(),
+                    archived_at: archive.archived_at,
+                    expires_at: archive.archived_at + DELETED_ARCHIVE_DAYS * 86400,
+                    emails: archive.emails,
+                })
+            })
+            .collect();
+        
+        archives.sort_by_key(|a| std::cmp::Reverse(a.archived_at));
+        Ok(archives)
+    }
+    
+    /// 从删除归档恢复账号，已存在的账号（同 ID）跳过，返回恢复数量
+    ///
+    /// `file_name` 只能是归档目录中的文件名，不接受其他路径
+    pub async fn restore_deleted_archive(&self, file_name: &str) -> AppResult<usize> {
+        self.ensure_unlocked().await?;
+        let is_plain_name = std::path::Path::new(file_name).file_name().and_then(|n| n.to_str()) == Some(file_name);
+        if !is_plain_name || !file_name.starts_with("deleted_") || !file_name.ends_with(".json") {
+            return Err(AppError::FileOperation(format!("无效的归档文件名: {}", file_name)));
+        }
+        let archive_path = self.deleted_archive_dir()?.join(file_name);
+        let archive: DeletedArchive = serde_json::from_str(&fs::read_to_string(&archive_path)?)?;
+        let plaintext = if archive.encryption == secrets::MODE_NONE {
+            archive.payload
+        } else {
+            let crypto = if archive.encryption == secrets::MODE_PASSWORD {
+                self.crypto_for_mode(secrets::MODE_PASSWORD)?
+            } else {
+                self.crypto_for_mode(secrets::MODE_NONE)?
+            };
+            crypto.decrypt(&archive.payload)
+                .map_err(|_| AppError::Config("无法解密归档，主密码可能已更换".to_string()))?
+        };
+        let accounts: Vec<Account> = serde_json::from_str(&plaintext)?;
+        
+        let mut config = self.config.write().await;
+        let mut restored = 0;
+        for account in accounts {
+            if !config.accounts.iter().any(|a| a.id == account.id) {
+                config.accounts.push(account);
+                restored += 1;
+            }
+        }
+        drop(config);
+        
+        if restored > 0 {
+            self.save().await?;
+        }
+        Ok(restored)
     }
 
     pub async fn update_account_password(&self, id: Uuid, new_password: String) -> AppResult<()> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         
         if let Some(account) = config.accounts.iter_mut().find(|a| a.id == id) {
@@ -304,15 +1041,77 @@ impl DataStore {
         Ok(())
     }
     
-    pub async fn update_account_token(&self, id: Uuid, token: String, expires_at: chrono::DateTime<chrono::Utc>) -> AppResult<()> {
+    /// 只更新账号的 Windsurf API Key，不覆盖调用方副本中可能过时的其他字段
+    pub async fn update_account_api_key(&self, id: Uuid, api_key: String) -> AppResult<()> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        account.windsurf_api_key = Some(api_key);
+        account.sync_onboarding();
+
+        drop(config);
+        self.save().await
+    }
+
+    /// 占用账号：已被他人占用且未过期时返回错误，自己重复占用则续期
+    pub async fn claim_account(&self, id: Uuid, user: &str, expires_at: chrono::DateTime<chrono::Utc>) -> AppResult<Account> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        if let Some(owner) = account.active_claim() {
+            if owner != user {
+                return Err(AppError::Unknown(format!("账号已被 {} 占用", owner)));
+            }
+        }
+        account.claimed_by = Some(user.to_string());
+        account.claim_expires_at = Some(expires_at);
+        let account = account.clone();
+
+        drop(config);
+        self.save().await?;
+        Ok(account)
+    }
+
+    /// 释放账号占用：`force` 为 false 时只能释放自己的占用
+    pub async fn release_account(&self, id: Uuid, user: &str, force: bool) -> AppResult<()> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        if !force {
+            if let Some(owner) = account.active_claim() {
+                if owner != user {
+                    return Err(AppError::Unknown(format!("账号已被 {} 占用，无法释放", owner)));
+                }
+            }
+        }
+        account.claimed_by = None;
+        account.claim_expires_at = None;
+
+        drop(config);
+        self.save().await?;
+        Ok(())
+    }
+
+    pub async fn update_account_token(&self, id: Uuid, token: String, expires_at: chrono::DateTime<chrono::Utc>, source: TokenSource) -> AppResult<()> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         
         if let Some(account) = config.accounts.iter_mut().find(|a| a.id == id) {
+            account.record_token_provenance(source, &token, None, Some(expires_at));
             // 直接保存Token，不加密
             account.token = Some(token);
             account.token_expires_at = Some(expires_at);
             account.last_login_at = Some(chrono::Utc::now());
             account.status = crate::models::AccountStatus::Active;
+            account.auth_failure_count = 0;
+            account.last_auth_error = None;
+            account.sync_onboarding();
         } else {
             return Err(AppError::AccountNotFound(id.to_string()));
         }
@@ -322,30 +1121,50 @@ impl DataStore {
         Ok(())
     }
     
+    /// 标记引导步骤完成（已完成的步骤不重复保存）
+    pub async fn mark_onboarding_step(&self, id: Uuid, step: OnboardingStep) -> AppResult<()> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        let changed = account.onboarding.complete(step);
+        drop(config);
+        
+        if changed {
+            self.save().await?;
+        }
+        Ok(())
+    }
+    
     /// 更新账号 token，默认立即保存
-    pub async fn update_account_tokens(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>) -> AppResult<()> {
-        self.update_account_tokens_internal(id, token, refresh_token, expires_at, true).await
+    pub async fn update_account_tokens(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>, source: TokenSource) -> AppResult<()> {
+        self.update_account_tokens_internal(id, token, refresh_token, expires_at, source, true).await
     }
     
     /// 更新账号 token，不立即保存（用于批量操作）
-    pub async fn update_account_tokens_no_save(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>) -> AppResult<()> {
-        self.update_account_tokens_internal(id, token, refresh_token, expires_at, false).await
+    pub async fn update_account_tokens_no_save(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>, source: TokenSource) -> AppResult<()> {
+        self.update_account_tokens_internal(id, token, refresh_token, expires_at, source, false).await
     }
     
     /// 内部方法：更新账号 token
-    async fn update_account_tokens_internal(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>, save_immediately: bool) -> AppResult<()> {
+    async fn update_account_tokens_internal(&self, id: Uuid, token: String, refresh_token: String, expires_at: chrono::DateTime<chrono::Utc>, source: TokenSource, save_immediately: bool) -> AppResult<()> {
+        self.ensure_unlocked().await?;
         // 保存 token 副本用于事件发送
         let token_for_event = token.clone();
         
         let mut config = self.config.write().await;
         
         if let Some(account) = config.accounts.iter_mut().find(|a| a.id == id) {
+            account.record_token_provenance(source, &token, Some(&refresh_token), Some(expires_at));
             // 保存两种token
             account.token = Some(token);
             account.refresh_token = Some(refresh_token);
             account.token_expires_at = Some(expires_at);
             account.last_login_at = Some(chrono::Utc::now());
             account.status = crate::models::AccountStatus::Active;
+            account.auth_failure_count = 0;
+            account.last_auth_error = None;
+            account.sync_onboarding();
         } else {
             return Err(AppError::AccountNotFound(id.to_string()));
         }
@@ -366,7 +1185,72 @@ impl DataStore {
             println!("[DataStore] Failed to emit token-refreshed event: {}", e);
         }
         
-        Ok(())
+        Ok(())
+    }
+    
+    /// 记录一次后台认证失败（不立即保存），达到阈值时标记为需要处理
+    ///
+    /// 返回 true 表示本次失败使账号进入“需要处理”状态
+    pub async fn record_auth_failure_no_save(&self, id: Uuid, error: String, threshold: u32) -> AppResult<bool> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+        
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        account.auth_failure_count += 1;
+        account.last_auth_error = Some(error);
+        
+        let newly_flagged = account.auth_failure_count >= threshold.max(1)
+            && !matches!(account.status, crate::models::AccountStatus::NeedsAttention);
+        if newly_flagged {
+            account.status = crate::models::AccountStatus::NeedsAttention;
+        }
+        
+        Ok(newly_flagged)
+    }
+    
+    /// 记录一次健康检查结果（不立即保存，批量结束后调用 flush）
+    pub async fn record_health_ping_no_save(&self, id: Uuid, disabled: Option<bool>) -> AppResult<()> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+        
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        account.last_health_ping_at = Some(chrono::Utc::now());
+        if disabled.is_some() {
+            account.is_disabled = disabled;
+        }
+        
+        Ok(())
+    }
+    
+    /// 标记账号疑似在其他设备使用（不立即保存），已有警告时不覆盖，返回是否新标记
+    pub async fn flag_shared_use_no_save(&self, id: Uuid, reason: String) -> AppResult<bool> {
+        self.ensure_unlocked().await?;
+        let mut config = self.config.write().await;
+        
+        let account = config.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::AccountNotFound(id.to_string()))?;
+        if account.shared_use_warning.is_some() {
+            return Ok(false);
+        }
+        account.shared_use_warning = Some(crate::models::SharedUseWarning {
+            reason,
+            detected_at: chrono::Utc::now(),
+        });
+        
+        Ok(true)
+    }
+    
+    /// 向前端发送事件
+    pub fn app_handle(&self) -> &tauri::AppHandle {
+        &self.app_handle
+    }
+    
+    pub fn emit_event<S: serde::Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Err(e) = self.app_handle.emit(event, payload) {
+            println!("[DataStore] Failed to emit {} event: {}", event, e);
+        }
     }
     
     /// 手动触发保存（用于批量操作结束后）
@@ -375,6 +1259,7 @@ impl DataStore {
     }
 
     pub async fn get_decrypted_password(&self, id: Uuid) -> AppResult<String> {
+        self.ensure_unlocked().await?;
         let config = self.config.read().await;
         
         let account = config.accounts
@@ -387,6 +1272,7 @@ impl DataStore {
     }
 
     pub async fn get_decrypted_token(&self, id: Uuid) -> AppResult<Option<String>> {
+        self.ensure_unlocked().await?;
         let config = self.config.read().await;
         
         let account = config.accounts
@@ -533,12 +1419,45 @@ impl DataStore {
         Ok(())
     }
 
+    // 会话标签（项目归属）
+    pub async fn get_session_labels(&self) -> AppResult<crate::models::SessionLabels> {
+        let config = self.config.read().await;
+        Ok(config.session_labels.clone())
+    }
+
+    /// 手动指定会话的标签，label 为 None 时清除
+    pub async fn assign_session_label(&self, session_ids: Vec<String>, label: Option<String>) -> AppResult<()> {
+        let mut config = self.config.write().await;
+        for session_id in session_ids {
+            match &label {
+                Some(label) => {
+                    config.session_labels.assignments.insert(session_id, label.clone());
+                }
+                None => {
+                    config.session_labels.assignments.remove(&session_id);
+                }
+            }
+        }
+        drop(config);
+        self.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_session_label_rules(&self, rules: Vec<crate::models::SessionLabelRule>) -> AppResult<()> {
+        let mut config = self.config.write().await;
+        config.session_labels.rules = rules;
+        drop(config);
+        self.save().await?;
+        Ok(())
+    }
+
     pub async fn batch_update_account_tags(
         &self,
         account_ids: Vec<String>,
         add_tags: Vec<String>,
         remove_tags: Vec<String>,
     ) -> AppResult<(usize, usize)> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         let mut success_count = 0;
         let mut failed_count = 0;
@@ -600,6 +1519,292 @@ impl DataStore {
         Ok(())
     }
 
+    // 分析快照管理
+    pub async fn save_snapshots(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_SNAPSHOTS).await? {
+            return Ok(());
+        }
+        let snapshots = self.analytics_snapshots.read().await;
+        let data = serde_json::to_string(&*snapshots)?;
+        drop(snapshots);
+        
+        let data = self.encode_cache(data).await?;
+        self.write_item(storage::KEY_SNAPSHOTS, data).await
+    }
+    
+    /// 抽取逻辑版本低于 `version` 且保留了原始响应的快照（只在读锁下复制原始响应）
+    pub async fn outdated_snapshots(&self, version: u32) -> Vec<OutdatedSnapshot> {
+        let snapshots = self.analytics_snapshots.read().await;
+        snapshots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.extractor_version < version)
+            .filter_map(|(index, s)| {
+                Some(OutdatedSnapshot {
+                    index,
+                    account_id: s.account_id,
+                    date: s.date.clone(),
+                    captured_at: s.captured_at,
+                    raw_body: s.raw_body.clone()?,
+                })
+            })
+            .collect()
+    }
+    
+    /// 写回重新处理后的快照数据，返回写入的数量
+    ///
+    /// 处理期间被新拉取的数据替换或移除的快照（同一下标的采集时间不同）跳过
+    pub async fn apply_reprocessed_snapshots(&self, results: Vec<(OutdatedSnapshot, AnalyticsData)>, version: u32) -> usize {
+        let mut snapshots = self.analytics_snapshots.write().await;
+        let mut applied = 0;
+        for (outdated, mut data) in results {
+            let Some(snapshot) = snapshots.get_mut(outdated.index) else {
+                continue;
+            };
+            if snapshot.account_id != outdated.account_id || snapshot.captured_at != outdated.captured_at {
+                continue;
+            }
+            data.timings = snapshot.data.timings.take();
+            snapshot.data = data;
+            snapshot.extractor_version = version;
+            applied += 1;
+        }
+        applied
+    }
+    
+    /// 记录分析快照：同一账户同一天只保留最新一份，每个账户最多保留 90 天
+    ///
+    /// `raw_body` 为原始响应体，保存后可在抽取逻辑升级时重新处理
+    pub async fn record_analytics_snapshot(&self, account_id: Uuid, data: AnalyticsData, raw_body: Option<&[u8]>) -> AppResult<()> {
+        let now = chrono::Utc::now();
+        let date = now.format("%Y-%m-%d").to_string();
+        let raw_body = raw_body.map(|body| general_purpose::STANDARD.encode(body));
+        
+        let mut snapshots = self.analytics_snapshots.write().await;
+        // 当日快照内容未变化时只更新采集时间，不重写快照文件
+        if let Some(existing) = snapshots.iter_mut().find(|s| s.account_id == account_id && s.date == date) {
+            if existing.raw_body == raw_body
+                && existing.extractor_version == crate::models::ANALYTICS_EXTRACTOR_VERSION
+                && same_analytics(&existing.data, &data)
+            {
+                existing.captured_at = now;
+                return Ok(());
+            }
+        }
+        snapshots.retain(|s| !(s.account_id == account_id && s.date == date));
+        snapshots.push(AnalyticsSnapshot {
+            account_id,
+            date,
+            captured_at: now,
+            data,
+            raw_body,
+            extractor_version: crate::models::ANALYTICS_EXTRACTOR_VERSION,
+        });
+        
+        let account_count = snapshots.iter().filter(|s| s.account_id == account_id).count();
+        if account_count > 90 {
+            let mut to_remove = account_count - 90;
+            snapshots.retain(|s| {
+                if to_remove > 0 && s.account_id == account_id {
+                    to_remove -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        
+        drop(snapshots);
+        self.save_snapshots().await?;
+        Ok(())
+    }
+    
+    /// 获取账户的分析快照（按日期升序）
+    pub async fn get_analytics_snapshots(&self, account_id: Uuid) -> AppResult<Vec<AnalyticsSnapshot>> {
+        let snapshots = self.analytics_snapshots.read().await;
+        let mut result: Vec<AnalyticsSnapshot> = snapshots.iter()
+            .filter(|s| s.account_id == account_id)
+            .cloned()
+            .collect();
+        result.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(result)
+    }
+
+    // 积分用量历史
+    pub async fn save_credits_history(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_CREDITS).await? {
+            return Ok(());
+        }
+        let history = self.credits_history.read().await;
+        let data = serde_json::to_string(&*history)?;
+        drop(history);
+        
+        let data = self.encode_cache(data).await?;
+        self.write_item(storage::KEY_CREDITS, data).await
+    }
+    
+    /// 记录积分用量快照，每个账户最多保留 200 条
+    pub async fn record_credits_snapshot(&self, account: &Account, usage: CreditsUsage) -> AppResult<CreditsSnapshot> {
+        let snapshot = CreditsSnapshot {
+            account_id: account.id,
+            captured_at: chrono::Utc::now(),
+            usage,
+            used_quota: account.used_quota,
+            total_quota: account.total_quota,
+            remaining_quota: match (account.used_quota, account.total_quota) {
+                (Some(used), Some(total)) => Some((total - used).max(0)),
+                _ => None,
+            },
+        };
+        
+        let mut history = self.credits_history.write().await;
+        history.push(snapshot.clone());
+        let account_count = history.iter().filter(|s| s.account_id == account.id).count();
+        if account_count > 200 {
+            let mut to_remove = account_count - 200;
+            history.retain(|s| {
+                if to_remove > 0 && s.account_id == account.id {
+                    to_remove -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        
+        drop(history);
+        self.save_credits_history().await?;
+        Ok(snapshot)
+    }
+    
+    /// 获取账户的积分用量历史（按时间升序）
+    pub async fn get_credits_history(&self, account_id: Uuid) -> AppResult<Vec<CreditsSnapshot>> {
+        let history = self.credits_history.read().await;
+        let mut result: Vec<CreditsSnapshot> = history.iter()
+            .filter(|s| s.account_id == account_id)
+            .cloned()
+            .collect();
+        result.sort_by_key(|s| s.captured_at);
+        Ok(result)
+    }
+
+    // 用量告警历史
+    pub async fn save_alerts_history(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_ALERTS).await? {
+            return Ok(());
+        }
+        let history = self.alerts_history.read().await;
+        let data = serde_json::to_string(&*history)?;
+        drop(history);
+        
+        self.write_item(storage::KEY_ALERTS, data).await
+    }
+    
+    /// 记录触发的告警，最多保留 500 条
+    pub async fn add_alert_events(&self, events: Vec<AlertEvent>) -> AppResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut history = self.alerts_history.write().await;
+        history.extend(events);
+        let excess = history.len().saturating_sub(500);
+        history.drain(..excess);
+        drop(history);
+        self.save_alerts_history().await
+    }
+    
+    /// 同一规则对同一账号最近一次告警的时间
+    pub async fn last_alert_at(&self, rule_id: &str, account_id: Uuid) -> Option<chrono::DateTime<chrono::Utc>> {
+        let history = self.alerts_history.read().await;
+        history.iter()
+            .filter(|e| e.rule_id == rule_id && e.account_id == account_id)
+            .map(|e| e.triggered_at)
+            .max()
+    }
+    
+    /// 获取告警历史（按时间降序）
+    pub async fn get_alerts_history(&self, account_id: Option<Uuid>) -> AppResult<Vec<AlertEvent>> {
+        let history = self.alerts_history.read().await;
+        let mut result: Vec<AlertEvent> = history.iter()
+            .filter(|e| account_id.map(|id| e.account_id == id).unwrap_or(true))
+            .cloned()
+            .collect();
+        result.sort_by_key(|e| std::cmp::Reverse(e.triggered_at));
+        Ok(result)
+    }
+
+    // 后台定时任务运行记录
+    pub async fn save_job_history(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_JOBS).await? {
+            return Ok(());
+        }
+        let history = self.job_history.read().await;
+        let data = serde_json::to_string(&*history)?;
+        drop(history);
+        
+        self.write_item(storage::KEY_JOBS, data).await
+    }
+    
+    /// 记录一次任务运行，最多保留 500 条
+    pub async fn add_job_run(&self, run: JobRun) -> AppResult<()> {
+        let mut history = self.job_history.write().await;
+        history.push(run);
+        let excess = history.len().saturating_sub(500);
+        history.drain(..excess);
+        drop(history);
+        self.save_job_history().await
+    }
+    
+    /// 获取任务运行记录（按时间升序）
+    pub async fn get_job_history(&self) -> AppResult<Vec<JobRun>> {
+        Ok(self.job_history.read().await.clone())
+    }
+
+    // 月度报告管理
+    pub async fn save_monthly_reports(&self) -> AppResult<()> {
+        if self.forward_write(storage::KEY_REPORTS).await? {
+            return Ok(());
+        }
+        let reports = self.monthly_reports.read().await;
+        let data = serde_json::to_string(&*reports)?;
+        drop(reports);
+        
+        self.write_item(storage::KEY_REPORTS, data).await
+    }
+    
+    /// 保存月度报告（同一账户同一月份覆盖）
+    pub async fn add_monthly_report(&self, report: MonthlyReport) -> AppResult<()> {
+        let mut reports = self.monthly_reports.write().await;
+        reports.retain(|r| !(r.account_id == report.account_id && r.month == report.month));
+        reports.push(report);
+        drop(reports);
+        self.save_monthly_reports().await
+    }
+    
+    pub async fn has_monthly_report(&self, account_id: Uuid, month: &str) -> bool {
+        let reports = self.monthly_reports.read().await;
+        reports.iter().any(|r| r.account_id == account_id && r.month == month)
+    }
+    
+    /// 获取月度报告列表（按月份降序）
+    pub async fn list_monthly_reports(&self, account_id: Option<Uuid>) -> AppResult<Vec<MonthlyReport>> {
+        let reports = self.monthly_reports.read().await;
+        let mut result: Vec<MonthlyReport> = reports.iter()
+            .filter(|r| account_id.map(|id| r.account_id == id).unwrap_or(true))
+            .cloned()
+            .collect();
+        result.sort_by(|a, b| b.month.cmp(&a.month).then_with(|| a.email.cmp(&b.email)));
+        Ok(result)
+    }
+    
+    pub async fn get_monthly_report(&self, account_id: Uuid, month: &str) -> AppResult<MonthlyReport> {
+        let reports = self.monthly_reports.read().await;
+        reports.iter()
+            .find(|r| r.account_id == account_id && r.month == month)
+            .cloned()
+            .ok_or_else(|| AppError::Config(format!("Monthly report {} not found", month)))
+    }
+
     pub async fn get_logs(&self, limit: Option<usize>) -> AppResult<Vec<OperationLog>> {
         let logs = self.logs.read().await;
         let logs_vec = logs.clone();
@@ -612,6 +1817,24 @@ impl DataStore {
         }
     }
 
+    /// 按级别、类型、时间范围与关键字筛选操作日志（最新的在前），分页返回
+    pub async fn query_logs(&self, filter: &crate::models::LogFilter, page: usize, page_size: usize) -> AppResult<crate::models::LogPage> {
+        let logs = self.logs.read().await;
+        let matched: Vec<&OperationLog> = logs.iter().rev().filter(|log| filter.matches(log)).collect();
+
+        let page = page.max(1);
+        let page_size = page_size.max(1);
+        let total = matched.len();
+        let items = matched
+            .into_iter()
+            .skip((page - 1).saturating_mul(page_size))
+            .take(page_size)
+            .cloned()
+            .collect();
+
+        Ok(crate::models::LogPage { items, total, page, page_size })
+    }
+
     pub async fn clear_logs(&self) -> AppResult<()> {
         let mut logs = self.logs.write().await;
         logs.clear();
@@ -639,7 +1862,7 @@ impl DataStore {
     /// 创建带时间戳的备份
     pub async fn create_timestamped_backup(&self) -> AppResult<PathBuf> {
         let config = self.config.read().await;
-        let data = serde_json::to_string_pretty(&*config)?;
+        let data = self.encode_config(&config)?;
         drop(config);
         
         let timestamp = Local::now().format("%Y%m%d_%H%M%S");
@@ -705,15 +1928,26 @@ impl DataStore {
     }
     
     /// 从指定路径导入数据
-    pub async fn import_data(&self, import_path: &PathBuf, merge: bool) -> AppResult<ImportResult> {
-        let data = fs::read_to_string(import_path)?;
-        let import_data: serde_json::Value = serde_json::from_str(&data)?;
+    ///
+    /// `dry_run` 为 true 时不备份也不修改数据，结果中附带将要新增、更新、跳过与移除的账号
+    pub async fn import_data(&self, import_data: serde_json::Value, merge: bool, dry_run: bool) -> AppResult<ImportResult> {
+        self.ensure_unlocked().await?;
         
         // 先创建当前数据的备份
-        self.create_timestamped_backup().await?;
+        if !dry_run {
+            self.create_timestamped_backup().await?;
+        }
         
-        let mut config = self.config.write().await;
+        let mut guard = self.config.write().await;
+        let mut staged;
+        let config: &mut AppConfig = if dry_run {
+            staged = (*guard).clone();
+            &mut staged
+        } else {
+            &mut guard
+        };
         let mut result = ImportResult::default();
+        let mut preview = ImportPreview::default();
         
         // 导入账号
         if let Some(accounts) = import_data.get("accounts") {
@@ -723,14 +1957,17 @@ impl DataStore {
                 // 合并模式：只添加不存在的账号
                 for account in imported_accounts {
                     if !config.accounts.iter().any(|a| a.email == account.email) {
+                        preview.added.push(account.email.clone());
                         config.accounts.push(account);
                         result.accounts_added += 1;
                     } else {
+                        preview.skipped.push(format!("{}: 账号已存在", account.email));
                         result.accounts_skipped += 1;
                     }
                 }
             } else {
                 // 替换模式：完全替换
+                preview = ImportPreview::replacing(&config.accounts, &imported_accounts, |a, b| a.email == b.email);
                 result.accounts_added = imported_accounts.len();
                 config.accounts = imported_accounts;
             }
@@ -747,8 +1984,12 @@ impl DataStore {
             }
         }
         
-        drop(config);
-        self.save().await?;
+        drop(guard);
+        if dry_run {
+            result.preview = Some(preview);
+        } else {
+            self.save().await?;
+        }
         
         Ok(result)
     }
@@ -795,7 +2036,10 @@ impl DataStore {
         let data = fs::read_to_string(backup_path)?;
         
         // 验证备份数据
-        let config: AppConfig = serde_json::from_str(&data)?;
+        let (mut config, _) = migrations::parse_config(&data)?;
+        self.decrypt_config(&mut config);
+        // 加密方式沿用当前配置，备份中的设置可能早于最近一次更换主密码
+        config.secret_encryption = self.config.read().await.secret_encryption.clone();
         
         // 先备份当前数据
         self.create_timestamped_backup().await?;
@@ -810,6 +2054,166 @@ impl DataStore {
         Ok(())
     }
     
+    /// 用导出文件中的数据替换账号、分组和标签（设置保持不变）
+    ///
+    /// 导出不含凭据时，沿用本地同 ID 账号已有的密码与 Token
+    pub async fn restore_exported_data(&self, accounts: Vec<Account>, groups: Vec<String>, tags: Vec<crate::models::GlobalTag>) -> AppResult<()> {
+        self.ensure_unlocked().await?;
+        // 先备份当前数据
+        self.create_timestamped_backup().await?;
+        
+        let mut config = self.config.write().await;
+        let mut restored = accounts;
+        for account in restored.iter_mut() {
+            if let Some(existing) = config.accounts.iter().find(|a| a.id == account.id) {
+                if account.password.is_empty() {
+                    account.password = existing.password.clone();
+                }
+                if account.refresh_token.is_none() {
+                    account.refresh_token = existing.refresh_token.clone();
+                    account.token = existing.token.clone();
+                    account.token_expires_at = existing.token_expires_at;
+                }
+                if account.windsurf_api_key.is_none() {
+                    account.windsurf_api_key = existing.windsurf_api_key.clone();
+                }
+                account.tag_colors = existing.tag_colors.clone();
+            }
+        }
+        
+        config.accounts = restored;
+        if !groups.is_empty() {
+            config.groups = groups;
+        }
+        if !tags.is_empty() {
+            config.tags = tags;
+        }
+        drop(config);
+        
+        self.save().await?;
+        Ok(())
+    }
+    
+    /// 统计导入时会新增的分析快照数量（与 `import_analytics_snapshots` 的去重规则一致），用于预演
+    pub async fn count_new_snapshots(&self, imported: &[AnalyticsSnapshot]) -> usize {
+        let snapshots = self.analytics_snapshots.read().await;
+        let mut seen: std::collections::HashSet<(Uuid, &str)> = std::collections::HashSet::new();
+        imported
+            .iter()
+            .filter(|snapshot| {
+                !snapshots.iter().any(|s| s.account_id == snapshot.account_id && s.date == snapshot.date)
+                    && seen.insert((snapshot.account_id, snapshot.date.as_str()))
+            })
+            .count()
+    }
+    
+    /// 导入分析快照（已存在同账号同日期的快照时跳过），返回导入数量
+    pub async fn import_analytics_snapshots(&self, imported: Vec<AnalyticsSnapshot>) -> AppResult<usize> {
+        let mut snapshots = self.analytics_snapshots.write().await;
+        let mut count = 0;
+        for snapshot in imported {
+            if !snapshots.iter().any(|s| s.account_id == snapshot.account_id && s.date == snapshot.date) {
+                snapshots.push(snapshot);
+                count += 1;
+            }
+        }
+        drop(snapshots);
+        
+        if count > 0 {
+            self.save_snapshots().await?;
+        }
+        Ok(count)
+    }
+    
+    /// 设置主密码（`None` 时改用系统密钥链保存的密钥），并用新密钥重新加密已保存的凭据
+    ///
+    /// 更换密钥后，旧备份中的凭据只能用旧密钥解密
+    pub async fn set_master_password(&self, password: Option<&str>) -> AppResult<()> {
+        self.ensure_local_writer()?;
+        self.ensure_unlocked().await?;
+        
+        let (encryption, crypto) = match password.filter(|p| !p.is_empty()) {
+            Some(password) => {
+                let salt = CryptoService::generate_salt();
+                let crypto = CryptoService::from_password(password, &salt)
+                    .map_err(|e| AppError::Config(format!("Failed to derive key: {}", e)))?;
+                let encryption = crate::models::SecretEncryption {
+                    mode: secrets::MODE_PASSWORD.to_string(),
+                    salt: Some(salt),
+                    check: Some(secrets::make_check(&crypto)?),
+                };
+                (encryption, crypto)
+            }
+            None => {
+                let encryption = crate::models::SecretEncryption {
+                    mode: secrets::MODE_KEYRING.to_string(),
+                    ..Default::default()
+                };
+                let crypto = secrets::open_crypto(&encryption)?
+                    .ok_or_else(|| AppError::Config("Failed to open keyring".to_string()))?;
+                (encryption, crypto)
+            }
+        };
+        
+        *self.crypto.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(crypto));
+        self.config.write().await.secret_encryption = encryption;
+        self.save().await?;
+        // 加密的缓存同样改用新密钥重写，否则重启后无法解密而被当作空缓存覆盖
+        self.apply_cache_storage().await
+    }
+    
+    /// 关闭凭据加密：之后凭据以明文保存（需先解锁）
+    pub async fn disable_encryption(&self) -> AppResult<()> {
+        self.ensure_local_writer()?;
+        self.ensure_unlocked().await?;
+        
+        {
+            let mut config = self.config.write().await;
+            config.secret_encryption = crate::models::SecretEncryption::default();
+            // 加密缓存依赖凭据加密的密钥，关闭后改为明文保存
+            if config.settings.cache_storage == secrets::CACHE_ENCRYPTED {
+                config.settings.cache_storage = secrets::CACHE_PLAINTEXT.to_string();
+            }
+        }
+        *self.crypto.write().unwrap_or_else(|e| e.into_inner()) = None;
+        self.save().await?;
+        self.apply_cache_storage().await
+    }
+    
+    /// 用主密码解锁：校验后从存储重新加载并解密账号配置
+    ///
+    /// 连接到后台进程时只设置密钥，数据由调用方从已解锁的后台进程同步
+    pub async fn unlock(&self, password: &str) -> AppResult<()> {
+        let encryption = self.config.read().await.secret_encryption.clone();
+        let crypto = secrets::unlock_with_password(&encryption, password)?;
+        *self.crypto.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(crypto));
+        if self.remote_writer().is_some() {
+            return Ok(());
+        }
+        
+        let storage = self.storage();
+        let (mut config, _) = Self::load_config(storage.as_ref())?;
+        config.settings.persistence_backend = storage.name().to_string();
+        self.decrypt_config(&mut config);
+        *self.config.write().await = config;
+        
+        // 加密的缓存在锁定期间无法读取，解锁后重新加载
+        let crypto = self.crypto();
+        *self.analytics_snapshots.write().await = Self::load_snapshots(storage.as_ref(), crypto.as_deref());
+        *self.credits_history.write().await = Self::load_credits_history(storage.as_ref(), crypto.as_deref());
+        Ok(())
+    }
+    
+    /// 获取启动完整性报告
+    pub fn get_startup_report(&self) -> IntegrityReport {
+        self.startup_report.clone()
+    }
+    
+    /// 获取本次启动时的数据迁移结果（未迁移时为 None）
+    pub fn get_migration_report(&self) -> Option<MigrationReport> {
+        self.migration_report.clone()
+    }
+    
     /// 获取数据目录路径
     pub fn get_data_dir(&self) -> PathBuf {
         self.config_path.parent()
@@ -819,6 +2223,7 @@ impl DataStore {
     
     /// 更新账户排序顺序（用于拖拽排序）
     pub async fn update_accounts_order(&self, account_ids: Vec<String>) -> AppResult<()> {
+        self.ensure_unlocked().await?;
         let mut config = self.config.write().await;
         
         // 为每个账户设置新的 sort_order
@@ -837,74 +2242,170 @@ impl DataStore {
     
     /// 获取排序后的账户列表
     pub async fn get_sorted_accounts(&self, sort_field: &crate::models::SortField, sort_direction: &crate::models::SortDirection) -> AppResult<Vec<Account>> {
-        use crate::models::{SortField, SortDirection};
-        
+        self.ensure_unlocked().await?;
         let config = self.config.read().await;
         let mut accounts = config.accounts.clone();
-        
-        // 根据排序字段排序
-        match sort_field {
-            SortField::Email => {
-                accounts.sort_by(|a, b| a.email.to_lowercase().cmp(&b.email.to_lowercase()));
-            }
-            SortField::CreatedAt => {
-                accounts.sort_by_key(|a| a.created_at);
-            }
-            SortField::UsedQuota => {
-                accounts.sort_by_key(|a| a.used_quota.unwrap_or(0));
-            }
-            SortField::RemainingQuota => {
-                accounts.sort_by_key(|a| {
-                    let total = a.total_quota.unwrap_or(0);
-                    let used = a.used_quota.unwrap_or(0);
-                    total - used
-                });
-            }
-            SortField::TokenExpiresAt => {
-                accounts.sort_by(|a, b| {
-                    match (&a.token_expires_at, &b.token_expires_at) {
-                        (Some(a_exp), Some(b_exp)) => a_exp.cmp(b_exp),
-                        (Some(_), None) => std::cmp::Ordering::Less,
-                        (None, Some(_)) => std::cmp::Ordering::Greater,
-                        (None, None) => std::cmp::Ordering::Equal,
-                    }
-                });
-            }
-            SortField::SubscriptionExpiresAt => {
-                accounts.sort_by(|a, b| {
-                    match (&a.subscription_expires_at, &b.subscription_expires_at) {
-                        (Some(a_exp), Some(b_exp)) => a_exp.cmp(b_exp),
-                        (Some(_), None) => std::cmp::Ordering::Less,
-                        (None, Some(_)) => std::cmp::Ordering::Greater,
-                        (None, None) => std::cmp::Ordering::Equal,
-                    }
-                });
-            }
-            SortField::PlanName => {
-                // 定义套餐优先级: Enterprise > Teams > Pro > Trial > Free > None
-                let plan_priority = |plan: &Option<String>| -> i32 {
-                    match plan.as_ref().map(|s| s.to_lowercase()).as_deref() {
-                        Some("enterprise") => 5,
-                        Some("teams") => 4,
-                        Some("pro") => 3,
-                        Some("trial") => 2,
-                        Some("free") => 1,
-                        _ => 0,
-                    }
-                };
-                accounts.sort_by(|a, b| plan_priority(&b.plan_name).cmp(&plan_priority(&a.plan_name)));
-            }
+        sort_accounts(&mut accounts, sort_field, sort_direction);
+        Ok(accounts)
+    }
+    
+    /// 按条件筛选、排序并分页，`page` 从 1 开始；未指定排序时按自定义顺序
+    pub async fn search_accounts(
+        &self,
+        filter: &crate::models::AccountFilter,
+        sort: Option<(crate::models::SortField, crate::models::SortDirection)>,
+        page: usize,
+        page_size: usize,
+    ) -> AppResult<crate::models::AccountPage> {
+        self.ensure_unlocked().await?;
+        let mut accounts: Vec<Account> = {
+            let config = self.config.read().await;
+            config.accounts.iter().filter(|a| filter.matches(a)).cloned().collect()
+        };
+        match &sort {
+            Some((field, direction)) => sort_accounts(&mut accounts, field, direction),
+            None => accounts.sort_by_key(|a| a.sort_order),
         }
         
-        // 根据排序方向反转
-        if *sort_direction == SortDirection::Desc && *sort_field != SortField::PlanName {
-            accounts.reverse();
-        } else if *sort_direction == SortDirection::Asc && *sort_field == SortField::PlanName {
-            accounts.reverse();
-        }
+        let page = page.max(1);
+        let page_size = page_size.max(1);
+        let total = accounts.len();
+        let items = accounts
+            .into_iter()
+            .skip((page - 1).saturating_mul(page_size))
+            .take(page_size)
+            .collect();
         
-        Ok(accounts)
+        Ok(crate::models::AccountPage { items, total, page, page_size })
+    }
+}
+
+/// 套餐优先级: Enterprise > Teams > Pro > Trial > Free > None
+fn plan_priority(plan: &Option<String>) -> i32 {
+    match plan.as_ref().map(|s| s.to_lowercase()).as_deref() {
+        Some("enterprise") => 5,
+        Some("teams") => 4,
+        Some("pro") => 3,
+        Some("trial") => 2,
+        Some("free") => 1,
+        _ => 0,
+    }
+}
+
+/// 风险系数（≥ 1）：认证失败、需要处理、疑似共用、Token 失效且无法刷新都会提高风险
+fn account_risk_score(account: &Account, now: chrono::DateTime<chrono::Utc>) -> f64 {
+    let mut risk = 1.0 + account.auth_failure_count as f64;
+    match account.status {
+        crate::models::AccountStatus::NeedsAttention => risk += 4.0,
+        crate::models::AccountStatus::Error(_) => risk += 2.0,
+        _ => {}
+    }
+    if account.shared_use_warning.is_some() {
+        risk += 2.0;
+    }
+    let token_expired = account.effective_token_expiry().0.map(|exp| exp <= now).unwrap_or(true);
+    if token_expired && account.refresh_token.is_none() {
+        risk += 3.0;
+    }
+    risk
+}
+
+/// 账号综合价值：剩余积分 × 套餐权重 ÷ 风险系数；已禁用或订阅失效的账号为 0
+pub fn account_value_score(account: &Account, now: chrono::DateTime<chrono::Utc>) -> f64 {
+    if account.is_disabled == Some(true) || account.subscription_active == Some(false) {
+        return 0.0;
+    }
+    let remaining = (account.total_quota.unwrap_or(0) - account.used_quota.unwrap_or(0)).max(0) as f64;
+    let weight = plan_priority(&account.plan_name).max(1) as f64;
+    remaining * weight / account_risk_score(account, now)
+}
+
+/// 渲染账号备注模板，支持 {email}、{nickname}、{created}（本地日期）
+///
+/// 新添加的账号尚未刷新，没有套餐信息，因此不提供 {plan}
+pub fn render_note_template(template: &str, account: &Account) -> String {
+    let created = account
+        .created_at
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d")
+        .to_string();
+    template
+        .replace("{email}", &account.email)
+        .replace("{nickname}", &account.nickname)
+        .replace("{created}", &created)
+}
+
+/// 按排序字段与方向排序账号列表
+fn sort_accounts(accounts: &mut [Account], sort_field: &crate::models::SortField, sort_direction: &crate::models::SortDirection) {
+    use crate::models::{SortField, SortDirection};
+    
+    // 根据排序字段排序
+    match sort_field {
+        SortField::Email => {
+            accounts.sort_by(|a, b| a.email.to_lowercase().cmp(&b.email.to_lowercase()));
+        }
+        SortField::CreatedAt => {
+            accounts.sort_by_key(|a| a.created_at);
+        }
+        SortField::UsedQuota => {
+            accounts.sort_by_key(|a| a.used_quota.unwrap_or(0));
+        }
+        SortField::RemainingQuota => {
+            accounts.sort_by_key(|a| {
+                let total = a.total_quota.unwrap_or(0);
+                let used = a.used_quota.unwrap_or(0);
+                total - used
+            });
+        }
+        SortField::TokenExpiresAt => {
+            accounts.sort_by(|a, b| {
+                match (&a.token_expires_at, &b.token_expires_at) {
+                    (Some(a_exp), Some(b_exp)) => a_exp.cmp(b_exp),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+            });
+        }
+        SortField::SubscriptionExpiresAt => {
+            accounts.sort_by(|a, b| {
+                match (&a.subscription_expires_at, &b.subscription_expires_at) {
+                    (Some(a_exp), Some(b_exp)) => a_exp.cmp(b_exp),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+            });
+        }
+        SortField::PlanName => {
+            accounts.sort_by(|a, b| plan_priority(&b.plan_name).cmp(&plan_priority(&a.plan_name)));
+        }
+        SortField::ValueScore => {
+            let now = chrono::Utc::now();
+            accounts.sort_by(|a, b| account_value_score(a, now).total_cmp(&account_value_score(b, now)));
+        }
     }
+    
+    // 根据排序方向反转
+    if *sort_direction == SortDirection::Desc && *sort_field != SortField::PlanName {
+        accounts.reverse();
+    } else if *sort_direction == SortDirection::Asc && *sort_field == SortField::PlanName {
+        accounts.reverse();
+    }
+}
+
+/// 批量添加时的一个账号
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct NewAccount {
+    pub email: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub group: Option<String>,
 }
 
 /// 导入结果
@@ -913,6 +2414,69 @@ pub struct ImportResult {
     pub accounts_added: usize,
     pub accounts_skipped: usize,
     pub groups_added: usize,
+    /// 预演（dry_run）时将要发生的变更
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImportPreview>,
+}
+
+/// 导入预演结果：按邮箱列出将新增、更新、跳过与移除的账号，不修改任何数据
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ImportPreview {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    /// 跳过的条目及原因
+    pub skipped: Vec<String>,
+    /// 替换模式下将被移除的账号
+    pub removed: Vec<String>,
+}
+
+impl ImportPreview {
+    /// 用 `incoming` 整体替换 `existing` 时的变更，`same` 判断两条记录是否为同一账号
+    pub fn replacing(existing: &[Account], incoming: &[Account], same: impl Fn(&Account, &Account) -> bool) -> Self {
+        let mut preview = Self::default();
+        for account in incoming {
+            if existing.iter().any(|e| same(e, account)) {
+                preview.updated.push(account.email.clone());
+            } else {
+                preview.added.push(account.email.clone());
+            }
+        }
+        preview.removed = existing
+            .iter()
+            .filter(|e| !incoming.iter().any(|a| same(e, a)))
+            .map(|e| e.email.clone())
+            .collect();
+        preview
+    }
+}
+
+/// 删除归档保留天数
+pub const DELETED_ARCHIVE_DAYS: i64 = 30;
+
+fn archive_expired(archived_at: i64, now: i64) -> bool {
+    now - archived_at > DELETED_ARCHIVE_DAYS * 86400
+}
+
+/// 删除归档文件：账号记录整体加密后写入 payload（无可用密钥时为明文）
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DeletedArchive {
+    version: u32,
+    archived_at: i64,
+    /// 归档密钥来源：keyring / password；none 表示 payload 为明文
+    encryption: String,
+    emails: Vec<String>,
+    payload: String,
+}
+
+/// 删除归档信息
+#[derive(Debug, serde::Serialize)]
+pub struct DeletedArchiveInfo {
+    /// 恢复时传入的归档文件名
+    pub file_name: String,
+    pub path: String,
+    pub archived_at: i64,
+    pub expires_at: i64,
+    pub emails: Vec<String>,
 }
 
 /// 备份信息
@@ -923,3 +2487,73 @@ pub struct BackupInfo {
     pub size: u64,
     pub created_at: Option<i64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyring_store_loads_plaintext_custom_headers() {
+        let crypto = CryptoService::from_password("keyring", &CryptoService::generate_salt()).unwrap();
+        let mut config = crate::models::AppConfig::default();
+        config.secret_encryption.mode = secrets::MODE_KEYRING.to_string();
+        config.settings.custom_headers.global.insert("x-gateway-key".to_string(), "g-key".to_string());
+        secrets::encrypt_config(&mut config, &crypto).unwrap();
+        assert!(secrets::is_encrypted(&config.settings.custom_headers.global["x-gateway-key"]));
+
+        // 与 new() 相同的读入流程：反序列化后用 keyring 密钥解密
+        let mut loaded: crate::models::AppConfig = serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+        DataStore::decrypt_loaded_config(&mut loaded, Some(&crypto));
+        assert_eq!(loaded.settings.custom_headers.global["x-gateway-key"], "g-key");
+    }
+
+    #[test]
+    fn test_render_note_template() {
+        let account = Account::new("a@example.com".to_string(), String::new(), "Alice".to_string(), Vec::new());
+        let created = account.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string();
+        assert_eq!(
+            render_note_template("{nickname} <{email}> {created}", &account),
+            format!("Alice <a@example.com> {}", created)
+        );
+        // 未知变量原样保留
+        assert_eq!(render_note_template("{plan}", &account), "{plan}");
+    }
+
+    #[test]
+    fn test_account_value_score() {
+        let now = chrono::Utc::now();
+        let account = |plan: &str, remaining: i32| {
+            let mut account = Account::new("a@example.com".to_string(), String::new(), "A".to_string(), Vec::new());
+            account.plan_name = Some(plan.to_string());
+            account.total_quota = Some(1000);
+            account.used_quota = Some(1000 - remaining);
+            account.refresh_token = Some("refresh".to_string());
+            account
+        };
+
+        // 剩余积分相同时套餐权重高的优先，权重相同时剩余积分多的优先
+        let pro = account_value_score(&account("Pro", 500), now);
+        assert!(pro > account_value_score(&account("Free", 500), now));
+        assert!(account_value_score(&account("Pro", 600), now) > pro);
+        assert_eq!(account_value_score(&account("Pro", 0), now), 0.0);
+
+        // 风险越高价值越低：认证失败、需要处理、无法刷新的过期 Token
+        let mut failing = account("Pro", 500);
+        failing.auth_failure_count = 2;
+        assert_eq!(account_value_score(&failing, now), pro / 3.0);
+        let mut attention = account("Pro", 500);
+        attention.status = crate::models::AccountStatus::NeedsAttention;
+        assert!(account_value_score(&attention, now) < account_value_score(&failing, now));
+        let mut no_refresh = account("Pro", 500);
+        no_refresh.refresh_token = None;
+        assert_eq!(account_value_score(&no_refresh, now), pro / 4.0);
+
+        // 已禁用或订阅失效的账号为 0
+        let mut disabled = account("Enterprise", 900);
+        disabled.is_disabled = Some(true);
+        assert_eq!(account_value_score(&disabled, now), 0.0);
+        let mut lapsed = account("Enterprise", 900);
+        lapsed.subscription_active = Some(false);
+        assert_eq!(account_value_score(&lapsed, now), 0.0);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::models::{Account, AppConfig};
+use super::journal::RecoveryReport;
+use super::secrets;
+use crate::services::keyring_service;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// 账号变化条目
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountChange {
+    pub id: String,
+    pub email: String,
+    /// 发生变化的字段（仅凭据类字段）
+    pub fields: Vec<String>,
+}
+
+/// 启动完整性报告：当前数据与上次保存时记录的校验清单的差异
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    pub generated_at: Option<DateTime<Utc>>,
+    /// 用于比较的校验清单的保存时间
+    pub baseline_saved_at: Option<DateTime<Utc>>,
+    pub current_account_count: usize,
+    pub baseline_account_count: usize,
+    /// 上次保存时存在但当前数据中消失的账号
+    pub accounts_missing: Vec<AccountChange>,
+    /// 当前数据中新增的账号
+    pub accounts_added: Vec<AccountChange>,
+    /// 凭据（token / refresh_token / API Key / 密码）发生变化的账号
+    pub credentials_changed: Vec<AccountChange>,
+    /// 本次启动时重放或回滚的未完成写入
+    pub recovered_writes: Vec<RecoveryReport>,
+    /// 是否存在需要用户确认的异常变化
+    pub has_unexpected_changes: bool,
+    pub error: Option<String>,
+}
+
+/// 单个账号的凭据指纹（SHA-256），未保存到存储的字段为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountFingerprint {
+    pub id: Uuid,
+    pub email: String,
+    pub token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub windsurf_api_key: Option<String>,
+    pub password: Option<String>,
+}
+
+/// 校验清单：每次保存账号配置时通过当前存储后端一并写入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityManifest {
+    pub saved_at: DateTime<Utc>,
+    pub accounts: Vec<AccountFingerprint>,
+}
+
+/// 仍为密文（无法解密）或密钥链占位值的凭据不含实际内容，不参与比较
+fn is_opaque(value: &str) -> bool {
+    secrets::is_encrypted(value) || keyring_service::is_placeholder(value)
+}
+
+fn fingerprint(value: Option<&str>) -> Option<String> {
+    value
+        .filter(|v| !is_opaque(v))
+        .map(|v| hex::encode(Sha256::digest(v.as_bytes())))
+}
+
+/// 根据内存中的配置生成校验清单；仅保存在内存中的会话凭据不会落盘，不记录指纹
+pub fn build_manifest(config: &AppConfig) -> IntegrityManifest {
+    let session_only = config.settings.memory_only_secrets;
+    let session = |value: Option<&str>| if session_only { None } else { fingerprint(value) };
+    IntegrityManifest {
+        saved_at: Utc::now(),
+        accounts: config
+            .accounts
+            .iter()
+            .map(|account| AccountFingerprint {
+                id: account.id,
+                email: account.email.clone(),
+                token: session(account.token.as_deref()),
+                refresh_token: session(account.refresh_token.as_deref()),
+                windsurf_api_key: session(account.windsurf_api_key.as_deref()),
+                password: fingerprint(Some(&account.password)),
+            })
+            .collect(),
+    }
+}
+
+/// 保存时有指纹的字段，当前值缺失或指纹不同即视为变化；当前值无法解密时跳过
+fn field_changed(saved: Option<&String>, current: Option<&str>) -> bool {
+    match (saved, current) {
+        (None, _) => false,
+        (Some(_), Some(value)) if is_opaque(value) => false,
+        (Some(hash), current) => fingerprint(current).as_ref() != Some(hash),
+    }
+}
+
+fn changed_credentials(saved: &AccountFingerprint, now: &Account) -> Vec<String> {
+    let mut fields = Vec::new();
+    if field_changed(saved.token.as_ref(), now.token.as_deref()) {
+        fields.push("token".to_string());
+    }
+    if field_changed(saved.refresh_token.as_ref(), now.refresh_token.as_deref()) {
+        fields.push("refresh_token".to_string());
+    }
+    if field_changed(saved.windsurf_api_key.as_ref(), now.windsurf_api_key.as_deref()) {
+        fields.push("windsurf_api_key".to_string());
+    }
+    if field_changed(saved.password.as_ref(), Some(&now.password)) {
+        fields.push("password".to_string());
+    }
+    fields
+}
+
+/// 比较当前配置与上次保存的校验清单，生成完整性报告
+pub fn build_report(
+    manifest: Option<&str>,
+    current: &AppConfig,
+    recovered_writes: Vec<RecoveryReport>,
+) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        generated_at: Some(Utc::now()),
+        current_account_count: current.accounts.len(),
+        recovered_writes,
+        ..Default::default()
+    };
+
+    let baseline: IntegrityManifest = match manifest.map(serde_json::from_str) {
+        Some(Ok(manifest)) => manifest,
+        Some(Err(e)) => {
+            report.error = Some(format!("无法读取校验清单: {}", e));
+            return report;
+        }
+        None => {
+            report.has_unexpected_changes = !report.recovered_writes.is_empty();
+            return report;
+        }
+    };
+
+    report.baseline_saved_at = Some(baseline.saved_at);
+    report.baseline_account_count = baseline.accounts.len();
+
+    for old in &baseline.accounts {
+        match current.accounts.iter().find(|a| a.id == old.id) {
+            Some(now) => {
+                let fields = changed_credentials(old, now);
+                if !fields.is_empty() {
+                    report.credentials_changed.push(AccountChange {
+                        id: now.id.to_string(),
+                        email: now.email.clone(),
+                        fields,
+                    });
+                }
+            }
+            None => report.accounts_missing.push(AccountChange {
+                id: old.id.to_string(),
+                email: old.email.clone(),
+                fields: Vec::new(),
+            }),
+        }
+    }
+
+    for now in &current.accounts {
+        if !baseline.accounts.iter().any(|a| a.id == now.id) {
+            report.accounts_added.push(AccountChange {
+                id: now.id.to_string(),
+                email: now.email.clone(),
+                fields: Vec::new(),
+            });
+        }
+    }
+
+    report.has_unexpected_changes = !report.accounts_missing.is_empty()
+        || !report.credentials_changed.is_empty()
+        || !report.recovered_writes.is_empty();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_against_manifest() {
+        let mut config = AppConfig::default();
+        let mut kept = Account::new("a@example.com".into(), "pw".into(), "a".into(), Vec::new());
+        kept.token = Some("token-1".into());
+        let removed = Account::new("b@example.com".into(), "pw".into(), "b".into(), Vec::new());
+        config.accounts = vec![kept, removed];
+        let manifest = serde_json::to_string(&build_manifest(&config)).unwrap();
+
+        // 未变化的数据不产生报告
+        let report = build_report(Some(&manifest), &config, Vec::new());
+        assert!(!report.has_unexpected_changes);
+
+        config.accounts[0].token = Some("token-2".into());
+        config.accounts.pop();
+        let report = build_report(Some(&manifest), &config, Vec::new());
+        assert!(report.has_unexpected_changes);
+        assert_eq!(report.credentials_changed[0].fields, vec!["token".to_string()]);
+        assert_eq!(report.accounts_missing[0].email, "b@example.com");
+
+        // 仍为密文的凭据不参与比较
+        config.accounts[0].token = Some("enc:v1:abc".into());
+        let report = build_report(Some(&manifest), &config, Vec::new());
+        assert!(report.credentials_changed.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 写入日志条目：记录一次尚未完成的 DataStore 写入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 写入序号（纳秒时间戳），用于在多个残留日志中挑选最新的一条
+    pub seq: u128,
+    /// 目标文件名（如 accounts.json）
+    pub target: String,
+    pub started_at: DateTime<Utc>,
+    /// 待写入的完整数据
+    pub data: String,
+}
+
+/// 启动时的恢复动作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// 日志中的数据完整，已重放到目标文件
+    Replayed,
+    /// 目标文件已是最新，仅清理残留日志
+    AlreadyApplied,
+    /// 日志数据不完整或无效，已丢弃，保留原文件
+    RolledBack,
+}
+
+/// 单个文件的恢复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    pub target: String,
+    pub action: RecoveryAction,
+    pub pending_since: Option<DateTime<Utc>>,
+    pub journals_found: usize,
+    pub temp_files_removed: usize,
+    pub detail: String,
+}
+
+fn file_stem(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("data")
+}
+
+fn target_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("data")
+        .to_string()
+}
+
+/// 列出目标文件名前缀匹配的残留文件（如 accounts.journal.* / accounts.tmp.*）
+fn list_with_prefix(path: &Path, prefix: &str) -> Vec<PathBuf> {
+    let parent = match path.parent() {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+
+    fs::read_dir(parent)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.starts_with(prefix))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 开始一次写入：先把完整数据落盘到日志文件，返回日志路径
+pub fn begin(path: &Path, data: &str) -> std::io::Result<PathBuf> {
+    let seq = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    let entry = JournalEntry {
+        seq,
+        target: target_name(path),
+        started_at: Utc::now(),
+        data: data.to_string(),
+    };
+
+    let parent = path.parent().unwrap_or(path);
+    let journal_path = parent.join(format!(
+        "{}.journal.{}.{}",
+        file_stem(path),
+        std::process::id(),
+        seq
+    ));
+
+    let serialized = serde_json::to_string(&entry)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    let mut file = fs::File::create(&journal_path)?;
+    file.write_all(serialized.as_bytes())?;
+    // 确保日志先于目标文件落盘
+    file.sync_all()?;
+
+    Ok(journal_path)
+}
+
+/// 写入完成后删除日志
+pub fn commit(journal_path: &Path) {
+    let _ = fs::remove_file(journal_path);
+}
+
+/// 检查目标文件是否有未完成的写入
+///
+/// `apply` 负责把日志中的数据写回目标文件（由调用方提供原子写实现）。
+/// 没有残留日志和临时文件时返回 None。
+pub fn recover<F>(path: &Path, apply: F) -> Option<RecoveryReport>
+where
+    F: Fn(&Path, &str) -> std::io::Result<()>,
+{
+    let stem = file_stem(path).to_string();
+    let journals = list_with_prefix(path, &format!("{}.journal.", stem));
+    let temp_files = list_with_prefix(path, &format!("{}.tmp.", stem));
+
+    if journals.is_empty() && temp_files.is_empty() {
+        return None;
+    }
+
+    // 未完成的临时文件一律丢弃，数据以日志为准
+    let mut temp_files_removed = 0;
+    for temp in &temp_files {
+        if fs::remove_file(temp).is_ok() {
+            temp_files_removed += 1;
+        }
+    }
+
+    // 读取所有可解析的日志，取序号最大的一条
+    let mut latest: Option<JournalEntry> = None;
+    let mut unreadable = 0;
+    for journal in &journals {
+        match fs::read_to_string(journal)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<JournalEntry>(&raw).ok())
+        {
+            Some(entry) => {
+                if latest.as_ref().map(|l| entry.seq > l.seq).unwrap_or(true) {
+                    latest = Some(entry);
+                }
+            }
+            None => unreadable += 1,
+        }
+    }
+
+    let mut report = RecoveryReport {
+        target: target_name(path),
+        action: RecoveryAction::RolledBack,
+        pending_since: latest.as_ref().map(|e| e.started_at),
+        journals_found: journals.len(),
+        temp_files_removed,
+        detail: String::new(),
+    };
+
+    match latest {
+        Some(entry) if serde_json::from_str::<serde_json::Value>(&entry.data).is_ok() => {
+            let current = fs::read_to_string(path).unwrap_or_default();
+            if current == entry.data {
+                report.action = RecoveryAction::AlreadyApplied;
+                report.detail = "目标文件已包含最后一次写入，仅清理残留日志".to_string();
+            } else {
+                match apply(path, &entry.data) {
+                    Ok(_) => {
+                        report.action = RecoveryAction::Replayed;
+                        report.detail = format!(
+                            "已重放 {} 开始的未完成写入",
+                            entry.started_at.to_rfc3339()
+                        );
+                    }
+                    Err(e) => {
+                        report.detail = format!("重放失败，保留原文件: {}", e);
+                    }
+                }
+            }
+        }
+        Some(_) => {
+            report.detail = "日志数据不是有效的 JSON，已回滚到写入前的文件".to_string();
+        }
+        None => {
+            report.detail = if unreadable > 0 {
+                format!("{} 个日志文件已损坏，已回滚到写入前的文件", unreadable)
+            } else {
+                "仅发现未完成的临时文件，已清理".to_string()
+            };
+        }
+    }
+
+    for journal in &journals {
+        let _ = fs::remove_file(journal);
+    }
+
+    Some(report)
+}
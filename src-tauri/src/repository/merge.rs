@@ -0,0 +1,142 @@
+//! 数据项的三方合并
+//!
+//! 界面进程连接到后台进程时，两边各自在内存中修改数据。写入时只把相对上次同步
+//! 的修改应用到对方的最新数据上，避免整份覆盖导致另一边的修改丢失。
+
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// 把 `base` → `ours` 的修改应用到 `theirs` 上，双方都修改的同一字段以 `ours` 为准
+///
+/// - 对象按字段递归合并
+/// - 元素都带 `id` 的数组（账号、日志等）按 id 合并，保持 `theirs` 的顺序，新增的追加在末尾
+/// - 其他数组（历史记录）按元素整体比较：保留 `theirs`，追加 `ours` 新增的元素，去掉 `ours` 删除的元素
+pub fn merge3(base: &Value, ours: &Value, theirs: &Value) -> Value {
+    if ours == base {
+        return theirs.clone();
+    }
+    if theirs == base {
+        return ours.clone();
+    }
+    match (base, ours, theirs) {
+        (Value::Object(base), Value::Object(ours), Value::Object(theirs)) => merge_objects(base, ours, theirs),
+        (Value::Array(base), Value::Array(ours), Value::Array(theirs)) => {
+            if is_keyed(base) && is_keyed(ours) && is_keyed(theirs) {
+                merge_keyed(base, ours, theirs)
+            } else {
+                merge_entries(base, ours, theirs)
+            }
+        }
+        _ => ours.clone(),
+    }
+}
+
+fn merge_objects(base: &Map<String, Value>, ours: &Map<String, Value>, theirs: &Map<String, Value>) -> Value {
+    let mut merged = theirs.clone();
+    for (key, value) in ours {
+        match (base.get(key), theirs.get(key)) {
+            (Some(base_value), Some(their_value)) => {
+                merged.insert(key.clone(), merge3(base_value, value, their_value));
+            }
+            // 对方删除了该字段而我们没有修改，保持删除
+            (Some(base_value), None) if base_value == value => {}
+            _ => {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    // 我们删除、对方未修改的字段
+    for (key, base_value) in base {
+        if !ours.contains_key(key) && theirs.get(key) == Some(base_value) {
+            merged.remove(key);
+        }
+    }
+    Value::Object(merged)
+}
+
+fn item_id(value: &Value) -> Option<&Value> {
+    value.as_object().and_then(|o| o.get("id"))
+}
+
+fn is_keyed(items: &[Value]) -> bool {
+    items.iter().all(|item| item_id(item).is_some())
+}
+
+fn find<'a>(items: &'a [Value], id: &Value) -> Option<&'a Value> {
+    items.iter().find(|item| item_id(item) == Some(id))
+}
+
+fn merge_keyed(base: &[Value], ours: &[Value], theirs: &[Value]) -> Value {
+    let mut merged = Vec::with_capacity(theirs.len());
+    for their_item in theirs {
+        let id = item_id(their_item).unwrap_or(&Value::Null);
+        match (find(base, id), find(ours, id)) {
+            // 我们删除的元素：对方未修改时删除，对方修改过则保留对方的版本
+            (Some(base_item), None) if base_item == their_item => {}
+            (Some(base_item), Some(our_item)) => merged.push(merge3(base_item, our_item, their_item)),
+            _ => merged.push(their_item.clone()),
+        }
+    }
+    for our_item in ours {
+        let id = item_id(our_item).unwrap_or(&Value::Null);
+        if find(theirs, id).is_none() && find(base, id).is_none() {
+            merged.push(our_item.clone());
+        }
+    }
+    Value::Array(merged)
+}
+
+fn merge_entries(base: &[Value], ours: &[Value], theirs: &[Value]) -> Value {
+    let key = |value: &Value| value.to_string();
+    let base_keys: HashSet<String> = base.iter().map(key).collect();
+    let our_keys: HashSet<String> = ours.iter().map(key).collect();
+    let mut seen = HashSet::new();
+
+    let mut merged: Vec<Value> = theirs
+        .iter()
+        .filter(|item| {
+            let k = key(item);
+            // 我们删除的元素不再保留
+            (our_keys.contains(&k) || !base_keys.contains(&k)) && seen.insert(k)
+        })
+        .cloned()
+        .collect();
+    for item in ours {
+        let k = key(item);
+        if !base_keys.contains(&k) && seen.insert(k) {
+            merged.push(item.clone());
+        }
+    }
+    Value::Array(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge3() {
+        // 两边修改不同账号的不同字段，均保留；我们新增的账号追加在末尾
+        let base = json!({"accounts": [{"id": 1, "token": "a", "notes": null}, {"id": 2, "token": "b"}], "groups": ["x"]});
+        let ours = json!({"accounts": [{"id": 1, "token": "a", "notes": "n"}, {"id": 2, "token": "b"}, {"id": 3}], "groups": ["x"]});
+        let theirs = json!({"accounts": [{"id": 1, "token": "a2", "notes": null}, {"id": 2, "token": "b"}], "groups": ["x", "y"]});
+        assert_eq!(
+            merge3(&base, &ours, &theirs),
+            json!({"accounts": [{"id": 1, "token": "a2", "notes": "n"}, {"id": 2, "token": "b"}, {"id": 3}], "groups": ["x", "y"]})
+        );
+
+        // 我们删除的账号被删除；双方修改同一字段时以我们为准
+        let ours = json!({"accounts": [{"id": 1, "token": "mine", "notes": null}], "groups": ["x"]});
+        assert_eq!(
+            merge3(&base, &ours, &theirs),
+            json!({"accounts": [{"id": 1, "token": "mine", "notes": null}], "groups": ["x", "y"]})
+        );
+
+        // 历史记录：对方追加的与我们追加的都保留
+        let base = json!([{"t": 1}]);
+        let ours = json!([{"t": 1}, {"t": 2}]);
+        let theirs = json!([{"t": 1}, {"t": 3}]);
+        assert_eq!(merge3(&base, &ours, &theirs), json!([{"t": 1}, {"t": 3}, {"t": 2}]));
+    }
+}
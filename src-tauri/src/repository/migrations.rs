@@ -0,0 +1,124 @@
+use crate::models::{AppConfig, Settings};
+use crate::utils::AppResult;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// 当前账号配置的结构版本，新增迁移时递增
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// 一次迁移：升级到 `version`，只修改原始 JSON，不依赖当前的结构体定义
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&mut Value),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "补全旧版本缺失的顶层字段与必填设置项",
+        apply: fill_required_settings,
+    },
+    Migration {
+        version: 2,
+        description: "补全旧版本账号缺失的必填字段",
+        apply: fill_required_account_fields,
+    },
+];
+
+/// 迁移结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// 已执行的迁移说明
+    pub applied: Vec<String>,
+    /// 迁移前的数据备份
+    pub backup_path: Option<String>,
+    pub migrated_at: DateTime<Utc>,
+}
+
+fn fill_required_settings(config: &mut Value) {
+    let Some(obj) = config.as_object_mut() else { return };
+    obj.entry("accounts").or_insert_with(|| json!([]));
+    obj.entry("groups").or_insert_with(|| json!(["默认分组"]));
+
+    let defaults = serde_json::to_value(Settings::default()).unwrap_or_default();
+    let settings = obj.entry("settings").or_insert_with(|| json!({}));
+    if let (Some(settings), Some(defaults)) = (settings.as_object_mut(), defaults.as_object()) {
+        for (key, value) in defaults {
+            settings.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+fn fill_required_account_fields(config: &mut Value) {
+    let Some(accounts) = config.get_mut("accounts").and_then(|a| a.as_array_mut()) else { return };
+    let now = Utc::now().to_rfc3339();
+    for account in accounts.iter_mut().filter_map(|a| a.as_object_mut()) {
+        let email = account.get("email").and_then(|e| e.as_str()).unwrap_or_default().to_string();
+        account.entry("id").or_insert_with(|| json!(uuid::Uuid::new_v4()));
+        account.entry("password").or_insert_with(|| json!(""));
+        account.entry("nickname").or_insert_with(|| json!(email));
+        account.entry("tags").or_insert_with(|| json!([]));
+        account.entry("created_at").or_insert_with(|| json!(now));
+        account.entry("status").or_insert_with(|| json!("inactive"));
+    }
+}
+
+/// 将原始配置升级到当前版本，已是最新版本时返回 None
+pub fn migrate(config: &mut Value) -> Option<MigrationReport> {
+    let from_version = config.get("schema_version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    if from_version >= CURRENT_SCHEMA_VERSION {
+        return None;
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from_version) {
+        (migration.apply)(config);
+        applied.push(migration.description.to_string());
+    }
+    if let Some(obj) = config.as_object_mut() {
+        obj.insert("schema_version".to_string(), json!(CURRENT_SCHEMA_VERSION));
+    }
+
+    Some(MigrationReport {
+        from_version,
+        to_version: CURRENT_SCHEMA_VERSION,
+        applied,
+        backup_path: None,
+        migrated_at: Utc::now(),
+    })
+}
+
+/// 解析账号配置，必要时先迁移
+pub fn parse_config(data: &str) -> AppResult<(AppConfig, Option<MigrationReport>)> {
+    let mut value: Value = serde_json::from_str(data)?;
+    let report = migrate(&mut value);
+    Ok((serde_json::from_value(value)?, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let legacy = r#"{
+            "accounts": [{ "email": "a@example.com", "token": null }],
+            "settings": { "theme": "dark" }
+        }"#;
+        let (config, report) = parse_config(legacy).unwrap();
+        let report = report.unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.applied.len(), MIGRATIONS.len());
+        assert_eq!(config.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(config.settings.theme, "dark");
+        assert_eq!(config.accounts[0].nickname, "a@example.com");
+
+        // 已迁移的数据不再重复迁移
+        let (_, report) = parse_config(&serde_json::to_string(&config).unwrap()).unwrap();
+        assert!(report.is_none());
+    }
+}
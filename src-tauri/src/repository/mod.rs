@@ -1,3 +1,11 @@
 pub mod data_store;
+pub mod integrity;
+pub mod journal;
+pub mod merge;
+pub mod migrations;
+pub mod secrets;
+pub mod storage;
 
 pub use data_store::*;
+pub use integrity::IntegrityReport;
+pub use migrations::MigrationReport;
@@ -0,0 +1,246 @@
+use crate::models::{Account, AppConfig, CustomHeadersConfig, SecretEncryption};
+use crate::utils::crypto::CryptoService;
+use crate::utils::{AppError, AppResult};
+
+pub const MODE_NONE: &str = "none";
+pub const MODE_KEYRING: &str = "keyring";
+pub const MODE_PASSWORD: &str = "password";
+
+/// 加密值的前缀，用于区分未启用加密时写入的明文
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// 分析缓存（快照、积分历史）的存放方式
+pub const CACHE_PLAINTEXT: &str = "plaintext";
+pub const CACHE_ENCRYPTED: &str = "encrypted";
+/// 仅保存在内存中，持久化数据始终为空
+pub const CACHE_MEMORY: &str = "memory";
+
+/// 用于校验主密码的固定明文
+const CHECK_PLAINTEXT: &str = "windsurf-account-manager";
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+fn encrypt_value(value: &mut String, crypto: &CryptoService) -> AppResult<()> {
+    if value.is_empty() || is_encrypted(value) {
+        return Ok(());
+    }
+    let ciphertext = crypto
+        .encrypt(value)
+        .map_err(|e| AppError::Config(format!("Failed to encrypt secret: {}", e)))?;
+    *value = format!("{}{}", ENCRYPTED_PREFIX, ciphertext);
+    Ok(())
+}
+
+/// 解密单个值，未加密的值保持不变，解密失败时返回 false
+fn decrypt_value(value: &mut String, crypto: &CryptoService) -> bool {
+    let plaintext = match value.strip_prefix(ENCRYPTED_PREFIX) {
+        Some(ciphertext) => crypto.decrypt(ciphertext),
+        None => return true,
+    };
+    match plaintext {
+        Ok(plaintext) => {
+            *value = plaintext;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// 加密账号中的凭据字段（密码、Token、Refresh Token、API Key），已加密的值保持不变
+pub fn encrypt_accounts(accounts: &mut [Account], crypto: &CryptoService) -> AppResult<()> {
+    for account in accounts {
+        encrypt_value(&mut account.password, crypto)?;
+        for value in [&mut account.token, &mut account.refresh_token, &mut account.windsurf_api_key]
+            .into_iter()
+            .flatten()
+        {
+            encrypt_value(value, crypto)?;
+        }
+    }
+    Ok(())
+}
+
+/// 解密账号中的凭据字段，返回无法解密的字段数量（保留密文）
+pub fn decrypt_accounts(accounts: &mut [Account], crypto: &CryptoService) -> usize {
+    let mut failed = 0;
+    for account in accounts {
+        if !decrypt_value(&mut account.password, crypto) {
+            failed += 1;
+        }
+        for value in [&mut account.token, &mut account.refresh_token, &mut account.windsurf_api_key]
+            .into_iter()
+            .flatten()
+        {
+            if !decrypt_value(value, crypto) {
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+fn header_values(config: &mut CustomHeadersConfig) -> impl Iterator<Item = &mut String> {
+    let endpoints = config.endpoints.values_mut().chain(config.accounts.values_mut());
+    config.global.values_mut().chain(endpoints.flat_map(|headers| headers.values_mut()))
+}
+
+/// 加密自定义请求头的值（租户 ID、网关密钥等），与账号凭据使用同一密钥
+pub fn encrypt_custom_headers(config: &mut CustomHeadersConfig, crypto: &CryptoService) -> AppResult<()> {
+    for value in header_values(config) {
+        encrypt_value(value, crypto)?;
+    }
+    Ok(())
+}
+
+/// 解密自定义请求头的值，返回无法解密的数量（保留密文）
+pub fn decrypt_custom_headers(config: &mut CustomHeadersConfig, crypto: &CryptoService) -> usize {
+    header_values(config).map(|value| decrypt_value(value, crypto)).filter(|ok| !ok).count()
+}
+
+/// 加密配置中的所有机密：账号凭据、自定义请求头与自动导出口令
+pub fn encrypt_config(config: &mut AppConfig, crypto: &CryptoService) -> AppResult<()> {
+    encrypt_accounts(&mut config.accounts, crypto)?;
+    encrypt_custom_headers(&mut config.settings.custom_headers, crypto)?;
+    if let Some(password) = &mut config.settings.auto_export.password {
+        encrypt_value(password, crypto)?;
+    }
+    Ok(())
+}
+
+/// 解密配置中的所有机密，返回无法解密的数量（保留密文）
+pub fn decrypt_config(config: &mut AppConfig, crypto: &CryptoService) -> usize {
+    let export_password_failed = config
+        .settings
+        .auto_export
+        .password
+        .as_mut()
+        .map(|password| !decrypt_value(password, crypto))
+        .unwrap_or(false);
+    decrypt_accounts(&mut config.accounts, crypto)
+        + decrypt_custom_headers(&mut config.settings.custom_headers, crypto)
+        + usize::from(export_password_failed)
+}
+
+/// 加密整段数据（用于缓存文件）
+pub fn encrypt_blob(data: &str, crypto: &CryptoService) -> AppResult<String> {
+    let mut value = data.to_string();
+    encrypt_value(&mut value, crypto)?;
+    Ok(value)
+}
+
+/// 解密整段数据，未加密的数据原样返回
+pub fn decrypt_blob(data: String, crypto: &CryptoService) -> Option<String> {
+    let mut value = data;
+    decrypt_value(&mut value, crypto).then_some(value)
+}
+
+/// 清除只在本次会话中保留的 Token（仅内存模式下写入存储前调用）
+pub fn strip_session_secrets(accounts: &mut [Account]) {
+    for account in accounts {
+        account.token = None;
+        account.token_expires_at = None;
+        account.refresh_token = None;
+        account.windsurf_api_key = None;
+    }
+}
+
+/// 生成主密码校验值
+pub fn make_check(crypto: &CryptoService) -> AppResult<String> {
+    crypto
+        .encrypt(CHECK_PLAINTEXT)
+        .map_err(|e| AppError::Config(format!("Failed to encrypt secret: {}", e)))
+}
+
+pub fn verify_check(crypto: &CryptoService, check: &str) -> bool {
+    crypto.decrypt(check).map(|p| p == CHECK_PLAINTEXT).unwrap_or(false)
+}
+
+/// 按配置打开密钥：keyring 模式从系统密钥链读取；未启用加密或需要主密码解锁时返回 None
+pub fn open_crypto(config: &SecretEncryption) -> AppResult<Option<CryptoService>> {
+    match config.mode.as_str() {
+        MODE_KEYRING => CryptoService::new()
+            .map(Some)
+            .map_err(|e| AppError::Config(format!("Failed to open keyring: {}", e))),
+        _ => Ok(None),
+    }
+}
+
+/// 由主密码打开密钥并校验
+pub fn unlock_with_password(config: &SecretEncryption, password: &str) -> AppResult<CryptoService> {
+    let (salt, check) = match (&config.salt, &config.check) {
+        (Some(salt), Some(check)) if config.mode == MODE_PASSWORD => (salt, check),
+        _ => return Err(AppError::Config("未设置主密码".to_string())),
+    };
+    let crypto = CryptoService::from_password(password, salt)
+        .map_err(|e| AppError::Config(format!("Failed to derive key: {}", e)))?;
+    if !verify_check(&crypto, check) {
+        return Err(AppError::Config("主密码错误".to_string()));
+    }
+    Ok(crypto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let crypto = CryptoService::from_password("master", &CryptoService::generate_salt()).unwrap();
+        let blob = encrypt_blob("[1,2]", &crypto).unwrap();
+        assert!(is_encrypted(&blob));
+        assert_eq!(decrypt_blob(blob, &crypto).as_deref(), Some("[1,2]"));
+        assert_eq!(decrypt_blob("[]".to_string(), &crypto).as_deref(), Some("[]"));
+    }
+
+    #[test]
+    fn test_encrypt_accounts_roundtrip() {
+        let salt = CryptoService::generate_salt();
+        let crypto = CryptoService::from_password("master", &salt).unwrap();
+        let config = SecretEncryption {
+            mode: MODE_PASSWORD.to_string(),
+            salt: Some(salt),
+            check: Some(make_check(&crypto).unwrap()),
+        };
+
+        let mut account = Account::new("a@example.com".to_string(), "pw".to_string(), "a".to_string(), Vec::new());
+        account.refresh_token = Some("rt".to_string());
+        account.windsurf_api_key = Some(String::new());
+        let mut accounts = vec![account];
+
+        encrypt_accounts(&mut accounts, &crypto).unwrap();
+        assert!(is_encrypted(&accounts[0].password));
+        assert!(is_encrypted(accounts[0].refresh_token.as_deref().unwrap()));
+        assert_eq!(accounts[0].windsurf_api_key.as_deref(), Some(""));
+        assert!(accounts[0].token.is_none());
+
+        assert!(unlock_with_password(&config, "wrong").is_err());
+        let unlocked = unlock_with_password(&config, "master").unwrap();
+        assert_eq!(decrypt_accounts(&mut accounts, &unlocked), 0);
+        assert_eq!(accounts[0].password, "pw");
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("rt"));
+
+        let mut headers = CustomHeadersConfig::default();
+        headers.global.insert("x-gateway-key".to_string(), "g-key".to_string());
+        headers.accounts.insert("id".to_string(), [("x-tenant-id".to_string(), "t".to_string())].into());
+        encrypt_custom_headers(&mut headers, &crypto).unwrap();
+        assert!(is_encrypted(&headers.global["x-gateway-key"]));
+        assert!(is_encrypted(&headers.accounts["id"]["x-tenant-id"]));
+        assert_eq!(decrypt_custom_headers(&mut headers, &unlocked), 0);
+        assert_eq!(headers.global["x-gateway-key"], "g-key");
+        assert_eq!(headers.accounts["id"]["x-tenant-id"], "t");
+    }
+
+    #[test]
+    fn test_encrypt_config_covers_export_password() {
+        let crypto = CryptoService::from_password("master", &CryptoService::generate_salt()).unwrap();
+        let mut config = AppConfig::default();
+        config.settings.auto_export.password = Some("export-pw".to_string());
+
+        encrypt_config(&mut config, &crypto).unwrap();
+        assert!(is_encrypted(config.settings.auto_export.password.as_deref().unwrap()));
+        assert_eq!(decrypt_config(&mut config, &crypto), 0);
+        assert_eq!(config.settings.auto_export.password.as_deref(), Some("export-pw"));
+    }
+}
@@ -0,0 +1,317 @@
+use super::journal::{self, RecoveryReport};
+use crate::utils::{AppError, AppResult};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 主配置（账号、分组、设置）
+pub const KEY_CONFIG: &str = "accounts";
+/// 操作日志
+pub const KEY_LOGS: &str = "logs";
+/// 分析数据历史快照
+pub const KEY_SNAPSHOTS: &str = "analytics_snapshots";
+/// 冻结的月度报告
+pub const KEY_REPORTS: &str = "monthly_reports";
+/// 积分用量历史
+pub const KEY_CREDITS: &str = "credits_history";
+/// 用量告警历史
+pub const KEY_ALERTS: &str = "alerts_history";
+/// 后台定时任务运行记录
+pub const KEY_JOBS: &str = "job_history";
+/// 账号配置的校验清单（每次保存配置时写入，启动时用于完整性检查）
+pub const KEY_INTEGRITY: &str = "integrity_manifest";
+
+/// DataStore 持久化的全部数据项
+pub const ALL_KEYS: [&str; 8] = [KEY_CONFIG, KEY_LOGS, KEY_SNAPSHOTS, KEY_REPORTS, KEY_CREDITS, KEY_ALERTS, KEY_JOBS, KEY_INTEGRITY];
+
+/// 后台进程与界面进程同步的数据项（校验清单由实际写入的后台进程生成）
+pub const SHARED_KEYS: [&str; 7] = [KEY_CONFIG, KEY_LOGS, KEY_SNAPSHOTS, KEY_REPORTS, KEY_CREDITS, KEY_ALERTS, KEY_JOBS];
+
+pub const BACKEND_JSON: &str = "json";
+pub const BACKEND_SQLITE: &str = "sqlite";
+pub const BACKEND_MEMORY: &str = "memory";
+
+/// 记录当前存储后端的标记文件（设置本身保存在存储中，启动时需要先知道后端）
+const BACKEND_MARKER_FILE: &str = "storage_backend";
+
+/// 环境变量覆盖存储后端（例如演示/测试时使用 memory，不落盘）
+const BACKEND_ENV: &str = "WAM_STORAGE_BACKEND";
+
+const SQLITE_FILE_NAME: &str = "data.sqlite3";
+
+/// DataStore 的持久化后端
+///
+/// 每个数据项以完整 JSON 文本读写，序列化与内存中的数据结构由 DataStore 负责
+pub trait Storage: Send + Sync {
+    /// 后端名称（json / sqlite / memory）
+    fn name(&self) -> &'static str;
+
+    /// 读取数据项，不存在时返回 None
+    fn load(&self, key: &str) -> AppResult<Option<String>>;
+
+    /// 覆盖写入数据项
+    fn save(&self, key: &str, data: &str) -> AppResult<()>;
+
+    /// 读取数据项的上一个版本（主数据损坏时使用），不支持的后端返回 None
+    fn load_backup(&self, _key: &str) -> AppResult<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// JSON 文件后端：每个数据项一个 `<key>.json`，带写入日志和 .backup 备份
+pub struct JsonFileStorage {
+    dir: PathBuf,
+}
+
+impl JsonFileStorage {
+    /// 打开数据目录，`recover` 为 true 时重放/回滚上次未完成的写入
+    ///
+    /// 后台进程正在运行时其写入可能尚未完成，此时由后台进程负责恢复，界面进程不应处理
+    pub fn open(dir: &Path, recover: bool) -> (Self, Vec<RecoveryReport>) {
+        let storage = Self { dir: dir.to_path_buf() };
+        if !recover {
+            return (storage, Vec::new());
+        }
+        let reports = ALL_KEYS
+            .iter()
+            .filter_map(|key| journal::recover(&storage.path(key), atomic_write))
+            .collect();
+        (storage, reports)
+    }
+
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn name(&self) -> &'static str {
+        BACKEND_JSON
+    }
+
+    fn load(&self, key: &str) -> AppResult<Option<String>> {
+        let path = self.path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?))
+    }
+
+    fn save(&self, key: &str, data: &str) -> AppResult<()> {
+        journaled_write(&self.path(key), data)?;
+        Ok(())
+    }
+
+    fn load_backup(&self, key: &str) -> AppResult<Option<String>> {
+        let path = self.path(key);
+        let backup_path = path.with_extension("json.backup");
+        if !backup_path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(&backup_path)?;
+        // 恢复成功后，将备份复制回主文件
+        fs::copy(&backup_path, &path)?;
+        Ok(Some(data))
+    }
+}
+
+/// 带日志的写入：先记录待写入数据，写入完成后删除日志
+fn journaled_write(path: &Path, data: &str) -> std::io::Result<()> {
+    let journal_path = journal::begin(path, data)?;
+    atomic_write(path, data)?;
+    journal::commit(&journal_path);
+    Ok(())
+}
+
+/// 原子写入：先写临时文件，创建备份，再重命名
+fn atomic_write(path: &Path, data: &str) -> std::io::Result<()> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // 使用时间戳+进程ID生成唯一临时文件名，避免并发冲突
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let pid = std::process::id();
+
+    let file_stem = path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("data");
+    let parent = path.parent().unwrap_or(path);
+
+    let temp_path = parent.join(format!("{}.tmp.{}.{}", file_stem, pid, timestamp));
+    let backup_path = path.with_extension("json.backup");
+
+    // 1. 先写入临时文件
+    fs::write(&temp_path, data)?;
+
+    // 2. 验证临时文件可以正常解析
+    let verify_data = fs::read_to_string(&temp_path)?;
+    if serde_json::from_str::<serde_json::Value>(&verify_data).is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Written data failed JSON validation"
+        ));
+    }
+
+    // 3. 如果原文件存在，创建备份
+    if path.exists() {
+        // 复制到备份文件（覆盖旧备份）
+        if let Err(e) = fs::copy(path, &backup_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    }
+
+    // 4. 原子重命名临时文件为目标文件
+    if let Err(e) = fs::rename(&temp_path, path) {
+        // 重命名失败时清理临时文件
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// SQLite 后端：所有数据项保存在 data.sqlite3 的 kv 表中，写入由 SQLite 事务保证原子性
+pub struct SqliteStorage {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        let conn = rusqlite::Connection::open(dir.join(SQLITE_FILE_NAME))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Self::init(conn)
+    }
+
+    /// 内存数据库（测试用）
+    #[cfg(test)]
+    pub fn open_in_memory() -> AppResult<Self> {
+        let conn = rusqlite::Connection::open_in_memory()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: rusqlite::Connection) -> AppResult<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS kv (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL,
+                 updated_at TEXT NOT NULL
+             );",
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn name(&self) -> &'static str {
+        BACKEND_SQLITE
+    }
+
+    fn load(&self, key: &str) -> AppResult<Option<String>> {
+        let conn = self.conn.lock().map_err(|e| AppError::Database(e.to_string()))?;
+        let result = conn.query_row("SELECT value FROM kv WHERE key = ?1", [key], |row| row.get(0));
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    fn save(&self, key: &str, data: &str) -> AppResult<()> {
+        let conn = self.conn.lock().map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "INSERT INTO kv (key, value, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            rusqlite::params![key, data, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// 内存后端：不落盘，用于测试和演示模式
+#[derive(Default)]
+pub struct MemoryStorage {
+    items: Mutex<HashMap<String, String>>,
+}
+
+impl Storage for MemoryStorage {
+    fn name(&self) -> &'static str {
+        BACKEND_MEMORY
+    }
+
+    fn load(&self, key: &str) -> AppResult<Option<String>> {
+        let items = self.items.lock().map_err(|e| AppError::Unknown(e.to_string()))?;
+        Ok(items.get(key).cloned())
+    }
+
+    fn save(&self, key: &str, data: &str) -> AppResult<()> {
+        let mut items = self.items.lock().map_err(|e| AppError::Unknown(e.to_string()))?;
+        items.insert(key.to_string(), data.to_string());
+        Ok(())
+    }
+}
+
+/// 读取当前选择的后端：环境变量优先，其次是数据目录中的标记文件，默认 JSON 文件
+pub fn selected_backend(dir: &Path) -> String {
+    if let Ok(backend) = std::env::var(BACKEND_ENV) {
+        if !backend.trim().is_empty() {
+            return backend.trim().to_lowercase();
+        }
+    }
+    fs::read_to_string(dir.join(BACKEND_MARKER_FILE))
+        .map(|s| s.trim().to_lowercase())
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| BACKEND_JSON.to_string())
+}
+
+/// 记录后端选择，下次启动生效
+pub fn write_backend_marker(dir: &Path, backend: &str) -> AppResult<()> {
+    fs::write(dir.join(BACKEND_MARKER_FILE), backend)?;
+    Ok(())
+}
+
+/// 按名称打开存储后端，同时返回 JSON 后端的启动恢复结果
+pub fn open_storage(dir: &Path, backend: &str, recover: bool) -> AppResult<(Arc<dyn Storage>, Vec<RecoveryReport>)> {
+    match backend {
+        BACKEND_JSON => {
+            let (storage, reports) = JsonFileStorage::open(dir, recover);
+            Ok((Arc::new(storage), reports))
+        }
+        BACKEND_SQLITE => Ok((Arc::new(SqliteStorage::open(dir)?), Vec::new())),
+        BACKEND_MEMORY => Ok((Arc::new(MemoryStorage::default()), Vec::new())),
+        other => Err(AppError::Config(format!("Unknown storage backend: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(storage: &dyn Storage) {
+        assert_eq!(storage.load(KEY_CONFIG).unwrap(), None);
+        storage.save(KEY_CONFIG, r#"{"accounts":[]}"#).unwrap();
+        storage.save(KEY_CONFIG, r#"{"accounts":[1]}"#).unwrap();
+        assert_eq!(storage.load(KEY_CONFIG).unwrap().as_deref(), Some(r#"{"accounts":[1]}"#));
+    }
+
+    #[test]
+    fn test_backends_round_trip() {
+        round_trip(&MemoryStorage::default());
+        round_trip(&SqliteStorage::open_in_memory().unwrap());
+
+        let dir = std::env::temp_dir().join(format!("storage_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (json, _) = JsonFileStorage::open(&dir, true);
+        round_trip(&json);
+        assert_eq!(json.load_backup(KEY_CONFIG).unwrap().as_deref(), Some(r#"{"accounts":[]}"#));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,171 @@
+use super::notification_service::{self, NotificationCategory};
+use crate::models::{Account, AlertEvent, AlertRule, AnalyticsData};
+use crate::repository::DataStore;
+use crate::utils::date_utils::{self, DayBucketing};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 计算规则使用的指标，数据中没有该指标时返回 None
+fn metric_value(metric: &str, account: &Account, data: &AnalyticsData, today: &str) -> Option<f64> {
+    let runs_today = || data.model_usage_details.iter().filter(|e| e.date == today);
+    match metric {
+        "daily_tokens" => Some(runs_today().map(|e| e.token_usage).sum::<i64>() as f64),
+        "daily_runs" => Some(runs_today().count() as f64),
+        "daily_accepted_lines" => Some(
+            data.daily_cascade_lines
+                .iter()
+                .filter(|d| d.date == today)
+                .map(|d| d.accepted_lines)
+                .sum::<i64>() as f64,
+        ),
+        "prompt_credits_used" => Some(data.credits_usage.prompt_credits_used as f64),
+        "credits_remaining" => match (account.used_quota, account.total_quota) {
+            (Some(used), Some(total)) => Some((total - used).max(0) as f64),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_triggered(rule: &AlertRule, value: f64) -> bool {
+    match rule.operator.as_str() {
+        "lt" => value < rule.threshold,
+        _ => value > rule.threshold,
+    }
+}
+
+/// 对单个账号检查所有适用的规则，冷却期内已告警的规则跳过
+fn evaluate(
+    rules: &[AlertRule],
+    account: &Account,
+    data: &AnalyticsData,
+    today: &str,
+    now: DateTime<Utc>,
+    last_alert: &dyn Fn(&str) -> Option<DateTime<Utc>>,
+) -> Vec<AlertEvent> {
+    let account_id = account.id.to_string();
+    rules
+        .iter()
+        .filter(|r| r.enabled)
+        .filter(|r| r.account_ids.is_empty() || r.account_ids.contains(&account_id))
+        .filter(|r| {
+            last_alert(&r.id)
+                .map(|last| now - last >= chrono::Duration::minutes(r.cooldown_minutes.max(0)))
+                .unwrap_or(true)
+        })
+        .filter_map(|rule| {
+            let value = metric_value(&rule.metric, account, data, today)?;
+            is_triggered(rule, value).then(|| AlertEvent {
+                id: Uuid::new_v4(),
+                rule_id: rule.id.clone(),
+                rule_name: rule.name.clone(),
+                account_id: account.id,
+                email: account.email.clone(),
+                metric: rule.metric.clone(),
+                operator: rule.operator.clone(),
+                value,
+                threshold: rule.threshold,
+                triggered_at: now,
+            })
+        })
+        .collect()
+}
+
+/// 拉取完整分析数据后在后台检查告警规则，触发的告警写入历史并通过 `usage-alert` 事件通知前端
+///
+/// 账号按 ID 重新读取，`credits_remaining` 使用拉取后存储中的最新额度
+pub async fn evaluate_after_refresh(store: Arc<DataStore>, account_id: Uuid, data: AnalyticsData) {
+    let settings = match store.get_settings().await {
+        Ok(settings) => settings,
+        Err(_) => return,
+    };
+    if settings.alert_rules.is_empty() {
+        return;
+    }
+    let account = match store.get_account(account_id).await {
+        Ok(account) => account,
+        Err(_) => return,
+    };
+
+    let bucketing = DayBucketing::from_settings(&settings.date_bucketing, settings.timezone.as_deref());
+    let now = Utc::now();
+    let today = date_utils::format_day(now.timestamp(), bucketing);
+
+    let mut last_alerts = Vec::new();
+    for rule in &settings.alert_rules {
+        last_alerts.push((rule.id.clone(), store.last_alert_at(&rule.id, account.id).await));
+    }
+    let last_alert = |rule_id: &str| {
+        last_alerts
+            .iter()
+            .find(|(id, _)| id == rule_id)
+            .and_then(|(_, at)| *at)
+    };
+
+    let events = evaluate(&settings.alert_rules, &account, &data, &today, now, &last_alert);
+    if events.is_empty() {
+        return;
+    }
+    for event in &events {
+        println!(
+            "[Alerts] {} triggered for {}: {} = {} ({} {})",
+            event.rule_name, event.email, event.metric, event.value, event.operator, event.threshold
+        );
+        notification_service::notify(
+            &store,
+            NotificationCategory::QuotaLow,
+            &format!("用量告警: {}", event.rule_name),
+            &format!("{} 的 {} 为 {}（阈值 {}）", event.email, event.metric, event.value, event.threshold),
+        )
+        .await;
+    }
+    if let Err(e) = store.add_alert_events(events.clone()).await {
+        println!("[Alerts] Failed to record alerts: {}", e);
+    }
+    store.emit_event("usage-alert", events);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModelUsageEntry;
+
+    #[test]
+    fn test_evaluate_rules() {
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        account.used_quota = Some(450);
+        account.total_quota = Some(500);
+        let run = |date: &str, tokens: i64| ModelUsageEntry::test_run("", "Claude", tokens).at(0, date);
+        let data = AnalyticsData {
+            model_usage_details: vec![run("2026-01-14", 800), run("2026-01-14", 400), run("2026-01-13", 5000)],
+            ..Default::default()
+        };
+        let rule = |id: &str, metric: &str, operator: &str, threshold: f64| AlertRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            metric: metric.to_string(),
+            operator: operator.to_string(),
+            threshold,
+            account_ids: Vec::new(),
+            enabled: true,
+            cooldown_minutes: 60,
+        };
+        let rules = vec![
+            rule("tokens", "daily_tokens", "gt", 1000.0),
+            rule("credits", "credits_remaining", "lt", 100.0),
+            rule("runs", "daily_runs", "gt", 5.0),
+        ];
+        let now = Utc::now();
+
+        let events = evaluate(&rules, &account, &data, "2026-01-14", now, &|_| None);
+        let fired: Vec<(&str, f64)> = events.iter().map(|e| (e.rule_id.as_str(), e.value)).collect();
+        assert_eq!(fired, vec![("tokens", 1200.0), ("credits", 50.0)]);
+
+        // 冷却期内不重复告警
+        let recent = |id: &str| (id == "tokens").then(|| now - chrono::Duration::minutes(10));
+        let events = evaluate(&rules, &account, &data, "2026-01-14", now, &recent);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule_id, "credits");
+    }
+}
@@ -0,0 +1,504 @@
+use crate::models::{
+    Account, AggregateAccountEntry, AggregateAnalyticsData, AnalyticsData, AnalyticsSnapshot, AnalyticsSummary,
+    CreditsUsage, DailyCascadeLinesCount, ModelUsageEntry, ModelUsageSummary, PeriodComparison, PeriodMetric,
+    RollingWindow, ToolUsageEntry, TopSession,
+};
+use crate::utils::date_utils::{self, DayBucketing};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// 分析数据的汇总计算：单账号实时/缓存视图、多账号合并视图与历史快照对比共用同一套逻辑，
+/// 避免不同视图中的数字不一致
+pub struct AnalyticsAggregator;
+
+impl AnalyticsAggregator {
+    /// 按模型汇总使用次数与 Token，百分比按使用次数计算
+    pub fn model_summary(details: &[ModelUsageEntry]) -> Vec<ModelUsageSummary> {
+        let mut summary_map: HashMap<String, (i64, i64)> = HashMap::new();
+
+        for entry in details {
+            let counter = summary_map.entry(entry.model_name.clone()).or_insert((0, 0));
+            counter.0 += entry.session_count;
+            counter.1 += entry.token_usage;
+        }
+
+        let total_count: i64 = summary_map.values().map(|(count, _)| count).sum();
+
+        let mut summary: Vec<ModelUsageSummary> = summary_map
+            .into_iter()
+            .map(|(model_name, (total_count_val, total_tokens))| {
+                let percentage = if total_count > 0 {
+                    (total_count_val as f64 / total_count as f64) * 100.0
+                } else {
+                    0.0
+                };
+
+                ModelUsageSummary {
+                    model_name,
+                    total_count: total_count_val,
+                    total_tokens,
+                    percentage,
+                }
+            })
+            .collect();
+
+        // 按使用次数降序排序
+        summary.sort_by_key(|s| Reverse(s.total_count));
+
+        summary
+    }
+
+    /// 计算总体统计摘要，最近 7/30 天的窗口按 `bucketing` 划分日期，与每日视图一致
+    pub fn summary(
+        cascade_lines: &[DailyCascadeLinesCount],
+        tool_usage: &[ToolUsageEntry],
+        model_details: &[ModelUsageEntry],
+        model_summary: &[ModelUsageSummary],
+        bucketing: DayBucketing,
+    ) -> AnalyticsSummary {
+        // 计算总代码行数
+        let total_accepted_lines: i64 = cascade_lines.iter().map(|s| s.accepted_lines).sum();
+        let total_suggested_lines: i64 = cascade_lines.iter().map(|s| s.suggested_lines).sum();
+
+        let avg_daily_accepted_lines = if !cascade_lines.is_empty() {
+            total_accepted_lines as f64 / cascade_lines.len() as f64
+        } else {
+            0.0
+        };
+
+        // 找到峰值日期（接受代码行数最多的一天）
+        let (peak_date, peak_lines) = cascade_lines
+            .iter()
+            .max_by_key(|s| s.accepted_lines)
+            .map(|s| (s.date.clone(), s.accepted_lines))
+            .unwrap_or_else(|| (String::new(), 0));
+
+        let total_tool_usage: i64 = tool_usage.iter().map(|t| t.count).sum();
+        let total_sessions: i64 = model_details.iter().map(|m| m.session_count).sum();
+        let total_tokens: i64 = model_details.iter().map(|m| m.token_usage).sum();
+
+        let primary_model = model_summary
+            .first()
+            .map(|m| m.model_name.clone())
+            .unwrap_or_else(|| String::from("N/A"));
+
+        let primary_tool = tool_usage
+            .first()
+            .map(|t| t.tool_name.clone())
+            .unwrap_or_else(|| String::from("N/A"));
+
+        let now = Utc::now().timestamp();
+
+        AnalyticsSummary {
+            total_accepted_lines,
+            total_suggested_lines,
+            avg_daily_accepted_lines,
+            peak_date,
+            peak_lines,
+            total_tool_usage,
+            total_sessions,
+            total_tokens,
+            primary_model,
+            primary_tool,
+            last_7_days: rolling_window(cascade_lines, model_details, 7, now, bucketing),
+            last_30_days: rolling_window(cascade_lines, model_details, 30, now, bucketing),
+            top_sessions: top_sessions(model_details, TOP_SESSIONS),
+        }
+    }
+
+    /// 重新计算模型汇总与总体统计（缓存的快照中最近 7/30 天的窗口相对拉取时间，需要按当前时间重算）
+    pub fn recompute(data: &mut AnalyticsData, bucketing: DayBucketing) {
+        data.model_usage_summary = Self::model_summary(&data.model_usage_details);
+        data.summary = Self::summary(
+            &data.daily_cascade_lines,
+            &data.tool_usage,
+            &data.model_usage_details,
+            &data.model_usage_summary,
+            bucketing,
+        );
+    }
+
+    /// 合并多个快照中的 Cascade 运行记录：按 会话/时间/模型 去重（后面的快照覆盖前面的），按时间升序
+    pub fn merge_runs<'a>(snapshots: impl IntoIterator<Item = &'a AnalyticsSnapshot>) -> Vec<ModelUsageEntry> {
+        let mut runs: HashMap<(String, i64, String), ModelUsageEntry> = HashMap::new();
+        for snapshot in snapshots {
+            for entry in &snapshot.data.model_usage_details {
+                runs.insert((entry.session_id.clone(), entry.timestamp, entry.model_name.clone()), entry.clone());
+            }
+        }
+        let mut runs: Vec<ModelUsageEntry> = runs.into_values().collect();
+        runs.sort_by_key(|e| e.timestamp);
+        runs
+    }
+
+    /// 合并多个账号的分析数据：按日期/工具/模型累加，再重新计算百分比和摘要
+    pub fn aggregate(sources: &[(Account, DateTime<Utc>, AnalyticsData)], bucketing: DayBucketing) -> AggregateAnalyticsData {
+        let mut lines_by_day: HashMap<String, DailyCascadeLinesCount> = HashMap::new();
+        let mut tool_counts: HashMap<String, i64> = HashMap::new();
+        let mut model_details: Vec<ModelUsageEntry> = Vec::new();
+        let mut credits_usage = CreditsUsage::default();
+        let mut accounts = Vec::new();
+
+        for (account, captured_at, data) in sources {
+            for day in &data.daily_cascade_lines {
+                let merged = lines_by_day.entry(day.date.clone()).or_insert_with(|| DailyCascadeLinesCount {
+                    timestamp: day.timestamp,
+                    date: day.date.clone(),
+                    accepted_lines: 0,
+                    suggested_lines: 0,
+                });
+                merged.accepted_lines += day.accepted_lines;
+                merged.suggested_lines += day.suggested_lines;
+            }
+            for tool in &data.tool_usage {
+                *tool_counts.entry(tool.tool_name.clone()).or_insert(0) += tool.count;
+            }
+            model_details.extend(data.model_usage_details.iter().cloned());
+
+            let credits = &data.credits_usage;
+            credits_usage.conversations += credits.conversations;
+            credits_usage.prompt_credits_used += credits.prompt_credits_used;
+            credits_usage.commands += credits.commands;
+            credits_usage.workflows += credits.workflows;
+            credits_usage.memories += credits.memories;
+            credits_usage.terminal_messages += credits.terminal_messages;
+
+            accounts.push(AggregateAccountEntry {
+                account_id: account.id,
+                email: account.email.clone(),
+                captured_at: *captured_at,
+                total_accepted_lines: data.summary.total_accepted_lines,
+                total_tokens: data.summary.total_tokens,
+            });
+        }
+
+        let mut daily_cascade_lines: Vec<DailyCascadeLinesCount> = lines_by_day.into_values().collect();
+        daily_cascade_lines.sort_by(|a, b| a.date.cmp(&b.date));
+
+        let total_tools: i64 = tool_counts.values().sum();
+        let mut tool_usage: Vec<ToolUsageEntry> = tool_counts
+            .into_iter()
+            .map(|(tool_name, count)| ToolUsageEntry {
+                tool_name,
+                count,
+                percentage: if total_tools > 0 {
+                    count as f64 / total_tools as f64 * 100.0
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        tool_usage.sort_by_key(|t| Reverse(t.count));
+
+        let model_usage_summary = Self::model_summary(&model_details);
+        let summary = Self::summary(&daily_cascade_lines, &tool_usage, &model_details, &model_usage_summary, bucketing);
+
+        AggregateAnalyticsData {
+            accounts,
+            daily_cascade_lines,
+            tool_usage,
+            model_usage_summary,
+            credits_usage,
+            summary,
+        }
+    }
+
+    /// 计算 [today - days + 1, today] 与之前 `days` 天的指标对比；变化不超过 5% 视为持平
+    pub fn compare_periods(snapshots: &[AnalyticsSnapshot], today: NaiveDate, days: i64) -> PeriodComparison {
+        let days = days.max(1);
+        let current_start = today - Duration::days(days - 1);
+        let previous_end = current_start - Duration::days(1);
+        let previous_start = current_start - Duration::days(days);
+        let format = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+        let (current_range, previous_range) = (
+            (format(current_start), format(today)),
+            (format(previous_start), format(previous_end)),
+        );
+
+        let daily = merge_daily_metrics(snapshots);
+        let total = |(start, end): &(String, String)| {
+            daily
+                .iter()
+                .filter(|(date, _)| date.as_str() >= start.as_str() && date.as_str() <= end.as_str())
+                .fold(DailyMetrics::default(), |mut sum, (_, m)| {
+                    sum.accepted_lines += m.accepted_lines;
+                    sum.suggested_lines += m.suggested_lines;
+                    sum.cascade_runs += m.cascade_runs;
+                    sum.tokens += m.tokens;
+                    sum.completions_accepted += m.completions_accepted;
+                    sum.chats_sent += m.chats_sent;
+                    sum.cascade_messages += m.cascade_messages;
+                    sum
+                })
+        };
+        let (current, previous) = (total(&current_range), total(&previous_range));
+
+        let metric = |name: &str, current: i64, previous: i64| {
+            let change = current - previous;
+            let change_percent = (previous > 0).then(|| change as f64 / previous as f64 * 100.0);
+            let trend = match change_percent {
+                Some(p) if p > 5.0 => "rising",
+                Some(p) if p < -5.0 => "falling",
+                None if current > 0 => "rising",
+                _ => "stable",
+            };
+            PeriodMetric {
+                metric: name.to_string(),
+                current,
+                previous,
+                change,
+                change_percent,
+                trend: trend.to_string(),
+            }
+        };
+
+        PeriodComparison {
+            days,
+            metrics: vec![
+                metric("accepted_lines", current.accepted_lines, previous.accepted_lines),
+                metric("suggested_lines", current.suggested_lines, previous.suggested_lines),
+                metric("cascade_runs", current.cascade_runs, previous.cascade_runs),
+                metric("tokens", current.tokens, previous.tokens),
+                metric("completions_accepted", current.completions_accepted, previous.completions_accepted),
+                metric("chats_sent", current.chats_sent, previous.chats_sent),
+                metric("cascade_messages", current.cascade_messages, previous.cascade_messages),
+            ],
+            current_start: current_range.0,
+            current_end: current_range.1,
+            previous_start: previous_range.0,
+            previous_end: previous_range.1,
+        }
+    }
+}
+
+/// 摘要中保留的会话排名数量
+const TOP_SESSIONS: usize = 5;
+
+/// 按会话汇总 Token 消耗，取前 `limit` 个
+fn top_sessions(model_details: &[ModelUsageEntry], limit: usize) -> Vec<TopSession> {
+    // 会话 ID -> (最早时间戳, 日期, 各模型 Token)
+    let mut sessions: HashMap<&str, (i64, &str, HashMap<&str, i64>)> = HashMap::new();
+    for entry in model_details.iter().filter(|e| !e.session_id.is_empty()) {
+        let session = sessions
+            .entry(entry.session_id.as_str())
+            .or_insert((entry.timestamp, entry.date.as_str(), HashMap::new()));
+        if entry.timestamp < session.0 {
+            session.0 = entry.timestamp;
+            session.1 = entry.date.as_str();
+        }
+        *session.2.entry(entry.model_name.as_str()).or_insert(0) += entry.token_usage;
+    }
+
+    let mut ranked: Vec<TopSession> = sessions
+        .into_iter()
+        .map(|(session_id, (_, date, models))| TopSession {
+            session_id: session_id.to_string(),
+            model: models
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(model, _)| model.to_string())
+                .unwrap_or_default(),
+            date: date.to_string(),
+            tokens: models.values().sum(),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.tokens.cmp(&a.tokens).then(a.session_id.cmp(&b.session_id)));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// 计算截至 `now` 所在日的最近 `days` 天汇总，日期按 `bucketing` 归属
+fn rolling_window(
+    cascade_lines: &[DailyCascadeLinesCount],
+    model_details: &[ModelUsageEntry],
+    days: i64,
+    now: i64,
+    bucketing: DayBucketing,
+) -> RollingWindow {
+    let today = date_utils::format_day(now, bucketing);
+    let since = NaiveDate::parse_from_str(&today, "%Y-%m-%d")
+        .map(|d| (d - Duration::days(days - 1)).format("%Y-%m-%d").to_string())
+        .unwrap_or(today);
+    // YYYY-MM-DD 可直接按字符串比较
+    let in_window = |timestamp: i64| {
+        let day = date_utils::format_day(timestamp, bucketing);
+        day != "Unknown" && day >= since
+    };
+
+    let lines: Vec<&DailyCascadeLinesCount> = cascade_lines.iter().filter(|d| in_window(d.timestamp)).collect();
+    let runs: Vec<&ModelUsageEntry> = model_details.iter().filter(|m| in_window(m.timestamp)).collect();
+
+    let accepted_lines: i64 = lines.iter().map(|d| d.accepted_lines).sum();
+    let tokens: i64 = runs.iter().map(|m| m.token_usage).sum();
+
+    RollingWindow {
+        days,
+        accepted_lines,
+        suggested_lines: lines.iter().map(|d| d.suggested_lines).sum(),
+        avg_daily_accepted_lines: accepted_lines as f64 / days as f64,
+        sessions: runs.iter().map(|m| m.session_count).sum(),
+        tokens,
+        avg_daily_tokens: tokens as f64 / days as f64,
+    }
+}
+
+/// 按日期合并的各项指标
+#[derive(Debug, Clone, Copy, Default)]
+struct DailyMetrics {
+    accepted_lines: i64,
+    suggested_lines: i64,
+    cascade_runs: i64,
+    tokens: i64,
+    completions_accepted: i64,
+    chats_sent: i64,
+    cascade_messages: i64,
+}
+
+/// 合并所有快照的每日数据：按日期的统计以最新快照为准，Cascade 运行按会话去重后按日期汇总
+fn merge_daily_metrics(snapshots: &[AnalyticsSnapshot]) -> HashMap<String, DailyMetrics> {
+    let mut days: HashMap<String, DailyMetrics> = HashMap::new();
+
+    // 快照按日期升序，后面的覆盖前面的
+    for snapshot in snapshots {
+        let data = &snapshot.data;
+        for day in &data.daily_cascade_lines {
+            let metrics = days.entry(day.date.clone()).or_default();
+            metrics.accepted_lines = day.accepted_lines;
+            metrics.suggested_lines = day.suggested_lines;
+        }
+        for day in &data.completions_by_day {
+            days.entry(day.date.clone()).or_default().completions_accepted = day.statistics.num_acceptances;
+        }
+        for day in &data.chats_by_day {
+            days.entry(day.date.clone()).or_default().chats_sent = day.stats.chats_sent;
+        }
+        for day in &data.cascade_stats.by_day {
+            days.entry(day.date.clone()).or_default().cascade_messages = day.messages_sent;
+        }
+    }
+
+    for entry in AnalyticsAggregator::merge_runs(snapshots) {
+        let metrics = days.entry(entry.date).or_default();
+        metrics.cascade_runs += 1;
+        metrics.tokens += entry.token_usage;
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_analytics() {
+        let account = |email: &str| Account::new(email.to_string(), String::new(), email.to_string(), Vec::new());
+        let data = |lines: i64, tool: &str, model: &str| {
+            let mut data = AnalyticsData::default();
+            data.daily_cascade_lines.push(DailyCascadeLinesCount {
+                timestamp: 0,
+                date: "2026-01-01".to_string(),
+                accepted_lines: lines,
+                suggested_lines: lines * 2,
+            });
+            data.tool_usage.push(ToolUsageEntry {
+                tool_name: tool.to_string(),
+                count: 1,
+                percentage: 100.0,
+            });
+            data.model_usage_details.push(ModelUsageEntry::test_run(&format!("session-{}", model), model, 100));
+            data
+        };
+        let now = Utc::now();
+        let sources = vec![
+            (account("a@example.com"), now, data(10, "edit", "Claude")),
+            (account("b@example.com"), now, data(5, "edit", "GPT-4o")),
+        ];
+        let aggregate = AnalyticsAggregator::aggregate(&sources, DayBucketing::Utc);
+        assert_eq!(aggregate.accounts.len(), 2);
+        assert_eq!(aggregate.daily_cascade_lines.len(), 1);
+        assert_eq!(aggregate.daily_cascade_lines[0].accepted_lines, 15);
+        assert_eq!(aggregate.tool_usage.len(), 1);
+        assert_eq!(aggregate.tool_usage[0].count, 2);
+        assert_eq!(aggregate.model_usage_summary.len(), 2);
+        assert_eq!(aggregate.model_usage_summary[0].percentage, 50.0);
+        assert_eq!(aggregate.summary.total_tokens, 200);
+    }
+
+    #[test]
+    fn test_compare_periods() {
+        let lines = |date: &str, accepted: i64| DailyCascadeLinesCount {
+            timestamp: 0,
+            date: date.to_string(),
+            accepted_lines: accepted,
+            suggested_lines: accepted * 2,
+        };
+        let snapshot = |date: &str, daily_cascade_lines: Vec<DailyCascadeLinesCount>| {
+            AnalyticsSnapshot::test_snapshot(uuid::Uuid::nil(), date, AnalyticsData { daily_cascade_lines, ..Default::default() })
+        };
+        // 后一个快照中的 01-10 覆盖前一个快照
+        let snapshots = vec![
+            snapshot("2026-01-10", vec![lines("2026-01-02", 100), lines("2026-01-10", 10)]),
+            snapshot("2026-01-14", vec![lines("2026-01-10", 40), lines("2026-01-14", 110)]),
+        ];
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 14).unwrap();
+        let comparison = AnalyticsAggregator::compare_periods(&snapshots, today, 7);
+        assert_eq!((comparison.current_start.as_str(), comparison.previous_start.as_str()), ("2026-01-08", "2026-01-01"));
+        let accepted = &comparison.metrics[0];
+        assert_eq!((accepted.current, accepted.previous, accepted.change), (150, 100, 50));
+        assert_eq!(accepted.change_percent, Some(50.0));
+        assert_eq!(accepted.trend, "rising");
+        assert_eq!(comparison.metrics[2].trend, "stable");
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let day = 86_400;
+        let now = 100 * day + 3600;
+        let lines: Vec<DailyCascadeLinesCount> = [100, 97, 90]
+            .iter()
+            .map(|d| DailyCascadeLinesCount {
+                timestamp: d * day,
+                date: String::new(),
+                accepted_lines: 14,
+                suggested_lines: 20,
+            })
+            .collect();
+
+        let week = rolling_window(&lines, &[], 7, now, DayBucketing::Utc);
+        assert_eq!(week.accepted_lines, 28);
+        assert_eq!(week.avg_daily_accepted_lines, 4.0);
+
+        // UTC 第 93 天 23:00 在上海已是第 94 天，落入按时区划分的 7 天窗口
+        let late = vec![DailyCascadeLinesCount { timestamp: 94 * day - 3600, date: String::new(), accepted_lines: 7, suggested_lines: 7 }];
+        assert_eq!(rolling_window(&late, &[], 7, now, DayBucketing::Utc).accepted_lines, 0);
+        let shanghai = DayBucketing::Local(date_utils::parse_timezone(Some("Asia/Shanghai")));
+        assert_eq!(rolling_window(&late, &[], 7, now, shanghai).accepted_lines, 7);
+
+        let month = rolling_window(&lines, &[], 30, now, DayBucketing::Utc);
+        assert_eq!(month.accepted_lines, 42);
+        assert_eq!(month.suggested_lines, 60);
+    }
+
+    #[test]
+    fn test_top_sessions() {
+        let run = |session: &str, model: &str, timestamp: i64, tokens: i64| {
+            ModelUsageEntry::test_run(session, model, tokens).at(timestamp, &format!("day-{}", timestamp))
+        };
+        let runs = vec![
+            run("a", "Claude", 2, 100),
+            run("a", "GPT-4o", 1, 300),
+            run("b", "Claude", 3, 200),
+            run("c", "Claude", 4, 50),
+        ];
+
+        let top = top_sessions(&runs, 2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].session_id, "a");
+        assert_eq!(top[0].tokens, 400);
+        assert_eq!(top[0].model, "GPT-4o");
+        assert_eq!(top[0].date, "day-1");
+        assert_eq!(top[1].session_id, "b");
+    }
+}
@@ -1,17 +1,125 @@
-use crate::utils::{AppError, AppResult};
+use crate::models::AnalyticsRequestPreset;
+use crate::utils::proto_builder::ProtoBuilder;
+use super::transport::{HttpTransport, Transport};
+use crate::utils::{AppError, AppResult, date_utils, proto_debug, retry};
 use std::sync::Arc;
+use uuid::Uuid;
 
 const WINDSURF_BASE_URL: &str = "https://web-backend.windsurf.com";
 
+/// 内置请求预设：official（模仿官网请求）、full（全部查询）、minimal（无时间戳，仅 cascade_runs）
+pub fn builtin_presets() -> Vec<AnalyticsRequestPreset> {
+    let preset = |name: &str, query_fields: &[u32], time_zone_fields: &[u32], include_timestamps: bool, end_nanos: Option<i32>| {
+        AnalyticsRequestPreset {
+            name: name.to_string(),
+            query_fields: query_fields.to_vec(),
+            time_zone_fields: time_zone_fields.to_vec(),
+            timezone: None,
+            include_timestamps,
+            end_nanos,
+            days: 30,
+        }
+    };
+    vec![
+        preset("official", &[20, 23, 24, 25, 31], &[], true, Some(999999000)),
+        preset("full", &[20, 23, 24, 25, 31, 1, 3, 12], &[2, 10], true, None),
+        preset("minimal", &[25], &[], false, None),
+    ]
+}
+
+/// 按名称查找预设，自定义预设优先于同名的内置预设
+pub fn find_preset(custom: &[AnalyticsRequestPreset], name: &str) -> Option<AnalyticsRequestPreset> {
+    custom
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .or_else(|| builtin_presets().into_iter().find(|p| p.name == name))
+}
+
+/// 预设是否包含完整请求（内置 full 预设）的全部查询，只有完整的数据才能写入快照
+pub fn is_complete_preset(preset: &AnalyticsRequestPreset) -> bool {
+    let full = builtin_presets().into_iter().find(|p| p.name == "full").unwrap_or_else(|| preset.clone());
+    preset.include_timestamps
+        && full.query_fields.iter().all(|f| preset.query_fields.contains(f))
+        && full.time_zone_fields.iter().all(|f| preset.time_zone_fields.contains(f))
+}
+
+/// 按预设构建 GetAnalytics 请求体（字段含义见 build_get_analytics_body）
+fn build_preset_body(preset: &AnalyticsRequestPreset, start_timestamp: i64, end_timestamp: i64, timezone: &str) -> Vec<u8> {
+    let mut body = ProtoBuilder::new();
+    for field in &preset.query_fields {
+        body = body.message(2, ProtoBuilder::new().message(*field, ProtoBuilder::new()));
+    }
+    for field in &preset.time_zone_fields {
+        let query = ProtoBuilder::new().string(1, timezone);
+        body = body.message(2, ProtoBuilder::new().message(*field, query));
+    }
+    if preset.include_timestamps {
+        body = body.message(3, ProtoBuilder::new().varint(1, start_timestamp as u64));
+        let mut end = ProtoBuilder::new().varint(1, end_timestamp as u64);
+        if let Some(nanos) = preset.end_nanos.filter(|n| *n != 0) {
+            end = end.varint(2, nanos as u64);
+        }
+        body = body.message(4, end);
+    }
+    body.build()
+}
+
+/// GetAnalytics 的降级策略，按顺序尝试直到有一个成功
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalyticsFallback {
+    /// 完整请求（团队账户包含 percent_code_written）
+    Full,
+    /// 团队请求失败后去掉 percent_code_written
+    TeamFallback,
+    /// 仅请求 cascade 数据
+    CascadeOnly,
+    /// 无时间戳请求（模仿官网）
+    NoTimestamp,
+}
+
+impl AnalyticsFallback {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnalyticsFallback::Full => "full",
+            AnalyticsFallback::TeamFallback => "team_fallback",
+            AnalyticsFallback::CascadeOnly => "cascade_only",
+            AnalyticsFallback::NoTimestamp => "no_timestamp",
+        }
+    }
+
+    /// 是否为完整数据（降级到仅 cascade 或无时间戳的请求缺少部分字段）
+    pub fn is_complete(self) -> bool {
+        matches!(self, AnalyticsFallback::Full | AnalyticsFallback::TeamFallback)
+    }
+
+    /// 降级顺序：只有团队账户需要先去掉 percent_code_written 再降级
+    pub fn ladder(is_team: bool) -> Vec<AnalyticsFallback> {
+        let mut ladder = vec![AnalyticsFallback::Full];
+        if is_team {
+            ladder.push(AnalyticsFallback::TeamFallback);
+        }
+        ladder.extend([AnalyticsFallback::CascadeOnly, AnalyticsFallback::NoTimestamp]);
+        ladder
+    }
+}
+
 pub struct AnalyticsService {
     client: Arc<reqwest::Client>,
+    transport: Arc<dyn Transport>,
 }
 
 impl AnalyticsService {
-    pub fn new() -> Self {
-        // 使用全局共享的 HTTP 客户端，避免每次请求都创建新实例
+    /// 以指定账号的身份发送请求（附加该账号的自定义请求头）
+    pub fn for_account(account_id: Uuid) -> Self {
+        Self::with_transport(Arc::new(HttpTransport::for_account(account_id)))
+    }
+
+    /// 使用指定的 HTTP 层（测试中传入 `MockTransport` 回放样例）
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
         Self {
             client: super::get_http_client(),
+            transport,
         }
     }
 
@@ -38,7 +146,7 @@ impl AnalyticsService {
     /// - 29: daily_active_user_counts
     /// - 31: cascade_summary (官网请求2用的，包含 conversations, credits, commands 等)
     /// 构建完整请求体（包含所有查询类型）
-    fn build_get_analytics_body(&self, start_timestamp: i64, end_timestamp: i64, is_team: bool) -> Vec<u8> {
+    fn build_get_analytics_body(&self, start_timestamp: i64, end_timestamp: i64, is_team: bool, timezone: &str) -> Vec<u8> {
         let mut body = Vec::new();
 
         // ===== Cascade 相关查询 =====
@@ -73,7 +181,7 @@ impl AnalyticsService {
         body.extend_from_slice(&[0x12, 0x02, 0x62, 0x00]);
         
         // ===== 带 time_zone 参数的查询 =====
-        let tz = b"Asia/Shanghai";
+        let tz = timezone.as_bytes();
         let tz_msg_len = 2 + tz.len(); // 0x0A + len + tz_bytes
         let query_msg_len = 2 + tz_msg_len; // field_tag + len + inner_msg
         
@@ -200,16 +308,18 @@ impl AnalyticsService {
     /// 调用 GetAnalytics API
     /// auth_token: Firebase JWT Token (用于 x-auth-token 头)
     /// is_team: 是否是团队账户，团队账户会额外请求 percent_code_written
-    pub async fn get_analytics(&self, auth_token: &str, start_timestamp: i64, end_timestamp: i64, is_team: bool) -> AppResult<Vec<u8>> {
+    /// timezone: 按天统计使用的时区（IANA 名称），为空时使用系统时区
+    pub async fn get_analytics(&self, auth_token: &str, start_timestamp: i64, end_timestamp: i64, is_team: bool, timezone: Option<&str>) -> AppResult<Vec<u8>> {
         let url = format!("{}/exa.user_analytics_pb.UserAnalyticsService/GetAnalytics", WINDSURF_BASE_URL);
         
-        let body = self.build_get_analytics_body(start_timestamp, end_timestamp, is_team);
+        let timezone = date_utils::resolve_timezone_name(timezone);
+        let body = self.build_get_analytics_body(start_timestamp, end_timestamp, is_team, &timezone);
 
-        println!("[GetAnalytics] Calling API with time range: {} - {}", start_timestamp, end_timestamp);
-        println!("[GetAnalytics] Request body length: {} bytes", body.len());
-        println!("[GetAnalytics] Request body hex: {}", body.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        crate::proto_log!("[GetAnalytics] Calling API with time range: {} - {}", start_timestamp, end_timestamp);
+        crate::proto_log!("[GetAnalytics] Request body length: {} bytes", body.len());
+        proto_debug::log_body("GetAnalytics", &body);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .header("accept", "*/*")
             .header("accept-language", "zh-CN,zh;q=0.9")
@@ -225,10 +335,8 @@ impl AnalyticsService {
             .header("sec-fetch-site", "same-site")
             .header("x-auth-token", auth_token)
             .header("Referer", "https://windsurf.com/")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| AppError::Api(format!("Request failed: {}", e)))?;
+            .body(body);
+        let response = self.transport.send("GetAnalytics", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -236,7 +344,7 @@ impl AnalyticsService {
             .to_vec();
         
         println!("[GetAnalytics] Response status: {}", status_code);
-        println!("[GetAnalytics] Response body length: {} bytes", response_body.len());
+        crate::proto_log!("[GetAnalytics] Response body length: {} bytes", response_body.len());
 
         if status_code != 200 {
             // 尝试解析错误响应
@@ -250,16 +358,58 @@ impl AnalyticsService {
         Ok(response_body)
     }
 
+    /// 使用请求预设调用 GetAnalytics API，时间范围为 end_timestamp 之前的 `preset.days` 天
+    pub async fn get_analytics_with_preset(
+        &self,
+        auth_token: &str,
+        preset: &AnalyticsRequestPreset,
+        end_timestamp: i64,
+        timezone: Option<&str>,
+    ) -> AppResult<Vec<u8>> {
+        let url = format!("{}/exa.user_analytics_pb.UserAnalyticsService/GetAnalytics", WINDSURF_BASE_URL);
+
+        let timezone = date_utils::resolve_timezone_name(preset.timezone.as_deref().or(timezone));
+        let start_timestamp = end_timestamp - preset.days.max(1) * 86400;
+        let body = build_preset_body(preset, start_timestamp, end_timestamp, &timezone);
+
+        crate::proto_log!("[GetAnalytics-Preset] Calling API with preset '{}'", preset.name);
+        proto_debug::log_body(&format!("GetAnalytics-Preset {}", preset.name), &body);
+
+        let request = self.client
+            .post(&url)
+            .header("accept", "*/*")
+            .header("connect-protocol-version", "1")
+            .header("content-type", "application/proto")
+            .header("x-auth-token", auth_token)
+            .header("Referer", "https://windsurf.com/")
+            .body(body);
+        let response = self.transport.send("GetAnalytics", request).await?;
+
+        let status_code = response.status().as_u16();
+        let response_body = response.bytes().await
+            .map_err(|e| AppError::Api(format!("Failed to read response: {}", e)))?
+            .to_vec();
+
+        println!("[GetAnalytics-Preset] Preset '{}' response status: {}", preset.name, status_code);
+
+        if status_code != 200 {
+            let error_text = String::from_utf8_lossy(&response_body);
+            return Err(AppError::Api(format!("API returned status code {}: {}", status_code, error_text)));
+        }
+
+        Ok(response_body)
+    }
+
     /// 降级请求：仅获取 cascade 数据（当完整请求失败时使用）
     pub async fn get_analytics_cascade_only(&self, auth_token: &str, start_timestamp: i64, end_timestamp: i64) -> AppResult<Vec<u8>> {
         let url = format!("{}/exa.user_analytics_pb.UserAnalyticsService/GetAnalytics", WINDSURF_BASE_URL);
         
         let body = self.build_cascade_only_body(start_timestamp, end_timestamp);
 
-        println!("[GetAnalytics-CascadeOnly] Fallback request with cascade-only queries");
-        println!("[GetAnalytics-CascadeOnly] Request body length: {} bytes", body.len());
+        crate::proto_log!("[GetAnalytics-CascadeOnly] Fallback request with cascade-only queries");
+        crate::proto_log!("[GetAnalytics-CascadeOnly] Request body length: {} bytes", body.len());
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .header("accept", "*/*")
             .header("accept-language", "zh-CN,zh;q=0.9")
@@ -275,10 +425,8 @@ impl AnalyticsService {
             .header("sec-fetch-site", "same-site")
             .header("x-auth-token", auth_token)
             .header("Referer", "https://windsurf.com/")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| AppError::Api(format!("Request failed: {}", e)))?;
+            .body(body);
+        let response = self.transport.send("GetAnalytics", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -304,10 +452,10 @@ impl AnalyticsService {
         
         let body = self.build_no_timestamp_body();
 
-        println!("[GetAnalytics-NoTimestamp] Final fallback with no timestamp");
-        println!("[GetAnalytics-NoTimestamp] Request body: {:?}", body.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        crate::proto_log!("[GetAnalytics-NoTimestamp] Final fallback with no timestamp");
+        proto_debug::log_body("GetAnalytics-NoTimestamp", &body);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .header("accept", "*/*")
             .header("accept-language", "zh-CN,zh;q=0.9")
@@ -323,10 +471,8 @@ impl AnalyticsService {
             .header("sec-fetch-site", "same-site")
             .header("x-auth-token", auth_token)
             .header("Referer", "https://windsurf.com/")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| AppError::Api(format!("Request failed: {}", e)))?;
+            .body(body);
+        let response = self.transport.send("GetAnalytics", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -345,4 +491,79 @@ impl AnalyticsService {
         
         Ok(response_body)
     }
+
+    /// 按降级顺序请求 GetAnalytics，返回第一个成功的响应与对应策略；全部失败时返回最后一个错误
+    ///
+    /// 降级只用于请求被服务端拒绝的情况；网络或 5xx 错误已由重试层处理过，直接返回，不再逐级重发
+    pub async fn get_analytics_with_fallback(
+        &self,
+        auth_token: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+        is_team: bool,
+        timezone: Option<&str>,
+    ) -> AppResult<(Vec<u8>, AnalyticsFallback)> {
+        let mut last_error = None;
+        for strategy in AnalyticsFallback::ladder(is_team) {
+            let result = match strategy {
+                AnalyticsFallback::Full => self.get_analytics(auth_token, start_timestamp, end_timestamp, is_team, timezone).await,
+                AnalyticsFallback::TeamFallback => self.get_analytics(auth_token, start_timestamp, end_timestamp, false, timezone).await,
+                AnalyticsFallback::CascadeOnly => self.get_analytics_cascade_only(auth_token, start_timestamp, end_timestamp).await,
+                AnalyticsFallback::NoTimestamp => self.get_analytics_no_timestamp(auth_token).await,
+            };
+            match result {
+                Ok(body) => return Ok((body, strategy)),
+                Err(e) if retry::is_transient(&e) => {
+                    println!("[GetAnalytics] Strategy '{}' failed after retries, skipping fallbacks: {}", strategy.as_str(), e);
+                    return Err(e);
+                }
+                Err(e) => {
+                    println!("[GetAnalytics] Strategy '{}' failed: {}", strategy.as_str(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| AppError::Api("No analytics strategy available".to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_presets_match_hand_built_bodies() {
+        let service = AnalyticsService::for_account(Uuid::nil());
+        let full = find_preset(&[], "full").unwrap();
+        assert_eq!(
+            build_preset_body(&full, 1700000000, 1702592000, "Asia/Shanghai"),
+            service.build_get_analytics_body(1700000000, 1702592000, false, "Asia/Shanghai")
+        );
+
+        let minimal = find_preset(&[], "minimal").unwrap();
+        assert_eq!(build_preset_body(&minimal, 0, 0, "UTC"), vec![0x12, 0x03, 0xCA, 0x01, 0x00]);
+
+        let custom = AnalyticsRequestPreset { name: "minimal".to_string(), query_fields: vec![20], ..minimal };
+        assert_eq!(find_preset(&[custom], "minimal").unwrap().query_fields, vec![20]);
+        assert!(find_preset(&[], "missing").is_none());
+    }
+
+    #[test]
+    fn test_fallback_ladder() {
+        use AnalyticsFallback::*;
+        assert_eq!(AnalyticsFallback::ladder(false), vec![Full, CascadeOnly, NoTimestamp]);
+        assert_eq!(AnalyticsFallback::ladder(true), vec![Full, TeamFallback, CascadeOnly, NoTimestamp]);
+    }
+
+    #[test]
+    fn test_complete_presets() {
+        let complete: Vec<String> = builtin_presets()
+            .into_iter()
+            .filter(is_complete_preset)
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(complete, vec!["full"]);
+        assert!(AnalyticsFallback::TeamFallback.is_complete());
+        assert!(!AnalyticsFallback::CascadeOnly.is_complete());
+    }
 }
@@ -143,9 +143,15 @@ impl AuthService {
             }
         };
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             
+            // 只有 400/401 是凭据被拒绝，其他状态（如 5xx）视为服务端错误
+            if !matches!(status.as_u16(), 400 | 401) {
+                return Err(AppError::Api(format!("HTTP {}: {}", status, error_text)));
+            }
+            
             // 解析Firebase错误并提供友好提示
             if error_text.contains("TOO_MANY_ATTEMPTS_TRY_LATER") {
                 return Err(AppError::AuthFailed("登录尝试次数过多，请15-30分钟后再试".to_string()));
@@ -217,7 +223,8 @@ impl AuthService {
             }
         };
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             
             // 如果refresh token失败，返回特定错误
@@ -225,7 +232,11 @@ impl AuthService {
                 return Err(AppError::TokenExpired);
             }
             
-            return Err(AppError::Api(error_text));
+            // 400/401 表示 Refresh Token 被拒绝（如账号被禁用），其他状态视为服务端错误
+            if matches!(status.as_u16(), 400 | 401) {
+                return Err(AppError::AuthFailed(error_text));
+            }
+            return Err(AppError::Api(format!("HTTP {}: {}", status, error_text)));
         }
 
         let refresh_response: RefreshTokenResponse = response.json().await?;
@@ -0,0 +1,222 @@
+use crate::utils::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 开机自启动的方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutostartMode {
+    /// 启动完整界面
+    Gui,
+    /// 以 `--daemon` 启动，只运行调度器与通知插件
+    Daemon,
+}
+
+impl AutostartMode {
+    /// 自启动项名称，两种方式互不影响
+    fn entry_name(self) -> &'static str {
+        match self {
+            AutostartMode::Gui => "windsurf-account-manager",
+            AutostartMode::Daemon => "windsurf-account-manager-daemon",
+        }
+    }
+
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            AutostartMode::Gui => &[],
+            AutostartMode::Daemon => &["--daemon"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartStatus {
+    pub gui: bool,
+    pub daemon: bool,
+}
+
+fn current_exe() -> AppResult<PathBuf> {
+    std::env::current_exe().map_err(|e| AppError::FileOperation(format!("无法获取程序路径: {}", e)))
+}
+
+/// 注册或移除自启动项
+pub fn set_enabled(mode: AutostartMode, enabled: bool) -> AppResult<()> {
+    if enabled {
+        let exe = current_exe()?;
+        platform::register(mode, &exe)?;
+        println!("[Autostart] Registered {} -> {:?} {:?}", mode.entry_name(), exe, mode.args());
+    } else {
+        platform::unregister(mode)?;
+        println!("[Autostart] Removed {}", mode.entry_name());
+    }
+    Ok(())
+}
+
+pub fn status() -> AutostartStatus {
+    AutostartStatus {
+        gui: platform::is_registered(AutostartMode::Gui),
+        daemon: platform::is_registered(AutostartMode::Daemon),
+    }
+}
+
+/// Windows：写入 HKCU\Software\Microsoft\Windows\CurrentVersion\Run
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::AutostartMode;
+    use crate::utils::{AppError, AppResult};
+    use std::path::Path;
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_READ, KEY_WRITE};
+    use winreg::RegKey;
+
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+    fn command_line(mode: AutostartMode, exe: &Path) -> String {
+        let mut command = format!("\"{}\"", exe.display());
+        for arg in mode.args() {
+            command.push(' ');
+            command.push_str(arg);
+        }
+        command
+    }
+
+    pub fn register(mode: AutostartMode, exe: &Path) -> AppResult<()> {
+        let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+            .create_subkey(RUN_KEY)
+            .map_err(|e| AppError::Config(format!("无法打开注册表启动项: {}", e)))?;
+        key.set_value(mode.entry_name(), &command_line(mode, exe))
+            .map_err(|e| AppError::Config(format!("无法写入注册表启动项: {}", e)))
+    }
+
+    pub fn unregister(mode: AutostartMode) -> AppResult<()> {
+        let key = RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags(RUN_KEY, KEY_READ | KEY_WRITE)
+            .map_err(|e| AppError::Config(format!("无法打开注册表启动项: {}", e)))?;
+        match key.delete_value(mode.entry_name()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(AppError::Config(format!("无法删除注册表启动项: {}", e)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_registered(mode: AutostartMode) -> bool {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(RUN_KEY)
+            .and_then(|key| key.get_value::<String, _>(mode.entry_name()))
+            .is_ok()
+    }
+}
+
+/// macOS：在 ~/Library/LaunchAgents 下写入 RunAtLoad 的 plist
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::AutostartMode;
+    use crate::utils::{AppError, AppResult};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn plist_path(mode: AutostartMode) -> AppResult<PathBuf> {
+        directories::BaseDirs::new()
+            .map(|dirs| {
+                dirs.home_dir()
+                    .join("Library")
+                    .join("LaunchAgents")
+                    .join(format!("com.chao.{}.plist", mode.entry_name()))
+            })
+            .ok_or_else(|| AppError::Config("无法定位用户目录".to_string()))
+    }
+
+    fn xml_escape(value: &str) -> String {
+        value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+
+    pub fn register(mode: AutostartMode, exe: &Path) -> AppResult<()> {
+        let path = plist_path(mode)?;
+        let mut arguments = format!("        <string>{}</string>\n", xml_escape(&exe.to_string_lossy()));
+        for arg in mode.args() {
+            arguments.push_str(&format!("        <string>{}</string>\n", arg));
+        }
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n    \
+                 <key>Label</key>\n    \
+                 <string>com.chao.{}</string>\n    \
+                 <key>ProgramArguments</key>\n    \
+                 <array>\n{}    </array>\n    \
+                 <key>RunAtLoad</key>\n    \
+                 <true/>\n\
+             </dict>\n\
+             </plist>\n",
+            mode.entry_name(),
+            arguments
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, plist)?;
+        Ok(())
+    }
+
+    pub fn unregister(mode: AutostartMode) -> AppResult<()> {
+        let path = plist_path(mode)?;
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_registered(mode: AutostartMode) -> bool {
+        plist_path(mode).map(|p| p.exists()).unwrap_or(false)
+    }
+}
+
+/// Linux：在 ~/.config/autostart 下写入 .desktop 文件（XDG Autostart）
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::AutostartMode;
+    use crate::utils::{AppError, AppResult};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn desktop_path(mode: AutostartMode) -> AppResult<PathBuf> {
+        directories::BaseDirs::new()
+            .map(|dirs| dirs.config_dir().join("autostart").join(format!("{}.desktop", mode.entry_name())))
+            .ok_or_else(|| AppError::Config("无法定位用户配置目录".to_string()))
+    }
+
+    pub fn register(mode: AutostartMode, exe: &Path) -> AppResult<()> {
+        let path = desktop_path(mode)?;
+        let mut exec = format!("\"{}\"", exe.display());
+        for arg in mode.args() {
+            exec.push(' ');
+            exec.push_str(arg);
+        }
+        let name = match mode {
+            AutostartMode::Gui => "Windsurf Account Manager",
+            AutostartMode::Daemon => "Windsurf Account Manager (后台)",
+        };
+        let desktop = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\nNoDisplay=true\n",
+            name, exec
+        );
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, desktop)?;
+        Ok(())
+    }
+
+    pub fn unregister(mode: AutostartMode) -> AppResult<()> {
+        let path = desktop_path(mode)?;
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_registered(mode: AutostartMode) -> bool {
+        desktop_path(mode).map(|p| p.exists()).unwrap_or(false)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::models::CustomHeadersConfig;
+use crate::repository::secrets;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// 当前生效的自定义请求头，由设置中的 customHeaders 控制
+static CONFIG: RwLock<Option<CustomHeadersConfig>> = RwLock::new(None);
+
+pub fn set_config(config: CustomHeadersConfig) {
+    if let Ok(mut current) = CONFIG.write() {
+        *current = Some(config);
+    }
+}
+
+/// 合并适用于本次请求的请求头：账号 > 接口（接口名或域名）> 全局
+fn resolve(
+    config: &CustomHeadersConfig,
+    label: &str,
+    host: Option<&str>,
+    account: Option<Uuid>,
+) -> HashMap<String, String> {
+    let mut headers = config.global.clone();
+    for key in [Some(label), host].into_iter().flatten() {
+        if let Some(endpoint) = config.endpoints.get(key) {
+            headers.extend(endpoint.clone());
+        }
+    }
+    if let Some(id) = account {
+        if let Some(account_headers) = config.accounts.get(&id.to_string()) {
+            headers.extend(account_headers.clone());
+        }
+    }
+    headers
+}
+
+/// 发送前附加自定义请求头，同名请求头覆盖服务层设置的值；无效的请求头跳过并记录日志
+///
+/// `account` 为发起请求的账号，由调用方传入（请求头中的凭据可能是 Token 也可能是 API Key）
+pub fn apply(label: &str, account: Option<Uuid>, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let config = match CONFIG.read() {
+        Ok(config) => match config.as_ref() {
+            Some(config) if config != &CustomHeadersConfig::default() => config.clone(),
+            _ => return request,
+        },
+        Err(_) => return request,
+    };
+
+    // 只从副本中读取域名；流式请求体无法复制时只按接口名、账号和全局附加
+    let host = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .and_then(|b| b.url().host_str().map(str::to_string));
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in resolve(&config, label, host.as_deref(), account) {
+        // 数据锁定期间请求头的值仍是密文，不发送
+        if secrets::is_encrypted(&value) {
+            println!("[CustomHeaders] Skipping encrypted header {:?} for {}: store is locked", name, label);
+            continue;
+        }
+        match (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => println!("[CustomHeaders] Skipping invalid header {:?} for {}", name, label),
+        }
+    }
+    // RequestBuilder::headers 会替换已有的同名请求头
+    request.headers(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_precedence() {
+        let account = Uuid::new_v4();
+        let header = |name: &str, value: &str| HashMap::from([(name.to_string(), value.to_string())]);
+        let config = CustomHeadersConfig {
+            global: HashMap::from([
+                ("x-tenant-id".to_string(), "global".to_string()),
+                ("x-gateway-key".to_string(), "g-key".to_string()),
+            ]),
+            endpoints: HashMap::from([
+                ("GetAnalytics".to_string(), header("x-tenant-id", "analytics")),
+                ("gateway.example.com".to_string(), header("x-gateway-key", "host-key")),
+            ]),
+            accounts: HashMap::from([(account.to_string(), header("x-tenant-id", "account"))]),
+        };
+
+        let headers = resolve(&config, "GetAnalytics", Some("gateway.example.com"), None);
+        assert_eq!(headers["x-tenant-id"], "analytics");
+        assert_eq!(headers["x-gateway-key"], "host-key");
+
+        let headers = resolve(&config, "GetAnalytics", None, Some(account));
+        assert_eq!(headers["x-tenant-id"], "account");
+        assert_eq!(headers["x-gateway-key"], "g-key");
+
+        assert_eq!(resolve(&config, "GetCurrentUser", None, None)["x-tenant-id"], "global");
+    }
+}
@@ -0,0 +1,319 @@
+use super::scheduler::Scheduler;
+use crate::models::JobKind;
+use crate::repository::{storage, DataStore};
+use crate::utils::{AppError, AppResult};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+/// 后台进程写入的连接信息文件
+const DAEMON_FILE_NAME: &str = "daemon.json";
+
+/// 连接与请求超时
+const IPC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// 后台进程连接信息（只监听本机回环地址，请求需携带 token）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub port: u16,
+    pub token: String,
+    pub pid: u32,
+}
+
+/// 请求格式：一行一个 JSON
+#[derive(Debug, Deserialize)]
+struct IpcRequest {
+    token: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// 界面进程连接到的后台进程（None 表示独立运行）
+static REMOTE: Lazy<RwLock<Option<DaemonInfo>>> = Lazy::new(|| RwLock::new(None));
+
+/// 后台进程写入数据项的通知（数据项名称），订阅的界面进程收到后同步该数据项
+static STORE_CHANGED: Lazy<broadcast::Sender<&'static str>> = Lazy::new(|| broadcast::channel(64).0);
+
+fn daemon_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(DAEMON_FILE_NAME)
+}
+
+/// 是否已连接到后台进程（此时定时任务由后台进程执行）
+pub fn is_connected() -> bool {
+    REMOTE.read().map(|r| r.is_some()).unwrap_or(false)
+}
+
+fn remote() -> Option<DaemonInfo> {
+    REMOTE.read().ok().and_then(|r| r.clone())
+}
+
+/// 后台进程：监听本机端口并写入连接信息文件
+pub async fn start_server(store: Arc<DataStore>, scheduler: Arc<Scheduler>) -> AppResult<DaemonInfo> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let info = DaemonInfo {
+        port: listener.local_addr()?.port(),
+        token: hex::encode(rand::thread_rng().gen::<[u8; 16]>()),
+        pid: std::process::id(),
+    };
+    fs::write(daemon_file(&store.get_data_dir()), serde_json::to_string_pretty(&info)?)?;
+
+    // 任一数据项写入后通知订阅的界面进程
+    store.set_save_hook(Box::new(|key| {
+        if storage::SHARED_KEYS.contains(&key) {
+            let _ = STORE_CHANGED.send(key);
+        }
+    }));
+
+    let token = info.token.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    println!("[DaemonIpc] Accept failed: {}", e);
+                    continue;
+                }
+            };
+            let store = store.clone();
+            let scheduler = scheduler.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &store, &scheduler, &token).await {
+                    println!("[DaemonIpc] Connection error: {}", e);
+                }
+            });
+        }
+    });
+
+    println!("[DaemonIpc] Listening on 127.0.0.1:{}", info.port);
+    Ok(info)
+}
+
+/// 后台进程退出时删除连接信息文件
+pub fn remove_daemon_file(data_dir: &Path) {
+    let _ = fs::remove_file(daemon_file(data_dir));
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    store: &Arc<DataStore>,
+    scheduler: &Arc<Scheduler>,
+    token: &str,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let request: IpcRequest = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                write_line(&mut writer, &json!({ "error": format!("Invalid request: {}", e) })).await?;
+                continue;
+            }
+        };
+        if request.token != token {
+            write_line(&mut writer, &json!({ "error": "Invalid token" })).await?;
+            return Ok(());
+        }
+
+        // 订阅：保持连接，数据变化时推送事件
+        if request.method == "subscribe" {
+            let mut changes = STORE_CHANGED.subscribe();
+            write_line(&mut writer, &json!({ "result": "subscribed" })).await?;
+            loop {
+                match changes.recv().await {
+                    Ok(key) => {
+                        write_line(&mut writer, &json!({ "event": "store_changed", "key": key })).await?;
+                    }
+                    // 丢失了部分通知时让界面进程同步全部数据项
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        for key in storage::SHARED_KEYS {
+                            write_line(&mut writer, &json!({ "event": "store_changed", "key": key })).await?;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                }
+            }
+        }
+
+        let response = match dispatch(&request.method, request.params, store, scheduler).await {
+            Ok(result) => json!({ "result": result }),
+            Err(e) => json!({ "error": e }),
+        };
+        write_line(&mut writer, &response).await?;
+    }
+    Ok(())
+}
+
+async fn write_line<W: AsyncWriteExt + Unpin>(writer: &mut W, message: &Value) -> std::io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await
+}
+
+async fn dispatch(
+    method: &str,
+    params: Value,
+    store: &Arc<DataStore>,
+    scheduler: &Arc<Scheduler>,
+) -> Result<Value, String> {
+    match method {
+        "ping" => Ok(json!({ "pid": std::process::id(), "version": env!("CARGO_PKG_VERSION") })),
+        "scheduler_status" => serde_json::to_value(scheduler.get_status().await).map_err(|e| e.to_string()),
+        "watchdog_events" => serde_json::to_value(scheduler.get_watchdog_events().await).map_err(|e| e.to_string()),
+        "run_job" => {
+            let job: JobKind = serde_json::from_value(params.get("job").cloned().unwrap_or(Value::Null))
+                .map_err(|e| format!("Invalid job: {}", e))?;
+            scheduler.run_job(job).await.map(Value::String)
+        }
+        "unlock" => {
+            let password = params.get("password").and_then(|v| v.as_str()).unwrap_or_default();
+            store.unlock(password).await.map_err(|e| e.to_string())?;
+            if let Ok(settings) = store.get_settings().await {
+                super::custom_headers::set_config(settings.custom_headers);
+            }
+            Ok(Value::Bool(true))
+        }
+        "read_item" => {
+            let key = params.get("key").and_then(|v| v.as_str()).unwrap_or_default();
+            store.item_value(key).await.map_err(|e| e.to_string())
+        }
+        "write_item" => {
+            let key = params.get("key").and_then(|v| v.as_str()).unwrap_or_default();
+            let base = params.get("base").cloned().unwrap_or(Value::Null);
+            let ours = params.get("ours").cloned().unwrap_or(Value::Null);
+            store.apply_remote_write(key, base, ours).await.map_err(|e| e.to_string())?;
+            Ok(Value::Bool(true))
+        }
+        _ => Err(format!("Unknown method: {}", method)),
+    }
+}
+
+async fn connect(info: &DaemonInfo) -> AppResult<TcpStream> {
+    tokio::time::timeout(IPC_TIMEOUT, TcpStream::connect(("127.0.0.1", info.port)))
+        .await
+        .map_err(|_| AppError::Network("Daemon connection timed out".to_string()))?
+        .map_err(AppError::from)
+}
+
+async fn call_with(info: &DaemonInfo, method: &str, params: Value) -> AppResult<Value> {
+    let stream = connect(info).await?;
+    let (reader, mut writer) = stream.into_split();
+    write_line(&mut writer, &json!({ "token": info.token, "method": method, "params": params })).await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    // 执行任务可能较久，只对连接设置超时
+    let line = lines
+        .next_line()
+        .await?
+        .ok_or_else(|| AppError::Network("Daemon closed the connection".to_string()))?;
+    let response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        return Err(AppError::Api(error.as_str().unwrap_or_default().to_string()));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+/// 调用已连接的后台进程
+pub async fn call(method: &str, params: Value) -> AppResult<Value> {
+    let info = remote().ok_or_else(|| AppError::Config("Not connected to daemon".to_string()))?;
+    call_with(&info, method, params).await
+}
+
+/// 界面进程启动时检测后台进程：连接信息文件存在且 ping 成功时返回连接信息
+///
+/// 在打开数据存储之前调用，后台进程运行时由它负责恢复未完成的写入
+pub async fn probe(data_dir: &Path) -> Option<DaemonInfo> {
+    let path = daemon_file(data_dir);
+    let info: DaemonInfo = fs::read_to_string(&path).ok().and_then(|d| serde_json::from_str(&d).ok())?;
+    if info.pid == std::process::id() || call_with(&info, "ping", Value::Null).await.is_err() {
+        // 后台进程已退出，清理残留文件
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+    Some(info)
+}
+
+/// 连接到后台进程：同步全部数据项，之后本进程的写入都交给后台进程合并保存
+///
+/// 同步失败时返回 false，由本进程独立运行
+pub async fn attach(info: DaemonInfo, store: Arc<DataStore>, scheduler: Arc<Scheduler>) -> bool {
+    for key in storage::SHARED_KEYS {
+        let synced: AppResult<()> = async {
+            let theirs = call_with(&info, "read_item", json!({ "key": key })).await?;
+            let current = store.item_value(key).await?;
+            store.absorb_remote(key, Some(current), theirs).await
+        }
+        .await;
+        if let Err(e) = synced {
+            println!("[DaemonIpc] Failed to sync {} from daemon: {}", key, e);
+            store.set_remote_writer(None);
+            return false;
+        }
+    }
+
+    if let Ok(mut remote) = REMOTE.write() {
+        *remote = Some(info.clone());
+    }
+    store.set_remote_writer(Some(Arc::new(|key, base, ours| {
+        Box::pin(async move {
+            call("write_item", json!({ "key": key, "base": base, "ours": ours })).await?;
+            Ok(())
+        })
+    })));
+
+    tokio::spawn(subscribe(info.clone(), store, scheduler));
+    println!("[DaemonIpc] Attached to daemon (pid {})", info.pid);
+    true
+}
+
+/// 用主密码解锁后台进程（后台进程没有界面，只能由界面进程转发），然后同步已解密的数据
+pub async fn unlock(store: &DataStore, password: &str) -> AppResult<()> {
+    call("unlock", json!({ "password": password })).await?;
+    for key in storage::SHARED_KEYS {
+        let theirs = call("read_item", json!({ "key": key })).await?;
+        store.absorb_remote(key, None, theirs).await?;
+    }
+    Ok(())
+}
+
+/// 订阅后台进程的数据变化，收到后合并该数据项并通知前端刷新
+async fn subscribe(info: DaemonInfo, store: Arc<DataStore>, scheduler: Arc<Scheduler>) {
+    let result: AppResult<()> = async {
+        let stream = connect(&info).await?;
+        let (reader, mut writer) = stream.into_split();
+        write_line(&mut writer, &json!({ "token": info.token, "method": "subscribe" })).await?;
+
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let message: Value = serde_json::from_str(&line)?;
+            if message.get("event").and_then(|v| v.as_str()) != Some("store_changed") {
+                continue;
+            }
+            let key = message.get("key").and_then(|v| v.as_str()).unwrap_or_default();
+            let theirs = call_with(&info, "read_item", json!({ "key": key })).await?;
+            store.absorb_remote(key, None, theirs).await?;
+            store.emit_event("store-reloaded", ());
+        }
+        Ok(())
+    }
+    .await;
+
+    // 后台进程退出后恢复独立运行，由本进程接管写入与定时任务
+    if let Ok(mut remote) = REMOTE.write() {
+        *remote = None;
+    }
+    store.set_remote_writer(None);
+    println!("[DaemonIpc] Detached from daemon: {:?}", result.err());
+    scheduler.start();
+    store.emit_event("daemon-detached", ());
+}
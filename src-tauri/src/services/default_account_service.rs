@@ -0,0 +1,221 @@
+use super::notification_service::{self, NotificationCategory};
+use super::WindsurfService;
+use crate::commands::api_commands::ensure_valid_token;
+use crate::commands::windsurf_info::get_current_windsurf_info;
+use crate::models::{Account, AccountStatus, OperationLog, OperationStatus, OperationType};
+use crate::repository::DataStore;
+use crate::utils::{retry, CommandError};
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 启动时默认账号的检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DefaultAccountStatus {
+    pub account_id: String,
+    pub email: String,
+    pub healthy: bool,
+    /// 异常原因，正常时为空
+    pub reason: Option<String>,
+    /// 是否已切换到该账号
+    pub applied: bool,
+    /// Windsurf 已登录该账号，未重新切换
+    pub already_active: bool,
+    /// 无法连接 Windsurf，本次未能确认账号状态
+    pub offline: bool,
+}
+
+/// 检查未通过的原因
+#[derive(Debug, PartialEq)]
+enum CheckFailure {
+    /// 账号本身不可用
+    Unusable(String),
+    /// 网络不可用或服务端故障，无法判断账号状态
+    Offline(String),
+}
+
+/// 只根据本地记录判断的问题（不发请求）
+fn local_problem(account: &Account) -> Option<String> {
+    if account.is_disabled == Some(true) {
+        return Some("账号已被禁用".to_string());
+    }
+    if matches!(account.status, AccountStatus::NeedsAttention) {
+        return Some("账号连续认证失败，需要人工处理".to_string());
+    }
+    if account.refresh_token.as_deref().map(str::is_empty).unwrap_or(true) {
+        return Some("账号没有refresh_token，请先登录".to_string());
+    }
+    None
+}
+
+/// Windsurf 当前登录的是否就是该账号（邮箱相同，双方都有 API Key 时还要求 API Key 相同）
+fn is_current_ide_account(account: &Account, email: Option<&str>, api_key: Option<&str>) -> bool {
+    let same_email = email.map(|e| e.eq_ignore_ascii_case(&account.email)).unwrap_or(false);
+    let same_key = match (api_key, account.windsurf_api_key.as_deref()) {
+        (Some(current), Some(ours)) if !current.is_empty() && !ours.is_empty() => current == ours,
+        _ => true,
+    };
+    same_email && same_key
+}
+
+/// 检查账号是否可用：本地状态、Token 刷新，再发起一次 GetCurrentUser
+async fn check(store: &Arc<DataStore>, account: &mut Account) -> Result<(), CheckFailure> {
+    if let Some(problem) = local_problem(account) {
+        return Err(CheckFailure::Unusable(problem));
+    }
+    let account_id = account.id;
+    match ensure_valid_token(store, account, account_id).await {
+        Ok(()) => {}
+        Err(e @ CommandError::Network(_)) => return Err(CheckFailure::Offline(format!("刷新Token失败: {}", e))),
+        Err(e) => return Err(CheckFailure::Unusable(format!("刷新Token失败: {}", e))),
+    }
+    let token = account.token.clone().unwrap_or_default();
+    let result = match WindsurfService::for_account(account_id).get_current_user(&token).await {
+        Ok(result) => result,
+        Err(e) if retry::is_transient(&e) => return Err(CheckFailure::Offline(format!("无法连接 Windsurf: {}", e))),
+        Err(e) => return Err(CheckFailure::Unusable(format!("获取账号信息失败: {}", e))),
+    };
+    if result.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        let status_code = result.get("status_code").and_then(|v| v.as_u64()).unwrap_or(0);
+        let reason = format!("获取账号信息失败 (HTTP {})", status_code);
+        return Err(if status_code >= 500 { CheckFailure::Offline(reason) } else { CheckFailure::Unusable(reason) });
+    }
+    if result.pointer("/user_info/user/disable_codeium").and_then(|v| v.as_bool()) == Some(true) {
+        return Err(CheckFailure::Unusable("账号已被禁用".to_string()));
+    }
+    Ok(())
+}
+
+/// 启动入口：按设置检查默认账号，异常时通知前端与插件，正常且开启自动应用时切换到该账号
+pub async fn run_startup(store: &Arc<DataStore>) -> Result<Option<DefaultAccountStatus>, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let config = settings.default_account;
+    let account_id = match config.account_id.as_deref().filter(|id| !id.is_empty()) {
+        Some(id) => Uuid::parse_str(id).map_err(|e| format!("默认账号 ID 无效: {}", e))?,
+        None => return Ok(None),
+    };
+    if !config.verify_on_startup && !config.apply_on_startup {
+        return Ok(None);
+    }
+
+    let mut account = match store.get_account(account_id).await {
+        Ok(account) => account,
+        Err(_) => {
+            let status = DefaultAccountStatus {
+                account_id: account_id.to_string(),
+                email: String::new(),
+                healthy: false,
+                reason: Some("默认账号不存在".to_string()),
+                applied: false,
+                already_active: false,
+                offline: false,
+            };
+            report_unhealthy(store, &status).await;
+            return Ok(Some(status));
+        }
+    };
+
+    let checked = if config.verify_on_startup {
+        check(store, &mut account).await
+    } else {
+        Ok(())
+    };
+    let mut status = DefaultAccountStatus {
+        account_id: account_id.to_string(),
+        email: account.email.clone(),
+        healthy: checked.is_ok(),
+        reason: None,
+        applied: false,
+        already_active: false,
+        offline: false,
+    };
+    match checked {
+        Ok(()) => {}
+        // 离线时无法判断账号是否可用，不按账号异常通知，也不切换
+        Err(CheckFailure::Offline(reason)) => {
+            println!("[DefaultAccount] Skipping startup check for {}: {}", account.email, reason);
+            status.reason = Some(reason);
+            status.offline = true;
+            return Ok(Some(status));
+        }
+        Err(CheckFailure::Unusable(reason)) => {
+            status.reason = Some(reason);
+            report_unhealthy(store, &status).await;
+            return Ok(Some(status));
+        }
+    }
+
+    // Windsurf 已登录该账号时不再切换（切换会重置机器 ID 并触发登录回调）
+    let current = get_current_windsurf_info().ok();
+    if config.apply_on_startup
+        && is_current_ide_account(
+            &account,
+            current.as_ref().and_then(|c| c.email.as_deref()),
+            current.as_ref().and_then(|c| c.api_key.as_deref()),
+        )
+    {
+        println!("[DefaultAccount] Windsurf is already using {}, skipping switch", account.email);
+        status.already_active = true;
+        return Ok(Some(status));
+    }
+
+    if config.apply_on_startup {
+        let result = crate::commands::switch_account_commands::switch_account_by_id(store, account_id).await?;
+        if result.get("success").and_then(|v| v.as_bool()) == Some(true) {
+            status.applied = true;
+        } else {
+            let error = result.get("error").and_then(|v| v.as_str()).unwrap_or_default();
+            status.reason = Some(format!("切换失败: {}", error));
+            report_unhealthy(store, &status).await;
+        }
+    }
+    Ok(Some(status))
+}
+
+async fn report_unhealthy(store: &Arc<DataStore>, status: &DefaultAccountStatus) {
+    let reason = status.reason.clone().unwrap_or_default();
+    println!("[DefaultAccount] {} is not usable: {}", status.account_id, reason);
+    let mut log = OperationLog::new(
+        OperationType::GetAccountInfo,
+        OperationStatus::Failed,
+        format!("默认账号检查异常: {}", reason),
+    );
+    if let Ok(account_id) = Uuid::parse_str(&status.account_id) {
+        log = log.with_account(account_id, status.email.clone());
+    }
+    let _ = store.add_log(log).await;
+    notification_service::notify(store, NotificationCategory::AccountBanned, "默认账号不可用", &reason).await;
+    store.emit_event("default-account-unhealthy", status.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_problem() {
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        assert!(local_problem(&account).is_some());
+
+        account.refresh_token = Some("refresh".to_string());
+        assert_eq!(local_problem(&account), None);
+
+        account.status = AccountStatus::NeedsAttention;
+        assert!(local_problem(&account).unwrap().contains("人工处理"));
+
+        account.is_disabled = Some(true);
+        assert_eq!(local_problem(&account).as_deref(), Some("账号已被禁用"));
+    }
+
+    #[test]
+    fn test_is_current_ide_account() {
+        let mut account = Account::new("A@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        assert!(is_current_ide_account(&account, Some("a@example.com"), Some("key")));
+        assert!(!is_current_ide_account(&account, Some("b@example.com"), None));
+        assert!(!is_current_ide_account(&account, None, None));
+
+        account.windsurf_api_key = Some("key".to_string());
+        assert!(is_current_ide_account(&account, Some("a@example.com"), Some("key")));
+        assert!(!is_current_ide_account(&account, Some("a@example.com"), Some("other")));
+    }
+}
@@ -0,0 +1,362 @@
+use crate::models::{OperationLog, OperationStatus, OperationType};
+use crate::repository::{DataStore, ImportPreview};
+use crate::utils::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// 默认的环境变量前缀
+pub const DEFAULT_ENV_PREFIX: &str = "WAM_ACCOUNT_";
+
+/// 从标准输入读取账号时使用的来源名称
+pub const STDIN_SOURCE: &str = "-";
+
+/// 从环境变量中解析出的一个账号
+///
+/// 变量格式：`<PREFIX><NAME>_EMAIL`、`<PREFIX><NAME>_TOKEN`（refresh_token），
+/// 可选 `<PREFIX><NAME>_PASSWORD`、`<PREFIX><NAME>_NICKNAME`、`<PREFIX><NAME>_GROUP`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvAccount {
+    pub name: String,
+    pub email: String,
+    pub refresh_token: Option<String>,
+    pub password: Option<String>,
+    pub nickname: Option<String>,
+    pub group: Option<String>,
+}
+
+/// 环境变量导入结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EnvImportResult {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: Vec<String>,
+    /// 预演（dry_run）时将要发生的变更
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImportPreview>,
+}
+
+/// 按前缀解析环境变量，缺少邮箱或凭据（token/密码）的条目被忽略并记录原因
+pub fn parse_env_accounts<I>(prefix: &str, vars: I) -> (Vec<EnvAccount>, Vec<String>)
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut entries: BTreeMap<String, EnvAccount> = BTreeMap::new();
+
+    for (key, value) in vars {
+        let rest = match key.strip_prefix(prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let (name, field) = match rest.rsplit_once('_') {
+            Some((name, field)) if !name.is_empty() => (name, field),
+            _ => continue,
+        };
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+
+        let entry = entries.entry(name.to_string()).or_insert_with(|| EnvAccount {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        match field {
+            "EMAIL" => entry.email = value,
+            "TOKEN" => entry.refresh_token = Some(value),
+            "PASSWORD" => entry.password = Some(value),
+            "NICKNAME" => entry.nickname = Some(value),
+            "GROUP" => entry.group = Some(value),
+            _ => {}
+        }
+    }
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, entry) in entries {
+        if entry.email.is_empty() {
+            skipped.push(format!("{}{}: 缺少 EMAIL", prefix, name));
+        } else if entry.refresh_token.is_none() && entry.password.is_none() {
+            skipped.push(format!("{}{}: 缺少 TOKEN 或 PASSWORD", prefix, name));
+        } else {
+            accounts.push(entry);
+        }
+    }
+    (accounts, skipped)
+}
+
+/// 标准输入 / 命名管道中的一个账号（`token` 是 `refresh_token` 的别名）
+#[derive(Debug, Deserialize)]
+struct PayloadAccount {
+    #[serde(default)]
+    email: String,
+    #[serde(default, alias = "token")]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    nickname: Option<String>,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+/// 解析账号载荷：一个 JSON 数组，或每行一个 JSON 对象（JSON Lines）
+///
+/// 无法解析或缺少邮箱/凭据的条目被忽略并记录原因（不包含凭据内容）
+pub fn parse_payload_accounts(content: &str) -> (Vec<EnvAccount>, Vec<String>) {
+    let items: Vec<Result<Value, String>> = if content.trim_start().starts_with('[') {
+        match serde_json::from_str::<Vec<Value>>(content) {
+            Ok(values) => values.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(format!("JSON 数组格式错误: {}", e))],
+        }
+    } else {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| format!("JSON 格式错误: {}", e)))
+            .collect()
+    };
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let name = format!("#{}", index + 1);
+        let payload = match item.and_then(|v| serde_json::from_value::<PayloadAccount>(v).map_err(|e| e.to_string())) {
+            Ok(payload) => payload,
+            Err(e) => {
+                skipped.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let entry = EnvAccount {
+            name: name.clone(),
+            email: payload.email.trim().to_string(),
+            refresh_token: non_empty(payload.refresh_token),
+            password: non_empty(payload.password),
+            nickname: non_empty(payload.nickname),
+            group: non_empty(payload.group),
+        };
+        if entry.email.is_empty() {
+            skipped.push(format!("{}: 缺少 email", name));
+        } else if entry.refresh_token.is_none() && entry.password.is_none() {
+            skipped.push(format!("{}: 缺少 refresh_token 或 password", name));
+        } else {
+            accounts.push(entry);
+        }
+    }
+    (accounts, skipped)
+}
+
+/// 从环境变量导入账号：新邮箱创建账号，已存在的邮箱更新凭据
+///
+/// 不访问网络，access token 由后台 Token 刷新任务或首次操作时获取
+pub async fn import_from_env(store: &DataStore, prefix: &str, dry_run: bool) -> AppResult<EnvImportResult> {
+    let prefix = if prefix.trim().is_empty() { DEFAULT_ENV_PREFIX } else { prefix.trim() };
+    let (entries, skipped) = parse_env_accounts(prefix, std::env::vars());
+    import_entries(store, entries, skipped, &format!("环境变量 {}*", prefix), dry_run).await
+}
+
+/// 从标准输入（`-`）或命名管道/文件读取账号载荷并导入，不产生临时文件
+pub async fn import_from_source(store: &DataStore, source: &str, dry_run: bool) -> AppResult<EnvImportResult> {
+    let source_owned = source.to_string();
+    let content = tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+        let mut content = String::new();
+        if source_owned == STDIN_SOURCE {
+            std::io::stdin().read_to_string(&mut content)?;
+        } else {
+            // 命名管道按普通文件读取，写入端关闭后结束
+            std::fs::File::open(&source_owned)?.read_to_string(&mut content)?;
+        }
+        Ok(content)
+    })
+    .await
+    .map_err(|e| AppError::Config(format!("Task join error: {}", e)))??;
+
+    let (entries, skipped) = parse_payload_accounts(&content);
+    let label = if source == STDIN_SOURCE { "标准输入".to_string() } else { source.to_string() };
+    import_entries(store, entries, skipped, &label, dry_run).await
+}
+
+/// 预演导入：按与实际导入相同的规则划分新增与更新，不访问 DataStore 的写接口
+fn preview_entries(existing: &[crate::models::Account], entries: &[EnvAccount], skipped: &[String]) -> ImportPreview {
+    let mut preview = ImportPreview {
+        skipped: skipped.to_vec(),
+        ..Default::default()
+    };
+    for entry in entries {
+        if existing.iter().any(|a| a.email.eq_ignore_ascii_case(&entry.email)) {
+            preview.updated.push(entry.email.clone());
+        } else {
+            preview.added.push(entry.email.clone());
+        }
+    }
+    preview
+}
+
+/// 导入解析后的账号：新邮箱创建账号，已存在的邮箱更新凭据
+async fn import_entries(
+    store: &DataStore,
+    entries: Vec<EnvAccount>,
+    skipped: Vec<String>,
+    source: &str,
+    dry_run: bool,
+) -> AppResult<EnvImportResult> {
+    if dry_run {
+        let existing = store.get_all_accounts().await?;
+        let preview = preview_entries(&existing, &entries, &skipped);
+        return Ok(EnvImportResult {
+            added: preview.added.len(),
+            updated: preview.updated.len(),
+            skipped,
+            preview: Some(preview),
+        });
+    }
+
+    let mut result = EnvImportResult {
+        skipped,
+        ..Default::default()
+    };
+    if entries.is_empty() {
+        return Ok(result);
+    }
+
+    let existing = store.get_all_accounts().await?;
+    let note_template = store.get_settings().await?.account_note_template;
+    for entry in entries {
+        let current = existing
+            .iter()
+            .find(|a| a.email.eq_ignore_ascii_case(&entry.email))
+            .cloned();
+
+        let mut account = match current {
+            Some(account) => {
+                result.updated += 1;
+                account
+            }
+            None => {
+                let nickname = entry
+                    .nickname
+                    .clone()
+                    .unwrap_or_else(|| entry.email.split('@').next().unwrap_or(&entry.email).to_string());
+                result.added += 1;
+                let mut account = store
+                    .add_account(entry.email.clone(), String::new(), nickname)
+                    .await?;
+                if let Some(template) = note_template.as_deref().filter(|t| !t.trim().is_empty()) {
+                    account.notes = Some(crate::repository::render_note_template(template, &account));
+                }
+                account
+            }
+        };
+
+        if let Some(refresh_token) = entry.refresh_token {
+            if account.refresh_token.as_deref() != Some(refresh_token.as_str()) {
+                account.refresh_token = Some(refresh_token);
+                // 凭据变化后旧的 access token 不再可信
+                account.token = None;
+                account.token_expires_at = None;
+            }
+        }
+        if let Some(password) = entry.password {
+            account.password = password;
+        }
+        if entry.group.is_some() {
+            account.group = entry.group;
+        }
+        store.update_account_no_save(account).await?;
+    }
+    store.flush().await?;
+
+    println!(
+        "[EnvImport] Imported accounts from {}: {} added, {} updated, {} skipped",
+        source,
+        result.added,
+        result.updated,
+        result.skipped.len()
+    );
+    let log = OperationLog::new(
+        OperationType::AddAccount,
+        OperationStatus::Success,
+        format!(
+            "从{}导入账号: 新增 {}，更新 {}，跳过 {}",
+            source,
+            result.added,
+            result.updated,
+            result.skipped.len()
+        ),
+    );
+    let _ = store.add_log(log).await;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_accounts() {
+        let vars = vec![
+            ("WAM_ACCOUNT_CI_BOT_EMAIL".to_string(), "bot@example.com".to_string()),
+            ("WAM_ACCOUNT_CI_BOT_TOKEN".to_string(), "rt-1".to_string()),
+            ("WAM_ACCOUNT_CI_BOT_GROUP".to_string(), "ci".to_string()),
+            ("WAM_ACCOUNT_2_EMAIL".to_string(), "two@example.com".to_string()),
+            ("WAM_ACCOUNT_3_TOKEN".to_string(), "rt-3".to_string()),
+            ("OTHER_VAR".to_string(), "x".to_string()),
+        ];
+
+        let (accounts, skipped) = parse_env_accounts(DEFAULT_ENV_PREFIX, vars);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "CI_BOT");
+        assert_eq!(accounts[0].email, "bot@example.com");
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("rt-1"));
+        assert_eq!(accounts[0].group.as_deref(), Some("ci"));
+        assert_eq!(skipped.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_payload_accounts() {
+        let lines = r#"{"email":"a@example.com","token":"rt-a","group":"ci"}
+
+{"email":"b@example.com"}
+not json
+"#;
+        let (accounts, skipped) = parse_payload_accounts(lines);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("rt-a"));
+        assert_eq!(accounts[0].group.as_deref(), Some("ci"));
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped[0].starts_with("#2"));
+
+        let array = r#"[{"email":"c@example.com","password":"pw","nickname":" "}]"#;
+        let (accounts, skipped) = parse_payload_accounts(array);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].password.as_deref(), Some("pw"));
+        assert!(accounts[0].nickname.is_none());
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_preview_entries() {
+        let existing = vec![crate::models::Account::new(
+            "Known@example.com".to_string(),
+            String::new(),
+            "known".to_string(),
+            Vec::new(),
+        )];
+        let entry = |email: &str| EnvAccount {
+            email: email.to_string(),
+            refresh_token: Some("rt".to_string()),
+            ..Default::default()
+        };
+        let skipped = vec!["#3: 缺少 email".to_string()];
+
+        let preview = preview_entries(&existing, &[entry("known@example.com"), entry("new@example.com")], &skipped);
+        assert_eq!(preview.updated, vec!["known@example.com"]);
+        assert_eq!(preview.added, vec!["new@example.com"]);
+        assert_eq!(preview.skipped, skipped);
+        assert!(preview.removed.is_empty());
+    }
+}
@@ -0,0 +1,1146 @@
+use crate::models::{
+    Account, AccountStatus, AnalyticsData, AnalyticsSnapshot, AutoExportConfig, CompletionByDay,
+    CompletionStatistics, DailyCascadeLinesCount, GlobalTag, JobRun, ModelUsageEntry, OperationLog,
+    OperationStatus, OperationType,
+};
+use crate::repository::{DataStore, ImportPreview};
+use crate::utils::crypto::CryptoService;
+use crate::utils::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 自动导出目录名前缀
+pub const EXPORT_DIR_PREFIX: &str = "windsurf_export_";
+/// 导出写入中的临时目录前缀
+const TEMP_DIR_PREFIX: &str = ".tmp_";
+/// 包含凭据的自动导出中加密后的账号文件
+const ENCRYPTED_ACCOUNTS_FILE: &str = "accounts.enc.json";
+
+/// 账号 CSV 的列（顺序即输出顺序）
+pub const ACCOUNT_CSV_COLUMNS: &[&str] = &[
+    "id",
+    "email",
+    "nickname",
+    "group",
+    "tags",
+    "status",
+    "plan_name",
+    "created_at",
+    "last_login_at",
+    "subscription_expires_at",
+    "sort_order",
+    "password",
+    "refresh_token",
+    "windsurf_api_key",
+];
+
+/// CSV 字段转义：包含逗号、引号或换行时用双引号包裹
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 将账号列表转为 CSV，`include_secrets` 为 false 时密码与凭据列留空
+pub fn accounts_to_csv(accounts: &[Account], include_secrets: bool) -> String {
+    let mut lines = vec![ACCOUNT_CSV_COLUMNS.join(",")];
+
+    for account in accounts {
+        let status = match &account.status {
+            AccountStatus::Active => "active".to_string(),
+            AccountStatus::Inactive => "inactive".to_string(),
+            AccountStatus::Error(message) => format!("error:{}", message),
+            AccountStatus::NeedsAttention => "needs_attention".to_string(),
+        };
+        let secret = |value: Option<&str>| {
+            if include_secrets {
+                value.unwrap_or_default().to_string()
+            } else {
+                String::new()
+            }
+        };
+
+        let fields = vec![
+            account.id.to_string(),
+            account.email.clone(),
+            account.nickname.clone(),
+            account.group.clone().unwrap_or_default(),
+            account.tags.join("|"),
+            status,
+            account.plan_name.clone().unwrap_or_default(),
+            account.created_at.to_rfc3339(),
+            account.last_login_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            account.subscription_expires_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            account.sort_order.to_string(),
+            secret(Some(account.password.as_str())),
+            secret(account.refresh_token.as_deref()),
+            secret(account.windsurf_api_key.as_deref()),
+        ];
+        lines.push(fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// 账号导出格式
+pub const ACCOUNT_EXPORT_JSON: &str = "json";
+pub const ACCOUNT_EXPORT_CSV: &str = "csv";
+
+/// 加密的账号导出文件：口令经 PBKDF2 派生密钥，内容用 AES-256-GCM 加密
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedAccountExport {
+    pub version: u32,
+    /// 解密后内容的格式（json / csv）
+    pub format: String,
+    pub salt: String,
+    pub payload: String,
+}
+
+/// 生成账号导出内容，`password` 不为空时整体加密
+pub fn build_account_export(
+    accounts: &[Account],
+    format: &str,
+    include_secrets: bool,
+    password: Option<&str>,
+) -> AppResult<String> {
+    let content = match format {
+        ACCOUNT_EXPORT_CSV => accounts_to_csv(accounts, include_secrets),
+        ACCOUNT_EXPORT_JSON => {
+            let accounts: Vec<Account> = if include_secrets {
+                accounts.to_vec()
+            } else {
+                accounts.iter().map(Account::redacted).collect()
+            };
+            serde_json::to_string_pretty(&json!({
+                "version": "1.0",
+                "exported_at": chrono::Utc::now().to_rfc3339(),
+                "include_secrets": include_secrets,
+                "accounts": accounts,
+            }))?
+        }
+        other => return Err(AppError::Config(format!("不支持的导出格式: {}", other))),
+    };
+
+    let Some(password) = password.filter(|p| !p.is_empty()) else {
+        return Ok(content);
+    };
+    let salt = CryptoService::generate_salt();
+    let crypto = CryptoService::from_password(password, &salt)
+        .map_err(|e| AppError::Config(e.to_string()))?;
+    let payload = crypto
+        .encrypt(&content)
+        .map_err(|e| AppError::Config(format!("Failed to encrypt export: {}", e)))?;
+    Ok(serde_json::to_string_pretty(&EncryptedAccountExport {
+        version: 1,
+        format: format.to_string(),
+        salt,
+        payload,
+    })?)
+}
+
+/// 解密账号导出文件，返回 (格式, 内容)
+pub fn decrypt_account_export(content: &str, password: &str) -> AppResult<(String, String)> {
+    let export: EncryptedAccountExport = serde_json::from_str(content)?;
+    let crypto = CryptoService::from_password(password, &export.salt)
+        .map_err(|e| AppError::Config(e.to_string()))?;
+    let plaintext = crypto
+        .decrypt(&export.payload)
+        .map_err(|_| AppError::Config("导出文件口令错误或文件已损坏".to_string()))?;
+    Ok((export.format, plaintext))
+}
+
+/// 读取导入文件：加密的账号导出先用口令解密，CSV 格式转换为 `{"accounts": [...]}`
+pub fn read_import_content(content: &str, password: Option<&str>) -> AppResult<Value> {
+    let (format, content) = if serde_json::from_str::<EncryptedAccountExport>(content).is_ok() {
+        let password = password
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| AppError::Config("导入文件已加密，请输入导出口令".to_string()))?;
+        decrypt_account_export(content, password)?
+    } else {
+        (ACCOUNT_EXPORT_JSON.to_string(), content.to_string())
+    };
+    if format == ACCOUNT_EXPORT_CSV {
+        return Ok(json!({ "accounts": accounts_from_csv(&content)? }));
+    }
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// 将分析数据展开为 JSON Lines 记录
+///
+/// 每条记录都带有 `record_type`、`account_id`、`email`、`date`，便于按行流式导入
+pub fn analytics_to_jsonl_rows(account: &Account, data: &AnalyticsData) -> Vec<Value> {
+    let account_id = account.id.to_string();
+    let mut rows = Vec::new();
+
+    for day in &data.daily_cascade_lines {
+        rows.push(json!({
+            "record_type": "cascade_lines",
+            "account_id": account_id,
+            "email": account.email,
+            "date": day.date,
+            "accepted_lines": day.accepted_lines,
+            "suggested_lines": day.suggested_lines,
+        }));
+    }
+
+    // 模型使用按 (日期, 模型) 聚合
+    let mut model_rows: HashMap<(String, String), (i64, i64, i64)> = HashMap::new();
+    for entry in &data.model_usage_details {
+        let item = model_rows
+            .entry((entry.date.clone(), entry.model_name.clone()))
+            .or_insert((0, 0, 0));
+        item.0 += 1;
+        item.1 += entry.session_count;
+        item.2 += entry.token_usage;
+    }
+    let mut model_keys: Vec<_> = model_rows.keys().cloned().collect();
+    model_keys.sort();
+    for key in model_keys {
+        let (runs, messages, tokens) = model_rows[&key];
+        rows.push(json!({
+            "record_type": "model_usage",
+            "account_id": account_id,
+            "email": account.email,
+            "date": key.0,
+            "model": key.1,
+            "runs": runs,
+            "messages": messages,
+            "tokens": tokens,
+        }));
+    }
+
+    for day in &data.completions_by_day {
+        rows.push(json!({
+            "record_type": "completions",
+            "account_id": account_id,
+            "email": account.email,
+            "date": day.date,
+            "acceptances": day.statistics.num_acceptances,
+            "rejections": day.statistics.num_rejections,
+            "lines_accepted": day.statistics.num_lines_accepted,
+            "bytes_accepted": day.statistics.num_bytes_accepted,
+        }));
+    }
+
+    for day in &data.chats_by_day {
+        rows.push(json!({
+            "record_type": "chats",
+            "account_id": account_id,
+            "email": account.email,
+            "date": day.date,
+            "chats_sent": day.stats.chats_sent,
+            "chats_accepted": day.stats.chats_accepted,
+            "chat_loc_used": day.stats.chat_loc_used,
+        }));
+    }
+
+    rows
+}
+
+/// 会话账单的一行：一个会话中某个模型的用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionLedgerRow {
+    pub session_id: String,
+    pub model: String,
+    /// 会话中该模型首次运行的日期
+    pub date: String,
+    pub messages: i64,
+    pub tokens: i64,
+    pub estimated_credits: f64,
+}
+
+/// 未配置单价的模型按每条消息 1 积分估算
+const DEFAULT_CREDIT_RATE: f64 = 1.0;
+
+/// 模型单条消息的估算积分（`rates` 来自设置中的 modelCreditRates）
+pub fn credit_rate(rates: &HashMap<String, f64>, model: &str) -> f64 {
+    rates.get(model).copied().unwrap_or(DEFAULT_CREDIT_RATE)
+}
+
+/// 按 (会话, 模型) 聚合 Cascade 运行记录
+///
+/// 积分按 `rates` 中模型的单条消息价格估算，未配置的模型按每条消息 1 积分计
+pub fn session_ledger_rows(runs: &[ModelUsageEntry], rates: &HashMap<String, f64>) -> Vec<SessionLedgerRow> {
+    let mut rows: Vec<SessionLedgerRow> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+
+    let mut sorted: Vec<&ModelUsageEntry> = runs.iter().collect();
+    sorted.sort_by_key(|e| e.timestamp);
+    for entry in sorted {
+        let key = (entry.session_id.clone(), entry.model_name.clone());
+        let i = *index.entry(key).or_insert_with(|| {
+            rows.push(SessionLedgerRow {
+                session_id: entry.session_id.clone(),
+                model: entry.model_name.clone(),
+                date: entry.date.clone(),
+                messages: 0,
+                tokens: 0,
+                estimated_credits: 0.0,
+            });
+            rows.len() - 1
+        });
+        rows[i].messages += entry.session_count;
+        rows[i].tokens += entry.token_usage;
+    }
+
+    for row in &mut rows {
+        row.estimated_credits = row.messages as f64 * credit_rate(rates, &row.model);
+    }
+    rows
+}
+
+/// 将会话账单转为 CSV
+pub fn session_ledger_csv(rows: &[SessionLedgerRow]) -> String {
+    let mut content = String::from("session_id,model,tokens,estimated_credits,date\n");
+    for row in rows {
+        let line = [
+            csv_field(&row.session_id),
+            csv_field(&row.model),
+            row.tokens.to_string(),
+            format!("{:.2}", row.estimated_credits),
+            csv_field(&row.date),
+        ]
+        .join(",");
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// 模型费用报表的一行：某个账号在某个周期内某个模型的用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelCostRow {
+    /// 周期：按月时为 YYYY-MM，否则为 YYYY-MM-DD
+    pub period: String,
+    pub account_email: String,
+    pub tags: Vec<String>,
+    pub model: String,
+    pub messages: i64,
+    pub tokens: i64,
+    pub estimated_credits: f64,
+}
+
+/// 合并账号的历史快照，按 (周期, 模型) 聚合 `since` 之后的 Cascade 运行记录
+///
+/// 同一条运行记录可能出现在多份快照中，按 (会话, 时间, 模型) 去重；积分估算规则同会话账单
+pub fn model_cost_rows(
+    account: &Account,
+    snapshots: &[AnalyticsSnapshot],
+    since: i64,
+    rates: &HashMap<String, f64>,
+    group_by_month: bool,
+) -> Vec<ModelCostRow> {
+    let mut runs: HashMap<(&str, i64, &str), &ModelUsageEntry> = HashMap::new();
+    for entry in snapshots.iter().flat_map(|s| &s.data.model_usage_details) {
+        if entry.timestamp >= since {
+            runs.insert((entry.session_id.as_str(), entry.timestamp, entry.model_name.as_str()), entry);
+        }
+    }
+
+    let mut grouped: HashMap<(String, String), (i64, i64)> = HashMap::new();
+    for entry in runs.into_values() {
+        let period = if group_by_month {
+            entry.date.get(..7).unwrap_or(&entry.date).to_string()
+        } else {
+            entry.date.clone()
+        };
+        let totals = grouped.entry((period, entry.model_name.clone())).or_default();
+        totals.0 += entry.session_count;
+        totals.1 += entry.token_usage;
+    }
+
+    let mut rows: Vec<ModelCostRow> = grouped
+        .into_iter()
+        .map(|((period, model), (messages, tokens))| {
+            let rate = credit_rate(rates, &model);
+            ModelCostRow {
+                period,
+                account_email: account.email.clone(),
+                tags: account.tags.clone(),
+                model,
+                messages,
+                tokens,
+                estimated_credits: messages as f64 * rate,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.period.cmp(&b.period).then_with(|| a.model.cmp(&b.model)));
+    rows
+}
+
+/// 将模型费用报表转为 CSV，多个标签以分号分隔
+pub fn model_cost_csv(rows: &[ModelCostRow]) -> String {
+    let mut content = String::from("period,account,tags,model,messages,tokens,estimated_credits\n");
+    for row in rows {
+        let line = [
+            csv_field(&row.period),
+            csv_field(&row.account_email),
+            csv_field(&row.tags.join(";")),
+            csv_field(&row.model),
+            row.messages.to_string(),
+            row.tokens.to_string(),
+            format!("{:.2}", row.estimated_credits),
+        ]
+        .join(",");
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// 枚举的 serde 名称（如 batch_operation），用于 CSV 中与日志筛选条件保持一致
+fn serde_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// 将操作日志（审计日志）转为 CSV，时间为 UTC（RFC 3339）
+pub fn audit_log_csv(logs: &[OperationLog]) -> String {
+    let mut content = String::from("timestamp,operation,status,account_id,account_email,message,details\n");
+    for log in logs {
+        let line = [
+            log.timestamp.to_rfc3339(),
+            serde_name(&log.operation_type),
+            serde_name(&log.status),
+            log.account_id.map(|id| id.to_string()).unwrap_or_default(),
+            csv_field(log.account_email.as_deref().unwrap_or_default()),
+            csv_field(&log.message),
+            csv_field(&log.details.as_ref().map(|d| d.to_string()).unwrap_or_default()),
+        ]
+        .join(",");
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// 任务历史的一行：批量操作或后台定时任务的一次执行
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobHistoryRow {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// batch（批量操作）或 scheduler（后台定时任务）
+    pub source: String,
+    /// 定时任务名称，批量操作为空
+    pub job: String,
+    /// success / failed / skipped
+    pub status: String,
+    pub message: String,
+}
+
+/// 合并批量操作日志、超时取消日志与定时任务运行记录，筛选 [since, until] 内的记录并按开始时间升序排列
+pub fn job_history_rows(
+    logs: &[OperationLog],
+    runs: &[JobRun],
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Vec<JobHistoryRow> {
+    let batch = logs
+        .iter()
+        .filter_map(|log| match log.operation_type {
+            OperationType::BatchOperation => Some((log, "batch")),
+            OperationType::JobCancelled => Some((log, "watchdog")),
+            _ => None,
+        })
+        .map(|(log, source)| JobHistoryRow {
+            started_at: log.timestamp,
+            finished_at: None,
+            source: source.to_string(),
+            job: String::new(),
+            status: serde_name(&log.status),
+            message: log.message.clone(),
+        });
+    let scheduled = runs.iter().map(|run| JobHistoryRow {
+        started_at: run.started_at,
+        finished_at: Some(run.finished_at),
+        source: "scheduler".to_string(),
+        job: serde_name(&run.job),
+        status: if run.skipped {
+            "skipped".to_string()
+        } else {
+            serde_name(&if run.success { OperationStatus::Success } else { OperationStatus::Failed })
+        },
+        message: run.message.clone(),
+    });
+
+    let mut rows: Vec<JobHistoryRow> = batch
+        .chain(scheduled)
+        .filter(|row| since.map(|s| row.started_at >= s).unwrap_or(true))
+        .filter(|row| until.map(|u| row.started_at <= u).unwrap_or(true))
+        .collect();
+    rows.sort_by_key(|row| row.started_at);
+    rows
+}
+
+/// 将任务历史转为 CSV，时间为 UTC（RFC 3339）
+pub fn job_history_csv(rows: &[JobHistoryRow]) -> String {
+    let mut content = String::from("started_at,finished_at,source,job,status,message\n");
+    for row in rows {
+        let line = [
+            row.started_at.to_rfc3339(),
+            row.finished_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            csv_field(&row.source),
+            csv_field(&row.job),
+            csv_field(&row.status),
+            csv_field(&row.message),
+        ]
+        .join(",");
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// 列出导出目录下的所有自动导出（按名称即时间升序）
+pub fn list_export_dirs(folder: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_dir()
+                        && path
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .map(|name| name.starts_with(EXPORT_DIR_PREFIX))
+                            .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// 删除中断的导出留下的临时目录
+fn remove_stale_temp_dirs(folder: &Path) {
+    let prefix = format!("{}{}", TEMP_DIR_PREFIX, EXPORT_DIR_PREFIX);
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let is_temp = path.is_dir()
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(&prefix))
+                .unwrap_or(false);
+        if is_temp {
+            println!("[ExportService] Removing incomplete export {}", path.display());
+            if let Err(e) = fs::remove_dir_all(&path) {
+                println!("[ExportService] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 距离上次导出是否已超过配置的间隔
+fn is_export_due(config: &AutoExportConfig, folder: &Path) -> bool {
+    let latest = match list_export_dirs(folder).pop() {
+        Some(dir) => dir,
+        None => return true,
+    };
+    let modified = match fs::metadata(&latest).and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return true,
+    };
+    let elapsed = modified.elapsed().unwrap_or_default();
+    elapsed.as_secs() >= config.interval_hours.max(1) * 3600
+}
+
+/// 写入一次导出：accounts.csv + analytics.jsonl + manifest.json，并按保留数量轮换
+///
+/// 包含凭据时账号数据用导出口令加密，写入 `ENCRYPTED_ACCOUNTS_FILE` 而不是明文 CSV
+pub async fn write_export(store: &Arc<DataStore>, config: &AutoExportConfig) -> AppResult<PathBuf> {
+    let password = config.password.as_deref().filter(|p| !p.is_empty());
+    if config.include_secrets && password.is_none() {
+        return Err(AppError::Config("自动导出包含凭据时必须设置导出口令".to_string()));
+    }
+    // 导出口令与凭据一起加密保存，主密码未解锁时仍是密文
+    if password.map(crate::repository::secrets::is_encrypted).unwrap_or(false) {
+        return Err(AppError::StoreLocked);
+    }
+    let folder = PathBuf::from(&config.folder);
+    fs::create_dir_all(&folder)?;
+    remove_stale_temp_dirs(&folder);
+
+    let accounts = store.get_all_accounts().await?;
+    let groups = store.get_groups().await?;
+    let tags = store.get_tags().await?;
+
+    // 分析数据使用每个账号最近一次的快照，避免导出时批量请求接口
+    let mut analytics_lines = Vec::new();
+    for account in &accounts {
+        let snapshots = store.get_analytics_snapshots(account.id).await?;
+        if let Some(latest) = snapshots.last() {
+            for row in analytics_to_jsonl_rows(account, &latest.data) {
+                analytics_lines.push(row.to_string());
+            }
+        }
+    }
+
+    let now = chrono::Local::now();
+    let manifest = json!({
+        "version": "1.0",
+        "exported_at": now.to_rfc3339(),
+        "include_secrets": config.include_secrets,
+        "account_count": accounts.len(),
+        "analytics_rows": analytics_lines.len(),
+        "groups": groups,
+        "tags": tags,
+    });
+    let export_dir = folder.join(format!("{}{}", EXPORT_DIR_PREFIX, now.format("%Y%m%d_%H%M%S")));
+    // 先写入临时目录，完整写完后再重命名，避免同步盘上出现半成品；写入失败时删除临时目录
+    let temp_dir = folder.join(format!("{}{}{}", TEMP_DIR_PREFIX, EXPORT_DIR_PREFIX, now.format("%Y%m%d_%H%M%S")));
+    fs::create_dir_all(&temp_dir)?;
+    if let Err(e) = write_export_files(&temp_dir, config, password, &accounts, &analytics_lines, &manifest) {
+        let _ = fs::remove_dir_all(&temp_dir);
+        return Err(e);
+    }
+    fs::rename(&temp_dir, &export_dir)?;
+
+    // 轮换：只保留最近 N 次导出
+    let dirs = list_export_dirs(&folder);
+    let keep = config.keep.max(1);
+    if dirs.len() > keep {
+        for old in &dirs[..dirs.len() - keep] {
+            if let Err(e) = fs::remove_dir_all(old) {
+                println!("[ExportService] Failed to remove old export {}: {}", old.display(), e);
+            }
+        }
+    }
+
+    Ok(export_dir)
+}
+
+/// 把一次导出的所有文件写入临时目录
+fn write_export_files(
+    temp_dir: &Path,
+    config: &AutoExportConfig,
+    password: Option<&str>,
+    accounts: &[Account],
+    analytics_lines: &[String],
+    manifest: &Value,
+) -> AppResult<()> {
+    if config.include_secrets {
+        let content = build_account_export(accounts, ACCOUNT_EXPORT_CSV, true, password)?;
+        fs::write(temp_dir.join(ENCRYPTED_ACCOUNTS_FILE), content)?;
+    } else {
+        fs::write(temp_dir.join("accounts.csv"), accounts_to_csv(accounts, false))?;
+    }
+    let mut analytics_content = analytics_lines.join("\n");
+    if !analytics_content.is_empty() {
+        analytics_content.push('\n');
+    }
+    fs::write(temp_dir.join("analytics.jsonl"), analytics_content)?;
+    fs::write(temp_dir.join("manifest.json"), serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// 定时任务入口：未启用或未到间隔时跳过
+pub async fn run_auto_export(store: &Arc<DataStore>) -> AppResult<String> {
+    let config = store.get_settings().await?.auto_export;
+    if !config.enabled {
+        return Ok("自动导出未启用".to_string());
+    }
+    if config.folder.trim().is_empty() {
+        return Err(AppError::Config("Auto export folder is not configured".to_string()));
+    }
+    if !is_export_due(&config, Path::new(&config.folder)) {
+        return Ok("未到导出间隔".to_string());
+    }
+
+    let export_dir = write_export(store, &config).await?;
+    println!("[ExportService] Exported to {}", export_dir.display());
+    Ok(format!("已导出到 {}", export_dir.display()))
+}
+
+/// 从导出恢复的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreResult {
+    pub export_dir: String,
+    pub exported_at: Option<String>,
+    pub accounts_restored: usize,
+    /// 导出中是否包含密码与凭据；不包含时沿用本地已有的凭据
+    pub secrets_included: bool,
+    pub groups_restored: usize,
+    pub snapshots_restored: usize,
+    /// 预演（dry_run）时将要发生的变更
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImportPreview>,
+}
+
+/// 解析 CSV（支持双引号包裹、转义引号和字段内换行）
+pub fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+fn parse_time(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+/// 生成批量导入模板：与批量导入对话框（邮箱密码模式）解析的格式一致，
+/// 每行 `邮箱 密码 [备注]`，以空白分隔，备注可省略
+///
+/// 对话框会把每个非空行都当作账号解析，因此模板中只放示例行、不放注释
+pub fn import_template_text() -> String {
+    "user1@example.com your-password 示例备注\nuser2@example.com another-password\n".to_string()
+}
+
+/// 由 accounts.csv 重建账号列表（按表头定位列，缺失的列使用默认值）
+pub fn accounts_from_csv(content: &str) -> AppResult<Vec<Account>> {
+    let mut rows = parse_csv(content).into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| AppError::FileOperation("accounts.csv is empty".to_string()))?;
+    let index: HashMap<&str, usize> = header.iter().enumerate().map(|(i, name)| (name.as_str(), i)).collect();
+    if !index.contains_key("email") {
+        return Err(AppError::FileOperation("accounts.csv has no email column".to_string()));
+    }
+
+    let mut accounts = Vec::new();
+    for row in rows {
+        let get = |column: &str| -> String {
+            index
+                .get(column)
+                .and_then(|i| row.get(*i))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let optional = |column: &str| -> Option<String> {
+            let value = get(column);
+            if value.is_empty() {
+                None
+            } else {
+                Some(value)
+            }
+        };
+
+        let email = get("email");
+        if email.is_empty() {
+            continue;
+        }
+
+        let tags = get("tags");
+        let mut account = Account::new(
+            email,
+            get("password"),
+            get("nickname"),
+            if tags.is_empty() {
+                Vec::new()
+            } else {
+                tags.split('|').map(|t| t.to_string()).collect()
+            },
+        );
+        if let Ok(id) = Uuid::parse_str(&get("id")) {
+            account.id = id;
+        }
+        account.group = optional("group");
+        account.status = match get("status").as_str() {
+            "active" => AccountStatus::Active,
+            "needs_attention" => AccountStatus::NeedsAttention,
+            status if status.starts_with("error:") => AccountStatus::Error(status[6..].to_string()),
+            _ => AccountStatus::Inactive,
+        };
+        account.plan_name = optional("plan_name");
+        if let Some(created_at) = parse_time(&get("created_at")) {
+            account.created_at = created_at;
+        }
+        account.last_login_at = parse_time(&get("last_login_at"));
+        account.subscription_expires_at = parse_time(&get("subscription_expires_at"));
+        account.sort_order = get("sort_order").parse().unwrap_or(0);
+        account.refresh_token = optional("refresh_token");
+        account.windsurf_api_key = optional("windsurf_api_key");
+
+        accounts.push(account);
+    }
+
+    Ok(accounts)
+}
+
+/// 由 analytics.jsonl 重建分析快照（每个账号一份，日期为导出日期）
+///
+/// JSONL 只保存了按日汇总的数据，因此恢复的快照只包含每日代码行数与补全统计
+pub fn snapshots_from_jsonl(content: &str, date: &str) -> Vec<AnalyticsSnapshot> {
+    let mut by_account: HashMap<Uuid, AnalyticsData> = HashMap::new();
+
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let row: Value = match serde_json::from_str(line) {
+            Ok(row) => row,
+            Err(_) => continue,
+        };
+        let account_id = match row.get("account_id").and_then(|v| v.as_str()).and_then(|v| Uuid::parse_str(v).ok()) {
+            Some(id) => id,
+            None => continue,
+        };
+        let int = |key: &str| row.get(key).and_then(|v| v.as_i64()).unwrap_or(0);
+        let day = row.get("date").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let timestamp = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc().timestamp())
+            .unwrap_or(0);
+        let data = by_account.entry(account_id).or_insert_with(AnalyticsData::empty);
+
+        match row.get("record_type").and_then(|v| v.as_str()) {
+            Some("cascade_lines") => data.daily_cascade_lines.push(DailyCascadeLinesCount {
+                timestamp,
+                date: day,
+                accepted_lines: int("accepted_lines"),
+                suggested_lines: int("suggested_lines"),
+            }),
+            Some("completions") => data.completions_by_day.push(CompletionByDay {
+                timestamp,
+                date: day,
+                statistics: CompletionStatistics {
+                    num_acceptances: int("acceptances"),
+                    num_rejections: int("rejections"),
+                    num_lines_accepted: int("lines_accepted"),
+                    num_bytes_accepted: int("bytes_accepted"),
+                    ..Default::default()
+                },
+            }),
+            _ => {}
+        }
+    }
+
+    let captured_at = chrono::Utc::now();
+    by_account
+        .into_iter()
+        .map(|(account_id, data)| AnalyticsSnapshot {
+            account_id,
+            date: date.to_string(),
+            captured_at,
+            data,
+            raw_body: None,
+            extractor_version: 0,
+        })
+        .collect()
+}
+
+/// 解析恢复路径：可以是某次导出目录，也可以是导出文件夹（使用最近一次导出）
+fn resolve_export_dir(path: &Path) -> AppResult<PathBuf> {
+    if path.join("accounts.csv").exists() || path.join(ENCRYPTED_ACCOUNTS_FILE).exists() {
+        return Ok(path.to_path_buf());
+    }
+    list_export_dirs(path)
+        .pop()
+        .ok_or_else(|| AppError::FileOperation(format!("No export found in {}", path.display())))
+}
+
+/// 读取导出的账号，加密的导出使用自动导出设置中的口令解密
+async fn read_exported_accounts(store: &Arc<DataStore>, export_dir: &Path) -> AppResult<Vec<Account>> {
+    let encrypted = export_dir.join(ENCRYPTED_ACCOUNTS_FILE);
+    if !encrypted.exists() {
+        return accounts_from_csv(&fs::read_to_string(export_dir.join("accounts.csv"))?);
+    }
+    let password = store
+        .get_settings()
+        .await?
+        .auto_export
+        .password
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| AppError::Config("导出文件已加密，请先在自动导出设置中填写导出口令".to_string()))?;
+    let (_, content) = decrypt_account_export(&fs::read_to_string(encrypted)?, &password)?;
+    accounts_from_csv(&content)
+}
+
+/// 从自动导出的文件重建数据（覆盖账号、分组和标签，恢复前会自动备份当前数据）
+///
+/// `dry_run` 为 true 时只读取导出文件，返回将要恢复、移除的账号与新增的快照数量
+pub async fn restore_from_export(store: &Arc<DataStore>, path: &Path, dry_run: bool) -> AppResult<RestoreResult> {
+    let export_dir = resolve_export_dir(path)?;
+
+    let manifest: Value = fs::read_to_string(export_dir.join("manifest.json"))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or(Value::Null);
+    let secrets_included = manifest.get("include_secrets").and_then(|v| v.as_bool()).unwrap_or(false);
+    let exported_at = manifest.get("exported_at").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let groups: Vec<String> = manifest
+        .get("groups")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let tags: Vec<GlobalTag> = manifest
+        .get("tags")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    let accounts = read_exported_accounts(store, &export_dir).await?;
+    let accounts_restored = accounts.len();
+    let groups_restored = groups.len();
+    let snapshot_date = exported_at
+        .as_deref()
+        .and_then(|t| t.get(0..10))
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+    let snapshots = fs::read_to_string(export_dir.join("analytics.jsonl"))
+        .ok()
+        .map(|content| snapshots_from_jsonl(&content, &snapshot_date));
+
+    if dry_run {
+        // 恢复按账号 ID 覆盖，同 ID 的账号视为更新
+        let existing = store.get_all_accounts().await?;
+        let preview = ImportPreview::replacing(&existing, &accounts, |a, b| a.id == b.id);
+        let snapshots_restored = match &snapshots {
+            Some(snapshots) => store.count_new_snapshots(snapshots).await,
+            None => 0,
+        };
+        return Ok(RestoreResult {
+            export_dir: export_dir.to_string_lossy().to_string(),
+            exported_at,
+            accounts_restored,
+            secrets_included,
+            groups_restored,
+            snapshots_restored,
+            preview: Some(preview),
+        });
+    }
+
+    store.restore_exported_data(accounts, groups, tags).await?;
+    let snapshots_restored = match snapshots {
+        Some(snapshots) => store.import_analytics_snapshots(snapshots).await?,
+        None => 0,
+    };
+
+    println!(
+        "[ExportService] Restored {} accounts from {}",
+        accounts_restored,
+        export_dir.display()
+    );
+
+    Ok(RestoreResult {
+        export_dir: export_dir.to_string_lossy().to_string(),
+        exported_at,
+        accounts_restored,
+        secrets_included,
+        groups_restored,
+        snapshots_restored,
+        preview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_history_rows_merges_and_filters() {
+        let now = Utc::now();
+        let mut batch = OperationLog::new(OperationType::BatchOperation, OperationStatus::Success, "批量重置积分: 成功 2/2 个账号".to_string());
+        batch.timestamp = now - chrono::Duration::hours(2);
+        let mut old = batch.clone();
+        old.timestamp = now - chrono::Duration::days(10);
+        let single = OperationLog::new(OperationType::Login, OperationStatus::Failed, "login".to_string());
+        let mut cancelled = OperationLog::new(OperationType::JobCancelled, OperationStatus::Failed, "后台任务超时".to_string());
+        cancelled.timestamp = now - chrono::Duration::minutes(30);
+        let run = JobRun {
+            job: crate::models::JobKind::TokenRefresh,
+            started_at: now - chrono::Duration::hours(1),
+            finished_at: now - chrono::Duration::hours(1),
+            success: true,
+            skipped: true,
+            message: "按流量计费的网络，已跳过".to_string(),
+        };
+
+        let rows = job_history_rows(&[old, single, batch, cancelled], &[run], Some(now - chrono::Duration::days(1)), None);
+        assert_eq!(rows.len(), 3);
+        assert_eq!((rows[0].source.as_str(), rows[0].status.as_str()), ("batch", "success"));
+        assert_eq!((rows[1].job.as_str(), rows[1].status.as_str()), ("token_refresh", "skipped"));
+        assert_eq!((rows[2].source.as_str(), rows[2].status.as_str()), ("watchdog", "failed"));
+
+        let csv = job_history_csv(&rows);
+        assert!(csv.lines().nth(2).unwrap().contains(",scheduler,token_refresh,skipped,"));
+    }
+
+    #[test]
+    fn test_accounts_to_csv_escapes_and_hides_secrets() {
+        let mut account = Account::new(
+            "a@example.com".to_string(),
+            "secret".to_string(),
+            "Team, \"A\"".to_string(),
+            vec!["x".to_string(), "y".to_string()],
+        );
+        account.refresh_token = Some("refresh-value".to_string());
+
+        let csv = accounts_to_csv(&[account], false);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains("\"Team, \"\"A\"\"\""));
+        assert!(row.contains("x|y"));
+        assert!(!row.contains("secret"));
+        assert!(!row.contains("refresh-value"));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let mut account = Account::new(
+            "b@example.com".to_string(),
+            "pw".to_string(),
+            "multi\nline, \"quoted\"".to_string(),
+            vec!["x".to_string()],
+        );
+        account.group = Some("g1".to_string());
+        account.refresh_token = Some("refresh-value".to_string());
+
+        let restored = accounts_from_csv(&accounts_to_csv(&[account.clone()], true)).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, account.id);
+        assert_eq!(restored[0].nickname, account.nickname);
+        assert_eq!(restored[0].group, account.group);
+        assert_eq!(restored[0].password, "pw");
+        assert_eq!(restored[0].refresh_token.as_deref(), Some("refresh-value"));
+    }
+
+    #[test]
+    fn test_encrypted_account_export() {
+        let mut account = Account::new(
+            "c@example.com".to_string(),
+            "pw".to_string(),
+            "C".to_string(),
+            Vec::new(),
+        );
+        account.windsurf_api_key = Some("api-key".to_string());
+
+        let plain = build_account_export(&[account.clone()], ACCOUNT_EXPORT_JSON, false, None).unwrap();
+        assert!(plain.contains("c@example.com"));
+        assert!(!plain.contains("api-key"));
+
+        let encrypted = build_account_export(&[account], ACCOUNT_EXPORT_CSV, true, Some("export-pass")).unwrap();
+        assert!(!encrypted.contains("c@example.com"));
+        let (format, content) = decrypt_account_export(&encrypted, "export-pass").unwrap();
+        assert_eq!(format, ACCOUNT_EXPORT_CSV);
+        assert!(content.contains("api-key"));
+        assert!(decrypt_account_export(&encrypted, "wrong").is_err());
+
+        // 导入时解密并转换 CSV
+        let imported = read_import_content(&encrypted, Some("export-pass")).unwrap();
+        assert_eq!(imported["accounts"][0]["email"], "c@example.com");
+        assert!(read_import_content(&encrypted, None).is_err());
+        assert_eq!(read_import_content(&plain, None).unwrap()["accounts"][0]["email"], "c@example.com");
+    }
+
+    #[test]
+    fn test_session_ledger_rows() {
+        let run = |session: &str, model: &str, timestamp: i64, messages: i64, tokens: i64| {
+            ModelUsageEntry::test_run(session, model, tokens)
+                .at(timestamp, &format!("2026-01-0{}", timestamp))
+                .messages(messages)
+        };
+        let runs = vec![
+            run("s1", "Claude", 2, 3, 300),
+            run("s1", "Claude", 1, 2, 200),
+            run("s1", "GPT-4o", 3, 1, 50),
+            run("s2", "Claude", 4, 4, 400),
+        ];
+        let rates = HashMap::from([("Claude".to_string(), 2.0)]);
+
+        let rows = session_ledger_rows(&runs, &rates);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].session_id, "s1");
+        assert_eq!(rows[0].date, "2026-01-01");
+        assert_eq!(rows[0].messages, 5);
+        assert_eq!(rows[0].tokens, 500);
+        assert_eq!(rows[0].estimated_credits, 10.0);
+        assert_eq!(rows[1].estimated_credits, 1.0);
+
+        let csv = session_ledger_csv(&rows);
+        assert_eq!(csv.lines().count(), 4);
+        assert_eq!(csv.lines().nth(1), Some("s1,Claude,500,10.00,2026-01-01"));
+    }
+
+    #[test]
+    fn test_model_cost_rows() {
+        let run = |session: &str, model: &str, timestamp: i64, date: &str, messages: i64| {
+            ModelUsageEntry::test_run(session, model, messages * 100).at(timestamp, date).messages(messages)
+        };
+        let account = Account::new("a@example.com".to_string(), String::new(), "A".to_string(), vec!["team-a".to_string(), "ops".to_string()]);
+        let snapshot = |runs: Vec<ModelUsageEntry>| {
+            AnalyticsSnapshot::test_snapshot(account.id, "2026-02-01", AnalyticsData { model_usage_details: runs, ..Default::default() })
+        };
+        // 第二份快照包含重复的运行记录
+        let snapshots = vec![
+            snapshot(vec![run("s1", "Claude", 10, "2026-01-05", 2), run("s2", "Claude", 20, "2026-01-20", 3)]),
+            snapshot(vec![run("s2", "Claude", 20, "2026-01-20", 3), run("s3", "GPT-4o", 30, "2026-02-01", 1)]),
+        ];
+        let rates = HashMap::from([("Claude".to_string(), 2.0)]);
+
+        let monthly = model_cost_rows(&account, &snapshots, 0, &rates, true);
+        assert_eq!(monthly.len(), 2);
+        assert_eq!(monthly[0].period, "2026-01");
+        assert_eq!(monthly[0].messages, 5);
+        assert_eq!(monthly[0].estimated_credits, 10.0);
+        assert_eq!(monthly[1].model, "GPT-4o");
+
+        let daily = model_cost_rows(&account, &snapshots, 15, &rates, false);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].period, "2026-01-20");
+
+        let csv = model_cost_csv(&monthly);
+        assert_eq!(csv.lines().nth(1), Some("2026-01,a@example.com,team-a;ops,Claude,5,500,10.00"));
+    }
+
+    #[test]
+    fn test_import_template_matches_importer() {
+        // 与 BatchImportDialog.vue 的邮箱密码模式相同：按空白拆分，第一段为邮箱且至少两段
+        let template = import_template_text();
+        let lines: Vec<Vec<&str>> = template
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|parts| parts.len() >= 2 && parts[0].contains('@')));
+        assert_eq!(lines[0][2..].join(" "), "示例备注");
+    }
+}
@@ -0,0 +1,216 @@
+use super::notification_service::{self, NotificationCategory};
+use super::shared_use_service::{self, SharedUseAlert};
+use super::WindsurfService;
+use crate::models::{Account, AccountStatus, HealthPingConfig, OperationLog, OperationStatus, OperationType};
+use crate::repository::DataStore;
+use crate::utils::AppResult;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// 服务端标记账号被禁用时的异常原因
+const DISABLED_REASON: &str = "账号已被禁用";
+
+/// 健康检查发现的异常账号（聚合后一次性通知前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthPingAlert {
+    pub account_id: String,
+    pub email: String,
+    pub reason: String,
+}
+
+/// 是否应对该账号发起健康检查：有未过期的 Token、未退出检查且已到检查间隔
+fn is_due(account: &Account, config: &HealthPingConfig, now: DateTime<Utc>) -> bool {
+    if account.health_ping_opt_out
+        || matches!(account.status, AccountStatus::NeedsAttention)
+        || account.is_disabled == Some(true)
+        || account.token.is_none()
+    {
+        return false;
+    }
+    // 过期的 Token 交给 Token 刷新任务处理
+    if account.token_expires_at.map(|exp| exp <= now).unwrap_or(true) {
+        return false;
+    }
+    let interval = account
+        .schedule_overrides
+        .health_ping_minutes
+        .unwrap_or(config.interval_minutes);
+    account
+        .last_health_ping_at
+        .map(|last| now - last >= chrono::Duration::minutes(interval.max(1)))
+        .unwrap_or(true)
+}
+
+/// 按类别汇总弹出通知：被禁用的账号与认证失败的账号分开
+async fn notify_alerts(store: &DataStore, alerts: &[HealthPingAlert]) {
+    let (banned, auth_failed): (Vec<&HealthPingAlert>, Vec<&HealthPingAlert>) =
+        alerts.iter().partition(|a| a.reason == DISABLED_REASON);
+    for (category, title, group) in [
+        (NotificationCategory::AccountBanned, "账号已被禁用", banned),
+        (NotificationCategory::TokenExpired, "账号认证失败", auth_failed),
+    ] {
+        if group.is_empty() {
+            continue;
+        }
+        let emails: Vec<&str> = group.iter().map(|a| a.email.as_str()).collect();
+        notification_service::notify(store, category, title, &emails.join(", ")).await;
+    }
+}
+
+/// 定时任务入口：对到期账号逐个发起 GetCurrentUser，请求之间随机等待；遇到限流时提前结束本轮
+pub async fn run_health_ping(store: &Arc<DataStore>) -> AppResult<String> {
+    let settings = store.get_settings().await?;
+    let config = settings.health_ping;
+    if !config.enabled {
+        return Ok("健康检查未启用".to_string());
+    }
+
+    let now = Utc::now();
+    let due: Vec<Account> = store
+        .get_all_accounts()
+        .await?
+        .into_iter()
+        .filter(|a| is_due(a, &config, now))
+        .collect();
+    if due.is_empty() {
+        return Ok("没有需要检查的账号".to_string());
+    }
+
+    let mut checked = 0;
+    let mut rate_limited = false;
+    let mut alerts = Vec::new();
+    let mut shared_alerts = Vec::new();
+
+    for (index, account) in due.iter().enumerate() {
+        if index > 0 && config.jitter_seconds > 0 {
+            let wait = rand::thread_rng().gen_range(0..=config.jitter_seconds);
+            tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+        }
+
+        let token = match &account.token {
+            Some(token) => token.clone(),
+            None => continue,
+        };
+        let result = match WindsurfService::for_account(account.id).get_current_user(&token).await {
+            Ok(result) => result,
+            Err(e) => {
+                // 网络错误不计入认证失败
+                println!("[HealthPing] Request failed for {}: {}", account.email, e);
+                continue;
+            }
+        };
+        checked += 1;
+
+        let status_code = result.get("status_code").and_then(|v| v.as_u64()).unwrap_or(0);
+        if status_code == 429 {
+            println!("[HealthPing] Rate limited, stopping this round");
+            rate_limited = true;
+            break;
+        }
+
+        // 对比服务端状态与本地记录，发现疑似在其他设备使用的账号
+        let shared_reason = if result.get("success").and_then(|v| v.as_bool()) == Some(true) {
+            let field = |name: &str| {
+                result
+                    .pointer(&format!("/user_info/user/{}", name))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            shared_use_service::profile_mismatch(account, &field("api_key"), &field("email"))
+        } else {
+            shared_use_service::early_revocation(account, status_code, Utc::now())
+        };
+        if let Some(reason) = shared_reason {
+            if store.flag_shared_use_no_save(account.id, reason.clone()).await? {
+                shared_alerts.push(SharedUseAlert {
+                    account_id: account.id.to_string(),
+                    email: account.email.clone(),
+                    reason,
+                });
+            }
+        }
+
+        if result.get("success").and_then(|v| v.as_bool()) == Some(true) {
+            let disabled = result
+                .pointer("/user_info/user/disable_codeium")
+                .and_then(|v| v.as_bool());
+            store.record_health_ping_no_save(account.id, disabled).await?;
+            if disabled == Some(true) {
+                alerts.push(HealthPingAlert {
+                    account_id: account.id.to_string(),
+                    email: account.email.clone(),
+                    reason: DISABLED_REASON.to_string(),
+                });
+            }
+        } else {
+            store.record_health_ping_no_save(account.id, None).await?;
+            if status_code == 401 || status_code == 403 {
+                let error = format!("健康检查认证失败 (HTTP {})", status_code);
+                store
+                    .record_auth_failure_no_save(account.id, error.clone(), settings.auth_failure_threshold)
+                    .await?;
+                alerts.push(HealthPingAlert {
+                    account_id: account.id.to_string(),
+                    email: account.email.clone(),
+                    reason: error,
+                });
+            }
+        }
+    }
+
+    store.flush().await?;
+
+    if !alerts.is_empty() {
+        let emails: Vec<&str> = alerts.iter().map(|a| a.email.as_str()).collect();
+        let log = OperationLog::new(
+            OperationType::GetAccountInfo,
+            OperationStatus::Failed,
+            format!("健康检查发现 {} 个异常账号: {}", alerts.len(), emails.join(", ")),
+        );
+        let _ = store.add_log(log).await;
+        notify_alerts(store, &alerts).await;
+        store.emit_event("health-ping-alert", alerts.clone());
+    }
+    if !shared_alerts.is_empty() {
+        store.emit_event("accounts-possibly-shared", shared_alerts.clone());
+    }
+
+    Ok(format!(
+        "健康检查: 已检查 {}/{}，异常 {} 个，疑似共享 {} 个{}",
+        checked,
+        due.len(),
+        alerts.len(),
+        shared_alerts.len(),
+        if rate_limited { "，遇到限流已提前结束" } else { "" }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let config = HealthPingConfig::default();
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        assert!(!is_due(&account, &config, now));
+
+        account.token = Some("token".to_string());
+        account.token_expires_at = Some(now + chrono::Duration::hours(1));
+        assert!(is_due(&account, &config, now));
+
+        account.last_health_ping_at = Some(now - chrono::Duration::minutes(10));
+        assert!(!is_due(&account, &config, now));
+
+        account.schedule_overrides.health_ping_minutes = Some(5);
+        assert!(is_due(&account, &config, now));
+
+        account.last_health_ping_at = None;
+        account.health_ping_opt_out = true;
+        assert!(!is_due(&account, &config, now));
+    }
+}
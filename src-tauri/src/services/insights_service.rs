@@ -0,0 +1,97 @@
+use crate::models::{AnalyticsData, InsightsConfig};
+use crate::utils::{AppError, AppResult};
+use serde_json::{json, Value};
+
+/// 提取匿名的聚合指标
+///
+/// 只包含统计数字与模型/语言/工具名称，不包含邮箱、Token、API Key 或会话 ID
+pub fn build_anonymized_metrics(data: &AnalyticsData) -> Value {
+    let summary = &data.summary;
+    let languages: Vec<Value> = data
+        .completions_by_language
+        .iter()
+        .filter(|l| l.statistics.num_lines_accepted > 0)
+        .map(|l| json!({ "language": l.language_name, "accepted_lines": l.statistics.num_lines_accepted }))
+        .collect();
+
+    json!({
+        "period_days": data.daily_cascade_lines.len().max(data.completions_by_day.len()),
+        "cascade": {
+            "accepted_lines": summary.total_accepted_lines,
+            "suggested_lines": summary.total_suggested_lines,
+            "avg_daily_accepted_lines": summary.avg_daily_accepted_lines,
+            "peak_lines": summary.peak_lines,
+            "sessions": summary.total_sessions,
+            "tokens": summary.total_tokens,
+            "daily_accepted_lines": data.daily_cascade_lines.iter().map(|d| d.accepted_lines).collect::<Vec<_>>(),
+        },
+        "completions": {
+            "acceptances": data.completion_stats.num_acceptances,
+            "rejections": data.completion_stats.num_rejections,
+            "accepted_lines": data.completion_stats.num_lines_accepted,
+            "acceptance_rate": data.completion_stats.acceptance_rate,
+            "active_days": data.completion_stats.active_developer_days,
+        },
+        "percent_code_written": data.percent_code_written.percent_code_written,
+        "models": data.model_usage_summary.iter().map(|m| json!({
+            "model": m.model_name,
+            "runs": m.total_count,
+            "tokens": m.total_tokens,
+            "share": m.percentage,
+        })).collect::<Vec<_>>(),
+        "tools": data.tool_usage.iter().take(10).map(|t| json!({
+            "tool": t.tool_name,
+            "count": t.count,
+        })).collect::<Vec<_>>(),
+        "languages": languages,
+        "chats_sent": data.chat_stats.chats_sent,
+    })
+}
+
+/// 调用用户配置的 OpenAI 兼容接口生成使用洞察
+pub async fn generate_insights(config: &InsightsConfig, metrics: &Value, english: bool) -> AppResult<String> {
+    if !config.enabled {
+        return Err(AppError::Config("Usage insights are disabled".to_string()));
+    }
+    if config.endpoint.trim().is_empty() || config.api_key.trim().is_empty() {
+        return Err(AppError::Config("Insights endpoint/api key is not configured".to_string()));
+    }
+
+    let system_prompt = if english {
+        "You are a coding productivity analyst. Given anonymized aggregate AI coding assistant metrics, \
+         write a short narrative of notable patterns followed by 3 concrete recommendations."
+    } else {
+        "你是编程效率分析师。根据匿名的 AI 编程助手聚合指标，先简要描述值得注意的使用模式，再给出 3 条具体建议。请用中文回答。"
+    };
+
+    let url = format!("{}/chat/completions", config.endpoint.trim_end_matches('/'));
+    let body = json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": system_prompt },
+            { "role": "user", "content": metrics.to_string() },
+        ],
+        "temperature": 0.3,
+    });
+
+    let client = super::get_http_client();
+    let response = client
+        .post(&url)
+        .bearer_auth(&config.api_key)
+        .json(&body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(AppError::Api(format!("Insights request failed: {} {}", status, text)));
+    }
+
+    let result: Value = response.json().await?;
+    result
+        .pointer("/choices/0/message/content")
+        .and_then(|v| v.as_str())
+        .map(|content| content.trim().to_string())
+        .ok_or_else(|| AppError::Api("Insights response has no content".to_string()))
+}
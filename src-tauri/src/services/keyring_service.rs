@@ -0,0 +1,118 @@
+use crate::models::Account;
+use keyring::Entry;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Refresh Token 保存在数据存储中（默认）
+pub const BACKEND_DATASTORE: &str = "datastore";
+/// Refresh Token 保存在系统密钥链（Windows 凭据管理器 / macOS 钥匙串 / Secret Service）
+pub const BACKEND_KEYRING: &str = "keyring";
+
+const SERVICE_NAME: &str = "WindsurfAccountManager.RefreshToken";
+
+/// 数据存储中代替 Refresh Token 的占位值
+const PLACEHOLDER_PREFIX: &str = "keyring:";
+
+/// 已写入密钥链的值，避免每次保存都访问密钥链
+static WRITTEN: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn is_placeholder(value: &str) -> bool {
+    value.starts_with(PLACEHOLDER_PREFIX)
+}
+
+fn placeholder_for(account_id: &str) -> String {
+    format!("{}{}", PLACEHOLDER_PREFIX, account_id)
+}
+
+fn entry(account_id: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE_NAME, account_id).map_err(|e| e.to_string())
+}
+
+pub fn store_refresh_token(account_id: &str, refresh_token: &str) -> Result<(), String> {
+    let mut written = WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+    if written.get(account_id).map(|v| v == refresh_token).unwrap_or(false) {
+        return Ok(());
+    }
+    entry(account_id)?.set_password(refresh_token).map_err(|e| e.to_string())?;
+    written.insert(account_id.to_string(), refresh_token.to_string());
+    Ok(())
+}
+
+pub fn load_refresh_token(account_id: &str) -> Result<Option<String>, String> {
+    match entry(account_id)?.get_password() {
+        Ok(value) => {
+            WRITTEN.lock().unwrap_or_else(|e| e.into_inner())
+                .insert(account_id.to_string(), value.clone());
+            Ok(Some(value))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn delete_refresh_token(account_id: &str) {
+    WRITTEN.lock().unwrap_or_else(|e| e.into_inner()).remove(account_id);
+    if let Ok(entry) = entry(account_id) {
+        match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => println!("[Keyring] Failed to delete refresh token for {}: {}", account_id, e),
+        }
+    }
+}
+
+/// 将账号的 Refresh Token 写入密钥链并替换为占位值；写入失败的账号保留原值（仍存数据存储）
+pub fn externalize_refresh_tokens(accounts: &mut [Account]) -> usize {
+    let mut failed = 0;
+    for account in accounts {
+        let id = account.id.to_string();
+        let Some(value) = account.refresh_token.as_mut() else { continue };
+        if value.is_empty() || is_placeholder(value) {
+            continue;
+        }
+        match store_refresh_token(&id, value) {
+            Ok(()) => *value = placeholder_for(&id),
+            Err(e) => {
+                println!("[Keyring] Failed to store refresh token for {}: {}", account.email, e);
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+/// 将占位值替换为密钥链中的 Refresh Token，返回无法读取的数量（保留占位值）
+pub fn resolve_refresh_tokens(accounts: &mut [Account]) -> usize {
+    let mut failed = 0;
+    for account in accounts {
+        let id = account.id.to_string();
+        let Some(value) = account.refresh_token.as_mut() else { continue };
+        if !is_placeholder(value) {
+            continue;
+        }
+        match load_refresh_token(&id) {
+            Ok(Some(token)) => *value = token,
+            Ok(None) => {
+                println!("[Keyring] No refresh token in keyring for {}", account.email);
+                failed += 1;
+            }
+            Err(e) => {
+                println!("[Keyring] Failed to read refresh token for {}: {}", account.email, e);
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder() {
+        let placeholder = placeholder_for("abc");
+        assert!(is_placeholder(&placeholder));
+        assert!(!is_placeholder("AMf-vBx"));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::utils::{AppError, AppResult};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// 项目仓库中维护的映射表地址（同目录下的 `.sha256` 文件用于发现下载不完整或损坏）
+pub const DEFAULT_MAPPINGS_URL: &str =
+    "https://raw.githubusercontent.com/gloryhry/windsurf-account-manager-simple/main/mappings/mappings.json";
+
+/// 本地保存的映射表文件名
+const MAPPINGS_FILE_NAME: &str = "mappings.json";
+
+/// 语言 / 模型 / 工具名称映射表
+///
+/// 覆盖内置映射：新增的语言或模型 ID 无需发布新版本即可正确显示
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MappingTable {
+    #[serde(default)]
+    pub version: u32,
+    /// 语言 ID -> 语言名称
+    #[serde(default)]
+    pub languages: HashMap<String, String>,
+    /// 模型 ID -> 模型名称
+    #[serde(default)]
+    pub models: HashMap<String, String>,
+    /// 工具原始名称 -> 显示名称
+    #[serde(default)]
+    pub tools: HashMap<String, String>,
+}
+
+/// 映射表更新结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingRefreshResult {
+    pub version: u32,
+    pub previous_version: Option<u32>,
+    pub sha256: String,
+    pub languages: usize,
+    pub models: usize,
+    pub tools: usize,
+}
+
+/// 当前生效的映射表（None 时只使用内置映射）
+static ACTIVE: Lazy<RwLock<Option<MappingTable>>> = Lazy::new(|| RwLock::new(None));
+
+fn install(table: MappingTable) {
+    if let Ok(mut active) = ACTIVE.write() {
+        *active = Some(table);
+    }
+}
+
+fn lookup<F>(f: F) -> Option<String>
+where
+    F: FnOnce(&MappingTable) -> Option<&String>,
+{
+    ACTIVE.read().ok()?.as_ref().and_then(f).cloned()
+}
+
+pub fn language_name(language_id: i32) -> Option<String> {
+    lookup(|t| t.languages.get(&language_id.to_string()))
+}
+
+pub fn model_name(model_id: i32) -> Option<String> {
+    lookup(|t| t.models.get(&model_id.to_string()))
+}
+
+pub fn tool_name(raw_name: &str) -> Option<String> {
+    lookup(|t| t.tools.get(raw_name))
+}
+
+/// 启动时加载本地保存的映射表，文件不存在或损坏时沿用内置映射
+pub fn load_local(data_dir: &Path) {
+    let path = data_dir.join(MAPPINGS_FILE_NAME);
+    if !path.exists() {
+        return;
+    }
+    match fs::read_to_string(&path).map(|data| serde_json::from_str::<MappingTable>(&data)) {
+        Ok(Ok(table)) => {
+            println!("[Mappings] Loaded local mappings v{}", table.version);
+            install(table);
+        }
+        _ => println!("[Mappings] Failed to load {}, using built-in mappings", path.display()),
+    }
+}
+
+/// 核对下载内容的 SHA-256 并解析映射表
+///
+/// `checksum` 为 `.sha256` 文件内容（`sha256sum` 输出格式，取第一个字段）。
+/// 校验值与映射表来自同一来源，只能发现传输损坏，不能防止篡改；来源可信由 HTTPS 保证，
+/// 映射表也只影响名称显示
+pub fn verify_and_parse(body: &[u8], checksum: &str) -> AppResult<(MappingTable, String)> {
+    let expected = checksum
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = hex::encode(Sha256::digest(body));
+    if expected.is_empty() || expected != actual {
+        return Err(AppError::Config(format!(
+            "Mapping checksum mismatch (incomplete or corrupted download): expected {}, got {}",
+            expected, actual
+        )));
+    }
+
+    let table: MappingTable = serde_json::from_slice(body)?;
+    Ok((table, actual))
+}
+
+/// 下载最新映射表，核对校验值后保存到数据目录并立即生效（只接受 HTTPS 地址）
+pub async fn refresh_mappings(data_dir: &Path, url: &str) -> AppResult<MappingRefreshResult> {
+    if !url.starts_with("https://") {
+        return Err(AppError::Config(format!("Mappings URL must use HTTPS: {}", url)));
+    }
+    let client = super::get_http_client();
+
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::Api(format!("Failed to download mappings: {}", response.status())));
+    }
+    let body = response.bytes().await?;
+
+    let checksum_url = format!("{}.sha256", url);
+    let response = client.get(&checksum_url).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::Api(format!("Failed to download mapping checksum: {}", response.status())));
+    }
+    let checksum = response.text().await?;
+
+    let (table, sha256) = verify_and_parse(&body, &checksum)?;
+
+    let previous_version = ACTIVE.read().ok().and_then(|a| a.as_ref().map(|t| t.version));
+    if let Some(previous) = previous_version {
+        if table.version < previous {
+            return Err(AppError::Config(format!(
+                "Downloaded mappings v{} are older than current v{}",
+                table.version, previous
+            )));
+        }
+    }
+
+    fs::write(data_dir.join(MAPPINGS_FILE_NAME), &body)?;
+
+    let result = MappingRefreshResult {
+        version: table.version,
+        previous_version,
+        sha256,
+        languages: table.languages.len(),
+        models: table.models.len(),
+        tools: table.tools.len(),
+    };
+    println!("[Mappings] Updated mappings to v{}", table.version);
+    install(table);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_and_parse() {
+        let body = br#"{"version":2,"languages":{"46":"Zig"},"models":{"6":"GPT-4o"}}"#;
+        let checksum = format!("{}  mappings.json\n", hex::encode(Sha256::digest(body)));
+
+        let (table, _) = verify_and_parse(body, &checksum).unwrap();
+        assert_eq!(table.version, 2);
+        assert_eq!(table.languages.get("46").map(String::as_str), Some("Zig"));
+        assert!(table.tools.is_empty());
+
+        assert!(verify_and_parse(body, "deadbeef").is_err());
+    }
+}
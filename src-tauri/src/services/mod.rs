@@ -2,13 +2,41 @@ pub mod auth_service;
 pub mod windsurf_service;
 pub mod proto_parser;
 pub mod analytics_service;
+pub mod analytics_aggregator;
+pub mod sink_service;
+pub mod report_service;
+pub mod scheduler;
+pub mod share_service;
+pub mod insights_service;
+pub mod export_service;
+pub mod token_refresh_service;
+pub mod user_info_cache;
+pub mod env_import_service;
+pub mod mapping_service;
+pub mod plugin_service;
+pub mod health_ping_service;
+pub mod session_label_service;
+pub mod shared_use_service;
+pub mod keyring_service;
+pub mod windsurf_config_service;
+pub mod safe_storage;
+pub mod daemon_ipc;
+pub mod autostart;
+pub mod prefetch_service;
+pub mod default_account_service;
+pub mod alert_service;
+pub mod notification_service;
+pub mod rate_limiter;
+pub mod custom_headers;
+pub mod network_cost;
+pub mod transport;
 
 pub use auth_service::*;
 pub use windsurf_service::*;
 // pub use proto_parser::*;
 pub use analytics_service::*;  // Not used directly yet, commented to avoid warnings
 
-use std::sync::atomic::{AtomicU32, AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicBool, Ordering};
 use std::sync::{OnceLock, RwLock, Mutex};
 use std::sync::Arc;
 
@@ -31,14 +59,21 @@ struct ProxyConfig {
 /// 连续失败计数器，用于判断是否需要重建客户端
 static CONSECUTIVE_FAILURES: AtomicU32 = AtomicU32::new(0);
 
+/// 请求总超时（秒），由设置中的 requestTimeoutSecs 控制
+static REQUEST_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(30);
+
+fn request_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS.load(Ordering::Relaxed))
+}
+
 /// 连续失败阈值，超过此值后重建HTTP客户端
 const FAILURE_THRESHOLD: u32 = 3;  // 降低阈值，更快重建
 
 /// 创建一个配置完善的 HTTP 客户端
 fn create_http_client() -> reqwest::Client {
     reqwest::Client::builder()
-        // 请求总超时：30秒
-        .timeout(std::time::Duration::from_secs(30))
+        // 请求总超时：默认30秒
+        .timeout(request_timeout())
         // 连接超时：10秒（避免连接卡住）
         .connect_timeout(std::time::Duration::from_secs(10))
         // 连接池配置 - 更激进的配置以避免僵死连接
@@ -59,7 +94,7 @@ fn create_http_client() -> reqwest::Client {
 /// 创建用于 googleapis 的 HTTP 客户端（支持代理）
 fn create_google_api_client(proxy_url: Option<&str>) -> reqwest::Client {
     let mut builder = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
+        .timeout(request_timeout())
         .connect_timeout(std::time::Duration::from_secs(15))
         .pool_max_idle_per_host(2)
         .pool_idle_timeout(std::time::Duration::from_secs(30))
@@ -168,6 +203,22 @@ pub fn update_proxy_config(enabled: bool, url: Option<String>) {
     }
 }
 
+/// 更新请求超时并重建两个 HTTP 客户端
+pub fn update_request_timeout(secs: u64) {
+    let secs = secs.max(1);
+    if REQUEST_TIMEOUT_SECS.swap(secs, Ordering::Relaxed) == secs {
+        return;
+    }
+    println!("[HTTP Client] Request timeout set to {}s", secs);
+    rebuild_http_client();
+
+    let (enabled, url) = PROXY_CONFIG
+        .get()
+        .and_then(|lock| lock.lock().ok().map(|c| (c.enabled, c.url.clone())))
+        .unwrap_or((false, None));
+    update_proxy_config(enabled, url);
+}
+
 /// 获取用于 googleapis 的 HTTP 客户端（支持代理）
 pub fn get_google_api_client() -> Arc<reqwest::Client> {
     let config_lock = PROXY_CONFIG.get_or_init(|| {
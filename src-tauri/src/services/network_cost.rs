@@ -0,0 +1,113 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 系统检测结果的缓存时间，避免每次调度都启动外部进程
+const DETECT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 最近一次系统检测：(检测时间, 结果)，`None` 表示当前平台无法检测
+static DETECTED: Mutex<Option<(Instant, Option<bool>)>> = Mutex::new(None);
+
+/// 当前网络是否按流量计费，以及判断依据
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeteredStatus {
+    pub metered: bool,
+    /// 设置中的手动标记
+    pub manual: bool,
+    /// 系统检测结果，`None` 表示不支持或检测失败
+    pub system: Option<bool>,
+}
+
+/// 综合手动标记与系统检测：任一为按流量计费即视为按流量计费
+pub async fn status(manual: bool) -> MeteredStatus {
+    let system = detect_cached().await;
+    MeteredStatus {
+        metered: manual || system == Some(true),
+        manual,
+        system,
+    }
+}
+
+pub async fn is_metered(manual: bool) -> bool {
+    status(manual).await.metered
+}
+
+async fn detect_cached() -> Option<bool> {
+    if let Ok(cache) = DETECTED.lock() {
+        if let Some((at, result)) = *cache {
+            if at.elapsed() < DETECT_CACHE_TTL {
+                return result;
+            }
+        }
+    }
+
+    let result = tokio::task::spawn_blocking(platform::detect).await.ok().flatten();
+    println!("[NetworkCost] System metered detection: {:?}", result);
+    if let Ok(mut cache) = DETECTED.lock() {
+        *cache = Some((Instant::now(), result));
+    }
+    result
+}
+
+/// Windows：读取当前 Internet 连接配置的 NetworkCostType（Fixed/Variable 为按流量计费）
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    pub fn detect() -> Option<bool> {
+        let script = "$p = [Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]::GetInternetConnectionProfile(); \
+                      if ($p) { $c = $p.GetConnectionCost(); \"$($c.NetworkCostType) $($c.Roaming) $($c.OverDataLimit)\" }";
+        let output = Command::new("powershell")
+            .creation_flags(CREATE_NO_WINDOW)
+            .args(["-NoProfile", "-Command", script])
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut parts = text.split_whitespace();
+        let cost_type = parts.next()?;
+        let flagged = parts.any(|p| p.eq_ignore_ascii_case("true"));
+        Some(matches!(cost_type, "Fixed" | "Variable") || flagged)
+    }
+}
+
+/// Linux：通过 D-Bus 读取 NetworkManager 的 Metered 属性
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    pub fn detect() -> Option<bool> {
+        let output = Command::new("busctl")
+            .args([
+                "--system",
+                "get-property",
+                "org.freedesktop.NetworkManager",
+                "/org/freedesktop/NetworkManager",
+                "org.freedesktop.NetworkManager",
+                "Metered",
+            ])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // 输出形如 "u 4"：0 未知、1 是、2 否、3 推测是、4 推测否
+        let text = String::from_utf8_lossy(&output.stdout);
+        match text.split_whitespace().nth(1)?.parse::<u32>().ok()? {
+            1 | 3 => Some(true),
+            2 | 4 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// 其他平台（macOS 等）没有可靠的命令行接口，只使用手动标记
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    pub fn detect() -> Option<bool> {
+        None
+    }
+}
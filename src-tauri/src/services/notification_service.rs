@@ -0,0 +1,89 @@
+use crate::models::NotificationConfig;
+use crate::repository::{DataStore, QuotaAlertPayload};
+use std::sync::Arc;
+use tauri::Listener;
+use tauri_plugin_notification::NotificationExt;
+
+/// 通知类别，每类可在设置中单独关闭
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    /// Token 刷新失败、认证失败
+    TokenExpired,
+    /// 后台拉取使用分析失败
+    AnalyticsFailed,
+    /// 额度不足、用量告警
+    QuotaLow,
+    /// 账号被禁用或不可用
+    AccountBanned,
+    /// 后台任务运行超时被取消
+    JobOverrun,
+}
+
+impl NotificationCategory {
+    fn is_enabled(self, config: &NotificationConfig) -> bool {
+        config.enabled
+            && match self {
+                Self::TokenExpired => config.token_expired,
+                Self::AnalyticsFailed => config.analytics_failed,
+                Self::QuotaLow => config.quota_low,
+                Self::AccountBanned => config.account_banned,
+                Self::JobOverrun => config.job_overrun,
+            }
+    }
+}
+
+/// 按设置弹出系统通知；关闭的类别只记录日志
+pub async fn notify(store: &DataStore, category: NotificationCategory, title: &str, body: &str) {
+    let config = match store.get_settings().await {
+        Ok(settings) => settings.notifications,
+        Err(_) => NotificationConfig::default(),
+    };
+    if !category.is_enabled(&config) {
+        println!("[Notification] {:?} disabled, skipped: {} - {}", category, title, body);
+        return;
+    }
+    if let Err(e) = store.app_handle().notification().builder().title(title).body(body).show() {
+        println!("[Notification] Failed to show {:?} notification: {}", category, e);
+    }
+}
+
+/// 监听存储层发出的额度提醒事件并弹出通知（应用启动时调用）
+pub fn register_listeners(app: &tauri::AppHandle, store: Arc<DataStore>) {
+    app.listen("quota-alert", move |event| {
+        let payload: QuotaAlertPayload = match serde_json::from_str(event.payload()) {
+            Ok(payload) => payload,
+            Err(e) => {
+                println!("[Notification] Invalid quota-alert payload: {}", e);
+                return;
+            }
+        };
+        let store = store.clone();
+        tauri::async_runtime::spawn(async move {
+            notify(
+                &store,
+                NotificationCategory::QuotaLow,
+                "额度不足",
+                &format!("{} 剩余额度 {:.1}%", payload.email, payload.remaining_percent),
+            )
+            .await;
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_toggles() {
+        let mut config = NotificationConfig::default();
+        assert!(NotificationCategory::QuotaLow.is_enabled(&config));
+
+        config.quota_low = false;
+        assert!(!NotificationCategory::QuotaLow.is_enabled(&config));
+        assert!(NotificationCategory::TokenExpired.is_enabled(&config));
+
+        config.enabled = false;
+        assert!(!NotificationCategory::TokenExpired.is_enabled(&config));
+    }
+}
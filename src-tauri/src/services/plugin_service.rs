@@ -0,0 +1,358 @@
+use crate::models::PluginConfig;
+use crate::repository::DataStore;
+use crate::utils::{AppError, AppResult};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Listener;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::Mutex;
+
+/// 插件协议版本（initialize 请求中告知插件）
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 转发给插件的应用事件：(应用内事件名, 插件协议中的事件名)
+const FORWARDED_EVENTS: &[(&str, &str)] = &[
+    ("account-switched", "account_switched"),
+    ("quota-alert", "quota_alert"),
+    ("accounts-need-attention", "accounts_need_attention"),
+    ("accounts-possibly-shared", "accounts_possibly_shared"),
+];
+
+/// 插件崩溃后首次重启前的等待时间，之后每次连续崩溃翻倍
+const RESPAWN_BACKOFF_BASE: Duration = Duration::from_secs(5);
+/// 重启等待时间上限
+const RESPAWN_BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// 运行超过该时长后退出视为偶发崩溃，重新从最短等待时间开始计算
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// 一个运行中的插件进程
+struct PluginProcess {
+    child: Child,
+    stdin: ChildStdin,
+    next_id: u64,
+    started_at: Instant,
+}
+
+impl PluginProcess {
+    /// 写入一行 JSON-RPC 消息
+    async fn send(&mut self, message: &Value) -> std::io::Result<()> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> std::io::Result<()> {
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params });
+        self.send(&message).await
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> std::io::Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.send(&message).await
+    }
+}
+
+/// 插件运行状态
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginStatus {
+    pub name: String,
+    pub command: String,
+    pub enabled: bool,
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub events: Vec<String>,
+}
+
+/// 每个插件的进程与重启状态；进程单独加锁，向一个插件写入时不阻塞其他插件
+#[derive(Default)]
+struct PluginSlot {
+    process: Option<Arc<Mutex<PluginProcess>>>,
+    /// 正在启动中（避免并发事件重复启动）
+    spawning: bool,
+    /// 连续崩溃或启动失败次数
+    crashes: u32,
+    /// 在此之前不重新启动
+    retry_at: Option<Instant>,
+}
+
+impl PluginSlot {
+    /// 记录一次崩溃或启动失败，按连续次数指数退避
+    fn record_crash(&mut self, ran_for: Option<Duration>) {
+        self.process = None;
+        self.crashes = match ran_for {
+            Some(ran_for) if ran_for >= STABLE_AFTER => 1,
+            _ => self.crashes + 1,
+        };
+        self.retry_at = Some(Instant::now() + respawn_backoff(self.crashes));
+    }
+}
+
+/// 第 n 次连续崩溃后的重启等待时间
+fn respawn_backoff(crashes: u32) -> Duration {
+    let factor = 2u32.saturating_pow(crashes.saturating_sub(1).min(16));
+    RESPAWN_BACKOFF_BASE.saturating_mul(factor).min(RESPAWN_BACKOFF_MAX)
+}
+
+/// 插件槽位表只在查找/替换进程句柄时短暂持有，不跨 await
+static SLOTS: Lazy<std::sync::Mutex<HashMap<String, PluginSlot>>> = Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn slots() -> std::sync::MutexGuard<'static, HashMap<String, PluginSlot>> {
+    SLOTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 启动插件进程并发送 initialize 请求
+async fn spawn_plugin(config: &PluginConfig) -> AppResult<PluginProcess> {
+    let mut child = Command::new(&config.command)
+        .args(&config.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::Config(format!("Failed to start plugin {}: {}", config.name, e)))?;
+
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| AppError::Config(format!("Plugin {} has no stdin", config.name)))?;
+
+    // 插件输出：响应只记录日志，`log` 通知打印到控制台
+    if let Some(stdout) = child.stdout.take() {
+        let name = config.name.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                handle_plugin_output(&name, &line);
+            }
+            println!("[Plugin] {} stdout closed", name);
+        });
+    }
+
+    let mut process = PluginProcess { child, stdin, next_id: 0, started_at: Instant::now() };
+    process
+        .request(
+            "initialize",
+            json!({
+                "protocol_version": PROTOCOL_VERSION,
+                "app_version": env!("CARGO_PKG_VERSION"),
+                "events": FORWARDED_EVENTS.iter().map(|(_, name)| *name).collect::<Vec<_>>(),
+            }),
+        )
+        .await?;
+
+    println!("[Plugin] Started {} (pid {:?})", config.name, process.child.id());
+    Ok(process)
+}
+
+fn handle_plugin_output(name: &str, line: &str) {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(_) => {
+            println!("[Plugin:{}] {}", name, line);
+            return;
+        }
+    };
+
+    if message.get("method").and_then(|m| m.as_str()) == Some("log") {
+        let text = message.pointer("/params/message").and_then(|m| m.as_str()).unwrap_or_default();
+        println!("[Plugin:{}] {}", name, text);
+    } else if let Some(error) = message.get("error") {
+        println!("[Plugin:{}] Error response: {}", name, error);
+    }
+}
+
+fn subscribes(config: &PluginConfig, event: &str) -> bool {
+    config.enabled && (config.events.is_empty() || config.events.iter().any(|e| e == event))
+}
+
+/// 将事件发送给所有订阅的插件；进程未启动或已退出时（重新）启动
+pub async fn dispatch_event(store: &DataStore, event: &str, payload: Value) -> AppResult<()> {
+    let plugins: Vec<PluginConfig> = store
+        .get_settings()
+        .await?
+        .plugins
+        .into_iter()
+        .filter(|p| subscribes(p, event))
+        .collect();
+    if plugins.is_empty() {
+        return Ok(());
+    }
+
+    let params = json!({ "event": event, "payload": payload });
+    for plugin in plugins {
+        let process = match acquire(&plugin).await {
+            Some(process) => process,
+            None => continue,
+        };
+
+        let mut guard = process.lock().await;
+        if let Err(e) = guard.notify("event", params.clone()).await {
+            println!("[Plugin] Failed to send {} to {}: {}", event, plugin.name, e);
+            let ran_for = guard.started_at.elapsed();
+            drop(guard);
+            if let Some(slot) = slots().get_mut(&plugin.name) {
+                if slot.process.as_ref().map(|p| Arc::ptr_eq(p, &process)).unwrap_or(false) {
+                    slot.record_crash(Some(ran_for));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 取得插件进程句柄；进程未启动或已退出时（重新）启动，崩溃后按退避时间等待
+async fn acquire(plugin: &PluginConfig) -> Option<Arc<Mutex<PluginProcess>>> {
+    let existing = slots().get(&plugin.name).and_then(|slot| slot.process.clone());
+    if let Some(process) = existing {
+        let mut guard = process.lock().await;
+        let exited = guard.child.try_wait().map(|status| status.is_some()).unwrap_or(true);
+        if !exited {
+            drop(guard);
+            return Some(process);
+        }
+        let ran_for = guard.started_at.elapsed();
+        drop(guard);
+        println!("[Plugin] {} exited", plugin.name);
+        if let Some(slot) = slots().get_mut(&plugin.name) {
+            if slot.process.as_ref().map(|p| Arc::ptr_eq(p, &process)).unwrap_or(false) {
+                slot.record_crash(Some(ran_for));
+            }
+        }
+    }
+
+    {
+        let mut slots = slots();
+        let slot = slots.entry(plugin.name.clone()).or_default();
+        if let Some(process) = &slot.process {
+            return Some(process.clone());
+        }
+        if slot.spawning || slot.retry_at.map(|at| Instant::now() < at).unwrap_or(false) {
+            return None;
+        }
+        slot.spawning = true;
+    }
+
+    let spawned = spawn_plugin(plugin).await;
+    let mut slots = slots();
+    let slot = slots.entry(plugin.name.clone()).or_default();
+    slot.spawning = false;
+    match spawned {
+        Ok(process) => {
+            let process = Arc::new(Mutex::new(process));
+            slot.process = Some(process.clone());
+            slot.retry_at = None;
+            Some(process)
+        }
+        Err(e) => {
+            println!("[Plugin] {}", e);
+            slot.record_crash(None);
+            None
+        }
+    }
+}
+
+/// 注册应用事件监听，把生命周期事件转发给插件（应用启动时调用）
+pub fn register_listeners(app: &tauri::AppHandle, store: Arc<DataStore>) {
+    for &(app_event, plugin_event) in FORWARDED_EVENTS {
+        let store = store.clone();
+        app.listen(app_event, move |event| {
+            let payload = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+            let store = store.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = dispatch_event(&store, plugin_event, payload).await {
+                    println!("[Plugin] Failed to dispatch {}: {}", plugin_event, e);
+                }
+            });
+        });
+    }
+}
+
+/// 停止所有插件进程（配置修改后调用，下次事件时按新配置启动）
+pub async fn stop_all() -> usize {
+    let processes: Vec<(String, Arc<Mutex<PluginProcess>>)> = slots()
+        .drain()
+        .filter_map(|(name, slot)| slot.process.map(|process| (name, process)))
+        .collect();
+    let count = processes.len();
+    for (name, process) in processes {
+        let mut process = process.lock().await;
+        let _ = process.notify("shutdown", Value::Null).await;
+        let _ = process.child.start_kill();
+        println!("[Plugin] Stopped {}", name);
+    }
+    count
+}
+
+/// 获取已配置插件的运行状态
+pub async fn list_status(store: &DataStore) -> AppResult<Vec<PluginStatus>> {
+    let plugins = store.get_settings().await?.plugins;
+
+    let mut statuses = Vec::with_capacity(plugins.len());
+    for plugin in plugins {
+        let process = slots().get(&plugin.name).and_then(|slot| slot.process.clone());
+        let (running, pid) = match process {
+            Some(process) => {
+                let mut process = process.lock().await;
+                let running = matches!(process.child.try_wait(), Ok(None));
+                (running, process.child.id().filter(|_| running))
+            }
+            None => (false, None),
+        };
+        statuses.push(PluginStatus {
+            name: plugin.name,
+            command: plugin.command,
+            enabled: plugin.enabled,
+            running,
+            pid,
+            events: plugin.events,
+        });
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribes() {
+        let mut plugin = PluginConfig {
+            name: "notify".to_string(),
+            command: "notify-plugin".to_string(),
+            args: Vec::new(),
+            enabled: true,
+            events: Vec::new(),
+        };
+        assert!(subscribes(&plugin, "quota_alert"));
+
+        plugin.events = vec!["account_switched".to_string()];
+        assert!(subscribes(&plugin, "account_switched"));
+        assert!(!subscribes(&plugin, "quota_alert"));
+
+        plugin.enabled = false;
+        assert!(!subscribes(&plugin, "account_switched"));
+    }
+
+    #[test]
+    fn test_respawn_backoff() {
+        assert_eq!(respawn_backoff(1), RESPAWN_BACKOFF_BASE);
+        assert_eq!(respawn_backoff(3), RESPAWN_BACKOFF_BASE * 4);
+        assert_eq!(respawn_backoff(40), RESPAWN_BACKOFF_MAX);
+
+        // 长时间运行后退出重新从最短等待开始
+        let mut slot = PluginSlot { crashes: 5, ..Default::default() };
+        slot.record_crash(Some(STABLE_AFTER));
+        assert_eq!(slot.crashes, 1);
+        slot.record_crash(Some(Duration::from_secs(1)));
+        assert_eq!(slot.crashes, 2);
+        assert!(slot.retry_at.is_some());
+    }
+}
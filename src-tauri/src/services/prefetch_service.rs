@@ -0,0 +1,150 @@
+use super::notification_service::{self, NotificationCategory};
+use crate::models::{Account, AccountStatus, EmptyReason, IdlePrefetchConfig};
+use crate::repository::DataStore;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 最近一次用户操作的时间（Unix 秒），0 表示本进程没有界面（后台进程）；界面进程启动时即记录一次
+static LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+
+/// 相邻两次预取之间的等待，避免集中请求
+const PREFETCH_GAP: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// 记录一次用户操作（前端在键盘、鼠标事件时节流调用）
+pub fn record_activity() {
+    LAST_ACTIVITY.store(Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+/// 是否已空闲超过 `idle_minutes`
+fn is_idle(now: DateTime<Utc>, idle_minutes: i64) -> bool {
+    let last = LAST_ACTIVITY.load(Ordering::Relaxed);
+    last == 0 || now.timestamp() - last >= idle_minutes.max(1) * 60
+}
+
+/// 选出需要预取的账号：固定账号或匹配标签、Token 可用且快照已过期；固定账号优先，其次最久未更新的优先
+fn select_accounts(
+    accounts: &[Account],
+    last_captured: &dyn Fn(Uuid) -> Option<DateTime<Utc>>,
+    config: &IdlePrefetchConfig,
+    now: DateTime<Utc>,
+) -> Vec<Uuid> {
+    let fresh = chrono::Duration::minutes(config.fresh_minutes.max(0));
+    let unfiltered = config.tags.is_empty() && config.pinned_accounts.is_empty();
+    let mut candidates: Vec<(bool, Option<DateTime<Utc>>, Uuid)> = accounts
+        .iter()
+        .filter(|a| a.token.is_some() || a.refresh_token.is_some())
+        .filter(|a| a.is_disabled != Some(true) && !matches!(a.status, AccountStatus::NeedsAttention))
+        .map(|a| (config.pinned_accounts.contains(&a.id), a))
+        .filter(|(pinned, a)| unfiltered || *pinned || a.tags.iter().any(|t| config.tags.contains(t)))
+        .map(|(pinned, a)| (!pinned, last_captured(a.id), a.id))
+        .filter(|(_, captured, _)| captured.map(|c| now - c >= fresh).unwrap_or(true))
+        .collect();
+    candidates.sort_by_key(|(unpinned, captured, _)| (*unpinned, *captured));
+    candidates.into_iter().take(config.max_accounts).map(|(_, _, id)| id).collect()
+}
+
+/// 定时任务入口：应用空闲时预取使用分析
+pub async fn run_idle_prefetch(store: &Arc<DataStore>) -> Result<String, String> {
+    let settings = store.get_settings().await.map_err(|e| e.to_string())?;
+    let config = settings.idle_prefetch;
+    if !config.enabled {
+        return Ok("空闲预取未启用".to_string());
+    }
+    let now = Utc::now();
+    if !is_idle(now, config.idle_minutes) {
+        return Ok("应用正在使用，跳过预取".to_string());
+    }
+
+    let accounts = store.get_all_accounts().await.map_err(|e| e.to_string())?;
+    let snapshots = store.analytics_snapshots.read().await;
+    let last_captured = |id: Uuid| {
+        snapshots
+            .iter()
+            .filter(|s| s.account_id == id)
+            .map(|s| s.captured_at)
+            .max()
+    };
+    let selected = select_accounts(&accounts, &last_captured, &config, now);
+    drop(snapshots);
+    if selected.is_empty() {
+        return Ok("没有需要预取的账号".to_string());
+    }
+
+    let mut fetched = 0;
+    let mut failed = 0;
+    for (index, id) in selected.iter().enumerate() {
+        // 用户回到应用后立即停止，把请求预算留给前台操作
+        if index > 0 {
+            tokio::time::sleep(PREFETCH_GAP).await;
+            if !is_idle(Utc::now(), config.idle_minutes) {
+                break;
+            }
+        }
+        // 请求失败时命令仍返回 Ok，空数据的原因里才能看出拉取失败
+        match crate::commands::analytics_commands::fetch_account_analytics(store, *id).await {
+            Ok((_, data))
+                if matches!(data.is_empty_because, Some(EmptyReason::FetchFailed | EmptyReason::ParseFailed)) =>
+            {
+                println!("[IdlePrefetch] Failed to prefetch {}: {:?}", id, data.is_empty_because);
+                failed += 1;
+            }
+            Ok(_) => fetched += 1,
+            Err(e) => {
+                println!("[IdlePrefetch] Failed to prefetch {}: {}", id, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        notification_service::notify(
+            store,
+            NotificationCategory::AnalyticsFailed,
+            "使用分析拉取失败",
+            &format!("后台预取时 {} 个账号的使用分析拉取失败", failed),
+        )
+        .await;
+    }
+
+    Ok(format!("预取 {}/{} 个账号的使用分析", fetched, selected.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_accounts() {
+        let now = Utc::now();
+        let account = |email: &str, tags: &[&str]| {
+            let mut account = Account::new(email.to_string(), String::new(), email.to_string(), tags.iter().map(|t| t.to_string()).collect());
+            account.token = Some("token".to_string());
+            account
+        };
+        let stale = account("stale@example.com", &["daily"]);
+        let fresh = account("fresh@example.com", &["daily"]);
+        let never = account("never@example.com", &["daily"]);
+        let untagged = account("other@example.com", &[]);
+        let accounts = vec![stale.clone(), fresh.clone(), never.clone(), untagged.clone()];
+
+        let captured = |id: Uuid| {
+            if id == stale.id {
+                Some(now - chrono::Duration::hours(3))
+            } else if id == fresh.id {
+                Some(now - chrono::Duration::minutes(10))
+            } else {
+                None
+            }
+        };
+        let config = IdlePrefetchConfig { tags: vec!["daily".to_string()], ..Default::default() };
+        assert_eq!(select_accounts(&accounts, &captured, &config, now), vec![never.id, stale.id]);
+
+        let limited = IdlePrefetchConfig { max_accounts: 1, tags: Vec::new(), ..Default::default() };
+        assert_eq!(select_accounts(&accounts, &captured, &limited, now).len(), 1);
+
+        // 固定账号不受标签限制，且排在其他账号之前
+        let pinned = IdlePrefetchConfig { tags: vec!["daily".to_string()], pinned_accounts: vec![untagged.id], ..Default::default() };
+        assert_eq!(select_accounts(&accounts, &captured, &pinned, now), vec![untagged.id, never.id, stale.id]);
+    }
+}
@@ -377,39 +377,44 @@ pub struct UserRole {
 // 兼容旧代码的别名
 pub type AdminInfo = UserRole;
 
+/// 解析 User 消息（GetCurrentUser 的 field 1，GetUsers 的成员列表同样使用该消息）
+/// 根据官方proto: api_key=1, name=2, email=3, signup_time=4, last_update_time=5, id=6, team_id=7...
+pub fn parse_user_basic_info(u: &serde_json::Map<String, Value>) -> UserBasicInfo {
+    UserBasicInfo {
+        api_key: u.get("string_1").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        name: u.get("string_2").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        email: u.get("string_3").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        id: u.get("string_6").and_then(|v| v.as_str()).unwrap_or("").to_string(),  // Firebase UID
+        team_id: u.get("string_7").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        team_status: u.get("int_8").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+        username: u.get("string_9").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        timezone: u.get("string_10").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        public_profile_enabled: u.get("int_11").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        pro: u.get("int_13").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        disable_codeium: u.get("int_16").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        newsletter: u.get("int_19").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        disabled_telemetry: u.get("int_20").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        signup_stage: u.get("string_22").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        used_trial: u.get("int_25").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
+        used_prompt_credits: u.get("int_28").and_then(|v| v.as_i64()).unwrap_or(0),
+        used_flow_credits: u.get("int_29").and_then(|v| v.as_i64()).unwrap_or(0),
+        referral_code: u.get("string_30").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        // Timestamp fields (protobuf Timestamp has seconds in field 1)
+        signup_time: u.get("subMesssage_4").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
+        last_update_time: u.get("subMesssage_5").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
+        first_windsurf_use_time: u.get("subMesssage_26").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
+        windsurf_pro_trial_end_time: u.get("subMesssage_27").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
+    }
+}
+
 /// 从解析的Protobuf数据中提取用户信息
 pub fn extract_user_info(parsed_data: &Value) -> Result<UserInfo, String> {
     let obj = parsed_data.as_object()
         .ok_or("解析数据不是对象")?;
 
     // 提取User信息 (field 1 = subMesssage_1)
-    // 根据官方proto: api_key=1, name=2, email=3, signup_time=4, last_update_time=5, id=6, team_id=7...
     let user = if let Some(u) = obj.get("subMesssage_1").and_then(|v| v.as_object()) {
-        UserBasicInfo {
-            api_key: u.get("string_1").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            name: u.get("string_2").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            email: u.get("string_3").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            id: u.get("string_6").and_then(|v| v.as_str()).unwrap_or("").to_string(),  // Firebase UID
-            team_id: u.get("string_7").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            team_status: u.get("int_8").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
-            username: u.get("string_9").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            timezone: u.get("string_10").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            public_profile_enabled: u.get("int_11").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            pro: u.get("int_13").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            disable_codeium: u.get("int_16").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            newsletter: u.get("int_19").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            disabled_telemetry: u.get("int_20").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            signup_stage: u.get("string_22").and_then(|v| v.as_str()).map(|s| s.to_string()),
-            used_trial: u.get("int_25").and_then(|v| v.as_i64()).map(|v| v == 1).unwrap_or(false),
-            used_prompt_credits: u.get("int_28").and_then(|v| v.as_i64()).unwrap_or(0),
-            used_flow_credits: u.get("int_29").and_then(|v| v.as_i64()).unwrap_or(0),
-            referral_code: u.get("string_30").and_then(|v| v.as_str()).map(|s| s.to_string()),
-            // Timestamp fields (protobuf Timestamp has seconds in field 1)
-            signup_time: u.get("subMesssage_4").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
-            last_update_time: u.get("subMesssage_5").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
-            first_windsurf_use_time: u.get("subMesssage_26").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
-            windsurf_pro_trial_end_time: u.get("subMesssage_27").and_then(|v| v.get("int_1")).and_then(|v| v.as_i64()),
-        }
+        parse_user_basic_info(u)
     } else {
         return Err("缺少用户基本信息".to_string());
     };
@@ -1597,7 +1602,7 @@ pub fn parse_get_analytics_response(response_body: &[u8]) -> Result<Value, Strin
     let mut parser = ProtobufParser::new(decoded_body);
     let parsed = parser.parse_message().map_err(|e| format!("Parse error: {}", e))?;
 
-    println!("[parse_get_analytics_response] Parsed data: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default());
+    crate::proto_log!("[parse_get_analytics_response] Parsed data: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default());
 
     Ok(json!({
         "success": true,
@@ -1606,6 +1611,56 @@ pub fn parse_get_analytics_response(response_body: &[u8]) -> Result<Value, Strin
     }))
 }
 
+/// 整理 GetUserStatus 的 JSON 响应
+///
+/// 响应结构：{ userStatus: { name, email, teamId, teamStatus, planStatus: { planInfo, ... }, ... }, planInfo }。
+/// 数值字段按 Connect JSON 规范可能以字符串返回，统一转换为数字；
+/// 名称中包含 limit 或以 max 开头的字段归入 `limits`，其余布尔字段归入 `flags`，便于前端直接展示限流状态。
+pub fn parse_user_status(data: &Value) -> Value {
+    let as_i64 = |v: Option<&Value>| -> Option<i64> {
+        v.and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+    };
+
+    let status = data.get("userStatus").unwrap_or(data);
+    let plan_status = status.get("planStatus");
+    let plan_info = plan_status
+        .and_then(|p| p.get("planInfo"))
+        .or_else(|| data.get("planInfo"));
+
+    let mut limits = serde_json::Map::new();
+    let mut flags = serde_json::Map::new();
+    let sources = [Some(status), plan_status, plan_info];
+    for object in sources.iter().flatten().filter_map(|v| v.as_object()) {
+        for (key, value) in object {
+            let lower = key.to_lowercase();
+            if lower.contains("limit") || lower.starts_with("max") {
+                limits.insert(key.clone(), value.clone());
+            } else if let Some(flag) = value.as_bool() {
+                flags.insert(key.clone(), json!(flag));
+            }
+        }
+    }
+
+    json!({
+        "name": status.get("name").and_then(|v| v.as_str()),
+        "email": status.get("email").and_then(|v| v.as_str()),
+        "team_id": status.get("teamId").and_then(|v| v.as_str()),
+        "team_status": status.get("teamStatus"),
+        "plan_name": plan_info.and_then(|p| p.get("planName")).and_then(|v| v.as_str()),
+        "teams_tier": plan_info.and_then(|p| p.get("teamsTier")),
+        "plan_start": plan_status.and_then(|p| p.get("planStart")),
+        "plan_end": plan_status.and_then(|p| p.get("planEnd")),
+        "available_prompt_credits": as_i64(plan_status.and_then(|p| p.get("availablePromptCredits"))),
+        "used_prompt_credits": as_i64(plan_status.and_then(|p| p.get("usedPromptCredits"))),
+        "available_flex_credits": as_i64(plan_status.and_then(|p| p.get("availableFlexCredits"))),
+        "used_flex_credits": as_i64(plan_status.and_then(|p| p.get("usedFlexCredits"))),
+        "user_features": status.get("userFeatures").cloned().unwrap_or_else(|| json!([])),
+        "permissions": status.get("permissions").cloned().unwrap_or_else(|| json!([])),
+        "limits": limits,
+        "flags": flags,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1620,4 +1675,29 @@ mod tests {
         let parsed = result.unwrap();
         assert!(parsed.is_object());
     }
+
+    #[test]
+    fn test_parse_user_status() {
+        let data = json!({
+            "userStatus": {
+                "name": "Dev",
+                "email": "dev@example.com",
+                "pro": true,
+                "planStatus": {
+                    "planInfo": { "planName": "Pro", "teamsTier": "TEAMS_TIER_PRO", "maxNumChatInputTokens": "16000" },
+                    "availablePromptCredits": "50000",
+                    "usedPromptCredits": 1200,
+                },
+                "isRateLimited": false,
+            }
+        });
+
+        let status = parse_user_status(&data);
+        assert_eq!(status["plan_name"], "Pro");
+        assert_eq!(status["available_prompt_credits"], 50000);
+        assert_eq!(status["used_prompt_credits"], 1200);
+        assert_eq!(status["flags"]["pro"], true);
+        assert!(status["limits"].get("isRateLimited").is_some());
+        assert!(status["limits"].get("maxNumChatInputTokens").is_some());
+    }
 }
@@ -0,0 +1,167 @@
+use crate::models::RateLimitConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 账号令牌桶数量超过该值时清理已经回满的桶
+const MAX_IDLE_BUCKETS: usize = 256;
+
+/// 令牌桶：按固定速率回填，最多积累 `capacity` 个令牌
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, burst: u32, now: Instant) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// 距离下一个令牌可用还需等待的时间，有令牌时为 0
+    fn wait_time(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+/// 全局桶 + 按账号划分的桶，两个桶都有令牌时才放行
+struct Limiter {
+    config: RateLimitConfig,
+    global: TokenBucket,
+    accounts: HashMap<u64, TokenBucket>,
+}
+
+impl Limiter {
+    fn new(config: RateLimitConfig) -> Self {
+        let global = TokenBucket::new(config.global_per_minute, config.global_burst, Instant::now());
+        Self { config, global, accounts: HashMap::new() }
+    }
+
+    /// 尝试为一次请求取令牌：成功返回 0，否则返回需要等待的时间（此时不扣令牌）
+    fn try_acquire(&mut self, account: Option<u64>, now: Instant) -> Duration {
+        let mut wait = self.global.wait_time(now);
+        if let Some(key) = account {
+            if self.accounts.len() > MAX_IDLE_BUCKETS {
+                self.accounts.retain(|_, bucket| {
+                    bucket.refill(now);
+                    !bucket.is_full()
+                });
+            }
+            let (per_minute, burst) = (self.config.account_per_minute, self.config.account_burst);
+            let bucket = self
+                .accounts
+                .entry(key)
+                .or_insert_with(|| TokenBucket::new(per_minute, burst, now));
+            wait = wait.max(bucket.wait_time(now));
+            if wait.is_zero() {
+                bucket.tokens -= 1.0;
+            }
+        }
+        if wait.is_zero() {
+            self.global.tokens -= 1.0;
+        }
+        wait
+    }
+}
+
+/// 当前生效的限速器，由设置中的 rateLimit 控制；未设置时使用默认配置
+static LIMITER: Mutex<Option<Limiter>> = Mutex::new(None);
+
+/// 更新限速配置，已有的令牌桶全部重置
+pub fn set_config(config: RateLimitConfig) {
+    if let Ok(mut limiter) = LIMITER.lock() {
+        *limiter = Some(Limiter::new(config));
+    }
+}
+
+/// 只保存 Token 的哈希，避免限速器中长期留存凭证
+pub(crate) fn account_key(token: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 发送请求前等待令牌；`account_token` 为空时只受全局限速
+pub async fn acquire(label: &str, account_token: Option<&str>) {
+    let account = account_token.filter(|t| !t.is_empty()).map(account_key);
+    let mut logged = false;
+    loop {
+        let wait = match LIMITER.lock() {
+            Ok(mut guard) => {
+                let limiter = guard.get_or_insert_with(|| Limiter::new(RateLimitConfig::default()));
+                if !limiter.config.enabled {
+                    return;
+                }
+                limiter.try_acquire(account, Instant::now())
+            }
+            Err(_) => return,
+        };
+        if wait.is_zero() {
+            return;
+        }
+        if !logged {
+            println!("[RateLimit] {} throttled, waiting {}ms", label, wait.as_millis());
+            logged = true;
+        }
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_limits() {
+        let config = RateLimitConfig {
+            enabled: true,
+            global_per_minute: 600,
+            global_burst: 10,
+            account_per_minute: 60,
+            account_burst: 2,
+        };
+        let mut limiter = Limiter::new(config);
+        let now = limiter.global.updated;
+
+        // 单账号突发 2 次后需要等待约 1 秒（每分钟 60 次）
+        assert!(limiter.try_acquire(Some(1), now).is_zero());
+        assert!(limiter.try_acquire(Some(1), now).is_zero());
+        let wait = limiter.try_acquire(Some(1), now);
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+
+        // 其他账号不受影响，但共享全局额度
+        assert!(limiter.try_acquire(Some(2), now).is_zero());
+        assert!(limiter.try_acquire(None, now).is_zero());
+        assert!(limiter.try_acquire(Some(1), now + Duration::from_secs(1)).is_zero());
+
+        for _ in 0..9 {
+            limiter.try_acquire(None, now + Duration::from_secs(1));
+        }
+        assert!(!limiter.try_acquire(None, now + Duration::from_secs(1)).is_zero());
+    }
+}
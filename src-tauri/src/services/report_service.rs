@@ -0,0 +1,124 @@
+use crate::models::{
+    Account, AnalyticsSnapshot, CompletionByDay, DailyCascadeLinesCount, ModelUsageEntry, MonthlyReport,
+    MonthlyReportSummary,
+};
+use crate::repository::DataStore;
+use crate::services::analytics_aggregator::AnalyticsAggregator;
+use crate::utils::AppResult;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// 由当月所有快照生成冻结的月度报告，当月没有快照时返回 None
+///
+/// 快照中的序列是滚动 30 天窗口，这里按日期/会话去重后只保留属于该月的数据
+pub fn build_monthly_report(
+    account: &Account,
+    month: &str,
+    snapshots: &[AnalyticsSnapshot],
+) -> Option<MonthlyReport> {
+    // 序列数据来自所有快照（上月底的快照也可能覆盖到本月之前的日期）
+    let mut cascade_lines: BTreeMap<String, DailyCascadeLinesCount> = BTreeMap::new();
+    let mut completions: BTreeMap<String, CompletionByDay> = BTreeMap::new();
+
+    for snapshot in snapshots {
+        for day in snapshot.data.daily_cascade_lines.iter().filter(|d| d.date.starts_with(month)) {
+            cascade_lines.insert(day.date.clone(), day.clone());
+        }
+        for day in snapshot.data.completions_by_day.iter().filter(|d| d.date.starts_with(month)) {
+            completions.insert(day.date.clone(), day.clone());
+        }
+    }
+    let runs: Vec<ModelUsageEntry> = AnalyticsAggregator::merge_runs(snapshots)
+        .into_iter()
+        .filter(|e| e.date.starts_with(month))
+        .collect();
+
+    let month_snapshots: Vec<&AnalyticsSnapshot> = snapshots
+        .iter()
+        .filter(|s| s.date.starts_with(month))
+        .collect();
+    if month_snapshots.is_empty() && cascade_lines.is_empty() && completions.is_empty() && runs.is_empty() {
+        return None;
+    }
+
+    let daily_cascade_lines: Vec<DailyCascadeLinesCount> = cascade_lines.into_values().collect();
+    let completions_by_day: Vec<CompletionByDay> = completions.into_values().collect();
+
+    // 模型汇总与单账号视图使用同一套计算
+    let total_runs = runs.len() as i64;
+    let model_usage_summary = AnalyticsAggregator::model_summary(&runs);
+
+    // 语言分布取当月最后一个快照
+    let completions_by_language = month_snapshots
+        .last()
+        .map(|s| s.data.completions_by_language.clone())
+        .unwrap_or_default();
+
+    let completions_accepted: i64 = completions_by_day.iter().map(|d| d.statistics.num_acceptances).sum();
+    let completions_rejected: i64 = completions_by_day.iter().map(|d| d.statistics.num_rejections).sum();
+
+    let summary = MonthlyReportSummary {
+        total_accepted_lines: daily_cascade_lines.iter().map(|d| d.accepted_lines).sum(),
+        total_suggested_lines: daily_cascade_lines.iter().map(|d| d.suggested_lines).sum(),
+        active_days: daily_cascade_lines.iter().filter(|d| d.accepted_lines > 0 || d.suggested_lines > 0).count() as i64,
+        total_runs,
+        total_tokens: runs.iter().map(|e| e.token_usage).sum(),
+        completions_accepted,
+        acceptance_rate: if completions_accepted + completions_rejected > 0 {
+            completions_accepted as f64 / (completions_accepted + completions_rejected) as f64 * 100.0
+        } else {
+            0.0
+        },
+        primary_model: model_usage_summary.first().map(|m| m.model_name.clone()).unwrap_or_default(),
+        primary_language: completions_by_language
+            .iter()
+            .max_by_key(|l| l.statistics.num_lines_accepted)
+            .map(|l| l.language_name.clone())
+            .unwrap_or_default(),
+    };
+
+    Some(MonthlyReport {
+        account_id: account.id,
+        email: account.email.clone(),
+        month: month.to_string(),
+        generated_at: chrono::Utc::now(),
+        snapshot_count: month_snapshots.len(),
+        summary,
+        daily_cascade_lines,
+        completions_by_day,
+        model_usage_summary,
+        completions_by_language,
+    })
+}
+
+/// 为所有已结束、尚未归档的月份生成报告，返回新生成的报告数量
+pub async fn generate_due_reports(store: &Arc<DataStore>) -> AppResult<usize> {
+    let current_month = chrono::Utc::now().format("%Y-%m").to_string();
+    let accounts = store.get_all_accounts().await?;
+    let mut generated = 0;
+
+    for account in &accounts {
+        let snapshots = store.get_analytics_snapshots(account.id).await?;
+
+        let mut months: Vec<String> = snapshots
+            .iter()
+            .filter_map(|s| s.date.get(0..7).map(|m| m.to_string()))
+            .filter(|m| m.as_str() < current_month.as_str())
+            .collect();
+        months.sort();
+        months.dedup();
+
+        for month in months {
+            if store.has_monthly_report(account.id, &month).await {
+                continue;
+            }
+            if let Some(report) = build_monthly_report(account, &month, &snapshots) {
+                println!("[ReportService] Generated monthly report {} for {}", month, account.email);
+                store.add_monthly_report(report).await?;
+                generated += 1;
+            }
+        }
+    }
+
+    Ok(generated)
+}
@@ -0,0 +1,141 @@
+//! 与 Electron safeStorage 兼容的加密
+//!
+//! Windsurf（VS Code）的 secret storage 先用 safeStorage 加密，再存入 state.vscdb，密文以 "v10"/"v11" 开头。
+//! 这里只实现本机能复现的 v10 格式：
+//! - Windows：AES-256-GCM，密钥保存在 `Local State` 中并受 DPAPI 保护
+//! - macOS：AES-128-CBC，密钥由钥匙串中 "Windsurf Safe Storage" 的口令派生
+//! - Linux：AES-128-CBC，使用 Chromium 的固定口令（v11 依赖系统密钥环，暂不支持）
+
+use crate::utils::{AppError, AppResult};
+
+/// safeStorage 密文前缀
+const PREFIX_V10: &[u8] = b"v10";
+
+/// 按 Windsurf 当前使用的格式加密；`existing` 为已有密文，格式不受支持时返回错误
+pub fn encrypt(plaintext: &[u8], existing: Option<&[u8]>) -> AppResult<Vec<u8>> {
+    if let Some(existing) = existing.filter(|e| !e.starts_with(PREFIX_V10)) {
+        let prefix = String::from_utf8_lossy(&existing[..existing.len().min(3)]).to_string();
+        return Err(AppError::Encryption(format!("不支持的 Windsurf 凭据加密格式: {}", prefix)));
+    }
+    let mut output = PREFIX_V10.to_vec();
+    output.extend(platform::encrypt(plaintext)?);
+    Ok(output)
+}
+
+/// Chromium 在 macOS / Linux 上由口令派生 AES-128 密钥（PBKDF2-HMAC-SHA1）
+#[cfg(not(windows))]
+fn derive_cbc_key(password: &[u8], iterations: u32) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, b"saltysalt", iterations, &mut key);
+    key
+}
+
+/// AES-128-CBC，IV 固定为 16 个空格
+#[cfg(not(windows))]
+fn cbc_encrypt(key: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+    cbc::Encryptor::<aes::Aes128>::new(key.into(), &[b' '; 16].into()).encrypt_padded_vec_mut::<Pkcs7>(plaintext)
+}
+
+#[cfg(windows)]
+mod platform {
+    use crate::utils::{AppError, AppResult};
+    use aes_gcm::{aead::{Aead, KeyInit}, Aes256Gcm, Key, Nonce};
+    use base64::{Engine as _, engine::general_purpose};
+    use rand::RngCore;
+    use serde_json::Value;
+
+    /// 读取 `Local State` 中的密钥并用 DPAPI 解密
+    fn master_key() -> AppResult<Vec<u8>> {
+        let path = crate::services::windsurf_config_service::windsurf_data_dir()
+            .map(|dir| dir.join("Local State"))
+            .ok_or_else(|| AppError::Encryption("未找到 Windsurf 的 Local State".to_string()))?;
+        let state: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let encoded = state["os_crypt"]["encrypted_key"]
+            .as_str()
+            .ok_or_else(|| AppError::Encryption("Local State 中没有 encrypted_key".to_string()))?;
+        let blob = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| AppError::Encryption(format!("Invalid encrypted_key: {}", e)))?;
+        let blob = blob
+            .strip_prefix(b"DPAPI")
+            .ok_or_else(|| AppError::Encryption("encrypted_key 不是 DPAPI 格式".to_string()))?;
+        unprotect(blob)
+    }
+
+    fn unprotect(data: &[u8]) -> AppResult<Vec<u8>> {
+        use std::ptr::null_mut;
+        use winapi::um::dpapi::CryptUnprotectData;
+        use winapi::um::winbase::LocalFree;
+        use winapi::um::wincrypt::DATA_BLOB;
+
+        let mut input = DATA_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+        let mut output = DATA_BLOB { cbData: 0, pbData: null_mut() };
+        let ok = unsafe { CryptUnprotectData(&mut input, null_mut(), null_mut(), null_mut(), null_mut(), 0, &mut output) };
+        if ok == 0 {
+            return Err(AppError::Encryption("DPAPI 解密 Windsurf 密钥失败".to_string()));
+        }
+        let key = unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+        unsafe { LocalFree(output.pbData as _) };
+        Ok(key)
+    }
+
+    /// 12 字节随机 nonce + AES-256-GCM 密文（含 tag）
+    pub fn encrypt(plaintext: &[u8]) -> AppResult<Vec<u8>> {
+        let key = master_key()?;
+        if key.len() != 32 {
+            return Err(AppError::Encryption("Windsurf 密钥长度无效".to_string()));
+        }
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|e| AppError::Encryption(e.to_string()))?;
+        let mut output = nonce.to_vec();
+        output.extend(ciphertext);
+        Ok(output)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use crate::utils::{AppError, AppResult};
+
+    pub fn encrypt(plaintext: &[u8]) -> AppResult<Vec<u8>> {
+        let password = keyring::Entry::new("Windsurf Safe Storage", "Windsurf Key")
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| AppError::Encryption(format!("无法读取钥匙串中的 Windsurf Safe Storage: {}", e)))?;
+        Ok(super::cbc_encrypt(&super::derive_cbc_key(password.as_bytes(), 1003), plaintext))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use crate::utils::AppResult;
+
+    pub fn encrypt(plaintext: &[u8]) -> AppResult<Vec<u8>> {
+        Ok(super::cbc_encrypt(&super::derive_cbc_key(b"peanuts", 1), plaintext))
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+    use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+
+    #[test]
+    fn test_encrypt_v10() {
+        let encrypted = encrypt(b"session", Some(b"v10abc")).unwrap();
+        assert!(encrypted.starts_with(PREFIX_V10));
+
+        let key = derive_cbc_key(b"peanuts", 1);
+        let plaintext = cbc::Decryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&encrypted[3..])
+            .unwrap();
+        assert_eq!(plaintext, b"session");
+
+        // 依赖系统密钥环的 v11 不支持
+        assert!(encrypt(b"session", Some(b"v11abc")).is_err());
+    }
+}
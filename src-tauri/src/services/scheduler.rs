@@ -0,0 +1,281 @@
+use super::notification_service::{self, NotificationCategory};
+use super::{export_service, health_ping_service, network_cost, prefetch_service, report_service, token_refresh_service};
+use crate::models::{JobKind, JobRun, OperationLog, OperationStatus, OperationType};
+use crate::repository::DataStore;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// 调度循环的检查间隔
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 启动后首次检查前的等待时间，避免与初始化抢资源
+const STARTUP_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 看门狗检查运行中任务的间隔
+const WATCHDOG_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 内存中保留的看门狗取消记录条数
+const MAX_WATCHDOG_EVENTS: usize = 100;
+
+/// 任务运行状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobState {
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_success: Option<bool>,
+    pub last_message: Option<String>,
+    pub run_count: u64,
+    /// 最近一次因按流量计费的网络而跳过
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+/// 看门狗取消超时任务的记录（用于诊断）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogEvent {
+    pub job: JobKind,
+    pub started_at: DateTime<Utc>,
+    pub cancelled_at: DateTime<Utc>,
+    pub max_minutes: i64,
+}
+
+/// 取消登记表中的一项：正在运行的任务及其取消句柄
+struct RunningJob {
+    job: JobKind,
+    started_at: DateTime<Utc>,
+    abort: tokio::task::AbortHandle,
+}
+
+/// 任务状态（用于前端展示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job: JobKind,
+    pub interval_minutes: i64,
+    #[serde(flatten)]
+    pub state: JobState,
+}
+
+/// 后台任务调度器：应用启动后常驻，按间隔执行各类定时任务
+pub struct Scheduler {
+    store: Arc<DataStore>,
+    states: Mutex<HashMap<JobKind, JobState>>,
+    /// 取消登记表：运行 ID → 正在运行的任务
+    running: std::sync::Mutex<HashMap<u64, RunningJob>>,
+    next_run_id: AtomicU64,
+    watchdog_events: Mutex<VecDeque<WatchdogEvent>>,
+}
+
+impl Scheduler {
+    pub fn new(store: Arc<DataStore>) -> Self {
+        Self {
+            store,
+            states: Mutex::new(HashMap::new()),
+            running: std::sync::Mutex::new(HashMap::new()),
+            next_run_id: AtomicU64::new(0),
+            watchdog_events: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 启动调度循环
+    pub fn start(self: &Arc<Self>) {
+        let scheduler = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(STARTUP_DELAY).await;
+            println!("[Scheduler] Started");
+            loop {
+                scheduler.tick().await;
+                tokio::time::sleep(TICK_INTERVAL).await;
+            }
+        });
+
+        // 看门狗独立运行：调度循环被卡住的任务阻塞时仍能取消它
+        let watchdog = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(WATCHDOG_INTERVAL).await;
+                watchdog.cancel_overruns().await;
+            }
+        });
+    }
+
+    /// 强制取消运行时间超过上限的任务，任务本身在 `run_job` 中记为失败
+    async fn cancel_overruns(&self) {
+        let now = Utc::now();
+        let overdue: Vec<RunningJob> = match self.running.lock() {
+            Ok(mut running) => {
+                let ids: Vec<u64> = running
+                    .iter()
+                    .filter(|(_, r)| now - r.started_at > r.job.max_duration())
+                    .map(|(id, _)| *id)
+                    .collect();
+                ids.iter().filter_map(|id| running.remove(id)).collect()
+            }
+            Err(_) => return,
+        };
+
+        for running in overdue {
+            running.abort.abort();
+            let max_minutes = running.job.max_duration().num_minutes();
+            println!(
+                "[Scheduler] Watchdog cancelled {:?} after running since {} (limit {}m)",
+                running.job, running.started_at, max_minutes
+            );
+
+            let mut events = self.watchdog_events.lock().await;
+            if events.len() >= MAX_WATCHDOG_EVENTS {
+                events.pop_front();
+            }
+            events.push_back(WatchdogEvent {
+                job: running.job,
+                started_at: running.started_at,
+                cancelled_at: now,
+                max_minutes,
+            });
+            drop(events);
+
+            let message = format!("后台任务 {:?} 运行超过 {} 分钟，已强制取消", running.job, max_minutes);
+            let log = OperationLog::new(OperationType::JobCancelled, OperationStatus::Failed, message.clone());
+            let _ = self.store.add_log(log).await;
+            notification_service::notify(&self.store, NotificationCategory::JobOverrun, "后台任务超时", &message).await;
+        }
+    }
+
+    /// 执行所有到期的任务
+    async fn tick(&self) {
+        let now = Utc::now();
+        for job in JobKind::all() {
+            let due = {
+                let states = self.states.lock().await;
+                states
+                    .get(job)
+                    .and_then(|s| s.last_run_at)
+                    .map(|last| now - last >= job.interval())
+                    .unwrap_or(true)
+            };
+
+            if due {
+                let _ = self.run_job(*job).await;
+            }
+        }
+    }
+
+    /// 大量请求的任务在按流量计费的网络下是否应跳过
+    async fn should_skip_metered(&self, job: JobKind) -> bool {
+        if !job.is_heavy() {
+            return false;
+        }
+        let Ok(settings) = self.store.get_settings().await else {
+            return false;
+        };
+        if job == JobKind::IdlePrefetch && !settings.idle_prefetch.skip_on_metered {
+            return false;
+        }
+        network_cost::is_metered(settings.metered_connection).await
+    }
+
+    /// 立即执行指定任务
+    pub async fn run_job(&self, job: JobKind) -> Result<String, String> {
+        let started_at = Utc::now();
+        let mut skipped = false;
+        // 未解锁时凭据仍是密文，跳过所有任务
+        let result = if self.store.is_locked().await {
+            Ok("数据已锁定，等待解锁".to_string())
+        } else if self.should_skip_metered(job).await {
+            skipped = true;
+            Ok("按流量计费的网络，已跳过".to_string())
+        } else {
+            // 在独立任务中执行并登记取消句柄，看门狗取消后这里得到 cancelled 错误
+            let task = tokio::spawn(Self::execute(self.store.clone(), job));
+            let run_id = self.next_run_id.fetch_add(1, Ordering::Relaxed);
+            if let Ok(mut running) = self.running.lock() {
+                running.insert(run_id, RunningJob { job, started_at, abort: task.abort_handle() });
+            }
+            let result = match task.await {
+                Ok(result) => result,
+                Err(e) if e.is_cancelled() => Err(format!(
+                    "任务运行超过 {} 分钟，已被看门狗取消",
+                    job.max_duration().num_minutes()
+                )),
+                Err(e) => Err(format!("任务异常退出: {}", e)),
+            };
+            if let Ok(mut running) = self.running.lock() {
+                running.remove(&run_id);
+            }
+            result
+        };
+
+        if let Err(e) = &result {
+            println!("[Scheduler] Job {:?} failed: {}", job, e);
+        }
+
+        let finished_at = Utc::now();
+        let message = match &result {
+            Ok(message) => message.clone(),
+            Err(e) => e.clone(),
+        };
+
+        let mut states = self.states.lock().await;
+        let state = states.entry(job).or_default();
+        state.last_run_at = Some(finished_at);
+        state.last_success = Some(result.is_ok());
+        state.last_message = Some(message.clone());
+        state.run_count += 1;
+        state.skipped = skipped;
+        drop(states);
+
+        let run = JobRun {
+            job,
+            started_at,
+            finished_at,
+            success: result.is_ok(),
+            skipped,
+            message,
+        };
+        if let Err(e) = self.store.add_job_run(run).await {
+            println!("[Scheduler] Failed to save job history: {}", e);
+        }
+
+        result
+    }
+
+    async fn execute(store: Arc<DataStore>, job: JobKind) -> Result<String, String> {
+        match job {
+            JobKind::MonthlyReport => report_service::generate_due_reports(&store)
+                .await
+                .map(|count| format!("生成 {} 份月度报告", count))
+                .map_err(|e| e.to_string()),
+            JobKind::AutoExport => export_service::run_auto_export(&store)
+                .await
+                .map_err(|e| e.to_string()),
+            JobKind::TokenRefresh => token_refresh_service::refresh_due_tokens(&store)
+                .await
+                .map_err(|e| e.to_string()),
+            JobKind::HealthPing => health_ping_service::run_health_ping(&store)
+                .await
+                .map_err(|e| e.to_string()),
+            JobKind::IdlePrefetch => prefetch_service::run_idle_prefetch(&store).await,
+            JobKind::ReprocessSnapshots => crate::commands::analytics_commands::reprocess_snapshots(&store).await,
+        }
+    }
+
+    /// 获取本次运行以来看门狗取消的任务（按时间升序）
+    pub async fn get_watchdog_events(&self) -> Vec<WatchdogEvent> {
+        self.watchdog_events.lock().await.iter().cloned().collect()
+    }
+
+    /// 获取所有任务的状态
+    pub async fn get_status(&self) -> Vec<JobStatus> {
+        let states = self.states.lock().await;
+        JobKind::all()
+            .iter()
+            .map(|job| JobStatus {
+                job: *job,
+                interval_minutes: job.interval().num_minutes(),
+                state: states.get(job).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
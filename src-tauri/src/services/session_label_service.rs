@@ -0,0 +1,152 @@
+use crate::models::{ModelUsageEntry, SessionLabelRule, SessionLabels};
+use crate::services::export_service;
+use crate::utils::{AppError, AppResult};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// 规则可匹配的会话元数据字段
+pub const RULE_FIELDS: &[&str] = &["session_id", "model", "mode"];
+
+/// 某个标签（项目）的用量汇总
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LabelUsage {
+    /// None 表示未归入任何标签
+    pub label: Option<String>,
+    pub sessions: usize,
+    pub runs: usize,
+    pub messages: i64,
+    pub tokens: i64,
+    pub estimated_credits: f64,
+}
+
+/// 校验规则：字段合法且正则可编译
+pub fn validate_rules(rules: &[SessionLabelRule]) -> AppResult<()> {
+    for rule in rules {
+        if rule.label.trim().is_empty() {
+            return Err(AppError::Config("Session label rule has an empty label".to_string()));
+        }
+        if !RULE_FIELDS.contains(&rule.field.as_str()) {
+            return Err(AppError::Config(format!("Unknown session label field: {}", rule.field)));
+        }
+        Regex::new(&rule.pattern)
+            .map_err(|e| AppError::Config(format!("Invalid pattern '{}': {}", rule.pattern, e)))?;
+    }
+    Ok(())
+}
+
+/// 预编译后的标签解析器
+pub struct LabelResolver<'a> {
+    labels: &'a SessionLabels,
+    rules: Vec<(&'a SessionLabelRule, Regex)>,
+}
+
+impl<'a> LabelResolver<'a> {
+    /// 无法编译的规则被忽略（保存时已校验，这里只防御旧数据）
+    pub fn new(labels: &'a SessionLabels) -> Self {
+        let rules = labels
+            .rules
+            .iter()
+            .filter_map(|rule| Regex::new(&rule.pattern).ok().map(|re| (rule, re)))
+            .collect();
+        Self { labels, rules }
+    }
+
+    pub fn resolve(&self, entry: &ModelUsageEntry) -> Option<String> {
+        if let Some(label) = self.labels.assignments.get(&entry.session_id) {
+            return Some(label.clone());
+        }
+        self.rules
+            .iter()
+            .find(|(rule, re)| {
+                let value = match rule.field.as_str() {
+                    "model" => &entry.model_name,
+                    "mode" => &entry.mode,
+                    _ => &entry.session_id,
+                };
+                re.is_match(value)
+            })
+            .map(|(rule, _)| rule.label.clone())
+    }
+}
+
+/// 按标签汇总 Cascade 运行记录，按 Token 用量降序，未标记的排在最后
+pub fn rollup_by_label(
+    runs: &[ModelUsageEntry],
+    labels: &SessionLabels,
+    rates: &HashMap<String, f64>,
+) -> Vec<LabelUsage> {
+    let resolver = LabelResolver::new(labels);
+    let mut usage: HashMap<Option<String>, (LabelUsage, HashSet<String>)> = HashMap::new();
+
+    for entry in runs {
+        let label = resolver.resolve(entry);
+        let (item, sessions) = usage.entry(label.clone()).or_insert_with(|| {
+            (
+                LabelUsage {
+                    label,
+                    ..Default::default()
+                },
+                HashSet::new(),
+            )
+        });
+        sessions.insert(entry.session_id.clone());
+        item.runs += 1;
+        item.messages += entry.session_count;
+        item.tokens += entry.token_usage;
+        item.estimated_credits += entry.session_count as f64 * export_service::credit_rate(rates, &entry.model_name);
+    }
+
+    let mut result: Vec<LabelUsage> = usage
+        .into_values()
+        .map(|(mut item, sessions)| {
+            item.sessions = sessions.len();
+            item
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        a.label
+            .is_none()
+            .cmp(&b.label.is_none())
+            .then(b.tokens.cmp(&a.tokens))
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_by_label() {
+        let mut labels = SessionLabels::default();
+        labels.assignments.insert("acme-1".to_string(), "Internal".to_string());
+        labels.rules.push(SessionLabelRule {
+            label: "Acme".to_string(),
+            pattern: "^acme-".to_string(),
+            field: "session_id".to_string(),
+        });
+        assert!(validate_rules(&labels.rules).is_ok());
+
+        let runs = vec![
+            ModelUsageEntry::test_run("acme-1", "Claude", 100),
+            ModelUsageEntry::test_run("acme-2", "Claude", 200),
+            ModelUsageEntry::test_run("acme-2", "GPT-4o", 50),
+            ModelUsageEntry::test_run("other", "Claude", 10),
+        ];
+        let rollup = rollup_by_label(&runs, &labels, &HashMap::new());
+        assert_eq!(rollup.len(), 3);
+        assert_eq!(rollup[0].label.as_deref(), Some("Acme"));
+        assert_eq!(rollup[0].sessions, 1);
+        assert_eq!(rollup[0].tokens, 250);
+        assert_eq!(rollup[1].label.as_deref(), Some("Internal"));
+        assert!(rollup[2].label.is_none());
+
+        let bad = vec![SessionLabelRule {
+            label: "X".to_string(),
+            pattern: "(".to_string(),
+            field: "session_id".to_string(),
+        }];
+        assert!(validate_rules(&bad).is_err());
+    }
+}
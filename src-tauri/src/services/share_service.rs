@@ -0,0 +1,247 @@
+use crate::models::{AnalyticsData, ShareUploadConfig};
+use crate::utils::{AppError, AppResult};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// HTML 转义
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// 隐藏邮箱用户名部分，只保留首字符与域名
+pub fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((name, domain)) => {
+            let first: String = name.chars().take(1).collect();
+            format!("{}***@{}", first, domain)
+        }
+        None => "***".to_string(),
+    }
+}
+
+/// 渲染单个账户的静态 HTML 报告（样式与图表全部内联，可离线打开）
+pub fn render_html_report(display_name: &str, plan_name: Option<&str>, data: &AnalyticsData) -> String {
+    let summary = &data.summary;
+    let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    // 每日接受行数柱状图（内联 SVG）
+    let max_lines = data.daily_cascade_lines.iter().map(|d| d.accepted_lines).max().unwrap_or(0).max(1);
+    let bar_width = 18;
+    let chart_height = 160;
+    let bars: String = data
+        .daily_cascade_lines
+        .iter()
+        .enumerate()
+        .map(|(index, day)| {
+            let height = (day.accepted_lines as f64 / max_lines as f64 * chart_height as f64).round() as i64;
+            format!(
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#4f7cff"><title>{}: {}</title></rect>"##,
+                index * (bar_width + 4),
+                chart_height - height,
+                bar_width,
+                height,
+                escape_html(&day.date),
+                day.accepted_lines
+            )
+        })
+        .collect();
+    let chart_width = (data.daily_cascade_lines.len() * (bar_width + 4)).max(1);
+
+    let model_rows: String = data
+        .model_usage_summary
+        .iter()
+        .map(|m| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>",
+                escape_html(&m.model_name),
+                m.total_count,
+                m.total_tokens,
+                m.percentage
+            )
+        })
+        .collect();
+
+    let tool_rows: String = data
+        .tool_usage
+        .iter()
+        .take(10)
+        .map(|t| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.1}%</td></tr>",
+                escape_html(&t.tool_name),
+                t.count,
+                t.percentage
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>Windsurf 使用报告 - {name}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", "Microsoft YaHei", sans-serif; margin: 32px; color: #1f2937; }}
+h1 {{ font-size: 22px; margin-bottom: 4px; }}
+.meta {{ color: #6b7280; font-size: 13px; margin-bottom: 24px; }}
+.cards {{ display: flex; flex-wrap: wrap; gap: 12px; margin-bottom: 24px; }}
+.card {{ border: 1px solid #e5e7eb; border-radius: 8px; padding: 12px 16px; min-width: 140px; }}
+.card .label {{ color: #6b7280; font-size: 12px; }}
+.card .value {{ font-size: 20px; font-weight: 600; }}
+table {{ border-collapse: collapse; margin-bottom: 24px; min-width: 420px; }}
+th, td {{ border-bottom: 1px solid #e5e7eb; padding: 6px 12px; text-align: left; font-size: 13px; }}
+h2 {{ font-size: 16px; }}
+</style>
+</head>
+<body>
+<h1>Windsurf 使用报告</h1>
+<div class="meta">{name} · 套餐: {plan} · 生成时间: {generated_at}</div>
+<div class="cards">
+<div class="card"><div class="label">接受代码行数</div><div class="value">{accepted}</div></div>
+<div class="card"><div class="label">建议代码行数</div><div class="value">{suggested}</div></div>
+<div class="card"><div class="label">日均接受行数</div><div class="value">{avg:.1}</div></div>
+<div class="card"><div class="label">会话数</div><div class="value">{sessions}</div></div>
+<div class="card"><div class="label">Token 消耗</div><div class="value">{tokens}</div></div>
+<div class="card"><div class="label">主要模型</div><div class="value">{primary_model}</div></div>
+</div>
+<h2>每日接受代码行数</h2>
+<svg width="{chart_width}" height="{chart_height}" role="img" aria-label="每日接受代码行数">{bars}</svg>
+<h2>模型使用</h2>
+<table><thead><tr><th>模型</th><th>次数</th><th>Token</th><th>占比</th></tr></thead><tbody>{model_rows}</tbody></table>
+<h2>工具使用</h2>
+<table><thead><tr><th>工具</th><th>次数</th><th>占比</th></tr></thead><tbody>{tool_rows}</tbody></table>
+</body>
+</html>
+"#,
+        name = escape_html(display_name),
+        plan = escape_html(plan_name.unwrap_or("-")),
+        generated_at = generated_at,
+        accepted = summary.total_accepted_lines,
+        suggested = summary.total_suggested_lines,
+        avg = summary.avg_daily_accepted_lines,
+        sessions = summary.total_sessions,
+        tokens = summary.total_tokens,
+        primary_model = escape_html(&summary.primary_model),
+        chart_width = chart_width,
+        chart_height = chart_height,
+        bars = bars,
+        model_rows = model_rows,
+        tool_rows = tool_rows,
+    )
+}
+
+/// 按配置上传报告，返回可访问的 URL；未配置上传目标时返回 None
+pub async fn upload_report(config: &ShareUploadConfig, file_name: &str, html: &str) -> AppResult<Option<String>> {
+    match config.target.as_str() {
+        "webdav" => upload_webdav(config, file_name, html).await.map(Some),
+        "s3" => upload_s3(config, file_name, html).await.map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// WebDAV：直接 PUT 到目录地址下
+async fn upload_webdav(config: &ShareUploadConfig, file_name: &str, html: &str) -> AppResult<String> {
+    let base = config.webdav_url.trim_end_matches('/');
+    if base.is_empty() {
+        return Err(AppError::Config("WebDAV url is empty".to_string()));
+    }
+    let url = format!("{}/{}", base, file_name);
+
+    let client = super::get_http_client();
+    let mut request = client
+        .put(&url)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(html.to_string());
+    if let Some(username) = &config.webdav_username {
+        request = request.basic_auth(username, config.webdav_password.clone());
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::Api(format!("WebDAV upload failed: {}", response.status())));
+    }
+    Ok(url)
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// S3（及兼容存储）：使用 SigV4 签名的 path-style PUT
+async fn upload_s3(config: &ShareUploadConfig, file_name: &str, html: &str) -> AppResult<String> {
+    if config.s3_bucket.is_empty() || config.s3_access_key.is_empty() || config.s3_secret_key.is_empty() {
+        return Err(AppError::Config("S3 bucket/access key/secret key is required".to_string()));
+    }
+
+    let endpoint = config.s3_endpoint.trim_end_matches('/');
+    let object_url = format!("{}/{}/{}", endpoint, config.s3_bucket, file_name);
+    let parsed = reqwest::Url::parse(&object_url)
+        .map_err(|e| AppError::Config(format!("Invalid S3 endpoint: {}", e)))?;
+    let host = match parsed.port() {
+        Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+        None => parsed.host_str().unwrap_or_default().to_string(),
+    };
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date_stamp = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(html.as_bytes()));
+
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        parsed.path(),
+        host,
+        payload_hash,
+        amz_date,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date_stamp, config.s3_region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", config.s3_secret_key).as_bytes(), &date_stamp);
+    let k_region = hmac_sha256(&k_date, &config.s3_region);
+    let k_service = hmac_sha256(&k_region, "s3");
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+        config.s3_access_key, scope, signature
+    );
+
+    let client = super::get_http_client();
+    let response = client
+        .put(parsed.as_str())
+        .header("x-amz-date", &amz_date)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("Authorization", authorization)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(html.to_string())
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::Api(format!("S3 upload failed: {} {}", status, body)));
+    }
+
+    Ok(match &config.s3_public_base_url {
+        Some(base) if !base.is_empty() => format!("{}/{}", base.trim_end_matches('/'), file_name),
+        _ => object_url,
+    })
+}
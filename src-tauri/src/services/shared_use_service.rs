@@ -0,0 +1,87 @@
+use crate::models::Account;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// 本地记录的过期时间之前至少还剩这么久仍被拒绝，才视为 Token 被提前作废
+const EARLY_REVOKE_MARGIN_MINUTES: i64 = 5;
+
+/// 新发现的疑似共享账号（聚合后一次性通知前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedUseAlert {
+    pub account_id: String,
+    pub email: String,
+    pub reason: String,
+}
+
+/// Token 在过期前被服务端拒绝：通常是账号在其他设备重新登录
+pub fn early_revocation(account: &Account, status_code: u64, now: DateTime<Utc>) -> Option<String> {
+    if status_code != 401 && status_code != 403 {
+        return None;
+    }
+    let expires_at = account.token_expires_at?;
+    if expires_at - now < chrono::Duration::minutes(EARLY_REVOKE_MARGIN_MINUTES) {
+        return None;
+    }
+    Some(format!(
+        "Token 在过期前 {} 分钟被拒绝 (HTTP {})，可能在其他设备重新登录",
+        (expires_at - now).num_minutes(),
+        status_code
+    ))
+}
+
+/// 服务端返回的资料与本地记录不一致（API Key 被重新生成或邮箱变化）
+pub fn profile_mismatch(account: &Account, api_key: &str, email: &str) -> Option<String> {
+    if let Some(known) = account.windsurf_api_key.as_deref().filter(|k| !k.is_empty()) {
+        if !api_key.is_empty() && known != api_key {
+            return Some("账号 API Key 已变化，可能在其他设备重新登录".to_string());
+        }
+    }
+    if !email.is_empty() && !email.eq_ignore_ascii_case(&account.email) {
+        return Some(format!("账号邮箱已变为 {}", email));
+    }
+    None
+}
+
+/// 对比 IDE 当前登录的会话与本地记录：找到同邮箱账号且 API Key 不一致时返回该账号和原因
+pub fn ide_mismatch<'a>(
+    accounts: &'a [Account],
+    ide_email: Option<&str>,
+    ide_api_key: Option<&str>,
+) -> Option<(&'a Account, String)> {
+    let email = ide_email.filter(|e| !e.is_empty())?;
+    let api_key = ide_api_key.filter(|k| !k.is_empty())?;
+    let account = accounts.iter().find(|a| a.email.eq_ignore_ascii_case(email))?;
+    match account.windsurf_api_key.as_deref() {
+        Some(known) if !known.is_empty() && known != api_key => {
+            Some((account, "IDE 中的登录会话与本地记录的 API Key 不一致".to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_use_signals() {
+        let now = Utc::now();
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        account.windsurf_api_key = Some("key-1".to_string());
+
+        account.token_expires_at = Some(now + chrono::Duration::minutes(30));
+        assert!(early_revocation(&account, 401, now).is_some());
+        assert!(early_revocation(&account, 500, now).is_none());
+        account.token_expires_at = Some(now + chrono::Duration::minutes(1));
+        assert!(early_revocation(&account, 401, now).is_none());
+
+        assert!(profile_mismatch(&account, "key-1", "A@example.com").is_none());
+        assert!(profile_mismatch(&account, "key-2", "a@example.com").is_some());
+        assert!(profile_mismatch(&account, "", "b@example.com").is_some());
+
+        let accounts = vec![account];
+        assert!(ide_mismatch(&accounts, Some("a@example.com"), Some("key-1")).is_none());
+        assert!(ide_mismatch(&accounts, Some("a@example.com"), Some("key-2")).is_some());
+        assert!(ide_mismatch(&accounts, Some("other@example.com"), Some("key-2")).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::models::ExternalSinkConfig;
+use crate::utils::{AppError, AppResult};
+use serde_json::Value;
+
+/// 外部数据库类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SinkKind {
+    ClickHouse,
+    Postgres,
+}
+
+impl SinkKind {
+    pub fn parse(kind: &str) -> AppResult<Self> {
+        match kind.to_lowercase().as_str() {
+            "clickhouse" => Ok(SinkKind::ClickHouse),
+            "postgres" | "postgresql" => Ok(SinkKind::Postgres),
+            other => Err(AppError::Config(format!("Unsupported sink kind: {}", other))),
+        }
+    }
+}
+
+/// 通用 SQL 写入器：把分析数据行写入 ClickHouse / PostgreSQL
+///
+/// 表结构（两种数据库列名一致）：
+/// collected_at, account_id, email, record_type, record_date, payload
+pub struct SqlSinkWriter {
+    config: ExternalSinkConfig,
+    kind: SinkKind,
+}
+
+impl SqlSinkWriter {
+    pub fn new(config: ExternalSinkConfig) -> AppResult<Self> {
+        let kind = SinkKind::parse(&config.kind)?;
+        if !is_valid_identifier(&config.table) {
+            return Err(AppError::Config(format!("Invalid sink table name: {}", config.table)));
+        }
+        if config.url.trim().is_empty() {
+            return Err(AppError::Config("Sink url is empty".to_string()));
+        }
+        Ok(Self { config, kind })
+    }
+
+    /// 建表语句
+    pub fn create_table_sql(&self) -> String {
+        match self.kind {
+            SinkKind::ClickHouse => format!(
+                "CREATE TABLE IF NOT EXISTS {} (collected_at DateTime, account_id String, email String, \
+                 record_type String, record_date String, payload String) \
+                 ENGINE = MergeTree ORDER BY (account_id, record_type, record_date)",
+                self.config.table
+            ),
+            SinkKind::Postgres => format!(
+                "CREATE TABLE IF NOT EXISTS {} (collected_at TIMESTAMPTZ NOT NULL, account_id TEXT NOT NULL, \
+                 email TEXT NOT NULL, record_type TEXT NOT NULL, record_date TEXT, payload JSONB)",
+                self.config.table
+            ),
+        }
+    }
+
+    /// 写入一批数据行（行格式同 export_analytics_jsonl），返回写入行数
+    pub async fn write_rows(&self, rows: &[Value]) -> AppResult<usize> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let collected_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let insert_sql = build_insert_sql(self.kind, &self.config.table, &collected_at, rows);
+
+        match self.kind {
+            SinkKind::ClickHouse => {
+                self.execute_clickhouse(&self.create_table_sql()).await?;
+                self.execute_clickhouse(&insert_sql).await?;
+            }
+            SinkKind::Postgres => {
+                let sql = format!("{};\n{};", self.create_table_sql(), insert_sql);
+                self.execute_postgres(&sql).await?;
+            }
+        }
+
+        Ok(rows.len())
+    }
+
+    /// ClickHouse 通过 HTTP 接口执行 SQL
+    async fn execute_clickhouse(&self, sql: &str) -> AppResult<()> {
+        let client = super::get_http_client();
+        let mut request = client.post(&self.config.url).body(sql.to_string());
+        if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.clone());
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::Database(format!("ClickHouse returned {}: {}", status, body)));
+        }
+        Ok(())
+    }
+
+    /// PostgreSQL 通过连接串执行 SQL
+    async fn execute_postgres(&self, sql: &str) -> AppResult<()> {
+        let (client, connection) = tokio_postgres::connect(&self.config.url, tokio_postgres::NoTls)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to connect to PostgreSQL: {}", e)))?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                println!("[SinkService] PostgreSQL connection error: {}", e);
+            }
+        });
+
+        client
+            .batch_execute(sql)
+            .await
+            .map_err(|e| AppError::Database(format!("PostgreSQL write failed: {}", e)))
+    }
+}
+
+/// 表名只允许字母、数字、下划线和点（schema.table）
+fn is_valid_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// SQL 字符串字面量转义：单引号双写；ClickHouse 还会解析反斜杠转义
+fn quote_literal(kind: SinkKind, value: &str) -> String {
+    let escaped = match kind {
+        SinkKind::ClickHouse => value.replace('\\', "\\\\"),
+        SinkKind::Postgres => value.to_string(),
+    };
+    format!("'{}'", escaped.replace('\'', "''"))
+}
+
+fn row_field(row: &Value, key: &str) -> String {
+    row.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 生成批量 INSERT 语句
+pub fn build_insert_sql(kind: SinkKind, table: &str, collected_at: &str, rows: &[Value]) -> String {
+    let values: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            format!(
+                "({}, {}, {}, {}, {}, {})",
+                quote_literal(kind, collected_at),
+                quote_literal(kind, &row_field(row, "account_id")),
+                quote_literal(kind, &row_field(row, "email")),
+                quote_literal(kind, &row_field(row, "record_type")),
+                quote_literal(kind, &row_field(row, "date")),
+                quote_literal(kind, &row.to_string()),
+            )
+        })
+        .collect();
+
+    format!(
+        "INSERT INTO {} (collected_at, account_id, email, record_type, record_date, payload) VALUES {}",
+        table,
+        values.join(", ")
+    )
+}
+
+/// 按配置写入外部数据库（未启用时直接返回）
+pub async fn write_to_sink(config: &ExternalSinkConfig, rows: &[Value]) -> AppResult<usize> {
+    if !config.enabled {
+        return Ok(0);
+    }
+    SqlSinkWriter::new(config.clone())?.write_rows(rows).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_insert_sql_escapes_quotes() {
+        let rows = vec![serde_json::json!({
+            "account_id": "a1",
+            "email": "o'brien@example.com",
+            "record_type": "cascade_lines",
+            "date": "2024-01-01",
+        })];
+        let sql = build_insert_sql(SinkKind::Postgres, "analytics", "2024-01-02 00:00:00", &rows);
+        assert!(sql.starts_with("INSERT INTO analytics"));
+        assert!(sql.contains("'o''brien@example.com'"));
+        assert!(!is_valid_identifier("analytics; DROP TABLE x"));
+    }
+}
@@ -0,0 +1,220 @@
+use super::notification_service::{self, NotificationCategory};
+use super::AuthService;
+use crate::models::{Account, AccountStatus, OperationLog, OperationStatus, OperationType, TokenSource};
+use crate::repository::DataStore;
+use crate::utils::{AppError, AppResult};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// 提前多久刷新即将过期的 Token
+const REFRESH_AHEAD_MINUTES: i64 = 30;
+
+/// 进入“需要处理”状态的账号（聚合后一次性通知前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct AttentionAccount {
+    pub account_id: String,
+    pub email: String,
+    pub failure_count: u32,
+    pub last_error: String,
+}
+
+/// 后台刷新失败事件（`token-refresh-failed`）
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenRefreshFailed {
+    pub account_id: String,
+    pub email: String,
+    pub error: String,
+}
+
+/// 后台任务是否应处理该账号
+fn is_schedulable(account: &Account) -> bool {
+    !matches!(account.status, AccountStatus::NeedsAttention)
+        && account.is_disabled != Some(true)
+        && (account.refresh_token.is_some() || !account.password.is_empty())
+}
+
+/// 账号的 Token 是否需要刷新
+///
+/// 在过期前 `REFRESH_AHEAD_MINUTES` 分钟内刷新；设置了账号级刷新间隔时，
+/// 距上次获取超过该间隔也会刷新。间隔只能让刷新更频繁，Token 有效期约 1 小时，
+/// 即将过期时总会刷新
+fn is_refresh_due(account: &Account, now: chrono::DateTime<chrono::Utc>) -> bool {
+    if account.token.is_none() {
+        return true;
+    }
+    let threshold = now + chrono::Duration::minutes(REFRESH_AHEAD_MINUTES);
+    if account.token_expires_at.map(|exp| exp <= threshold).unwrap_or(true) {
+        return true;
+    }
+    account.schedule_overrides.token_refresh_minutes.is_some_and(|minutes| {
+        account.token_history.last()
+            .map(|p| now - p.obtained_at >= chrono::Duration::minutes(minutes.max(1)))
+            .unwrap_or(true)
+    })
+}
+
+/// 是否为凭据被拒绝（只有这类失败计入连续认证失败；断网、超时、5xx 不计入）
+fn is_auth_failure(error: &AppError) -> bool {
+    matches!(error, AppError::AuthFailed(_) | AppError::TokenExpired)
+}
+
+/// 刷新单个账号的 Token：优先使用 refresh_token，失败时回退到密码登录
+async fn refresh_account(store: &Arc<DataStore>, account: &Account) -> AppResult<()> {
+    let auth_service = AuthService::new();
+
+    let refreshed = match &account.refresh_token {
+        Some(refresh_token) => auth_service.refresh_token(refresh_token).await,
+        None => Err(AppError::TokenExpired),
+    };
+
+    let ((token, refresh_token, expires_at), source) = match refreshed {
+        Ok(result) => (result, TokenSource::RefreshToken),
+        Err(refresh_error) => {
+            let password = store.get_decrypted_password(account.id).await?;
+            if password.is_empty() {
+                return Err(refresh_error);
+            }
+            let result = auth_service.sign_in(&account.email, &password).await?;
+            (result, TokenSource::PasswordLogin)
+        }
+    };
+
+    store
+        .update_account_tokens_no_save(account.id, token, refresh_token, expires_at, source)
+        .await
+}
+
+/// 定时任务入口：刷新即将过期的 Token，并统计连续认证失败
+pub async fn refresh_due_tokens(store: &Arc<DataStore>) -> AppResult<String> {
+    let settings = store.get_settings().await?;
+    if !settings.auto_refresh_token {
+        return Ok("自动刷新Token未启用".to_string());
+    }
+
+    let now = chrono::Utc::now();
+    let due: Vec<Account> = store
+        .get_all_accounts()
+        .await?
+        .into_iter()
+        .filter(is_schedulable)
+        .filter(|a| is_refresh_due(a, now))
+        .collect();
+
+    if due.is_empty() {
+        return Ok("没有需要刷新的Token".to_string());
+    }
+
+    let results: Vec<(Account, AppResult<()>)> = stream::iter(due)
+        .map(|account| {
+            let store = store.clone();
+            async move {
+                let result = refresh_account(&store, &account).await;
+                (account, result)
+            }
+        })
+        .buffer_unordered(settings.concurrent_limit.max(1))
+        .collect()
+        .await;
+
+    let mut success_count = 0;
+    let mut flagged = Vec::new();
+    let mut failed = Vec::new();
+    for (account, result) in &results {
+        match result {
+            Ok(()) => success_count += 1,
+            Err(e) => {
+                println!("[TokenRefresh] Failed to refresh {}: {}", account.email, e);
+                store.emit_event("token-refresh-failed", TokenRefreshFailed {
+                    account_id: account.id.to_string(),
+                    email: account.email.clone(),
+                    error: e.to_string(),
+                });
+                if !is_auth_failure(e) {
+                    continue;
+                }
+                // 只在账号首次认证失败时通知，连续失败直到恢复前不再重复通知
+                if account.auth_failure_count == 0 {
+                    failed.push(account.email.as_str());
+                }
+                if store
+                    .record_auth_failure_no_save(account.id, e.to_string(), settings.auth_failure_threshold)
+                    .await?
+                {
+                    flagged.push(AttentionAccount {
+                        account_id: account.id.to_string(),
+                        email: account.email.clone(),
+                        failure_count: account.auth_failure_count + 1,
+                        last_error: e.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    store.flush().await?;
+
+    if !failed.is_empty() {
+        notification_service::notify(
+            store,
+            NotificationCategory::TokenExpired,
+            "Token 刷新失败",
+            &format!("{} 个账号刷新 Token 失败: {}", failed.len(), failed.join(", ")),
+        )
+        .await;
+    }
+
+    // 本轮新进入“需要处理”状态的账号只发一次聚合通知
+    if !flagged.is_empty() {
+        let emails: Vec<&str> = flagged.iter().map(|a| a.email.as_str()).collect();
+        let log = OperationLog::new(
+            OperationType::RefreshToken,
+            OperationStatus::Failed,
+            format!("{} 个账号连续认证失败，已停止自动处理: {}", flagged.len(), emails.join(", ")),
+        );
+        let _ = store.add_log(log).await;
+        store.emit_event("accounts-need-attention", flagged.clone());
+    }
+
+    Ok(format!(
+        "刷新Token: 成功 {}/{}，新增需要处理 {} 个",
+        success_count,
+        results.len(),
+        flagged.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_refresh_due_with_override() {
+        let now = chrono::Utc::now();
+        let mut account = Account::new("a@example.com".to_string(), String::new(), "a".to_string(), Vec::new());
+        assert!(is_refresh_due(&account, now));
+
+        account.token = Some("token".to_string());
+        account.token_expires_at = Some(now + chrono::Duration::minutes(10));
+        account.record_token_provenance(TokenSource::RefreshToken, "token", None, account.token_expires_at);
+        assert!(is_refresh_due(&account, now));
+
+        // 间隔较长时不会推迟即将过期的刷新
+        account.schedule_overrides.token_refresh_minutes = Some(24 * 60);
+        assert!(is_refresh_due(&account, now));
+
+        // 间隔较短时在有效期内也按间隔刷新
+        account.token_expires_at = Some(now + chrono::Duration::minutes(60));
+        account.schedule_overrides.token_refresh_minutes = Some(15);
+        assert!(!is_refresh_due(&account, now + chrono::Duration::minutes(10)));
+        assert!(is_refresh_due(&account, now + chrono::Duration::minutes(20)));
+    }
+
+    #[test]
+    fn test_is_auth_failure() {
+        assert!(is_auth_failure(&AppError::TokenExpired));
+        assert!(is_auth_failure(&AppError::AuthFailed("INVALID_LOGIN_CREDENTIALS".to_string())));
+        assert!(!is_auth_failure(&AppError::Network("connection refused".to_string())));
+        assert!(!is_auth_failure(&AppError::Api("HTTP 503 Service Unavailable: ".to_string())));
+    }
+}
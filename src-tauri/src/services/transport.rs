@@ -0,0 +1,126 @@
+use crate::utils::{retry, AppResult};
+use futures::future::BoxFuture;
+use uuid::Uuid;
+#[cfg(test)]
+use crate::utils::AppError;
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::Mutex;
+
+pub type TransportFuture<'a> = BoxFuture<'a, AppResult<reqwest::Response>>;
+
+/// Windsurf 接口的 HTTP 层：`WindsurfService` / `AnalyticsService` 只负责构建请求和解析响应，
+/// 实际发送交给 Transport，测试中可以换成 `MockTransport` 回放抓取的响应
+pub trait Transport: Send + Sync {
+    /// 发送只读请求
+    fn send<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a>;
+
+    /// 发送有副作用的请求
+    fn send_mutation<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a>;
+
+    /// 只发送一次，由调用方自行决定是否重试
+    fn send_once<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a>;
+}
+
+/// 默认实现：经过限速与重试后由 reqwest 发送
+#[derive(Debug, Default)]
+pub struct HttpTransport {
+    /// 发起请求的账号，用于附加按账号配置的自定义请求头
+    account: Option<Uuid>,
+}
+
+impl HttpTransport {
+    pub fn for_account(account: Uuid) -> Self {
+        Self { account: Some(account) }
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(retry::send(label, self.account, request))
+    }
+
+    fn send_mutation<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(retry::send_mutation(label, self.account, request))
+    }
+
+    fn send_once<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(retry::send_once(label, self.account, request))
+    }
+}
+
+/// 一次被拦截的请求
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub label: String,
+    pub url: String,
+    pub body: Vec<u8>,
+}
+
+/// 按接口名（与日志中的 label 一致，如 `GetAnalytics`）返回预设响应，不访问网络
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<HashMap<String, (u16, Vec<u8>)>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+#[cfg(test)]
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为指定接口设置响应，同一接口的多次请求返回相同响应
+    pub fn with_response(self, label: &str, status: u16, body: Vec<u8>) -> Self {
+        if let Ok(mut responses) = self.responses.lock() {
+            responses.insert(label.to_string(), (status, body));
+        }
+        self
+    }
+
+    /// 已拦截的请求（按发送顺序）
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn respond(&self, label: &str, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+        let built = request.build()?;
+        let body = built.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec()).unwrap_or_default();
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(RecordedRequest {
+                label: label.to_string(),
+                url: built.url().to_string(),
+                body,
+            });
+        }
+
+        let (status, body) = self
+            .responses
+            .lock()
+            .ok()
+            .and_then(|responses| responses.get(label).cloned())
+            .ok_or_else(|| AppError::Api(format!("MockTransport: 未设置 {} 的响应", label)))?;
+        let mut response = http::Response::new(body);
+        *response.status_mut() = http::StatusCode::from_u16(status)
+            .map_err(|e| AppError::Api(format!("MockTransport: 无效的状态码 {}: {}", status, e)))?;
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+#[cfg(test)]
+impl Transport for MockTransport {
+    fn send<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(async move { self.respond(label, request) })
+    }
+
+    fn send_mutation<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(async move { self.respond(label, request) })
+    }
+
+    fn send_once<'a>(&'a self, label: &'a str, request: reqwest::RequestBuilder) -> TransportFuture<'a> {
+        Box::pin(async move { self.respond(label, request) })
+    }
+}
@@ -0,0 +1,75 @@
+use super::WindsurfService;
+use crate::utils::AppResult;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OnceCell, Semaphore};
+
+/// GetCurrentUser 结果的缓存时间：覆盖一次仪表盘加载中的重复请求
+const CACHE_TTL: Duration = Duration::from_secs(15);
+
+/// 同时进行中的 GetCurrentUser 请求上限，避免触发服务端限流
+const MAX_IN_FLIGHT: usize = 8;
+
+struct CacheEntry {
+    created_at: Instant,
+    cell: Arc<OnceCell<Value>>,
+}
+
+static CACHE: Lazy<Mutex<HashMap<String, CacheEntry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static IN_FLIGHT: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_IN_FLIGHT));
+
+/// 缓存键使用 token 的摘要，不在内存中额外保存 token 原文
+fn cache_key(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// 获取（或创建）某个 token 对应的共享结果单元
+///
+/// 同一 token 的并发请求共享同一个单元，只有第一个请求真正访问接口
+fn shared_cell(key: &str) -> Arc<OnceCell<Value>> {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    cache.retain(|_, entry| now.duration_since(entry.created_at) < CACHE_TTL);
+
+    cache
+        .entry(key.to_string())
+        .or_insert_with(|| CacheEntry {
+            created_at: now,
+            cell: Arc::new(OnceCell::new()),
+        })
+        .cell
+        .clone()
+}
+
+/// 带短时缓存和请求合并的 GetCurrentUser
+///
+/// 只缓存成功的响应；失败的响应会立即从缓存中移除，下一次调用重新请求
+pub async fn get_current_user_cached(service: &WindsurfService, token: &str) -> AppResult<Value> {
+    let key = cache_key(token);
+    let cell = shared_cell(&key);
+
+    let result = cell
+        .get_or_try_init(|| async {
+            let _permit = IN_FLIGHT.acquire().await.ok();
+            service.get_current_user(token).await
+        })
+        .await?
+        .clone();
+
+    if result.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        invalidate(token);
+    }
+
+    Ok(result)
+}
+
+/// 使某个 token 的缓存失效（token 刷新或账号信息被修改后调用）
+pub fn invalidate(token: &str) {
+    let key = cache_key(token);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.remove(&key);
+}
@@ -0,0 +1,232 @@
+use crate::services::safe_storage;
+use crate::utils::{AppError, AppResult};
+use chrono::Local;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Windsurf 保存登录状态的键
+const AUTH_STATUS_KEY: &str = "windsurfAuthStatus";
+
+/// Windsurf 扩展在 secret storage 中保存登录会话的键
+const SESSIONS_SECRET_KEY: &str = r#"secret://{"extensionId":"codeium.windsurf","key":"windsurf_auth.sessions"}"#;
+
+/// 最多保留的登录状态备份数量
+const MAX_BACKUPS: usize = 10;
+
+/// Windsurf 的用户数据目录
+///
+/// Windows: %APPDATA%\Windsurf，macOS: ~/Library/Application Support/Windsurf，Linux: ~/.config/Windsurf
+pub fn windsurf_data_dir() -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|dirs| dirs.config_dir().join("Windsurf"))
+}
+
+/// Windsurf 的 globalStorage 目录
+pub fn global_storage_dir() -> Option<PathBuf> {
+    windsurf_data_dir().map(|dir| dir.join("User").join("globalStorage"))
+}
+
+/// Windsurf 是否正在运行（无法检测时视为未运行）
+#[cfg(target_os = "windows")]
+pub fn is_windsurf_running() -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    std::process::Command::new("tasklist")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["/FI", "IMAGENAME eq Windsurf.exe", "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("Windsurf.exe"))
+        .unwrap_or(false)
+}
+
+/// Windsurf 是否正在运行（无法检测时视为未运行）
+#[cfg(not(target_os = "windows"))]
+pub fn is_windsurf_running() -> bool {
+    let name = if cfg!(target_os = "macos") { "Windsurf" } else { "windsurf" };
+    std::process::Command::new("pgrep")
+        .args(["-x", name])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Windsurf 登录状态数据库（state.vscdb）路径
+pub fn state_db_path() -> Option<PathBuf> {
+    global_storage_dir().map(|dir| dir.join("state.vscdb"))
+}
+
+/// 将新账号信息合并进现有的登录状态 JSON，保留 Windsurf 写入的其他字段
+fn merge_auth_status(existing: Option<&str>, email: &str, name: &str, api_key: &str) -> String {
+    let mut status = existing
+        .and_then(|s| serde_json::from_str::<Value>(s).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| json!({}));
+    status["email"] = json!(email);
+    status["name"] = json!(name);
+    status["apiKey"] = json!(api_key);
+    // 套餐与团队属于上一个账号，交给 Windsurf 启动后重新获取
+    if let Some(obj) = status.as_object_mut() {
+        obj.remove("planName");
+        obj.remove("teamId");
+    }
+    status.to_string()
+}
+
+/// 备份当前登录状态数据库，返回备份路径
+pub fn backup_state_db(backup_dir: &Path) -> AppResult<PathBuf> {
+    let db_path = state_db_path()
+        .filter(|p| p.exists())
+        .ok_or_else(|| AppError::FileOperation("未找到 Windsurf 登录状态数据库 (state.vscdb)".to_string()))?;
+
+    fs::create_dir_all(backup_dir)?;
+    let stamp = Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+    let mut backup_path = backup_dir.join(format!("state_{}.vscdb", stamp));
+    let mut suffix = 1;
+    while backup_path.exists() {
+        backup_path = backup_dir.join(format!("state_{}_{}.vscdb", stamp, suffix));
+        suffix += 1;
+    }
+
+    // 使用 SQLite 的备份接口，-wal 中尚未合并的内容也会一并备份
+    let connection = rusqlite::Connection::open(&db_path)
+        .map_err(|e| AppError::Database(format!("Failed to open state.vscdb: {}", e)))?;
+    connection
+        .backup(rusqlite::DatabaseName::Main, &backup_path, None)
+        .map_err(|e| AppError::Database(format!("Failed to back up state.vscdb: {}", e)))?;
+
+    // 只保留最近的备份
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("state_") && name.ends_with(".vscdb"))
+                .unwrap_or(false)
+        })
+        .collect();
+    backups.sort();
+    if backups.len() > MAX_BACKUPS {
+        for old in &backups[..backups.len() - MAX_BACKUPS] {
+            let _ = fs::remove_file(old);
+        }
+    }
+
+    Ok(backup_path)
+}
+
+/// 写入登录状态的结果
+pub struct AuthWriteResult {
+    pub backup_path: PathBuf,
+    /// 是否同时写入了 secret storage 中的登录会话
+    pub session_written: bool,
+}
+
+/// 解析 secret storage 中以 `{"type":"Buffer","data":[...]}` 保存的密文
+fn buffer_bytes(value: &str) -> Option<Vec<u8>> {
+    serde_json::from_str::<Value>(value)
+        .ok()?
+        .get("data")?
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().map(|b| b as u8))
+        .collect()
+}
+
+/// 按 Windsurf 的格式加密并写入登录会话
+fn write_session(connection: &rusqlite::Connection, email: &str, name: &str, api_key: &str) -> AppResult<()> {
+    let existing: Option<String> = connection
+        .query_row("SELECT value FROM ItemTable WHERE key = ?", [SESSIONS_SECRET_KEY], |row| row.get(0))
+        .ok();
+    let session = json!([{
+        "id": uuid::Uuid::new_v4().to_string(),
+        "accessToken": api_key,
+        "account": { "label": if name.is_empty() { email } else { name }, "id": email },
+        "scopes": [],
+    }]);
+    let encrypted = safe_storage::encrypt(
+        session.to_string().as_bytes(),
+        existing.as_deref().and_then(buffer_bytes).as_deref(),
+    )?;
+    let value = json!({ "type": "Buffer", "data": encrypted }).to_string();
+    connection
+        .execute(
+            "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
+            [SESSIONS_SECRET_KEY, value.as_str()],
+        )
+        .map_err(|e| AppError::Database(format!("Failed to write state.vscdb: {}", e)))?;
+    Ok(())
+}
+
+/// 将账号写入 Windsurf 的登录状态与登录会话（写入前自动备份，Windsurf 需重启后生效）
+///
+/// Windsurf 退出时会用内存中的状态覆盖 state.vscdb，因此运行中拒绝写入。
+/// 无法按 Windsurf 的格式加密会话时删除旧账号的会话，Windsurf 启动后按登录状态重新登录
+pub fn write_auth_status(email: &str, name: &str, api_key: &str, backup_dir: &Path) -> AppResult<AuthWriteResult> {
+    if is_windsurf_running() {
+        return Err(AppError::FileOperation("Windsurf 正在运行，请先退出 Windsurf 再切换账号".to_string()));
+    }
+    let db_path = state_db_path()
+        .filter(|p| p.exists())
+        .ok_or_else(|| AppError::FileOperation("未找到 Windsurf 登录状态数据库 (state.vscdb)".to_string()))?;
+    let backup_path = backup_state_db(backup_dir)?;
+
+    let mut connection = rusqlite::Connection::open(&db_path)
+        .map_err(|e| AppError::Database(format!("Failed to open state.vscdb: {}", e)))?;
+    let transaction = connection
+        .transaction()
+        .map_err(|e| AppError::Database(format!("Failed to write state.vscdb: {}", e)))?;
+
+    let existing: Option<String> = transaction
+        .query_row("SELECT value FROM ItemTable WHERE key = ?", [AUTH_STATUS_KEY], |row| row.get(0))
+        .ok();
+    let status = merge_auth_status(existing.as_deref(), email, name, api_key);
+    transaction
+        .execute(
+            "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
+            [AUTH_STATUS_KEY, status.as_str()],
+        )
+        .map_err(|e| AppError::Database(format!("Failed to write state.vscdb: {}", e)))?;
+
+    let session_written = match write_session(&transaction, email, name, api_key) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("[WindsurfConfig] Failed to write auth session, removing the previous one: {}", e);
+            transaction
+                .execute("DELETE FROM ItemTable WHERE key = ?", [SESSIONS_SECRET_KEY])
+                .map_err(|e| AppError::Database(format!("Failed to write state.vscdb: {}", e)))?;
+            false
+        }
+    };
+    transaction
+        .commit()
+        .map_err(|e| AppError::Database(format!("Failed to write state.vscdb: {}", e)))?;
+
+    println!("[WindsurfConfig] Wrote auth status for {} to {:?}", email, db_path);
+    Ok(AuthWriteResult { backup_path, session_written })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_auth_status() {
+        let existing = r#"{"email":"old@example.com","apiKey":"old","planName":"Pro","allowedCommandModelConfigsProtoBinaryBase64":["x"]}"#;
+        let merged: Value = serde_json::from_str(&merge_auth_status(Some(existing), "new@example.com", "New", "key")).unwrap();
+        assert_eq!(merged["email"], "new@example.com");
+        assert_eq!(merged["apiKey"], "key");
+        assert!(merged.get("planName").is_none());
+        assert!(merged.get("allowedCommandModelConfigsProtoBinaryBase64").is_some());
+
+        let fresh: Value = serde_json::from_str(&merge_auth_status(Some("not json"), "a@example.com", "A", "k")).unwrap();
+        assert_eq!(fresh["name"], "A");
+    }
+
+    #[test]
+    fn test_buffer_bytes() {
+        assert_eq!(buffer_bytes(r#"{"type":"Buffer","data":[118,49,48]}"#), Some(b"v10".to_vec()));
+        assert_eq!(buffer_bytes("not json"), None);
+    }
+}
@@ -1,9 +1,12 @@
-use crate::utils::{AppError, AppResult};
+use super::transport::{HttpTransport, Transport};
+use crate::utils::{AppError, AppResult, proto_debug};
+use crate::utils::proto_builder::ProtoBuilder;
 use base64::{Engine, engine::general_purpose};
 use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
+use uuid::Uuid;
 
 const WINDSURF_BASE_URL: &str = "https://web-backend.windsurf.com";
 
@@ -25,13 +28,25 @@ pub struct AttemptResult {
 
 pub struct WindsurfService {
     client: Arc<reqwest::Client>,
+    transport: Arc<dyn Transport>,
 }
 
 impl WindsurfService {
     pub fn new() -> Self {
         // 使用全局共享的 HTTP 客户端，避免每次请求都创建新实例
+        Self::with_transport(Arc::new(HttpTransport::default()))
+    }
+
+    /// 以指定账号的身份发送请求（附加该账号的自定义请求头）
+    pub fn for_account(account_id: Uuid) -> Self {
+        Self::with_transport(Arc::new(HttpTransport::for_account(account_id)))
+    }
+
+    /// 使用指定的 HTTP 层（测试中传入 `MockTransport` 回放样例）
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
         Self {
             client: super::get_http_client(),
+            transport,
         }
     }
 
@@ -261,7 +276,7 @@ impl WindsurfService {
         for i in 0..retry_times {
             let body = self.build_request_body(token, seat_count);
             
-            let result = self.client
+            let request = self.client
                 .post(&url)
                 .body(body)
                 .header("accept", "*/*")
@@ -279,9 +294,9 @@ impl WindsurfService {
                 .header("sec-fetch-site", "same-site")
                 .header("x-debug-email", "")
                 .header("x-debug-team-name", "")
-                .header("Referer", "https://windsurf.com/")
-                .send()
-                .await;
+                .header("Referer", "https://windsurf.com/");
+            // 重试次数由 retry_times 控制，这里不再叠加全局重试
+            let result = self.transport.send_once("UpdateSeats", request).await;
             
             match result {
                 Ok(response) => {
@@ -392,12 +407,9 @@ impl WindsurfService {
         println!("[GetTeamCreditEntries] Token length: {} bytes", token_length);
         println!("[GetTeamCreditEntries] Request body length: {} bytes", full_body.len());
         
-        // 打印前几个字节用于调试
-        if full_body.len() >= 3 {
-            println!("[GetTeamCreditEntries] Body prefix: {:02x} {:02x} {:02x}", full_body[0], full_body[1], full_body[2]);
-        }
+        proto_debug::log_body("GetTeamCreditEntries", &full_body);
         
-        let result = self.client
+        let request = self.client
             .post(&url)
             .body(full_body)
             .header("accept", "*/*")
@@ -416,9 +428,8 @@ impl WindsurfService {
             .header("x-auth-token", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await;
+            .header("Referer", "https://windsurf.com/");
+        let result = self.transport.send("GetTeamCreditEntries", request).await;
         
         match result {
             Ok(response) => {
@@ -511,7 +522,7 @@ impl WindsurfService {
         
         println!("[GetTeamBilling] Sending request to {}", url);
         
-        let result = self.client
+        let request = self.client
             .post(&url)
             .body(full_body)
             .header("accept", "*/*")
@@ -530,9 +541,8 @@ impl WindsurfService {
             .header("x-auth-token", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await;
+            .header("Referer", "https://windsurf.com/");
+        let result = self.transport.send("GetTeamBilling", request).await;
         
         match result {
             Ok(response) => {
@@ -594,7 +604,7 @@ impl WindsurfService {
         
         let body = self.build_update_plan_body(token, plan_type, period, preview);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -612,9 +622,8 @@ impl WindsurfService {
             .header("sec-fetch-site", "same-site")
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpdatePlan", request).await?;
         
         let status_code = response.status().as_u16();
         let response_bytes = response.bytes().await.unwrap_or_default();
@@ -691,9 +700,9 @@ impl WindsurfService {
         let body = self.build_cancel_plan_body(token, reason);
 
         println!("[CancelPlan] Request body length: {} bytes", body.len());
-        println!("[CancelPlan] Request body hex: {}", body.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        proto_debug::log_body("CancelPlan", &body);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -712,9 +721,8 @@ impl WindsurfService {
             .header("x-api-key", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("CancelPlan", request).await?;
 
         let status_code = response.status().as_u16();
         let response_bytes = response.bytes().await.unwrap_or_default();
@@ -747,9 +755,9 @@ impl WindsurfService {
         let body = self.build_resume_plan_body(token);
 
         println!("[ResumePlan] Request body length: {} bytes", body.len());
-        println!("[ResumePlan] Request body hex: {}", body.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        proto_debug::log_body("ResumePlan", &body);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -768,9 +776,8 @@ impl WindsurfService {
             .header("x-api-key", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("ResumePlan", request).await?;
 
         let status_code = response.status().as_u16();
         let response_bytes = response.bytes().await.unwrap_or_default();
@@ -790,26 +797,15 @@ impl WindsurfService {
     pub async fn get_current_user(&self, token: &str) -> AppResult<serde_json::Value> {
         let url = format!("{}/exa.seat_management_pb.SeatManagementService/GetCurrentUser", WINDSURF_BASE_URL);
         
-        // 构建请求体：0x0a + token长度(varint) + token + 0x10 0x01 0x18 0x01 0x20 0x01
-        let token_bytes = token.as_bytes();
-        let token_length = token_bytes.len();
+        // 构建请求体：field 1 = token，field 2/3/4 = true
+        let body = ProtoBuilder::new()
+            .string(1, token)
+            .varint(2, 1)
+            .varint(3, 1)
+            .varint(4, 1)
+            .build();
         
-        let mut body = vec![0x0a];
-        
-        // 添加varint编码的token长度
-        if token_length < 128 {
-            body.push(token_length as u8);
-        } else {
-            body.push(((token_length & 0x7F) | 0x80) as u8);
-            body.push((token_length >> 7) as u8);
-        }
-        
-        body.extend_from_slice(token_bytes);
-        
-        // 添加额外的字段
-        body.extend_from_slice(&[0x10, 0x01, 0x18, 0x01, 0x20, 0x01]);
-        
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -828,9 +824,8 @@ impl WindsurfService {
             .header("x-auth-token", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetCurrentUser", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await?;
@@ -901,7 +896,7 @@ impl WindsurfService {
         
         body.extend_from_slice(token_bytes);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -920,9 +915,8 @@ impl WindsurfService {
             .header("x-auth-token", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetPlanStatus", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await?;
@@ -1091,7 +1085,7 @@ impl WindsurfService {
 
         let body = self.build_subscribe_to_plan_body(token, price_id, success_url, cancel_url);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1111,9 +1105,8 @@ impl WindsurfService {
             .header("x-auth-token", token)
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("SubscribeToPlan", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await?;
@@ -1199,7 +1192,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1215,10 +1208,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetTeamConfigRecord", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -1371,7 +1362,7 @@ impl WindsurfService {
             body.push(if val { 0x01 } else { 0x00 });
         }
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1387,10 +1378,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpdateTeamConfigExternal", request).await?;
 
         let status_code = response.status().as_u16();
 
@@ -1432,7 +1421,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1448,10 +1437,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetCascadeModelConfigsForSite", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -1502,7 +1489,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1518,10 +1505,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetCommandModelConfigsForSite", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -1571,7 +1556,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1587,10 +1572,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetTeamOrganizationalControlsForSite", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -1718,7 +1701,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1734,10 +1717,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpsertTeamOrganizationalControlsForSite", request).await?;
 
         let status_code = response.status().as_u16();
 
@@ -1776,7 +1757,7 @@ impl WindsurfService {
             }
         });
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .json(&request_body)
             .header("accept", "*/*")
@@ -1792,10 +1773,8 @@ impl WindsurfService {
             .header("sec-fetch-dest", "empty")
             .header("sec-fetch-mode", "cors")
             .header("sec-fetch-site", "same-site")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetAvailableCascadePlugins", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.text().await
@@ -1822,6 +1801,96 @@ impl WindsurfService {
         }
     }
 
+    /// 获取用户状态 (GetUserStatus API)
+    /// IDE 自身使用的状态接口，包含套餐限制、功能开关和限流状态，GetCurrentUser 不返回这些信息
+    pub async fn get_user_status(&self, api_key: &str) -> AppResult<serde_json::Value> {
+        let url = format!("{}/exa.seat_management_pb.SeatManagementService/GetUserStatus", WINDSURF_BASE_URL);
+
+        let request_body = serde_json::json!({
+            "metadata": {
+                "ideName": "windsurf",
+                "extensionVersion": "1.0.0",
+                "apiKey": api_key,
+                "os": "unknown",
+                "ideVersion": "1.3.7"
+            }
+        });
+
+        let request = self.client
+            .post(&url)
+            .json(&request_body)
+            .header("accept", "*/*")
+            .header("accept-language", "zh-CN,zh;q=0.9")
+            .header("cache-control", "no-cache")
+            .header("connect-protocol-version", "1")
+            .header("content-type", "application/json")
+            .header("pragma", "no-cache")
+            .header("priority", "u=1, i")
+            .header("sec-ch-ua", r#""Chromium";v="142", "Google Chrome";v="142", "Not_A Brand";v="99""#)
+            .header("sec-ch-ua-mobile", "?0")
+            .header("sec-ch-ua-platform", r#""Windows""#)
+            .header("sec-fetch-dest", "empty")
+            .header("sec-fetch-mode", "cors")
+            .header("sec-fetch-site", "same-site")
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetUserStatus", request).await?;
+
+        let status_code = response.status().as_u16();
+        let response_body = response.text().await
+            .map_err(|e| AppError::Api(e.to_string()))?;
+
+        println!("[GetUserStatus] Status code: {}", status_code);
+
+        if status_code == 200 {
+            let parsed: serde_json::Value = serde_json::from_str(&response_body)
+                .unwrap_or_else(|_| serde_json::json!({}));
+
+            Ok(serde_json::json!({
+                "success": true,
+                "status_code": status_code,
+                "user_status": super::proto_parser::parse_user_status(&parsed),
+                "raw_data": parsed,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }))
+        } else {
+            Ok(serde_json::json!({
+                "success": false,
+                "status_code": status_code,
+                "error": "获取用户状态失败",
+                "error_details": response_body,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }))
+        }
+    }
+
+    /// 向任意 Windsurf 接口发送原始 protobuf 请求体（开发者控制台），返回状态码与原始响应体
+    ///
+    /// `path` 只能是接口路径（如 /exa.seat_management_pb.SeatManagementService/GetCurrentUser），
+    /// 请求固定发往 Windsurf 后端，避免把账号 Token 发送到其他主机
+    pub async fn call_raw_endpoint(&self, path: &str, token: &str, body: Vec<u8>) -> AppResult<(u16, Vec<u8>)> {
+        if !path.starts_with("/exa.") || path.contains("://") || path.contains("..") {
+            return Err(AppError::Api(format!("无效的接口路径: {}", path)));
+        }
+        let url = format!("{}{}", WINDSURF_BASE_URL, path);
+
+        let request = self.client
+            .post(&url)
+            .body(body)
+            .header("accept", "*/*")
+            .header("connect-protocol-version", "1")
+            .header("content-type", "application/proto")
+            .header("x-auth-token", token)
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_once("RawEndpoint", request).await?;
+
+        let status_code = response.status().as_u16();
+        let response_body = response.bytes().await
+            .map_err(|e| AppError::Api(e.to_string()))?;
+
+        println!("[RawEndpoint] {} -> {} ({} bytes)", path, status_code, response_body.len());
+        Ok((status_code, response_body.to_vec()))
+    }
+
     /// 删除用户 (DeleteUser API)
     /// DeleteUserRequest: auth_token=1, api_key=3
     pub async fn delete_user(&self, token: &str, api_key: &str) -> AppResult<serde_json::Value> {
@@ -1858,7 +1927,7 @@ impl WindsurfService {
 
         log::info!("[DeleteUser] Request body size: {} bytes", request_body.len());
 
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(request_body)
             .header("accept", "*/*")
@@ -1876,10 +1945,8 @@ impl WindsurfService {
             .header("sec-fetch-site", "same-site")
             .header("x-debug-email", "")
             .header("x-debug-team-name", "")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("DeleteUser", request).await?;
 
         let status_code = response.status().as_u16();
         log::info!("[DeleteUser] Response status: {}", status_code);
@@ -1942,7 +2009,7 @@ impl WindsurfService {
             body.extend(self.encode_string_field(2, gid));
         }
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -1952,10 +2019,8 @@ impl WindsurfService {
             .header("content-type", "application/proto")
             .header("pragma", "no-cache")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetUsers", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -2007,17 +2072,15 @@ impl WindsurfService {
             body.extend(item);
         }
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("GrantPreapproval", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -2055,17 +2118,15 @@ impl WindsurfService {
         let mut body = self.encode_string_field(1, token);
         body.extend(self.encode_string_field(2, api_key));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("RemoveUserFromTeam", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[RemoveUserFromTeam] Status: {}", status_code);
@@ -2099,17 +2160,15 @@ impl WindsurfService {
         let mut body = self.encode_string_field(1, token);
         body.extend(self.encode_string_field(2, approval_id));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("RevokePreapproval", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[RevokePreapproval] Status: {}", status_code);
@@ -2142,17 +2201,15 @@ impl WindsurfService {
         
         let body = self.encode_string_field(1, token);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetPreapprovals", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -2194,17 +2251,15 @@ impl WindsurfService {
         
         let body = self.encode_string_field(1, token);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetPreapprovalForUser", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -2256,17 +2311,15 @@ impl WindsurfService {
         let mut body = self.encode_string_field(1, token);
         body.extend(self.encode_string_field(2, approval_id));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("AcceptPreapproval", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[AcceptPreapproval] Status: {}", status_code);
@@ -2300,17 +2353,15 @@ impl WindsurfService {
         let mut body = self.encode_string_field(1, token);
         body.extend(self.encode_string_field(2, approval_id));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("RejectPreapproval", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[RejectPreapproval] Status: {}", status_code);
@@ -2344,16 +2395,14 @@ impl WindsurfService {
         let mut body = self.encode_string_field(1, api_key);
         body.extend(self.encode_string_field(2, invite_id));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("RequestTeamAccess", request).await?;
 
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await
@@ -2416,17 +2465,15 @@ impl WindsurfService {
         }
         body.extend(inner_msg);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpdateUserTeamStatus", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[UpdateUserTeamStatus] Status: {}", status_code);
@@ -2505,17 +2552,15 @@ impl WindsurfService {
         body.push(0x20); // field 4, wire type 0
         body.extend(self.encode_varint(top_up_increment as u64));
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpdateCreditTopUpSettings", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[UpdateCreditTopUpSettings] Status: {}", status_code);
@@ -2565,7 +2610,7 @@ impl WindsurfService {
         }
         body.extend_from_slice(token_bytes);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
@@ -2575,9 +2620,8 @@ impl WindsurfService {
             .header("content-type", "application/proto")
             .header("pragma", "no-cache")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send("GetPlanStatus", request).await?;
         
         let status_code = response.status().as_u16();
         let response_body = response.bytes().await?;
@@ -2653,17 +2697,15 @@ impl WindsurfService {
         body.push((3 << 3) | 0); // field 3, wire type 0 (varint)
         body.push(if disable_access { 1 } else { 0 });
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("UpdateCodeiumAccess", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[UpdateCodeiumAccess] Status: {}, disable={}", status_code, disable_access);
@@ -2703,17 +2745,15 @@ impl WindsurfService {
             body.extend(self.encode_string_field(4, gid));
         }
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("AddUserRole", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[AddUserRole] Status: {}, role={}", status_code, role);
@@ -2752,17 +2792,15 @@ impl WindsurfService {
             body.extend(self.encode_string_field(4, gid));
         }
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .body(body)
             .header("accept", "*/*")
             .header("connect-protocol-version", "1")
             .header("content-type", "application/proto")
             .header("x-auth-token", token)
-            .header("Referer", "https://windsurf.com/")
-            .send()
-            .await
-            .map_err(|e| AppError::Api(e.to_string()))?;
+            .header("Referer", "https://windsurf.com/");
+        let response = self.transport.send_mutation("RemoveUserRole", request).await?;
 
         let status_code = response.status().as_u16();
         println!("[RemoveUserRole] Status: {}, role={}", status_code, role);
@@ -2789,3 +2827,32 @@ impl WindsurfService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::transport::MockTransport;
+    use crate::utils::fixtures;
+
+    /// 回放 tests/fixtures/current_user 下的 GetCurrentUser 样例，解析出的 user_info 必须与期望一致
+    #[test]
+    fn test_get_current_user_fixtures() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let loaded = fixtures::load_fixtures(&fixtures::fixture_dir("current_user")).unwrap();
+        assert!(!loaded.is_empty());
+        for fixture in loaded {
+            let transport = Arc::new(MockTransport::new().with_response("GetCurrentUser", 200, fixture.body.clone()));
+            let service = WindsurfService::with_transport(transport.clone());
+            let result = runtime.block_on(service.get_current_user("token")).unwrap();
+            assert_eq!(result["success"], true, "{}", fixture.name);
+            let diffs = fixtures::json_subset_diff(&fixture.expected, &result["user_info"]);
+            assert!(diffs.is_empty(), "{}: {:?}", fixture.name, diffs);
+
+            // 请求体 field 1 为 token
+            let requests = transport.requests();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0].label, "GetCurrentUser");
+            assert_eq!(&requests[0].body[..7], &[0x0a, 5, b't', b'o', b'k', b'e', b'n']);
+        }
+    }
+}
@@ -5,11 +5,16 @@ use aes_gcm::{
 use anyhow::Result;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use keyring::Entry;
+use pbkdf2::pbkdf2_hmac;
 use rand::{RngCore, thread_rng};
+use sha2::Sha256;
 
 const APP_NAME: &str = "WindsurfAccountManager";
 const KEY_NAME: &str = "MasterKey";
 
+/// 主密码派生密钥的迭代次数
+const PBKDF2_ROUNDS: u32 = 200_000;
+
 pub struct CryptoService {
     cipher: Aes256Gcm,
 }
@@ -24,6 +29,24 @@ impl CryptoService {
         Ok(Self { cipher })
     }
 
+    /// 由主密码和盐（base64）派生密钥（PBKDF2-HMAC-SHA256）
+    pub fn from_password(password: &str, salt: &str) -> Result<Self> {
+        let salt = STANDARD.decode(salt)?;
+        let mut key_bytes = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, PBKDF2_ROUNDS, &mut key_bytes);
+        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+        let cipher = Aes256Gcm::new(key);
+        
+        Ok(Self { cipher })
+    }
+
+    /// 生成随机盐（base64）
+    pub fn generate_salt() -> String {
+        let mut salt = [0u8; 16];
+        thread_rng().fill_bytes(&mut salt);
+        STANDARD.encode(salt)
+    }
+
     fn get_or_create_key() -> Result<String> {
         let entry = Entry::new(APP_NAME, KEY_NAME)?;
         
@@ -85,12 +108,6 @@ impl CryptoService {
         String::from_utf8(plaintext)
             .map_err(|e| anyhow::anyhow!("Invalid UTF-8: {}", e))
     }
-
-    pub fn set_master_password(&self, _password: &str) -> Result<()> {
-        // 这里可以实现主密码功能，用密码派生密钥
-        // 暂时不实现，使用系统密钥链即可
-        Ok(())
-    }
 }
 
 #[cfg(test)]
@@ -107,4 +124,17 @@ mod tests {
         
         assert_eq!(plaintext, decrypted);
     }
+
+    #[test]
+    fn test_from_password() {
+        let salt = CryptoService::generate_salt();
+        let service = CryptoService::from_password("master", &salt).unwrap();
+        let encrypted = service.encrypt("token").unwrap();
+
+        let same = CryptoService::from_password("master", &salt).unwrap();
+        assert_eq!(same.decrypt(&encrypted).unwrap(), "token");
+
+        let wrong = CryptoService::from_password("other", &salt).unwrap();
+        assert!(wrong.decrypt(&encrypted).is_err());
+    }
 }
@@ -1,4 +1,5 @@
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, Datelike, Timelike, Utc, Duration};
+use chrono_tz::Tz;
 
 /// 格式化日期时间为用户友好的字符串
 pub fn format_datetime(dt: &DateTime<Utc>) -> String {
@@ -25,6 +26,73 @@ pub fn parse_iso_datetime(s: &str) -> Result<DateTime<Utc>, chrono::ParseError>
     s.parse::<DateTime<Utc>>()
 }
 
+/// 解析 IANA 时区名称（如 Asia/Shanghai），未配置或无效时返回 None（使用系统时区）
+pub fn parse_timezone(name: Option<&str>) -> Option<Tz> {
+    name.map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .and_then(|n| n.parse::<Tz>().ok())
+}
+
+/// 请求中使用的时区名称：设置中的有效时区优先，其次为系统时区，都无法确定时为 UTC
+pub fn resolve_timezone_name(configured: Option<&str>) -> String {
+    if let Some(name) = configured.map(|n| n.trim()).filter(|n| parse_timezone(Some(n)).is_some()) {
+        return name.to_string();
+    }
+    iana_time_zone::get_timezone()
+        .ok()
+        .filter(|name| parse_timezone(Some(name)).is_some())
+        .unwrap_or_else(|| "UTC".to_string())
+}
+
+/// 将 Unix 时间戳转换为指定时区的 (星期, 小时)，星期从周一 = 0 开始
+pub fn weekday_hour(timestamp: i64, tz: Option<Tz>) -> Option<(usize, usize)> {
+    let utc = DateTime::from_timestamp(timestamp, 0)?;
+    let (weekday, hour) = match tz {
+        Some(tz) => {
+            let local = utc.with_timezone(&tz);
+            (local.weekday(), local.hour())
+        }
+        None => {
+            let local = utc.with_timezone(&chrono::Local);
+            (local.weekday(), local.hour())
+        }
+    };
+    Some((weekday.num_days_from_monday() as usize, hour as usize))
+}
+
+/// 按天统计时日期的归属方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DayBucketing {
+    /// 按 UTC 日期
+    Utc,
+    /// 按设置中的时区（None 为系统时区）
+    Local(Option<Tz>),
+}
+
+impl DayBucketing {
+    /// 根据设置解析：`mode` 为 "timezone" 时按时区，否则按 UTC
+    pub fn from_settings(mode: &str, timezone: Option<&str>) -> Self {
+        if mode.eq_ignore_ascii_case("timezone") {
+            DayBucketing::Local(parse_timezone(timezone))
+        } else {
+            DayBucketing::Utc
+        }
+    }
+}
+
+/// 将 Unix 时间戳格式化为日期（YYYY-MM-DD），时间戳无效时返回 "Unknown"
+pub fn format_day(timestamp: i64, bucketing: DayBucketing) -> String {
+    let utc = match DateTime::from_timestamp(timestamp, 0) {
+        Some(utc) if timestamp > 0 => utc,
+        _ => return "Unknown".to_string(),
+    };
+    match bucketing {
+        DayBucketing::Utc => utc.format("%Y-%m-%d").to_string(),
+        DayBucketing::Local(Some(tz)) => utc.with_timezone(&tz).format("%Y-%m-%d").to_string(),
+        DayBucketing::Local(None) => utc.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,4 +111,25 @@ mod tests {
         assert!(is_token_expired(&past));
         assert!(!is_token_expired(&future));
     }
+
+    #[test]
+    fn test_weekday_hour_with_timezone() {
+        // 2024-01-01T00:00:00Z 是周一，上海时间 08:00
+        let ts = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap().timestamp();
+        let tz = parse_timezone(Some("Asia/Shanghai"));
+        assert_eq!(weekday_hour(ts, tz), Some((0, 8)));
+        assert!(parse_timezone(Some("Not/AZone")).is_none());
+        assert_eq!(resolve_timezone_name(Some(" Europe/Berlin ")), "Europe/Berlin");
+        assert!(parse_timezone(Some(&resolve_timezone_name(Some("Not/AZone")))).is_some());
+    }
+
+    #[test]
+    fn test_format_day_bucketing() {
+        // 2024-01-01T20:00:00Z 在上海已是 1 月 2 日
+        let ts = "2024-01-01T20:00:00Z".parse::<DateTime<Utc>>().unwrap().timestamp();
+        assert_eq!(format_day(ts, DayBucketing::Utc), "2024-01-01");
+        let shanghai = DayBucketing::from_settings("timezone", Some("Asia/Shanghai"));
+        assert_eq!(format_day(ts, shanghai), "2024-01-02");
+        assert_eq!(format_day(0, DayBucketing::Utc), "Unknown");
+    }
 }
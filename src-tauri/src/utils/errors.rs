@@ -1,3 +1,4 @@
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -32,6 +33,12 @@ pub enum AppError {
     #[error("Token expired")]
     TokenExpired,
     
+    #[error("数据已锁定，请先输入主密码解锁")]
+    StoreLocked,
+    
+    #[error("Token not available in this session (memory-only secrets mode): {0}")]
+    SessionSecretUnavailable(String),
+    
     #[error("Authentication failed: {0}")]
     AuthFailed(String),
     
@@ -56,3 +63,116 @@ impl From<AppError> for tauri::ipc::InvokeError {
         tauri::ipc::InvokeError::from(error.to_string())
     }
 }
+
+/// 返回给前端的错误，序列化为 `{ "kind": "auth", "message": "..." }`，前端可按类型处理（如 auth 时提示重新登录）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CommandError {
+    /// Token 失效、认证失败，需要重新登录
+    Auth(String),
+    /// 网络不可用或请求超时
+    Network(String),
+    /// 请求过于频繁
+    RateLimited(String),
+    /// 账号或资源不存在
+    NotFound(String),
+    /// 响应或数据解析失败
+    Parse(String),
+    Other(String),
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    /// 根据错误信息推断类型，用于只有字符串的错误（接口状态码、旧命令的错误信息）
+    pub fn classify(message: String) -> Self {
+        let lower = message.to_lowercase();
+        let has = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+        if has(&["status code 401", "status code 403", "unauthenticated", "token expired", "authentication failed", "permission_denied", "not available in this session"]) {
+            CommandError::Auth(message)
+        } else if has(&["status code 429", "rate limit", "resource_exhausted", "too many requests"]) {
+            CommandError::RateLimited(message)
+        } else if has(&["not found", "未找到", "不存在"]) {
+            CommandError::NotFound(message)
+        } else if has(&["network error", "error sending request", "timed out", "request failed", "connection"]) {
+            CommandError::Network(message)
+        } else if has(&["parse", "decode", "serialization error"]) {
+            CommandError::Parse(message)
+        } else {
+            CommandError::Other(message)
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::Auth(m)
+            | CommandError::Network(m)
+            | CommandError::RateLimited(m)
+            | CommandError::NotFound(m)
+            | CommandError::Parse(m)
+            | CommandError::Other(m) => m,
+        }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<AppError> for CommandError {
+    fn from(error: AppError) -> Self {
+        let message = error.to_string();
+        match error {
+            AppError::TokenExpired | AppError::AuthFailed(_) | AppError::SessionSecretUnavailable(_) => CommandError::Auth(message),
+            AppError::Network(_) => CommandError::Network(message),
+            AppError::HttpRequest(e) if e.is_decode() => CommandError::Parse(message),
+            AppError::HttpRequest(_) => CommandError::Network(message),
+            AppError::AccountNotFound(_) => CommandError::NotFound(message),
+            AppError::Serialization(_) => CommandError::Parse(message),
+            _ => CommandError::classify(message),
+        }
+    }
+}
+
+/// 仍返回字符串错误的命令调用返回 `CommandError` 的函数时使用
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        error.message().to_string()
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::classify(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::classify(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_error_kind() {
+        assert_eq!(CommandError::from(AppError::TokenExpired), CommandError::Auth("Token expired".to_string()));
+        assert!(matches!(
+            CommandError::from(AppError::Api("API returned status code 429: slow down".to_string())),
+            CommandError::RateLimited(_)
+        ));
+        assert!(matches!(CommandError::from("账号没有有效的 Token"), CommandError::Other(_)));
+        // 经字符串传递的错误仍能识别为认证错误
+        let message = AppError::SessionSecretUnavailable("a@example.com".to_string()).to_string();
+        assert!(matches!(CommandError::from(message), CommandError::Auth(_)));
+
+        let json = serde_json::to_value(CommandError::NotFound("Account not found: x".to_string())).unwrap();
+        assert_eq!(json["kind"], "not_found");
+        assert_eq!(json["message"], "Account not found: x");
+    }
+}
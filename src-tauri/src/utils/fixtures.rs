@@ -0,0 +1,153 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 原始响应文件后缀
+pub const BODY_SUFFIX: &str = ".bin";
+
+/// 期望结果文件后缀
+pub const EXPECTED_SUFFIX: &str = ".expected.json";
+
+/// 一个抽取回归样例：原始 GetAnalytics 响应体 + 期望的抽取结果（可以只包含部分字段）
+#[derive(Debug, Clone)]
+pub struct AnalyticsFixture {
+    pub name: String,
+    pub body: Vec<u8>,
+    pub expected: Value,
+}
+
+/// 单个样例的运行结果
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureResult {
+    pub name: String,
+    pub passed: bool,
+    /// 与期望结果不一致的字段
+    pub diffs: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// 仓库内的样例目录：tests/fixtures/<kind>（analytics、current_user）
+pub fn fixture_dir(kind: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(kind)
+}
+
+/// 仓库内的默认样例目录
+pub fn default_fixture_dir() -> PathBuf {
+    fixture_dir("analytics")
+}
+
+/// 加载目录下所有成对的 `<name>.bin` / `<name>.expected.json`，目录不存在时返回空列表
+pub fn load_fixtures(dir: &Path) -> io::Result<Vec<AnalyticsFixture>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut fixtures = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = match path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(BODY_SUFFIX))
+        {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        let expected_path = dir.join(format!("{}{}", name, EXPECTED_SUFFIX));
+        if !expected_path.exists() {
+            println!("[Fixtures] Skipping {}: missing {}", name, expected_path.display());
+            continue;
+        }
+
+        let expected: Value = serde_json::from_str(&fs::read_to_string(&expected_path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", name, e)))?;
+        fixtures.push(AnalyticsFixture {
+            name,
+            body: fs::read(&path)?,
+            expected,
+        });
+    }
+
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+/// 比较期望值与实际值：只检查期望中出现的字段，数组要求长度一致并逐项比较
+pub fn json_subset_diff(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut diffs = Vec::new();
+    collect_diffs(expected, actual, "$", &mut diffs);
+    diffs
+}
+
+fn collect_diffs(expected: &Value, actual: &Value, path: &str, diffs: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_value) in expected_map {
+                let child = format!("{}.{}", path, key);
+                match actual_map.get(key) {
+                    Some(actual_value) => collect_diffs(expected_value, actual_value, &child, diffs),
+                    None => diffs.push(format!("{}: missing", child)),
+                }
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            if expected_items.len() != actual_items.len() {
+                diffs.push(format!(
+                    "{}: expected {} items, got {}",
+                    path,
+                    expected_items.len(),
+                    actual_items.len()
+                ));
+                return;
+            }
+            for (index, (e, a)) in expected_items.iter().zip(actual_items).enumerate() {
+                collect_diffs(e, a, &format!("{}[{}]", path, index), diffs);
+            }
+        }
+        // 浮点数允许微小误差
+        (Value::Number(e), Value::Number(a)) if e.is_f64() || a.is_f64() => {
+            let (e, a) = (e.as_f64().unwrap_or_default(), a.as_f64().unwrap_or_default());
+            if (e - a).abs() > 1e-6 {
+                diffs.push(format!("{}: expected {}, got {}", path, e, a));
+            }
+        }
+        _ => {
+            if expected != actual {
+                diffs.push(format!("{}: expected {}, got {}", path, expected, actual));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_subset_diff() {
+        let actual = json!({
+            "summary": { "total_accepted_lines": 120, "primary_model": "Claude" },
+            "tool_usage": [{ "tool_name": "edit", "count": 3 }],
+            "extra": true,
+        });
+
+        let matching = json!({ "summary": { "total_accepted_lines": 120 } });
+        assert!(json_subset_diff(&matching, &actual).is_empty());
+
+        let changed = json!({
+            "summary": { "total_accepted_lines": 100 },
+            "tool_usage": [],
+            "missing": 1,
+        });
+        let diffs = json_subset_diff(&changed, &actual);
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs.iter().any(|d| d.starts_with("$.summary.total_accepted_lines")));
+    }
+}
@@ -0,0 +1,107 @@
+use serde::Deserialize;
+
+/// 需要格式化的数值类型
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// 字节数：4383029 → "4.38 MB"
+    Bytes,
+    /// 紧凑计数：1200000 → "1.2M"（中文界面为 "120万"）
+    Compact,
+    /// 千分位：4383029 → "4,383,029"
+    Thousands,
+    /// 百分比：0.1234 → "12.3%"
+    Percent,
+}
+
+fn is_chinese(locale: &str) -> bool {
+    locale.to_lowercase().starts_with("zh")
+}
+
+/// 保留最多 `digits` 位小数并去掉末尾的 0
+fn trim_decimal(value: f64, digits: usize) -> String {
+    let s = format!("{:.*}", digits, value);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes.abs();
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    let sign = if bytes < 0.0 { "-" } else { "" };
+    if unit == 0 {
+        format!("{}{} {}", sign, value as u64, UNITS[unit])
+    } else {
+        format!("{}{:.2} {}", sign, value, UNITS[unit])
+    }
+}
+
+pub fn format_compact(value: f64, locale: &str) -> String {
+    let abs = value.abs();
+    let sign = if value < 0.0 { "-" } else { "" };
+    let (divisor, suffix) = if is_chinese(locale) {
+        match abs {
+            v if v >= 1e8 => (1e8, "亿"),
+            v if v >= 1e4 => (1e4, "万"),
+            _ => (1.0, ""),
+        }
+    } else {
+        match abs {
+            v if v >= 1e9 => (1e9, "B"),
+            v if v >= 1e6 => (1e6, "M"),
+            v if v >= 1e3 => (1e3, "K"),
+            _ => (1.0, ""),
+        }
+    };
+    format!("{}{}{}", sign, trim_decimal(abs / divisor, 1), suffix)
+}
+
+pub fn format_thousands(value: f64) -> String {
+    let rounded = value.round() as i64;
+    let digits = rounded.unsigned_abs().to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if rounded < 0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+pub fn format_metric(value: f64, kind: MetricKind, locale: &str) -> String {
+    match kind {
+        MetricKind::Bytes => format_bytes(value),
+        MetricKind::Compact => format_compact(value, locale),
+        MetricKind::Thousands => format_thousands(value),
+        MetricKind::Percent => format!("{}%", trim_decimal(value * 100.0, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_metric() {
+        assert_eq!(format_bytes(4383029.0), "4.38 MB");
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_compact(1_200_000.0, "en-US"), "1.2M");
+        assert_eq!(format_compact(1_200_000.0, "zh-CN"), "120万");
+        assert_eq!(format_compact(950.0, "en-US"), "950");
+        assert_eq!(format_thousands(4383029.0), "4,383,029");
+        assert_eq!(format_thousands(-1000.0), "-1,000");
+        assert_eq!(format_metric(0.1234, MetricKind::Percent, "en-US"), "12.3%");
+    }
+}
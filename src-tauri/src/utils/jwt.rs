@@ -0,0 +1,33 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// 解码 JWT 的 payload 部分（不校验签名）
+pub fn decode_payload(token: &str) -> Option<Value> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// 从 JWT 的 exp 声明中读取过期时间
+pub fn expires_at(token: &str) -> Option<DateTime<Utc>> {
+    let exp = decode_payload(token)?.get("exp")?.as_i64()?;
+    DateTime::from_timestamp(exp, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expires_at() {
+        // {"alg":"none"}.{"exp":1704067200,"email":"a@b.c"}.
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(br#"{"exp":1704067200,"email":"a@b.c"}"#);
+        let token = format!("{}.{}.sig", header, payload);
+
+        let exp = expires_at(&token).unwrap();
+        assert_eq!(exp.timestamp(), 1704067200);
+        assert!(expires_at("not-a-jwt").is_none());
+    }
+}
@@ -2,5 +2,11 @@ pub mod crypto;
 pub mod errors;
 pub mod date_utils;
 pub mod card_generator;
+pub mod jwt;
+pub mod proto_debug;
+pub mod proto_builder;
+pub mod fixtures;
+pub mod retry;
+pub mod format;
 
-pub use errors::{AppError, AppResult};
+pub use errors::{AppError, AppResult, CommandError, CommandResult};
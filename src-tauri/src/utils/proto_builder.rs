@@ -0,0 +1,150 @@
+use serde::Deserialize;
+
+/// 单个字段的值，`wire_type` 决定编码方式
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "wire_type", content = "value", rename_all = "snake_case")]
+pub enum ProtoValue {
+    Varint(u64),
+    Bool(bool),
+    Fixed64(u64),
+    Fixed32(u32),
+    String(String),
+    /// 十六进制编码的原始字节
+    Bytes(String),
+    /// 嵌套消息
+    Message(Vec<ProtoField>),
+}
+
+/// 字段号 + 值，例如 `{ "field": 1, "wire_type": "string", "value": "token" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtoField {
+    pub field: u32,
+    #[serde(flatten)]
+    pub value: ProtoValue,
+}
+
+pub fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn encode_tag(field: u32, wire_type: u8, out: &mut Vec<u8>) {
+    encode_varint(((field as u64) << 3) | wire_type as u64, out);
+}
+
+fn encode_length_delimited(field: u32, data: &[u8], out: &mut Vec<u8>) {
+    encode_tag(field, 2, out);
+    encode_varint(data.len() as u64, out);
+    out.extend_from_slice(data);
+}
+
+/// 按顺序编码字段列表
+pub fn encode_fields(fields: &[ProtoField]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for field in fields {
+        if field.field == 0 {
+            return Err("字段号必须大于 0".to_string());
+        }
+        match &field.value {
+            ProtoValue::Varint(v) => {
+                encode_tag(field.field, 0, &mut out);
+                encode_varint(*v, &mut out);
+            }
+            ProtoValue::Bool(v) => {
+                encode_tag(field.field, 0, &mut out);
+                encode_varint(*v as u64, &mut out);
+            }
+            ProtoValue::Fixed64(v) => {
+                encode_tag(field.field, 1, &mut out);
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            ProtoValue::Fixed32(v) => {
+                encode_tag(field.field, 5, &mut out);
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            ProtoValue::String(v) => encode_length_delimited(field.field, v.as_bytes(), &mut out),
+            ProtoValue::Bytes(v) => {
+                let bytes = hex::decode(v).map_err(|e| format!("字段 {} 不是有效的十六进制: {}", field.field, e))?;
+                encode_length_delimited(field.field, &bytes, &mut out);
+            }
+            ProtoValue::Message(nested) => {
+                let bytes = encode_fields(nested)?;
+                encode_length_delimited(field.field, &bytes, &mut out);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 在代码中逐个追加字段的构建器
+#[derive(Debug, Default)]
+pub struct ProtoBuilder {
+    buf: Vec<u8>,
+}
+
+impl ProtoBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn varint(mut self, field: u32, value: u64) -> Self {
+        encode_tag(field, 0, &mut self.buf);
+        encode_varint(value, &mut self.buf);
+        self
+    }
+
+    pub fn string(mut self, field: u32, value: &str) -> Self {
+        encode_length_delimited(field, value.as_bytes(), &mut self.buf);
+        self
+    }
+
+    pub fn message(mut self, field: u32, nested: ProtoBuilder) -> Self {
+        encode_length_delimited(field, &nested.buf, &mut self.buf);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_fields() {
+        // 与 GetCurrentUser 请求体一致：0x0a + 长度 + token + 0x10 0x01 0x18 0x01 0x20 0x01
+        let body = ProtoBuilder::new()
+            .string(1, "abc")
+            .varint(2, 1)
+            .varint(3, 1)
+            .varint(4, 1)
+            .build();
+        assert_eq!(body, vec![0x0a, 3, b'a', b'b', b'c', 0x10, 0x01, 0x18, 0x01, 0x20, 0x01]);
+
+        let fields: Vec<ProtoField> = serde_json::from_str(
+            r#"[
+                { "field": 1, "wire_type": "string", "value": "abc" },
+                { "field": 2, "wire_type": "bool", "value": true },
+                { "field": 3, "wire_type": "bool", "value": true },
+                { "field": 4, "wire_type": "varint", "value": 1 }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(encode_fields(&fields).unwrap(), body);
+
+        let mut out = Vec::new();
+        encode_varint(300, &mut out);
+        assert_eq!(out, vec![0xAC, 0x02]);
+
+        let nested: Vec<ProtoField> = serde_json::from_str(
+            r#"[{ "field": 20, "wire_type": "message", "value": [{ "field": 1, "wire_type": "bytes", "value": "ff" }] }]"#,
+        )
+        .unwrap();
+        assert_eq!(encode_fields(&nested).unwrap(), vec![0xA2, 0x01, 3, 0x0a, 1, 0xff]);
+    }
+}
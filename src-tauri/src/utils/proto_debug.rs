@@ -0,0 +1,342 @@
+use crate::services::proto_parser::ProtobufParser;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// 单个日志文件的大小上限，超过后轮换
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// 保留的历史日志数量（proto-debug.log.1 ~ proto-debug.log.N）
+const MAX_ROTATED_FILES: usize = 3;
+
+const LOG_FILE_NAME: &str = "proto-debug.log";
+
+/// 抓包文件目录（位于数据目录下）
+const CAPTURE_DIR_NAME: &str = "captures";
+
+/// 最多保留的抓包文件数量，超过后删除最旧的
+const MAX_CAPTURE_FILES: usize = 500;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 控制台协议详细日志（逐条解析输出），调试构建默认开启，发布构建默认关闭
+static VERBOSE: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 抓包模式：把每个请求/响应体完整写入 captures/ 下的独立文件
+static CAPTURE: AtomicBool = AtomicBool::new(false);
+static CAPTURE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 初始化调试日志目录与开关（应用启动时调用）
+pub fn init(data_dir: &Path, enabled: bool) {
+    if let Ok(mut path) = LOG_PATH.lock() {
+        *path = Some(data_dir.join(LOG_FILE_NAME));
+    }
+    if let Ok(mut dir) = CAPTURE_DIR.lock() {
+        *dir = Some(data_dir.join(CAPTURE_DIR_NAME));
+    }
+    set_enabled(enabled);
+}
+
+/// 切换调试日志开关（设置变化时调用）
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 切换抓包模式（设置变化时调用）
+pub fn set_capture(enabled: bool) {
+    CAPTURE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_capturing() -> bool {
+    CAPTURE.load(Ordering::Relaxed)
+}
+
+/// 切换控制台协议详细日志，None 时使用构建类型的默认值
+pub fn set_verbose(verbose: Option<bool>) {
+    VERBOSE.store(verbose.unwrap_or(cfg!(debug_assertions)), Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// 仅在开启协议详细日志时输出到控制台（用于解析过程中的逐条日志）
+#[macro_export]
+macro_rules! proto_log {
+    ($($arg:tt)*) => {
+        if $crate::utils::proto_debug::is_verbose() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// 记录原始请求/响应体（十六进制），仅在开启调试日志时写入 proto-debug.log
+pub fn log_body(tag: &str, body: &[u8]) {
+    if !is_enabled() {
+        return;
+    }
+    let hex: String = body.iter().map(|b| format!("{:02x}", b)).collect();
+    write_line(&format!("[{}] {} bytes: {}", tag, body.len(), hex));
+}
+
+/// 抓包文件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureInfo {
+    pub name: String,
+    pub size: u64,
+}
+
+/// 十六进制 + 按通用规则解析出的 JSON（无法解析时为 null）
+fn describe_body(body: &[u8]) -> Value {
+    let parsed = if body.is_empty() {
+        Value::Null
+    } else {
+        ProtobufParser::new(body.to_vec()).parse_message().unwrap_or(Value::Null)
+    };
+    json!({
+        "length": body.len(),
+        "hex": hex::encode(body),
+        "parsed": parsed,
+    })
+}
+
+/// 抓包文件中需要隐藏的凭据前缀（Firebase JWT、Windsurf API Key）
+const SECRET_PREFIXES: &[&[u8]] = &[b"eyJ", b"sk-ws-"];
+
+/// 按前缀识别的凭据最短长度，避免误伤普通字符串
+const MIN_SECRET_LEN: usize = 20;
+
+fn is_secret_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.')
+}
+
+/// 把请求/响应体中的凭据替换为等长的 `*`：长度不变，protobuf 结构仍可解析
+///
+/// `secrets` 为请求头中已知的凭据（x-auth-token 等），其余按 JWT / API Key 前缀识别
+fn redact(body: &[u8], secrets: &[String]) -> Vec<u8> {
+    let mut out = body.to_vec();
+    for secret in secrets.iter().map(|s| s.as_bytes()).filter(|s| s.len() >= 8) {
+        let mut i = 0;
+        while i + secret.len() <= out.len() {
+            if &out[i..i + secret.len()] == secret {
+                out[i..i + secret.len()].fill(b'*');
+                i += secret.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+    let mut i = 0;
+    while i < out.len() {
+        if SECRET_PREFIXES.iter().any(|prefix| out[i..].starts_with(prefix)) {
+            let end = (i..out.len()).find(|&j| !is_secret_byte(out[j])).unwrap_or(out.len());
+            if end - i >= MIN_SECRET_LEN {
+                out[i..end].fill(b'*');
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+fn capture_dir() -> Option<PathBuf> {
+    CAPTURE_DIR.lock().ok().and_then(|dir| dir.clone())
+}
+
+/// 保存一次完整的请求/响应（仅在开启抓包模式时写入），写入前隐藏其中的 Token 与 API Key
+pub fn capture(tag: &str, request: &[u8], status: u16, response: &[u8], secrets: &[String]) {
+    if !is_capturing() {
+        return;
+    }
+    let Some(dir) = capture_dir() else {
+        return;
+    };
+    let now = chrono::Local::now();
+    let safe_tag: String = tag
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("{}_{}.json", now.format("%Y%m%d-%H%M%S%.3f"), safe_tag));
+    let content = json!({
+        "tag": tag,
+        "capturedAt": now.to_rfc3339(),
+        "status": status,
+        "request": describe_body(&redact(request, secrets)),
+        "response": describe_body(&redact(response, secrets)),
+    });
+
+    let result = fs::create_dir_all(&dir).and_then(|_| {
+        let text = serde_json::to_string_pretty(&content).unwrap_or_default();
+        fs::write(&path, text)
+    });
+    match result {
+        Ok(_) => prune_captures(&dir, MAX_CAPTURE_FILES),
+        Err(e) => println!("[ProtoDebug] Failed to write capture {}: {}", path.display(), e),
+    }
+}
+
+/// 文件名以时间开头，按名称排序即按时间排序
+fn sorted_captures(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map(|ext| ext == "json").unwrap_or(false))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn prune_captures(dir: &Path, keep: usize) {
+    let files = sorted_captures(dir);
+    if files.len() > keep {
+        for path in &files[..files.len() - keep] {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// 列出抓包文件，最新的在前
+pub fn list_captures() -> Vec<CaptureInfo> {
+    let Some(dir) = capture_dir() else {
+        return Vec::new();
+    };
+    sorted_captures(&dir)
+        .into_iter()
+        .rev()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            Some(CaptureInfo { name, size })
+        })
+        .collect()
+}
+
+/// 读取指定抓包文件，只接受 captures/ 下的文件名
+pub fn read_capture(name: &str) -> Result<Value, String> {
+    if name.contains('/') || name.contains('\\') || name.contains("..") || !name.ends_with(".json") {
+        return Err(format!("无效的抓包文件名: {}", name));
+    }
+    let dir = capture_dir().ok_or("抓包目录尚未初始化")?;
+    let text = fs::read_to_string(dir.join(name)).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+fn write_line(line: &str) {
+    let guard = match LOG_PATH.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let path = match guard.as_ref() {
+        Some(path) => path,
+        None => return,
+    };
+
+    rotate_if_needed(path, MAX_LOG_SIZE, MAX_ROTATED_FILES);
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| {
+            writeln!(
+                file,
+                "{} {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                line
+            )
+        });
+    if let Err(e) = result {
+        println!("[ProtoDebug] Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// 文件超过上限时轮换：log -> log.1 -> log.2 ...，最旧的被删除
+fn rotate_if_needed(path: &Path, max_size: u64, keep: usize) {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size < max_size {
+        return;
+    }
+
+    let rotated = |index: usize| PathBuf::from(format!("{}.{}", path.display(), index));
+    let _ = fs::remove_file(rotated(keep));
+    for index in (1..keep).rev() {
+        let _ = fs::rename(rotated(index), rotated(index + 1));
+    }
+    let _ = fs::rename(path, rotated(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_if_needed() {
+        let dir = std::env::temp_dir().join(format!("proto_debug_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOG_FILE_NAME);
+
+        fs::write(&path, "0123456789").unwrap();
+        rotate_if_needed(&path, 100, 2);
+        assert!(path.exists());
+
+        rotate_if_needed(&path, 5, 2);
+        assert!(!path.exists());
+        assert!(dir.join(format!("{}.1", LOG_FILE_NAME)).exists());
+
+        fs::write(&path, "0123456789").unwrap();
+        rotate_if_needed(&path, 5, 2);
+        assert!(dir.join(format!("{}.2", LOG_FILE_NAME)).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_redact() {
+        let jwt = format!("eyJ{}", "a".repeat(40));
+        let mut body = vec![0x0a, jwt.len() as u8];
+        body.extend(jwt.as_bytes());
+        body.extend([0x12, 0x0c]);
+        body.extend(b"header-token");
+        body.extend([0x1a, 0x03]);
+        body.extend(b"eyJ");
+
+        let redacted = redact(&body, &["header-token".to_string()]);
+        assert_eq!(redacted.len(), body.len());
+        assert!(!String::from_utf8_lossy(&redacted).contains("aaaa"));
+        assert!(!String::from_utf8_lossy(&redacted).contains("header-token"));
+        // 短字符串不视为凭据
+        assert!(redacted.ends_with(b"eyJ"));
+    }
+
+    #[test]
+    fn test_prune_captures() {
+        let dir = std::env::temp_dir().join(format!("proto_capture_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["20240101-000000.000_A.json", "20240102-000000.000_B.json", "20240103-000000.000_C.json"] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        prune_captures(&dir, 2);
+        let remaining: Vec<String> = sorted_captures(&dir)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(remaining, vec!["20240102-000000.000_B.json", "20240103-000000.000_C.json"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
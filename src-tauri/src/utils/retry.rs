@@ -0,0 +1,207 @@
+use crate::models::ApiRetryConfig;
+use crate::services::{custom_headers, rate_limiter};
+use crate::utils::proto_debug;
+use crate::utils::{AppError, AppResult};
+use rand::Rng;
+use uuid::Uuid;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// 当前生效的重试策略，由设置中的 apiRetry 控制
+static POLICY: RwLock<Option<ApiRetryConfig>> = RwLock::new(None);
+
+pub fn set_policy(config: ApiRetryConfig) {
+    if let Ok(mut policy) = POLICY.write() {
+        *policy = Some(config);
+    }
+}
+
+fn policy() -> ApiRetryConfig {
+    POLICY.read().ok().and_then(|p| p.clone()).unwrap_or_default()
+}
+
+/// 第 `attempt` 次失败后的等待时间（attempt 从 1 开始）
+fn backoff_delay(config: &ApiRetryConfig, attempt: u32) -> Duration {
+    let exp = config.base_delay_ms.saturating_mul(1u64 << (attempt - 1).min(16));
+    let delay = exp.min(config.max_delay_ms);
+    if config.jitter && delay > 1 {
+        // 保留一半固定等待，另一半随机，避免多个账号同时重试
+        let half = delay / 2;
+        Duration::from_millis(half + rand::thread_rng().gen_range(0..=half))
+    } else {
+        Duration::from_millis(delay)
+    }
+}
+
+/// 发送前从请求中取出的信息
+#[derive(Default)]
+struct RequestInfo {
+    /// 请求头中的账号 Token，用于按账号限速
+    token: Option<String>,
+    /// 请求体，仅在开启抓包模式时保留
+    body: Option<Vec<u8>>,
+    /// 请求头中的凭据，写入抓包文件前从请求/响应体中隐藏
+    secrets: Vec<String>,
+}
+
+/// 携带凭据的请求头
+const SECRET_HEADERS: &[&str] = &["x-auth-token", "authorization", "x-api-key"];
+
+fn inspect(request: &reqwest::RequestBuilder) -> RequestInfo {
+    let Some(built) = request.try_clone().and_then(|r| r.build().ok()) else {
+        return RequestInfo::default();
+    };
+    let capturing = proto_debug::is_capturing();
+    RequestInfo {
+        token: built
+            .headers()
+            .get("x-auth-token")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: if capturing {
+            built.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec())
+        } else {
+            None
+        },
+        secrets: if capturing {
+            SECRET_HEADERS
+                .iter()
+                .filter_map(|name| built.headers().get(*name))
+                .filter_map(|v| v.to_str().ok())
+                .map(|v| v.trim_start_matches("Bearer ").to_string())
+                .collect()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// 抓包模式下读出响应体写入抓包文件，再用相同的状态码、响应头和响应体重新构造响应
+async fn capture_response(label: &str, info: &RequestInfo, response: reqwest::Response) -> AppResult<reqwest::Response> {
+    let Some(request_body) = info.body.as_deref() else {
+        return Ok(response);
+    };
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    proto_debug::capture(label, request_body, status.as_u16(), &body, &info.secrets);
+
+    let mut rebuilt = http::Response::new(body.to_vec());
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(reqwest::Response::from(rebuilt))
+}
+
+fn is_retryable_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_request()
+}
+
+/// 发送只读请求：网络错误或 5xx 时按退避策略重试，用尽后返回带尝试次数的错误
+///
+/// 请求体必须可复制（字节体），流式请求体只会发送一次
+pub async fn send(label: &str, account: Option<Uuid>, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+    let config = policy();
+    let max_attempts = config.max_attempts.max(1);
+    let mut attempt = 1;
+    let request = custom_headers::apply(label, account, request);
+    let info = inspect(&request);
+    let mut pending = request;
+
+    loop {
+        let next = if attempt < max_attempts { pending.try_clone() } else { None };
+        let can_retry = next.is_some();
+
+        rate_limiter::acquire(label, info.token.as_deref()).await;
+        match pending.send().await {
+            Ok(response) if response.status().is_server_error() && can_retry => {
+                println!("[Retry] {} returned {} (attempt {}/{})", label, response.status(), attempt, max_attempts);
+            }
+            Ok(response) if response.status().is_server_error() && attempt > 1 => {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                return Err(AppError::Api(format!(
+                    "API returned status code {} (共尝试 {} 次): {}",
+                    status, attempt, body
+                )));
+            }
+            Ok(response) => return capture_response(label, &info, response).await,
+            Err(e) if is_retryable_error(&e) && can_retry => {
+                println!("[Retry] {} failed: {} (attempt {}/{})", label, e, attempt, max_attempts);
+            }
+            Err(e) if attempt > 1 => {
+                return Err(AppError::Network(format!("{} (共尝试 {} 次)", e, attempt)));
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        tokio::time::sleep(backoff_delay(&config, attempt)).await;
+        attempt += 1;
+        pending = next.expect("retry requires a cloned request");
+    }
+}
+
+/// 只发送一次，不重试：用于调用方自己控制重试次数的请求（如 UpdateSeats 的 retry_times）
+pub async fn send_once(label: &str, account: Option<Uuid>, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+    let request = custom_headers::apply(label, account, request);
+    let info = inspect(&request);
+    rate_limiter::acquire(label, info.token.as_deref()).await;
+    let response = request.send().await?;
+    capture_response(label, &info, response).await
+}
+
+/// 重试后仍失败的错误是否属于网络 / 服务端的临时故障（而不是请求本身被拒绝）
+pub fn is_transient(e: &AppError) -> bool {
+    match e {
+        AppError::Network(_) => true,
+        AppError::HttpRequest(e) => is_retryable_error(e),
+        AppError::Api(message) => message.contains("status code 5"),
+        _ => false,
+    }
+}
+
+/// 发送有副作用的请求（修改套餐、座位、成员等）：只在连接未建立时重试，避免重复提交
+pub async fn send_mutation(label: &str, account: Option<Uuid>, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+    let config = policy();
+    let max_attempts = config.max_attempts.max(1);
+    let mut attempt = 1;
+    let request = custom_headers::apply(label, account, request);
+    let info = inspect(&request);
+    let mut pending = request;
+
+    loop {
+        let next = if attempt < max_attempts { pending.try_clone() } else { None };
+
+        rate_limiter::acquire(label, info.token.as_deref()).await;
+        match pending.send().await {
+            Ok(response) => return capture_response(label, &info, response).await,
+            Err(e) if e.is_connect() && next.is_some() => {
+                println!("[Retry] {} could not connect: {} (attempt {}/{})", label, e, attempt, max_attempts);
+            }
+            Err(e) if attempt > 1 => {
+                return Err(AppError::Network(format!("{} (共尝试 {} 次)", e, attempt)));
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        tokio::time::sleep(backoff_delay(&config, attempt)).await;
+        attempt += 1;
+        pending = next.expect("retry requires a cloned request");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let config = ApiRetryConfig { max_attempts: 5, base_delay_ms: 500, max_delay_ms: 3000, jitter: false };
+        assert_eq!(backoff_delay(&config, 1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&config, 2), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(&config, 4), Duration::from_millis(3000));
+
+        let jittered = ApiRetryConfig { jitter: true, ..config };
+        let delay = backoff_delay(&jittered, 2);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1000));
+    }
+}
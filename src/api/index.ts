@@ -1,4 +1,5 @@
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from './invoke';
+export { CommandError, type CommandErrorKind } from './invoke';
 import type { Account, Settings, OperationLog, UpdateSeatsResult, BillingInfo, BatchResult, GlobalTag, SortField, SortDirection, SortConfig } from '@/types';
 import type { AnalyticsData } from '@/types/analytics';
 
@@ -18,6 +19,11 @@ export const accountApi = {
     return await invoke('get_all_accounts');
   },
 
+  // 不含密码与凭据，只读面板窗口使用
+  async getRedactedAccounts(): Promise<Account[]> {
+    return await invoke('get_redacted_accounts');
+  },
+
   async getAccount(id: string): Promise<Account> {
     return await invoke('get_account', { id });
   },
@@ -325,6 +331,11 @@ export const settingsApi = {
     return await invoke('get_settings');
   },
 
+  // 不含密钥与连接串，只读面板窗口使用
+  async getRedactedSettings(): Promise<Settings> {
+    return await invoke('get_redacted_settings');
+  },
+
   async updateSettings(settings: Settings): Promise<void> {
     return await invoke('update_settings', { settings });
   },
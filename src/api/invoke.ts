@@ -0,0 +1,46 @@
+import { invoke as tauriInvoke, type InvokeArgs, type InvokeOptions } from '@tauri-apps/api/core';
+
+// 与后端 CommandError 的 kind 对应
+export type CommandErrorKind = 'auth' | 'network' | 'rate_limited' | 'not_found' | 'parse' | 'other';
+
+/**
+ * 命令调用失败时抛出的错误
+ * 后端新命令返回 { kind, message }，旧命令返回字符串，这里统一成同一种形式；
+ * toString() 只返回错误信息，`${error}`、error.toString() 与 error.message 的显示都不变
+ */
+export class CommandError extends Error {
+  kind: CommandErrorKind;
+
+  constructor(kind: CommandErrorKind, message: string) {
+    super(message);
+    this.name = 'CommandError';
+    this.kind = kind;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
+
+export function toCommandError(error: unknown): CommandError {
+  if (error instanceof CommandError) {
+    return error;
+  }
+  if (error && typeof error === 'object' && 'kind' in error && 'message' in error) {
+    const { kind, message } = error as { kind: CommandErrorKind; message: unknown };
+    return new CommandError(kind, String(message));
+  }
+  if (error instanceof Error) {
+    return new CommandError('other', error.message);
+  }
+  return new CommandError('other', String(error));
+}
+
+// 替代 @tauri-apps/api/core 的 invoke，失败时抛出 CommandError
+export async function invoke<T>(cmd: string, args?: InvokeArgs, options?: InvokeOptions): Promise<T> {
+  try {
+    return await tauriInvoke<T>(cmd, args, options);
+  } catch (error) {
+    throw toCommandError(error);
+  }
+}
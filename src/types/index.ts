@@ -353,7 +353,8 @@ export type OperationType =
   | 'add_account'
   | 'delete_account'
   | 'edit_account'
-  | 'batch_operation';
+  | 'batch_operation'
+  | 'job_cancelled';
 
 export type OperationStatus = 'success' | 'failed' | 'pending' | 'processing';
 
@@ -1,4 +1,4 @@
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '@/api/invoke';
 
 export interface VirtualCard {
   card_number: string;